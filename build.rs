@@ -0,0 +1,160 @@
+//! Detects which optional APIs the linked Opus provides and exposes them as
+//! `cfg` flags, allowing to build against older system libraries.
+//!
+//! The library is located the way `audiopus_sys` locates the Opus it links:
+//! in `LIBOPUS_LIB_DIR` or `OPUS_LIB_DIR` if set, otherwise via `pkg-config`
+//! unless disabled by `LIBOPUS_NO_PKG` or `OPUS_NO_PKG`. Its symbols are
+//! probed for the optional APIs. If no library is found, `audiopus_sys`
+//! builds and links its bundled Opus.
+//!
+//! The `OPUS_VERSION` environment variable overrides the detection.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The Opus version `audiopus_sys` bundles.
+const BUNDLED_VERSION: (u8, u8) = (1, 3);
+
+/// The symbols of the optional APIs and their `cfg` flags.
+const PROBES: [(&str, &str); 2] = [
+    ("opus_projection_decoder_create", "opus_projection"),
+    ("opus_dred_decode", "opus_dred"),
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    for variable in &[
+        "OPUS_VERSION",
+        "LIBOPUS_LIB_DIR",
+        "OPUS_LIB_DIR",
+        "LIBOPUS_NO_PKG",
+        "OPUS_NO_PKG",
+    ] {
+        println!("cargo:rerun-if-env-changed={}", variable);
+    }
+
+    for (_, flag) in &PROBES {
+        println!("cargo:rustc-check-cfg=cfg({})", flag);
+    }
+
+    let flags = match env::var("OPUS_VERSION").ok().and_then(|v| parse(&v)) {
+        Some(version) => version_flags(version),
+        None => linked_library()
+            .and_then(|library| probe_symbols(&library))
+            .unwrap_or_else(|| version_flags(BUNDLED_VERSION)),
+    };
+
+    for flag in flags {
+        println!("cargo:rustc-cfg={}", flag);
+    }
+}
+
+/// Gets the flags of the APIs Opus provides since `version`.
+fn version_flags(version: (u8, u8)) -> Vec<&'static str> {
+    let mut flags = Vec::new();
+
+    if version >= (1, 3) {
+        flags.push("opus_projection");
+    }
+
+    if version >= (1, 5) {
+        flags.push("opus_dred");
+    }
+
+    flags
+}
+
+/// Finds the Opus library `audiopus_sys` links, `None` if it builds the
+/// bundled one.
+fn linked_library() -> Option<PathBuf> {
+    let directory = env::var_os("LIBOPUS_LIB_DIR")
+        .or_else(|| env::var_os("OPUS_LIB_DIR"))
+        .map(PathBuf::from)
+        .or_else(pkg_config_libdir)?;
+
+    fs::read_dir(directory)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .find(|path| is_opus_library(path))
+}
+
+/// Queries `pkg-config` for the directory of the system's Opus.
+fn pkg_config_libdir() -> Option<PathBuf> {
+    if env::var_os("LIBOPUS_NO_PKG").is_some() || env::var_os("OPUS_NO_PKG").is_some() {
+        return None;
+    }
+
+    let output = Command::new("pkg-config")
+        .args(&["--variable=libdir", "opus"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let directory = String::from_utf8(output.stdout).ok()?;
+    let directory = directory.trim();
+
+    if directory.is_empty() {
+        return None;
+    }
+
+    Some(PathBuf::from(directory))
+}
+
+/// Whether `path` is a static or shared Opus library, e.g. `libopus.a`,
+/// `libopus.so.0` or `opus.lib`.
+fn is_opus_library(path: &Path) -> bool {
+    let name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+
+    let extension = name
+        .strip_prefix("libopus.")
+        .or_else(|| name.strip_prefix("opus."));
+
+    match extension {
+        Some(extension) => {
+            ["a", "lib", "dylib", "so"].contains(&extension) || extension.starts_with("so.")
+        }
+        None => false,
+    }
+}
+
+/// Gets the flags of the optional APIs whose symbols `library` contains.
+fn probe_symbols(library: &Path) -> Option<Vec<&'static str>> {
+    let bytes = fs::read(library).ok()?;
+
+    Some(
+        PROBES
+            .iter()
+            .filter(|(symbol, _)| contains(&bytes, symbol.as_bytes()))
+            .map(|(_, flag)| *flag)
+            .collect(),
+    )
+}
+
+/// Whether `haystack` contains `needle`.
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+/// Parses the major and minor version from e.g. `1.3.1`.
+fn parse(version: &str) -> Option<(u8, u8)> {
+    let mut numbers = version.trim().split('.');
+    let major = numbers.next()?.parse().ok()?;
+    let minor = numbers
+        .next()
+        .and_then(|minor| minor.split('-').next())
+        .map_or(Some(0), |minor| minor.parse().ok())?;
+
+    Some((major, minor))
+}
@@ -0,0 +1,318 @@
+//! Implementations of Opus the [`Encoder`] and [`Decoder`] run on.
+//!
+//! By default, coders run on the Opus linked at build time.
+//! With the `dlopen`-feature, [`OpusLibrary::install`] makes coders created
+//! afterwards run on a library loaded at runtime instead.
+//!
+//! [`Encoder`]: crate::coder::Encoder
+//! [`Decoder`]: crate::coder::Decoder
+//! [`OpusLibrary::install`]: crate::dynamic::OpusLibrary::install
+
+#[cfg(feature = "dlopen")]
+use crate::dynamic::OpusLibrary;
+use crate::ffi;
+use std::fmt::Debug;
+#[cfg(feature = "dlopen")]
+use std::{
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+/// The Opus functions backing an [`Encoder`] or [`Decoder`], mirroring
+/// Opus' own signatures.
+///
+/// A state must only be passed to the backend that created it.
+///
+/// [`Encoder`]: crate::coder::Encoder
+/// [`Decoder`]: crate::coder::Decoder
+pub trait OpusBackend: Debug + Sync {
+    /// Whether this is the Opus linked at build time, which functions
+    /// outside of this trait and states wrapped via `from_raw` call into.
+    fn is_linked(&self) -> bool {
+        false
+    }
+
+    unsafe fn encoder_get_size(&self, channels: i32) -> i32;
+
+    unsafe fn encoder_create(
+        &self,
+        sample_rate: i32,
+        channels: i32,
+        application: i32,
+        error: *mut i32,
+    ) -> *mut ffi::OpusEncoder;
+
+    unsafe fn encoder_init(
+        &self,
+        encoder: *mut ffi::OpusEncoder,
+        sample_rate: i32,
+        channels: i32,
+        application: i32,
+    ) -> i32;
+
+    unsafe fn encode(
+        &self,
+        encoder: *mut ffi::OpusEncoder,
+        pcm: *const i16,
+        frame_size: i32,
+        data: *mut u8,
+        max_data_bytes: i32,
+    ) -> i32;
+
+    unsafe fn encode_float(
+        &self,
+        encoder: *mut ffi::OpusEncoder,
+        pcm: *const f32,
+        frame_size: i32,
+        data: *mut u8,
+        max_data_bytes: i32,
+    ) -> i32;
+
+    /// Issues a CTL `request` passing `value` as argument.
+    unsafe fn encoder_ctl(&self, encoder: *mut ffi::OpusEncoder, request: i32, value: i32) -> i32;
+
+    /// Issues a CTL `request` writing the requested value to `value`.
+    unsafe fn encoder_ctl_get(
+        &self,
+        encoder: *mut ffi::OpusEncoder,
+        request: i32,
+        value: *mut i32,
+    ) -> i32;
+
+    unsafe fn encoder_destroy(&self, encoder: *mut ffi::OpusEncoder);
+
+    unsafe fn decoder_get_size(&self, channels: i32) -> i32;
+
+    unsafe fn decoder_create(
+        &self,
+        sample_rate: i32,
+        channels: i32,
+        error: *mut i32,
+    ) -> *mut ffi::OpusDecoder;
+
+    unsafe fn decoder_init(
+        &self,
+        decoder: *mut ffi::OpusDecoder,
+        sample_rate: i32,
+        channels: i32,
+    ) -> i32;
+
+    unsafe fn decode(
+        &self,
+        decoder: *mut ffi::OpusDecoder,
+        data: *const u8,
+        len: i32,
+        pcm: *mut i16,
+        frame_size: i32,
+        decode_fec: i32,
+    ) -> i32;
+
+    unsafe fn decode_float(
+        &self,
+        decoder: *mut ffi::OpusDecoder,
+        data: *const u8,
+        len: i32,
+        pcm: *mut f32,
+        frame_size: i32,
+        decode_fec: i32,
+    ) -> i32;
+
+    unsafe fn decoder_get_nb_samples(
+        &self,
+        decoder: *const ffi::OpusDecoder,
+        data: *const u8,
+        len: i32,
+    ) -> i32;
+
+    /// Issues a CTL `request` passing `value` as argument.
+    unsafe fn decoder_ctl(&self, decoder: *mut ffi::OpusDecoder, request: i32, value: i32) -> i32;
+
+    /// Issues a CTL `request` writing the requested value to `value`.
+    unsafe fn decoder_ctl_get(
+        &self,
+        decoder: *mut ffi::OpusDecoder,
+        request: i32,
+        value: *mut i32,
+    ) -> i32;
+
+    unsafe fn decoder_destroy(&self, decoder: *mut ffi::OpusDecoder);
+}
+
+/// The Opus linked at build time.
+#[derive(Debug)]
+pub struct Linked;
+
+/// The backend multistream and projection coders run on, as well as states
+/// wrapped via `from_raw`.
+pub static LINKED: Linked = Linked;
+
+impl OpusBackend for Linked {
+    fn is_linked(&self) -> bool {
+        true
+    }
+
+    unsafe fn encoder_get_size(&self, channels: i32) -> i32 {
+        ffi::opus_encoder_get_size(channels)
+    }
+
+    unsafe fn encoder_create(
+        &self,
+        sample_rate: i32,
+        channels: i32,
+        application: i32,
+        error: *mut i32,
+    ) -> *mut ffi::OpusEncoder {
+        ffi::opus_encoder_create(sample_rate, channels, application, error)
+    }
+
+    unsafe fn encoder_init(
+        &self,
+        encoder: *mut ffi::OpusEncoder,
+        sample_rate: i32,
+        channels: i32,
+        application: i32,
+    ) -> i32 {
+        ffi::opus_encoder_init(encoder, sample_rate, channels, application)
+    }
+
+    unsafe fn encode(
+        &self,
+        encoder: *mut ffi::OpusEncoder,
+        pcm: *const i16,
+        frame_size: i32,
+        data: *mut u8,
+        max_data_bytes: i32,
+    ) -> i32 {
+        ffi::opus_encode(encoder, pcm, frame_size, data, max_data_bytes)
+    }
+
+    unsafe fn encode_float(
+        &self,
+        encoder: *mut ffi::OpusEncoder,
+        pcm: *const f32,
+        frame_size: i32,
+        data: *mut u8,
+        max_data_bytes: i32,
+    ) -> i32 {
+        ffi::opus_encode_float(encoder, pcm, frame_size, data, max_data_bytes)
+    }
+
+    unsafe fn encoder_ctl(&self, encoder: *mut ffi::OpusEncoder, request: i32, value: i32) -> i32 {
+        ffi::opus_encoder_ctl(encoder, request, value)
+    }
+
+    unsafe fn encoder_ctl_get(
+        &self,
+        encoder: *mut ffi::OpusEncoder,
+        request: i32,
+        value: *mut i32,
+    ) -> i32 {
+        ffi::opus_encoder_ctl(encoder, request, value)
+    }
+
+    unsafe fn encoder_destroy(&self, encoder: *mut ffi::OpusEncoder) {
+        ffi::opus_encoder_destroy(encoder)
+    }
+
+    unsafe fn decoder_get_size(&self, channels: i32) -> i32 {
+        ffi::opus_decoder_get_size(channels)
+    }
+
+    unsafe fn decoder_create(
+        &self,
+        sample_rate: i32,
+        channels: i32,
+        error: *mut i32,
+    ) -> *mut ffi::OpusDecoder {
+        ffi::opus_decoder_create(sample_rate, channels, error)
+    }
+
+    unsafe fn decoder_init(
+        &self,
+        decoder: *mut ffi::OpusDecoder,
+        sample_rate: i32,
+        channels: i32,
+    ) -> i32 {
+        ffi::opus_decoder_init(decoder, sample_rate, channels)
+    }
+
+    unsafe fn decode(
+        &self,
+        decoder: *mut ffi::OpusDecoder,
+        data: *const u8,
+        len: i32,
+        pcm: *mut i16,
+        frame_size: i32,
+        decode_fec: i32,
+    ) -> i32 {
+        ffi::opus_decode(decoder, data, len, pcm, frame_size, decode_fec)
+    }
+
+    unsafe fn decode_float(
+        &self,
+        decoder: *mut ffi::OpusDecoder,
+        data: *const u8,
+        len: i32,
+        pcm: *mut f32,
+        frame_size: i32,
+        decode_fec: i32,
+    ) -> i32 {
+        ffi::opus_decode_float(decoder, data, len, pcm, frame_size, decode_fec)
+    }
+
+    unsafe fn decoder_get_nb_samples(
+        &self,
+        decoder: *const ffi::OpusDecoder,
+        data: *const u8,
+        len: i32,
+    ) -> i32 {
+        ffi::opus_decoder_get_nb_samples(decoder, data, len)
+    }
+
+    unsafe fn decoder_ctl(&self, decoder: *mut ffi::OpusDecoder, request: i32, value: i32) -> i32 {
+        ffi::opus_decoder_ctl(decoder, request, value)
+    }
+
+    unsafe fn decoder_ctl_get(
+        &self,
+        decoder: *mut ffi::OpusDecoder,
+        request: i32,
+        value: *mut i32,
+    ) -> i32 {
+        ffi::opus_decoder_ctl(decoder, request, value)
+    }
+
+    unsafe fn decoder_destroy(&self, decoder: *mut ffi::OpusDecoder) {
+        ffi::opus_decoder_destroy(decoder)
+    }
+}
+
+/// The library installed via [`OpusLibrary::install`], null if none.
+///
+/// Installed libraries are leaked, hence coders may keep referring to them
+/// after another library has been installed.
+///
+/// [`OpusLibrary::install`]: crate::dynamic::OpusLibrary::install
+#[cfg(feature = "dlopen")]
+static INSTALLED: AtomicPtr<OpusLibrary> = AtomicPtr::new(ptr::null_mut());
+
+/// Makes `library` the backend of coders created from now on.
+#[cfg(feature = "dlopen")]
+pub fn install(library: OpusLibrary) {
+    INSTALLED.store(Box::into_raw(Box::new(library)), Ordering::Release);
+}
+
+/// Gets the backend newly created coders run on.
+pub fn current() -> &'static dyn OpusBackend {
+    #[cfg(feature = "dlopen")]
+    {
+        let library = INSTALLED.load(Ordering::Acquire);
+
+        if !library.is_null() {
+            // Installed libraries are never freed.
+            return unsafe { &*library };
+        }
+    }
+
+    &LINKED
+}
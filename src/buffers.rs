@@ -0,0 +1,142 @@
+//! Conversions between interleaved and planar signals.
+//!
+//! Opus expects and produces interleaved signals, i.e. one sample per
+//! channel after another, while planar signals keep one buffer per channel.
+
+use crate::{Error, FrameDuration, Result, Sample, SampleRate};
+
+/// Gets the length of an interleaved buffer holding a frame of
+/// `frame_duration` with `channels` at `sample_rate`, e.g. to allocate
+/// fixed buffers at compile time.
+pub const fn pcm_buffer_len(
+    sample_rate: SampleRate,
+    channels: usize,
+    frame_duration: FrameDuration,
+) -> usize {
+    frame_duration.samples(sample_rate, channels)
+}
+
+/// Interleaves the planar `input`, one slice per channel, into `output`.
+///
+/// On success, returns the amount of samples per channel.
+///
+/// **Errors**:
+/// Returns [`Error::InvalidChannels`] if `input` contains no channels and
+/// [`Error::SignalsExpectedLen`] if the channels differ in length or
+/// `output` cannot hold all samples.
+///
+/// [`Error::InvalidChannels`]: crate::error::Error::InvalidChannels
+/// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+pub fn interleave<S: Sample>(input: &[&[S]], output: &mut [S]) -> Result<usize> {
+    let channels = input.len();
+    let samples = input.first().ok_or(Error::InvalidChannels(0))?.len();
+
+    if input.iter().any(|channel| channel.len() != samples) {
+        return Err(Error::SignalsExpectedLen(samples));
+    }
+
+    if output.len() < samples * channels {
+        return Err(Error::SignalsExpectedLen(samples * channels));
+    }
+
+    for (index, frame) in output.chunks_exact_mut(channels).take(samples).enumerate() {
+        for (sample, channel) in frame.iter_mut().zip(input) {
+            *sample = channel[index];
+        }
+    }
+
+    Ok(samples)
+}
+
+/// Deinterleaves the interleaved `input` into `output`, one slice per
+/// channel.
+///
+/// On success, returns the amount of samples per channel.
+///
+/// **Errors**:
+/// Returns [`Error::InvalidChannels`] if `output` contains no channels and
+/// [`Error::SignalsExpectedLen`] if `input` does not contain the same
+/// amount of samples for every channel or a channel in `output` cannot hold
+/// all its samples.
+///
+/// [`Error::InvalidChannels`]: crate::error::Error::InvalidChannels
+/// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+pub fn deinterleave<S: Sample>(input: &[S], output: &mut [&mut [S]]) -> Result<usize> {
+    let channels = output.len();
+
+    if channels == 0 {
+        return Err(Error::InvalidChannels(0));
+    }
+
+    let samples = input.len() / channels;
+
+    if input.len() % channels != 0 {
+        return Err(Error::SignalsExpectedLen(samples * channels));
+    }
+
+    if output.iter().any(|channel| channel.len() < samples) {
+        return Err(Error::SignalsExpectedLen(samples));
+    }
+
+    for (index, frame) in input.chunks_exact(channels).enumerate() {
+        for (sample, channel) in frame.iter().zip(output.iter_mut()) {
+            channel[index] = *sample;
+        }
+    }
+
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deinterleave, interleave, pcm_buffer_len};
+    use crate::{Error, FrameDuration, SampleRate};
+    use matches::assert_matches;
+
+    #[test]
+    fn buffer_len() {
+        const STEREO_20MS: usize = pcm_buffer_len(SampleRate::Hz48000, 2, FrameDuration::Ms20);
+        assert_eq!([0_i16; STEREO_20MS].len(), 1920);
+    }
+
+    #[test]
+    fn interleave_and_deinterleave() {
+        let left = [1_i16, 2, 3];
+        let right = [4_i16, 5, 6];
+        let mut interleaved = [0_i16; 6];
+
+        assert_matches!(
+            interleave(&[&left[..], &right[..]], &mut interleaved),
+            Ok(3)
+        );
+        assert_eq!(interleaved, [1, 4, 2, 5, 3, 6]);
+
+        let mut left = [0_i16; 3];
+        let mut right = [0_i16; 4];
+
+        assert_matches!(
+            deinterleave(&interleaved, &mut [&mut left[..], &mut right[..]]),
+            Ok(3)
+        );
+        assert_eq!(left, [1, 2, 3]);
+        assert_eq!(right, [4, 5, 6, 0]);
+
+        let mut too_short = [0_f32; 5];
+        assert_matches!(
+            interleave(&[&[0.5_f32; 3][..], &[0.5; 3]], &mut too_short),
+            Err(Error::SignalsExpectedLen(6))
+        );
+        assert_matches!(
+            interleave(&[&[0.5_f32; 3][..], &[0.5; 2]], &mut [0.0_f32; 6]),
+            Err(Error::SignalsExpectedLen(3))
+        );
+        assert_matches!(
+            deinterleave(&[0_i16; 5], &mut [&mut [0; 3][..], &mut [0; 3][..]]),
+            Err(Error::SignalsExpectedLen(4))
+        );
+        assert_matches!(
+            interleave::<i16>(&[], &mut []),
+            Err(Error::InvalidChannels(0))
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! Framing of Opus packets over byte streams such as TCP or WebSockets via
+//! `tokio_util`'s `Framed`, see [`OpusFrameCodec`].
+//!
+//! [`OpusFrameCodec`]: crate::codec::OpusFrameCodec
+
+use crate::{
+    packet::{Packet, PacketBuf},
+    Error, TryFrom,
+};
+use bytes::{Buf, BufMut, BytesMut};
+use std::io::Error as IoError;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// The length of the big-endian length prefix, in bytes.
+const PREFIX_LEN: usize = 2;
+
+/// `OpusFrameCodec` frames Opus packets on byte streams, each packet
+/// prefixed by its length as big-endian `u16`.
+///
+/// Malformed frames, such as empty ones, fail with
+/// [`ErrorKind::InvalidData`] wrapping the crate's [`Error`].
+///
+/// [`ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+/// [`Error`]: crate::error::Error
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct OpusFrameCodec;
+
+impl OpusFrameCodec {
+    /// Creates a new `OpusFrameCodec`.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Decoder for OpusFrameCodec {
+    type Item = PacketBuf;
+    type Error = IoError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<PacketBuf>, IoError> {
+        if src.len() < PREFIX_LEN {
+            return Ok(None);
+        }
+
+        let len = usize::from(u16::from_be_bytes([src[0], src[1]]));
+
+        if src.len() < PREFIX_LEN + len {
+            src.reserve(PREFIX_LEN + len - src.len());
+
+            return Ok(None);
+        }
+
+        src.advance(PREFIX_LEN);
+        let packet = src.split_to(len).freeze();
+
+        Ok(Some(PacketBuf::try_from(packet)?))
+    }
+}
+
+impl<'a> Encoder<Packet<'a>> for OpusFrameCodec {
+    type Error = IoError;
+
+    fn encode(&mut self, packet: Packet<'a>, dst: &mut BytesMut) -> Result<(), IoError> {
+        let payload = packet.as_slice();
+        let len = u16::try_from(payload.len()).map_err(|_| Error::PacketTooLarge)?;
+
+        dst.reserve(PREFIX_LEN + payload.len());
+        dst.put_u16(len);
+        dst.extend_from_slice(payload);
+
+        Ok(())
+    }
+}
+
+impl Encoder<PacketBuf> for OpusFrameCodec {
+    type Error = IoError;
+
+    fn encode(&mut self, packet: PacketBuf, dst: &mut BytesMut) -> Result<(), IoError> {
+        Encoder::<Packet<'_>>::encode(self, packet.as_packet(), dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OpusFrameCodec;
+    use crate::packet::Packet;
+    use bytes::BytesMut;
+    use std::io::ErrorKind;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    #[test]
+    fn frame_packets() {
+        let mut codec = OpusFrameCodec::new();
+        let mut buffer = BytesMut::new();
+
+        let first = [0xf8, 1, 2, 3];
+        let second = [0xfc, 4];
+        codec
+            .encode(Packet::new(&first).unwrap(), &mut buffer)
+            .unwrap();
+        codec
+            .encode(Packet::new(&second).unwrap(), &mut buffer)
+            .unwrap();
+        assert_eq!(&buffer[..3], &[0, 4, 0xf8]);
+
+        // A partial frame waits for more data.
+        let mut partial = buffer.split_to(3);
+        assert!(codec.decode(&mut partial).unwrap().is_none());
+        partial.unsplit(buffer);
+        let mut buffer = partial;
+
+        let decoded = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(decoded.as_slice(), &first);
+        let decoded = codec.decode(&mut buffer).unwrap().unwrap();
+        assert_eq!(decoded.as_slice(), &second);
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+
+        let mut empty = BytesMut::from(&[0_u8, 0][..]);
+        assert_eq!(
+            codec.decode(&mut empty).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+}
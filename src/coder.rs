@@ -1,12 +1,41 @@
 use crate::{Error, SampleRate};
 
+#[cfg(all(feature = "opus_1_5", opus_dred))]
+pub use self::dred::{Dred, DredDecoder, DredParse, OpusDRED, OpusDREDDecoder};
+#[cfg(feature = "rayon")]
+pub use self::parallel::encode_streams;
 pub use self::{
-    decoder::{size, Decoder},
+    cbr::CbrPacketizer,
+    config::{DecoderConfig, DecoderSettings, EncoderConfig, EncoderSettings},
+    ctl::CtlRequest,
+    decoder::{size, DecodeRequest, Decoder},
+    dtx::{DtxEncoder, EncodeOutput},
     encoder::Encoder,
+    fec::FecChannel,
+    frames::EncodeFrames,
+    in_place::{CoderArena, DecoderInPlace, EncoderInPlace},
+    pool::{DecoderPool, StreamStats},
+    streaming::{GapFill, StreamDecoder, StreamEncoder},
 };
 
+mod cbr;
+mod config;
+mod ctl;
 mod decoder;
+#[cfg(all(feature = "opus_1_5", opus_dred))]
+pub(crate) mod dred;
+mod dtx;
 mod encoder;
+pub(crate) mod fec;
+mod frames;
+mod in_place;
+pub mod multistream;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod pool;
+#[cfg(opus_projection)]
+pub mod projection;
+mod streaming;
 
 /// A set of methods that both `Encoder` and `Decoder` have implemented.
 ///
@@ -16,6 +45,11 @@ mod encoder;
 ///
 /// [`Decoder`]: decoder/struct.Decoder.html
 pub trait GenericCtl {
+    /// Gets the final state of the codec's entropy coder.
+    ///
+    /// An encoder and a decoder having coded the same packet report the
+    /// same range, which checks bit-exactness, e.g. with the Opus test
+    /// vectors or when transcoding transparently.
     fn final_range(&self) -> Result<u32, Error>;
 
     fn phase_inversion_disabled(&self) -> Result<bool, Error>;
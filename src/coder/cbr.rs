@@ -0,0 +1,136 @@
+use super::Encoder;
+use crate::{
+    packet::{self, MutPacket},
+    Error, ErrorCode, Result, Sample,
+};
+use std::convert::TryFrom;
+
+/// `CbrPacketizer` wraps an [`Encoder`] and pads every encoded packet to
+/// exactly the same length, as required by transports expecting a constant
+/// bitrate.
+///
+/// **Info**:
+/// Packets are capped at the configured length, the encoder should be
+/// configured with a bitrate matching the length, see [`Encoder::set_vbr`].
+///
+/// [`Encoder`]: crate::coder::Encoder
+/// [`Encoder::set_vbr`]: crate::coder::Encoder::set_vbr
+#[derive(Debug)]
+pub struct CbrPacketizer {
+    encoder: Encoder,
+    packet_len: usize,
+}
+
+impl CbrPacketizer {
+    /// Creates a new `CbrPacketizer` emitting packets of `packet_len` bytes.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidPadLen`] if `packet_len` is zero or exceeds
+    /// `std::i32::MAX`.
+    ///
+    /// [`Error::InvalidPadLen`]: crate::error::Error::InvalidPadLen
+    pub fn new(encoder: Encoder, packet_len: usize) -> Result<Self> {
+        if packet_len == 0 || packet_len > std::i32::MAX as usize {
+            return Err(Error::InvalidPadLen(packet_len));
+        }
+
+        Ok(Self {
+            encoder,
+            packet_len,
+        })
+    }
+
+    /// Gets the length every emitted packet has.
+    pub fn packet_len(&self) -> usize {
+        self.packet_len
+    }
+
+    /// Gets the wrapped encoder.
+    pub fn encoder(&self) -> &Encoder {
+        &self.encoder
+    }
+
+    /// Gets the wrapped encoder mutably, e.g. to adjust its settings.
+    pub fn encoder_mut(&mut self) -> &mut Encoder {
+        &mut self.encoder
+    }
+
+    /// Unwraps the encoder.
+    pub fn into_inner(self) -> Encoder {
+        self.encoder
+    }
+
+    /// Encodes an Opus frame and pads it to the configured length.
+    ///
+    /// The `input` signal (interleaved if 2 channels) will be encoded into the
+    /// `output` payload and on success returns the length of the
+    /// encoded packet, which is always the configured length.
+    /// The samples are either `i16` or `f32`, see [`Sample`].
+    ///
+    /// **Errors**:
+    /// Returns [`BufferTooSmall`] if `output` is shorter than the configured
+    /// length.
+    ///
+    /// [`Sample`]: crate::sample::Sample
+    /// [`BufferTooSmall`]: crate::error::ErrorCode::BufferTooSmall
+    pub fn encode<S: Sample>(&self, input: &[S], output: &mut [u8]) -> Result<usize> {
+        let output = self.output(output)?;
+        let len = self.encoder.encode(input, output)?;
+
+        self.pad(output, len)
+    }
+
+    /// Restricts `output` to the configured length, so the encoder cannot
+    /// exceed it.
+    fn output<'a>(&self, output: &'a mut [u8]) -> Result<&'a mut [u8]> {
+        if output.len() < self.packet_len {
+            return Err(Error::Opus(ErrorCode::BufferTooSmall));
+        }
+
+        Ok(&mut output[..self.packet_len])
+    }
+
+    fn pad(&self, output: &mut [u8], len: usize) -> Result<usize> {
+        packet::pad(MutPacket::try_from(output)?, len, self.packet_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CbrPacketizer;
+    use crate::{
+        coder::Encoder, packet::Packet, Application, Channels, Error, ErrorCode, SampleRate,
+    };
+    use matches::assert_matches;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn constant_packet_len() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        let packetizer = CbrPacketizer::new(encoder, 160).unwrap();
+
+        // 48000Hz * 2 channels * 20 ms / 1000
+        const STEREO_20MS: usize = 48000 * 2 * 20 / 1000;
+        let silence = [0_i16; STEREO_20MS];
+        let noise: Vec<i16> = (0..STEREO_20MS as i32)
+            .map(|i| ((i * 7919) % 20000) as i16)
+            .collect();
+        let mut output = [0_u8; 256];
+
+        for input in [&silence[..], &noise[..]].iter() {
+            let len = packetizer.encode(input, &mut output).unwrap();
+            assert_eq!(len, 160);
+            assert_matches!(Packet::try_from(&output[..len]).unwrap().nb_frames(), Ok(1));
+        }
+
+        assert_matches!(
+            packetizer.encode(&silence, &mut output[..100]),
+            Err(Error::Opus(ErrorCode::BufferTooSmall))
+        );
+
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        assert_matches!(CbrPacketizer::new(encoder, 0), Err(Error::InvalidPadLen(0)));
+    }
+}
@@ -0,0 +1,416 @@
+//! Plain-data descriptions of coder settings, e.g. to load them from
+//! configuration files.
+
+use super::{Decoder, Encoder, GenericCtl};
+use crate::{
+    Application, Bandwidth, Bitrate, Channels, Error, FrameDuration, Result, SampleRate, Signal,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(all(feature = "opus_1_5", opus_dred))]
+use std::time::Duration;
+
+/// Settings applicable to an [`Encoder`].
+///
+/// Every setting left as `None` keeps the encoder's current value.
+///
+/// [`Encoder`]: crate::coder::Encoder
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct EncoderConfig {
+    pub application: Option<Application>,
+    pub bitrate: Option<Bitrate>,
+    pub complexity: Option<u8>,
+    pub vbr: Option<bool>,
+    pub vbr_constraint: Option<bool>,
+    pub inband_fec: Option<bool>,
+    pub packet_loss_perc: Option<u8>,
+    pub force_channels: Option<Channels>,
+    pub max_bandwidth: Option<Bandwidth>,
+    pub bandwidth: Option<Bandwidth>,
+    pub signal: Option<Signal>,
+    pub dtx: Option<bool>,
+    pub lsb_depth: Option<u8>,
+    pub prediction_disabled: Option<bool>,
+    pub phase_inversion_disabled: Option<bool>,
+    /// The frame duration, `Some(None)` selects it by the input's length,
+    /// see [`Encoder::set_frame_duration`].
+    ///
+    /// [`Encoder::set_frame_duration`]: crate::coder::Encoder::set_frame_duration
+    pub frame_duration: Option<Option<FrameDuration>>,
+    #[cfg(all(feature = "opus_1_5", opus_dred))]
+    pub dred_duration: Option<Duration>,
+}
+
+impl EncoderConfig {
+    /// Applies every configured setting to `encoder`.
+    ///
+    /// **Errors**:
+    /// Returns the first error Opus reports, settings applied before
+    /// remain in effect.
+    pub fn apply(&self, encoder: &mut Encoder) -> Result<()> {
+        if let Some(application) = self.application {
+            encoder.set_application(application)?;
+        }
+
+        if let Some(bitrate) = self.bitrate {
+            encoder.set_bitrate(bitrate)?;
+        }
+
+        if let Some(complexity) = self.complexity {
+            encoder.set_complexity(complexity)?;
+        }
+
+        if let Some(vbr) = self.vbr {
+            encoder.set_vbr(vbr)?;
+        }
+
+        if let Some(vbr_constraint) = self.vbr_constraint {
+            encoder.set_vbr_constraint(vbr_constraint)?;
+        }
+
+        if let Some(inband_fec) = self.inband_fec {
+            encoder.set_inband_fec(inband_fec)?;
+        }
+
+        if let Some(percentage) = self.packet_loss_perc {
+            encoder.set_packet_loss_perc(percentage)?;
+        }
+
+        if let Some(channels) = self.force_channels {
+            encoder.set_force_channels(channels)?;
+        }
+
+        if let Some(bandwidth) = self.max_bandwidth {
+            encoder.set_max_bandwidth(bandwidth)?;
+        }
+
+        if let Some(bandwidth) = self.bandwidth {
+            encoder.set_bandwidth(bandwidth)?;
+        }
+
+        if let Some(signal) = self.signal {
+            encoder.set_signal(signal)?;
+        }
+
+        if let Some(dtx) = self.dtx {
+            encoder.set_dtx(dtx)?;
+        }
+
+        if let Some(lsb_depth) = self.lsb_depth {
+            encoder.set_lsb_depth(lsb_depth)?;
+        }
+
+        if let Some(prediction_disabled) = self.prediction_disabled {
+            encoder.set_prediction_disabled(prediction_disabled)?;
+        }
+
+        if let Some(disabled) = self.phase_inversion_disabled {
+            encoder.set_phase_inversion_disabled(disabled)?;
+        }
+
+        if let Some(frame_duration) = self.frame_duration {
+            encoder.set_frame_duration(frame_duration)?;
+        }
+
+        #[cfg(all(feature = "opus_1_5", opus_dred))]
+        if let Some(duration) = self.dred_duration {
+            encoder.set_dred_duration(duration)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A snapshot of an [`Encoder`]'s settings, see [`Encoder::settings`].
+///
+/// [`Encoder`]: crate::coder::Encoder
+/// [`Encoder::settings`]: crate::coder::Encoder::settings
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EncoderSettings {
+    pub sample_rate: SampleRate,
+    pub application: Application,
+    /// The configured bitrate, [`Bitrate::Auto`] or [`Bitrate::Max`] rather
+    /// than the rate Opus picked for them.
+    ///
+    /// [`Bitrate::Auto`]: crate::Bitrate::Auto
+    /// [`Bitrate::Max`]: crate::Bitrate::Max
+    pub bitrate: Bitrate,
+    pub complexity: u8,
+    pub vbr: bool,
+    pub vbr_constraint: bool,
+    pub inband_fec: bool,
+    pub packet_loss_perc: u8,
+    pub force_channels: Channels,
+    pub max_bandwidth: Bandwidth,
+    /// The bandwidth of the last encoded frame.
+    pub bandwidth: Bandwidth,
+    pub signal: Signal,
+    pub dtx: bool,
+    pub lsb_depth: u8,
+    pub prediction_disabled: bool,
+    pub phase_inversion_disabled: bool,
+    /// The frame duration, `None` if selected by the input's length.
+    pub frame_duration: Option<FrameDuration>,
+    #[cfg(all(feature = "opus_1_5", opus_dred))]
+    pub dred_duration: Duration,
+}
+
+impl From<EncoderSettings> for EncoderConfig {
+    /// Converts a snapshot to a configuration restoring it.
+    ///
+    /// The bandwidth is left unset, as forcing the last encoded one would
+    /// prevent the encoder from selecting it automatically.
+    fn from(settings: EncoderSettings) -> Self {
+        Self {
+            application: Some(settings.application),
+            bitrate: Some(settings.bitrate),
+            complexity: Some(settings.complexity),
+            vbr: Some(settings.vbr),
+            vbr_constraint: Some(settings.vbr_constraint),
+            inband_fec: Some(settings.inband_fec),
+            packet_loss_perc: Some(settings.packet_loss_perc),
+            force_channels: Some(settings.force_channels),
+            max_bandwidth: Some(settings.max_bandwidth),
+            bandwidth: None,
+            signal: Some(settings.signal),
+            dtx: Some(settings.dtx),
+            lsb_depth: Some(settings.lsb_depth),
+            prediction_disabled: Some(settings.prediction_disabled),
+            phase_inversion_disabled: Some(settings.phase_inversion_disabled),
+            frame_duration: Some(settings.frame_duration),
+            #[cfg(all(feature = "opus_1_5", opus_dred))]
+            dred_duration: Some(settings.dred_duration),
+        }
+    }
+}
+
+/// Settings applicable to a [`Decoder`].
+///
+/// Every setting left as `None` keeps the decoder's current value.
+///
+/// [`Decoder`]: crate::coder::Decoder
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct DecoderConfig {
+    /// The sample rate a decoder is created with, see [`build`].
+    ///
+    /// [`build`]: crate::coder::DecoderConfig::build
+    pub sample_rate: Option<SampleRate>,
+    /// The channels a decoder is created with, see [`build`].
+    ///
+    /// [`build`]: crate::coder::DecoderConfig::build
+    pub channels: Option<Channels>,
+    /// The gain in Q8 dB units, see [`Decoder::set_gain`].
+    ///
+    /// [`Decoder::set_gain`]: crate::coder::Decoder::set_gain
+    pub gain: Option<i32>,
+    pub phase_inversion_disabled: Option<bool>,
+}
+
+impl DecoderConfig {
+    /// Creates a new decoder at the configured sample rate and channels,
+    /// 48 kHz stereo if absent, and applies every other setting to it.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn build(&self) -> Result<Decoder> {
+        let mut decoder = Decoder::new(
+            self.sample_rate.unwrap_or(SampleRate::Hz48000),
+            self.channels.unwrap_or(Channels::Stereo),
+        )?;
+        self.apply(&mut decoder)?;
+
+        Ok(decoder)
+    }
+
+    /// Applies every configured setting to `decoder`.
+    ///
+    /// The sample rate and channels cannot change after creation, they are
+    /// only checked against the decoder's.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidSampleRate`] or [`Error::InvalidChannels`]
+    /// if the configured one differs from the decoder's, otherwise the
+    /// first error Opus reports, settings applied before remain in effect.
+    ///
+    /// [`Error::InvalidSampleRate`]: crate::error::Error::InvalidSampleRate
+    /// [`Error::InvalidChannels`]: crate::error::Error::InvalidChannels
+    pub fn apply(&self, decoder: &mut Decoder) -> Result<()> {
+        if let Some(sample_rate) = self.sample_rate {
+            if decoder.sample_rate()? != sample_rate {
+                return Err(Error::InvalidSampleRate(sample_rate as i32));
+            }
+        }
+
+        if let Some(channels) = self.channels {
+            if decoder.channels() != channels {
+                return Err(Error::InvalidChannels(channels as i32));
+            }
+        }
+
+        if let Some(gain) = self.gain {
+            decoder.set_gain(gain)?;
+        }
+
+        if let Some(disabled) = self.phase_inversion_disabled {
+            decoder.set_phase_inversion_disabled(disabled)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A snapshot of a [`Decoder`]'s settings, see [`Decoder::settings`].
+///
+/// [`Decoder`]: crate::coder::Decoder
+/// [`Decoder::settings`]: crate::coder::Decoder::settings
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DecoderSettings {
+    pub sample_rate: SampleRate,
+    /// The gain in Q8 dB units, see [`Decoder::set_gain`].
+    ///
+    /// [`Decoder::set_gain`]: crate::coder::Decoder::set_gain
+    pub gain: i32,
+    pub phase_inversion_disabled: bool,
+}
+
+impl From<DecoderSettings> for DecoderConfig {
+    /// Converts a snapshot to a configuration restoring it.
+    fn from(settings: DecoderSettings) -> Self {
+        Self {
+            sample_rate: Some(settings.sample_rate),
+            channels: None,
+            gain: Some(settings.gain),
+            phase_inversion_disabled: Some(settings.phase_inversion_disabled),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecoderConfig, EncoderConfig};
+    use crate::{
+        coder::{Decoder, Encoder},
+        Application, Bitrate, Channels, Error, ErrorCode, FrameDuration, SampleRate, Signal,
+    };
+    use matches::assert_matches;
+
+    #[test]
+    fn apply_encoder_config() {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+
+        let config = EncoderConfig {
+            application: Some(Application::Voip),
+            bitrate: Some(Bitrate::BitsPerSecond(32000)),
+            complexity: Some(5),
+            signal: Some(Signal::Voice),
+            dtx: Some(true),
+            ..EncoderConfig::default()
+        };
+
+        config.apply(&mut encoder).unwrap();
+
+        assert_matches!(encoder.application(), Ok(Application::Voip));
+        assert_matches!(encoder.bitrate(), Ok(Bitrate::BitsPerSecond(32000)));
+        assert_matches!(encoder.complexity(), Ok(5));
+        assert_matches!(encoder.signal(), Ok(Signal::Voice));
+        assert_matches!(encoder.dtx(), Ok(true));
+        assert_matches!(encoder.vbr(), Ok(true));
+
+        let invalid_config = EncoderConfig {
+            complexity: Some(11),
+            ..EncoderConfig::default()
+        };
+
+        assert_matches!(
+            invalid_config.apply(&mut encoder),
+            Err(Error::Opus(ErrorCode::BadArgument))
+        );
+    }
+
+    #[test]
+    fn apply_decoder_config() {
+        let mut decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+
+        let config = DecoderConfig {
+            gain: Some(256),
+            ..DecoderConfig::default()
+        };
+
+        config.apply(&mut decoder).unwrap();
+
+        assert_matches!(decoder.gain(), Ok(256));
+
+        let config = DecoderConfig {
+            sample_rate: Some(SampleRate::Hz16000),
+            channels: Some(Channels::Mono),
+            ..config
+        };
+
+        assert_matches!(
+            config.apply(&mut decoder),
+            Err(Error::InvalidSampleRate(16000))
+        );
+
+        let decoder = config.build().unwrap();
+        assert_matches!(decoder.sample_rate(), Ok(SampleRate::Hz16000));
+        assert_eq!(decoder.channels(), Channels::Mono);
+        assert_matches!(decoder.gain(), Ok(256));
+    }
+
+    #[test]
+    fn settings_snapshot() {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+
+        let config = EncoderConfig {
+            bitrate: Some(Bitrate::BitsPerSecond(32000)),
+            complexity: Some(5),
+            inband_fec: Some(true),
+            lsb_depth: Some(16),
+            frame_duration: Some(Some(FrameDuration::Ms10)),
+            ..EncoderConfig::default()
+        };
+        config.apply(&mut encoder).unwrap();
+
+        let settings = encoder.settings().unwrap();
+        assert_eq!(settings.sample_rate, SampleRate::Hz48000);
+        assert_eq!(settings.application, Application::Audio);
+        assert_eq!(settings.bitrate, Bitrate::BitsPerSecond(32000));
+        assert_eq!(settings.complexity, 5);
+        assert!(settings.inband_fec);
+        assert_eq!(settings.lsb_depth, 16);
+        assert!(!settings.dtx);
+        assert_eq!(settings.frame_duration, Some(FrameDuration::Ms10));
+
+        let mut restored =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Voip).unwrap();
+        EncoderConfig::from(settings).apply(&mut restored).unwrap();
+        assert_eq!(restored.settings().unwrap(), settings);
+
+        for &bitrate in &[Bitrate::Auto, Bitrate::Max] {
+            encoder.set_bitrate(bitrate).unwrap();
+            encoder.encode_to_vec(&[0_i16; 960]).unwrap();
+
+            let settings = encoder.settings().unwrap();
+            assert_eq!(settings.bitrate, bitrate);
+
+            EncoderConfig::from(settings).apply(&mut restored).unwrap();
+            assert_eq!(restored.settings().unwrap().bitrate, bitrate);
+        }
+
+        let decoder = Decoder::new(SampleRate::Hz24000, Channels::Mono).unwrap();
+        decoder.set_gain(-256).unwrap();
+
+        let settings = decoder.settings().unwrap();
+        assert_eq!(settings.sample_rate, SampleRate::Hz24000);
+        assert_eq!(settings.gain, -256);
+        assert!(!settings.phase_inversion_disabled);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::ffi;
+
+/// A CTL request to issue to Opus via `raw_ctl`, e.g. [`Encoder::raw_ctl`].
+///
+/// Set-requests carry their argument, get-requests make `raw_ctl` return the
+/// requested value.
+/// [`CtlRequest::Other`] and [`CtlRequest::OtherGet`] allow issuing requests
+/// this crate does not know yet.
+///
+/// [`Encoder::raw_ctl`]: crate::coder::Encoder::raw_ctl
+/// [`CtlRequest::Other`]: crate::coder::CtlRequest::Other
+/// [`CtlRequest::OtherGet`]: crate::coder::CtlRequest::OtherGet
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum CtlRequest {
+    SetApplication(i32),
+    GetApplication,
+    SetBitrate(i32),
+    GetBitrate,
+    SetMaxBandwidth(i32),
+    GetMaxBandwidth,
+    SetVbr(i32),
+    GetVbr,
+    SetBandwidth(i32),
+    GetBandwidth,
+    SetComplexity(i32),
+    GetComplexity,
+    SetInbandFec(i32),
+    GetInbandFec,
+    SetPacketLossPerc(i32),
+    GetPacketLossPerc,
+    SetDtx(i32),
+    GetDtx,
+    SetVbrConstraint(i32),
+    GetVbrConstraint,
+    SetForceChannels(i32),
+    GetForceChannels,
+    SetSignal(i32),
+    GetSignal,
+    GetLookahead,
+    GetSampleRate,
+    GetFinalRange,
+    GetPitch,
+    SetGain(i32),
+    GetGain,
+    SetLsbDepth(i32),
+    GetLsbDepth,
+    GetLastPacketDuration,
+    SetExpertFrameDuration(i32),
+    GetExpertFrameDuration,
+    SetPredictionDisabled(i32),
+    GetPredictionDisabled,
+    SetPhaseInversionDisabled(i32),
+    GetPhaseInversionDisabled,
+    GetInDtx,
+    ResetState,
+    /// Any request by its ID, passing the second value as integer argument.
+    Other(i32, i32),
+    /// Any request by its ID, passing a pointer Opus writes the requested
+    /// integer value to.
+    OtherGet(i32),
+}
+
+impl CtlRequest {
+    /// Gets the request's ID as defined by Opus.
+    pub fn id(self) -> i32 {
+        match self {
+            CtlRequest::SetApplication(_) => ffi::OPUS_SET_APPLICATION_REQUEST,
+            CtlRequest::GetApplication => ffi::OPUS_GET_APPLICATION_REQUEST,
+            CtlRequest::SetBitrate(_) => ffi::OPUS_SET_BITRATE_REQUEST,
+            CtlRequest::GetBitrate => ffi::OPUS_GET_BITRATE_REQUEST,
+            CtlRequest::SetMaxBandwidth(_) => ffi::OPUS_SET_MAX_BANDWIDTH_REQUEST,
+            CtlRequest::GetMaxBandwidth => ffi::OPUS_GET_MAX_BANDWIDTH_REQUEST,
+            CtlRequest::SetVbr(_) => ffi::OPUS_SET_VBR_REQUEST,
+            CtlRequest::GetVbr => ffi::OPUS_GET_VBR_REQUEST,
+            CtlRequest::SetBandwidth(_) => ffi::OPUS_SET_BANDWIDTH_REQUEST,
+            CtlRequest::GetBandwidth => ffi::OPUS_GET_BANDWIDTH_REQUEST,
+            CtlRequest::SetComplexity(_) => ffi::OPUS_SET_COMPLEXITY_REQUEST,
+            CtlRequest::GetComplexity => ffi::OPUS_GET_COMPLEXITY_REQUEST,
+            CtlRequest::SetInbandFec(_) => ffi::OPUS_SET_INBAND_FEC_REQUEST,
+            CtlRequest::GetInbandFec => ffi::OPUS_GET_INBAND_FEC_REQUEST,
+            CtlRequest::SetPacketLossPerc(_) => ffi::OPUS_SET_PACKET_LOSS_PERC_REQUEST,
+            CtlRequest::GetPacketLossPerc => ffi::OPUS_GET_PACKET_LOSS_PERC_REQUEST,
+            CtlRequest::SetDtx(_) => ffi::OPUS_SET_DTX_REQUEST,
+            CtlRequest::GetDtx => ffi::OPUS_GET_DTX_REQUEST,
+            CtlRequest::SetVbrConstraint(_) => ffi::OPUS_SET_VBR_CONSTRAINT_REQUEST,
+            CtlRequest::GetVbrConstraint => ffi::OPUS_GET_VBR_CONSTRAINT_REQUEST,
+            CtlRequest::SetForceChannels(_) => ffi::OPUS_SET_FORCE_CHANNELS_REQUEST,
+            CtlRequest::GetForceChannels => ffi::OPUS_GET_FORCE_CHANNELS_REQUEST,
+            CtlRequest::SetSignal(_) => ffi::OPUS_SET_SIGNAL_REQUEST,
+            CtlRequest::GetSignal => ffi::OPUS_GET_SIGNAL_REQUEST,
+            CtlRequest::GetLookahead => ffi::OPUS_GET_LOOKAHEAD_REQUEST,
+            CtlRequest::GetSampleRate => ffi::OPUS_GET_SAMPLE_RATE_REQUEST,
+            CtlRequest::GetFinalRange => ffi::OPUS_GET_FINAL_RANGE_REQUEST,
+            CtlRequest::GetPitch => ffi::OPUS_GET_PITCH_REQUEST,
+            CtlRequest::SetGain(_) => ffi::OPUS_SET_GAIN_REQUEST,
+            CtlRequest::GetGain => ffi::OPUS_GET_GAIN_REQUEST,
+            CtlRequest::SetLsbDepth(_) => ffi::OPUS_SET_LSB_DEPTH_REQUEST,
+            CtlRequest::GetLsbDepth => ffi::OPUS_GET_LSB_DEPTH_REQUEST,
+            CtlRequest::GetLastPacketDuration => ffi::OPUS_GET_LAST_PACKET_DURATION_REQUEST,
+            CtlRequest::SetExpertFrameDuration(_) => ffi::OPUS_SET_EXPERT_FRAME_DURATION_REQUEST,
+            CtlRequest::GetExpertFrameDuration => ffi::OPUS_GET_EXPERT_FRAME_DURATION_REQUEST,
+            CtlRequest::SetPredictionDisabled(_) => ffi::OPUS_SET_PREDICTION_DISABLED_REQUEST,
+            CtlRequest::GetPredictionDisabled => ffi::OPUS_GET_PREDICTION_DISABLED_REQUEST,
+            CtlRequest::SetPhaseInversionDisabled(_) => {
+                ffi::OPUS_SET_PHASE_INVERSION_DISABLED_REQUEST
+            }
+            CtlRequest::GetPhaseInversionDisabled => ffi::OPUS_GET_PHASE_INVERSION_DISABLED_REQUEST,
+            CtlRequest::GetInDtx => ffi::OPUS_GET_IN_DTX_REQUEST,
+            CtlRequest::ResetState => ffi::OPUS_RESET_STATE,
+            CtlRequest::Other(id, _) | CtlRequest::OtherGet(id) => id,
+        }
+    }
+
+    /// Gets the integer argument passed along the request, `None` if Opus
+    /// expects a pointer to write the requested value to instead.
+    pub fn argument(self) -> Option<i32> {
+        match self {
+            CtlRequest::SetApplication(value)
+            | CtlRequest::SetBitrate(value)
+            | CtlRequest::SetMaxBandwidth(value)
+            | CtlRequest::SetVbr(value)
+            | CtlRequest::SetBandwidth(value)
+            | CtlRequest::SetComplexity(value)
+            | CtlRequest::SetInbandFec(value)
+            | CtlRequest::SetPacketLossPerc(value)
+            | CtlRequest::SetDtx(value)
+            | CtlRequest::SetVbrConstraint(value)
+            | CtlRequest::SetForceChannels(value)
+            | CtlRequest::SetSignal(value)
+            | CtlRequest::SetGain(value)
+            | CtlRequest::SetLsbDepth(value)
+            | CtlRequest::SetExpertFrameDuration(value)
+            | CtlRequest::SetPredictionDisabled(value)
+            | CtlRequest::SetPhaseInversionDisabled(value)
+            | CtlRequest::Other(_, value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CtlRequest;
+    use crate::{
+        coder::{Decoder, Encoder},
+        ffi, Application, Channels, SampleRate,
+    };
+    use matches::assert_matches;
+
+    #[test]
+    fn raw_requests() {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+
+        unsafe {
+            assert_matches!(encoder.raw_ctl(CtlRequest::SetBitrate(64_000)), Ok(_));
+            assert_matches!(encoder.raw_ctl(CtlRequest::GetBitrate), Ok(64_000));
+            assert_matches!(
+                encoder.raw_ctl(CtlRequest::Other(ffi::OPUS_SET_COMPLEXITY_REQUEST, 3)),
+                Ok(_)
+            );
+            assert_matches!(
+                encoder.raw_ctl(CtlRequest::OtherGet(ffi::OPUS_GET_COMPLEXITY_REQUEST)),
+                Ok(3)
+            );
+            assert_matches!(encoder.raw_ctl(CtlRequest::ResetState), Ok(_));
+        }
+        assert_matches!(encoder.complexity(), Ok(3));
+
+        let mut decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+
+        unsafe {
+            assert_matches!(decoder.raw_ctl(CtlRequest::SetGain(256)), Ok(_));
+            assert_matches!(decoder.raw_ctl(CtlRequest::GetGain), Ok(256));
+        }
+        assert_matches!(decoder.gain(), Ok(256));
+    }
+}
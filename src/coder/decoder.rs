@@ -1,15 +1,132 @@
-use super::GenericCtl;
+#[cfg(all(feature = "opus_1_5", opus_dred))]
+use super::Dred;
+use super::{CtlRequest, DecoderConfig, DecoderSettings, GenericCtl};
+#[cfg(feature = "opus_1_5")]
+use crate::Enhancement;
 use crate::{
-    error::try_map_opus_error, ffi, packet::Packet, Channels, ErrorCode, MutSignals, Result,
-    SampleRate,
+    backend::{self, OpusBackend, LINKED},
+    error::try_map_opus_error,
+    ffi,
+    packet::Packet,
+    Channels, Error, ErrorCode, MutSignals, Result, Sample, SampleRate,
 };
-use std::convert::TryFrom;
+use std::{
+    convert::TryFrom,
+    mem::{ManuallyDrop, MaybeUninit},
+    time::Duration,
+};
+
+/// A received packet to decode, or how to recover a lost one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum DecodeRequest<'a> {
+    /// Decodes a received packet.
+    Normal(Packet<'a>),
+    /// Conceals a lost packet lasting `duration`, which must be a multiple
+    /// of 2.5 ms.
+    ///
+    /// Without a `duration`, the loss lasts as long as the last packet
+    /// decoded or concealed. Before the first packet, the whole output is
+    /// concealed.
+    Lost { duration: Option<Duration> },
+    /// Recovers a lost packet from the forward error correction (FEC) data
+    /// carried by the packet received after it.
+    ///
+    /// The output must be sized to the lost packet's duration. If the packet
+    /// carries no FEC data, the loss is concealed instead.
+    /// The packet itself must be decoded via [`DecodeRequest::Normal`]
+    /// afterwards.
+    ///
+    /// [`DecodeRequest::Normal`]: crate::coder::DecodeRequest::Normal
+    Fec(Packet<'a>),
+}
+
+impl<'a> DecodeRequest<'a> {
+    /// Gets the packet to pass to Opus, `None` for a concealed loss.
+    pub(crate) fn packet(self) -> Option<Packet<'a>> {
+        match self {
+            DecodeRequest::Normal(packet) | DecodeRequest::Fec(packet) => Some(packet),
+            DecodeRequest::Lost { .. } => None,
+        }
+    }
+
+    /// Whether Opus shall decode the packet's FEC data.
+    pub(crate) fn fec(self) -> bool {
+        matches!(self, DecodeRequest::Fec(_))
+    }
+
+    /// Gets the number of samples per channel to decode into an `output` of
+    /// `output_len` samples interleaving `channels`.
+    ///
+    /// The coder's `sample_rate` and `last_packet_duration` are only queried
+    /// for lost packets, see `lost_samples`.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::SignalsExpectedLen`] if `output_len` cannot hold the
+    /// duration of a lost packet.
+    ///
+    /// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+    pub(crate) fn frame_size(
+        self,
+        channels: usize,
+        output_len: i32,
+        sample_rate: impl FnOnce() -> Result<SampleRate>,
+        last_packet_duration: impl FnOnce() -> Result<u32>,
+    ) -> Result<i32> {
+        match self.lost_samples(sample_rate, last_packet_duration)? {
+            None => Ok(output_len / channels as i32),
+            Some(samples) => {
+                let samples = samples.min(i32::MAX as usize);
+
+                if samples.saturating_mul(channels) > output_len as usize {
+                    return Err(Error::SignalsExpectedLen(samples.saturating_mul(channels)));
+                }
+
+                Ok(samples as i32)
+            }
+        }
+    }
+
+    /// Gets the samples per channel a lost packet lasts, its `duration` at
+    /// the coder's `sample_rate` or else the coder's `last_packet_duration`.
+    ///
+    /// Returns `None` for received packets and if no packet preceded the
+    /// loss.
+    pub(crate) fn lost_samples(
+        self,
+        sample_rate: impl FnOnce() -> Result<SampleRate>,
+        last_packet_duration: impl FnOnce() -> Result<u32>,
+    ) -> Result<Option<usize>> {
+        match self {
+            DecodeRequest::Normal(_) | DecodeRequest::Fec(_) => Ok(None),
+            DecodeRequest::Lost {
+                duration: Some(duration),
+            } => Ok(Some(sample_rate()?.samples_in(duration))),
+            DecodeRequest::Lost { duration: None } => {
+                last_packet_duration().map(|last| Some(last as usize).filter(|&last| last > 0))
+            }
+        }
+    }
+}
+
+/// The duration [`Decoder::decode_to_vec`] conceals for a
+/// [`DecodeRequest::Lost`] without duration before the first packet.
+///
+/// [`Decoder::decode_to_vec`]: crate::coder::Decoder::decode_to_vec
+/// [`DecodeRequest::Lost`]: crate::coder::DecodeRequest::Lost
+const DEFAULT_LOSS: Duration = Duration::from_millis(20);
+
+/// The maximum gain adjustment in dB accepted by [`Decoder::set_gain_db`].
+///
+/// [`Decoder::set_gain_db`]: crate::coder::Decoder::set_gain_db
+const MAX_GAIN_DB: f32 = 32.0;
 
 /// `Decoder` to decode.
 #[derive(Debug)]
 pub struct Decoder {
     pointer: *mut ffi::OpusDecoder,
     channels: Channels,
+    /// The Opus implementation `pointer` stems from.
+    backend: &'static dyn OpusBackend,
 }
 
 /// The Opus decoder can be sent between threads unless the Opus library
@@ -52,49 +169,316 @@ impl Decoder {
     pub fn new(sample_rate: SampleRate, channels: Channels) -> Result<Decoder> {
         let mut opus_code = 0;
 
-        let pointer = unsafe {
-            ffi::opus_decoder_create(sample_rate as i32, channels as i32, &mut opus_code)
-        };
+        let backend = backend::current();
+
+        let pointer =
+            unsafe { backend.decoder_create(sample_rate as i32, channels as i32, &mut opus_code) };
 
         if opus_code == ffi::OPUS_OK || !pointer.is_null() {
-            return Ok(Decoder { pointer, channels });
+            return Ok(Decoder {
+                pointer,
+                channels,
+                backend,
+            });
         }
 
         Err(ErrorCode::from(opus_code).into())
     }
 
-    /// Decodes an Opus packet as `input` and writes decoded data into `output`.
-    /// Passing `None` as `input` indicates a packet loss.
+    /// Wraps an existing Opus decoder state, e.g. one returned by
+    /// [`into_raw`].
+    ///
+    /// **Safety**:
+    /// `pointer` must point to a valid Opus decoder coding `channels`,
+    /// created by the Opus linked at build time.
+    /// The returned `Decoder` takes ownership and destroys the state via
+    /// `opus_decoder_destroy`, unless it is prevented from being dropped,
+    /// e.g. via [`ManuallyDrop`].
+    ///
+    /// [`into_raw`]: struct.Decoder.html#method.into_raw
+    /// [`ManuallyDrop`]: std::mem::ManuallyDrop
+    pub unsafe fn from_raw(pointer: *mut ffi::OpusDecoder, channels: Channels) -> Self {
+        Self::from_raw_with(pointer, channels, &LINKED)
+    }
+
+    /// Wraps an existing Opus decoder state created by `backend`.
+    ///
+    /// **Safety**:
+    /// Same as [`from_raw`], for states of `backend` instead of the linked
+    /// Opus.
+    ///
+    /// [`from_raw`]: struct.Decoder.html#method.from_raw
+    pub(crate) unsafe fn from_raw_with(
+        pointer: *mut ffi::OpusDecoder,
+        channels: Channels,
+        backend: &'static dyn OpusBackend,
+    ) -> Self {
+        Decoder {
+            pointer,
+            channels,
+            backend,
+        }
+    }
+
+    /// Releases ownership of the Opus decoder state, e.g. to hand it to
+    /// another C library.
+    ///
+    /// The state is no longer destroyed, reclaim it via [`from_raw`].
+    ///
+    /// **Errors**:
+    /// Returns [`ErrorCode::Unimplemented`] if the decoder runs on an Opus
+    /// loaded at runtime via the `dlopen`-feature, as [`from_raw`] can only
+    /// wrap states of the linked Opus. The decoder is destroyed in that case.
+    ///
+    /// [`from_raw`]: struct.Decoder.html#method.from_raw
+    /// [`ErrorCode::Unimplemented`]: crate::error::ErrorCode::Unimplemented
+    pub fn into_raw(self) -> Result<*mut ffi::OpusDecoder> {
+        if !self.backend.is_linked() {
+            return Err(Error::Opus(ErrorCode::Unimplemented));
+        }
+
+        Ok(ManuallyDrop::new(self).pointer)
+    }
+
+    /// Gets a pointer to the Opus decoder state.
+    pub fn as_ptr(&self) -> *const ffi::OpusDecoder {
+        self.pointer
+    }
+
+    /// Gets a mutable pointer to the Opus decoder state.
+    pub fn as_mut_ptr(&mut self) -> *mut ffi::OpusDecoder {
+        self.pointer
+    }
+
+    /// Gets the channels the decoder outputs.
+    pub fn channels(&self) -> Channels {
+        self.channels
+    }
+
+    /// Re-initialises the decoder in place as if constructed via
+    /// [`Decoder::new`], without reallocating its state.
+    ///
+    /// Every setting is reset to its default.
     ///
     /// **Errors**:
-    /// Returns [Error::Opus] when Opus encountered a problem.
+    /// Returns [`Error::InvalidChannels`] if `channels` need a larger state
+    /// than the current channels, e.g. stereo after mono, and [Error::Opus]
+    /// when Opus encountered a problem.
     ///
+    /// [`Decoder::new`]: struct.Decoder.html#method.new
+    /// [`Error::InvalidChannels`]: crate::error::Error::InvalidChannels
     /// [Error::Opus]: crate::error::Error::Opus
-    pub fn decode(
+    pub fn reinit(&mut self, sample_rate: SampleRate, channels: Channels) -> Result<()> {
+        let size = unsafe { self.backend.decoder_get_size(channels as i32) as usize };
+
+        if size > self.size() {
+            return Err(Error::InvalidChannels(channels as i32));
+        }
+
+        try_map_opus_error(unsafe {
+            self.backend
+                .decoder_init(self.pointer, sample_rate as i32, channels as i32)
+        })?;
+        self.channels = channels;
+
+        Ok(())
+    }
+
+    /// Decodes the packet or recovers the loss described by `request`,
+    /// writing the decoded data into `output`.
+    ///
+    /// On success, returns the decoded samples per channel.
+    /// The samples are either `i16` or `f32`, see [`Sample`].
+    ///
+    /// **Errors**:
+    /// Returns [`Error::SignalsExpectedLen`] if `output` cannot hold the
+    /// duration of a [`DecodeRequest::Lost`] and [Error::Opus] when Opus
+    /// encountered a problem.
+    ///
+    /// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+    /// [`DecodeRequest::Lost`]: crate::coder::DecodeRequest::Lost
+    /// [Error::Opus]: crate::error::Error::Opus
+    /// [`Sample`]: crate::sample::Sample
+    pub fn decode<S: Sample>(
         &mut self,
-        input: Option<Packet<'_>>,
-        mut output: MutSignals<'_, i16>,
-        fec: bool,
+        request: DecodeRequest<'_>,
+        mut output: MutSignals<'_, S>,
     ) -> Result<usize> {
-        let (input_pointer, input_len) = if let Some(value) = input {
-            (value.as_ptr(), value.i32_len())
-        } else {
-            (std::ptr::null(), 0)
-        };
+        let frame_size = request.frame_size(
+            self.channels as usize,
+            output.i32_len(),
+            || self.sample_rate(),
+            || self.last_packet_duration(),
+        )?;
+
+        unsafe {
+            self.decode_raw(
+                request.packet(),
+                output.as_mut_ptr(),
+                frame_size,
+                request.fec(),
+            )
+        }
+    }
+
+    /// Recovers audio lost `offset` samples before the packet `dred` was
+    /// parsed from, writing it into `output`.
+    ///
+    /// On success, returns the recovered samples per channel.
+    ///
+    /// **Errors**:
+    /// Returns [`ErrorCode::Unimplemented`] if the decoder does not run on
+    /// the Opus linked at build time and [Error::Opus] when Opus encountered
+    /// a problem.
+    ///
+    /// [`ErrorCode::Unimplemented`]: crate::error::ErrorCode::Unimplemented
+    /// [Error::Opus]: crate::error::Error::Opus
+    #[cfg(all(feature = "opus_1_5", opus_dred))]
+    pub fn decode_dred<S: Sample>(
+        &mut self,
+        dred: &Dred,
+        offset: u32,
+        mut output: MutSignals<'_, S>,
+    ) -> Result<usize> {
+        if !self.backend.is_linked() {
+            return Err(Error::Opus(ErrorCode::Unimplemented));
+        }
 
         try_map_opus_error(unsafe {
-            ffi::opus_decode(
+            S::dred_decode(
                 self.pointer,
-                input_pointer,
-                input_len,
+                dred.as_ptr(),
+                offset.min(i32::MAX as u32) as i32,
                 output.as_mut_ptr(),
                 output.i32_len() / self.channels as i32,
-                fec as i32,
             )
         })
         .map(|n| n as usize)
     }
 
+    /// Decodes the packet or recovers the loss described by `request` into
+    /// possibly uninitialised `output`, sparing to initialise the buffer
+    /// beforehand.
+    ///
+    /// On success, returns the initialised part of `output` holding the
+    /// decoded samples.
+    ///
+    /// **Errors**:
+    /// Returns [Error::SignalsTooLarge] if `output`'s length exceeds
+    /// `std::i32::MAX`, [`Error::SignalsExpectedLen`] if it cannot hold the
+    /// duration of a [`DecodeRequest::Lost`], and [Error::Opus] when Opus
+    /// encountered a problem.
+    ///
+    /// [Error::SignalsTooLarge]: crate::error::Error::SignalsTooLarge
+    /// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+    /// [`DecodeRequest::Lost`]: crate::coder::DecodeRequest::Lost
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn decode_uninit<'a, S: Sample>(
+        &mut self,
+        request: DecodeRequest<'_>,
+        output: &'a mut [MaybeUninit<S>],
+    ) -> Result<&'a mut [S]> {
+        if output.len() > std::i32::MAX as usize {
+            return Err(Error::SignalsTooLarge);
+        }
+
+        let frame_size = request.frame_size(
+            self.channels as usize,
+            output.len() as i32,
+            || self.sample_rate(),
+            || self.last_packet_duration(),
+        )?;
+        let output_pointer = output.as_mut_ptr() as *mut S;
+
+        let samples = unsafe {
+            self.decode_raw(request.packet(), output_pointer, frame_size, request.fec())?
+        };
+
+        // Opus initialised the decoded samples at the start of `output`.
+        Ok(unsafe {
+            std::slice::from_raw_parts_mut(output_pointer, samples * self.channels as usize)
+        })
+    }
+
+    /// Decodes the packet or recovers the loss described by `request` into
+    /// a newly allocated buffer sized to hold all decoded samples.
+    ///
+    /// Recovering via [`DecodeRequest::Fec`] assumes the lost packet lasted
+    /// as long as the packet carrying its FEC data. A [`DecodeRequest::Lost`]
+    /// without duration before the first packet is concealed for 20 ms.
+    ///
+    /// The samples are either `i16` or `f32`, see [`Sample`].
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`DecodeRequest::Fec`]: crate::coder::DecodeRequest::Fec
+    /// [`DecodeRequest::Lost`]: crate::coder::DecodeRequest::Lost
+    /// [Error::Opus]: crate::error::Error::Opus
+    /// [`Sample`]: crate::sample::Sample
+    pub fn decode_to_vec<S: Sample>(&mut self, request: DecodeRequest<'_>) -> Result<Vec<S>> {
+        let sample_rate = self.sample_rate()?;
+
+        let samples = match request {
+            DecodeRequest::Normal(packet) | DecodeRequest::Fec(packet) => {
+                packet.nb_samples(sample_rate)?
+            }
+            DecodeRequest::Lost { .. } => request
+                .lost_samples(|| Ok(sample_rate), || self.last_packet_duration())?
+                .unwrap_or_else(|| sample_rate.samples_in(DEFAULT_LOSS)),
+        };
+
+        let channels = self.channels as usize;
+        let mut output = vec![S::default(); samples * channels];
+        let len = self.decode(request, MutSignals::try_from(&mut output)?)?;
+        output.truncate(len * channels);
+
+        Ok(output)
+    }
+
+    /// Conceals a burst of lost packets lasting `duration` via packet loss
+    /// concealment (PLC), returning the concealed audio.
+    ///
+    /// The duration is rounded down to a multiple of 2.5 ms and concealed in
+    /// frames lasting as long as the last packet, at most 120 ms.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn conceal<S: Sample>(&mut self, duration: Duration) -> Result<Vec<S>> {
+        // Opus conceals multiples of 2.5 ms, up to 120 ms per call.
+        let sample_rate = self.sample_rate()?;
+        let step = sample_rate as usize / 400;
+        let max_frame_size = match self.last_packet_duration()? as usize / step * step {
+            0 => step * 48,
+            last => last.min(step * 48),
+        };
+        let samples = sample_rate.samples_in(duration) / step * step;
+
+        let channels = self.channels as usize;
+        let mut output = vec![S::default(); samples * channels];
+        let mut concealed = 0;
+
+        while concealed < samples {
+            let frame_size = (samples - concealed).min(max_frame_size);
+            let frame = &mut output[concealed * channels..(concealed + frame_size) * channels];
+
+            let decoded =
+                unsafe { self.decode_raw(None, frame.as_mut_ptr(), frame_size as i32, false)? };
+
+            if decoded == 0 {
+                break;
+            }
+
+            concealed += decoded;
+        }
+
+        output.truncate(concealed * channels);
+
+        Ok(output)
+    }
+
     /// Decodes an Opus frame from floating point input.
     ///
     /// The `input` signal (interleaved if 2 channels) will be encoded into the
@@ -105,11 +489,29 @@ impl Decoder {
     /// Returns [Error::Opus] when Opus encountered a problem.
     ///
     /// [Error::Opus]: crate::error::Error::Opus
+    #[deprecated(note = "use `decode` instead, it accepts `f32` output")]
     pub fn decode_float(
         &mut self,
         input: Option<Packet<'_>>,
         mut output: MutSignals<'_, f32>,
         fec: bool,
+    ) -> Result<usize> {
+        let frame_size = output.i32_len() / self.channels as i32;
+
+        unsafe { self.decode_raw(input, output.as_mut_ptr(), frame_size, fec) }
+    }
+
+    /// Decodes `input` into `frame_size` samples per channel at `output`,
+    /// `None` conceals a lost packet.
+    ///
+    /// **Safety**:
+    /// `output` must be valid for writing `frame_size` samples per channel.
+    unsafe fn decode_raw<S: Sample>(
+        &mut self,
+        input: Option<Packet<'_>>,
+        output: *mut S,
+        frame_size: i32,
+        fec: bool,
     ) -> Result<usize> {
         let (input_pointer, input_len) = if let Some(value) = input {
             (value.as_ptr(), value.i32_len())
@@ -117,16 +519,15 @@ impl Decoder {
             (std::ptr::null(), 0)
         };
 
-        try_map_opus_error(unsafe {
-            ffi::opus_decode_float(
-                self.pointer,
-                input_pointer,
-                input_len,
-                output.as_mut_ptr(),
-                output.i32_len() / self.channels as i32,
-                fec as i32,
-            )
-        })
+        try_map_opus_error(S::decode(
+            self.backend,
+            self.pointer,
+            input_pointer,
+            input_len,
+            output,
+            frame_size,
+            fec as i32,
+        ))
         .map(|n| n as usize)
     }
 
@@ -138,7 +539,7 @@ impl Decoder {
     /// [Error::Opus]: crate::error::Error::Opus
     pub fn nb_samples(&self, input: Packet<'_>) -> Result<usize> {
         unsafe {
-            try_map_opus_error(ffi::opus_decoder_get_nb_samples(
+            try_map_opus_error(self.backend.decoder_get_nb_samples(
                 self.pointer,
                 input.as_ptr(),
                 input.i32_len(),
@@ -158,7 +559,10 @@ impl Decoder {
     fn decoder_ctl_request(&self, request: i32) -> Result<i32> {
         let mut value = 0;
 
-        let ffi_result = unsafe { ffi::opus_decoder_ctl(self.pointer, request, &mut value) };
+        let ffi_result = unsafe {
+            self.backend
+                .decoder_ctl_get(self.pointer, request, &mut value)
+        };
 
         try_map_opus_error(ffi_result)?;
 
@@ -174,11 +578,44 @@ impl Decoder {
     ///
     /// [Error::Opus]: crate::error::Error::Opus
     fn set_decoder_ctl_request(&self, request: i32, value: i32) -> Result<()> {
-        try_map_opus_error(unsafe { ffi::opus_decoder_ctl(self.pointer, request, value) })?;
+        try_map_opus_error(unsafe { self.backend.decoder_ctl(self.pointer, request, value) })?;
 
         Ok(())
     }
 
+    /// Issues a typed CTL `request` to Opus, e.g. a request this crate does
+    /// not wrap yet.
+    /// On success, returns the requested value for get-requests, otherwise
+    /// zero.
+    ///
+    /// **Safety**:
+    /// Opus must accept the argument of [`CtlRequest::Other`] as integer,
+    /// passing it to a request expecting a pointer is undefined behaviour.
+    /// Likewise, Opus must write a single integer to the pointer
+    /// [`CtlRequest::OtherGet`] passes.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`CtlRequest::Other`]: crate::coder::CtlRequest::Other
+    /// [`CtlRequest::OtherGet`]: crate::coder::CtlRequest::OtherGet
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub unsafe fn raw_ctl(&mut self, request: CtlRequest) -> Result<i32> {
+        let mut value = 0;
+
+        let ffi_result = match request.argument() {
+            Some(argument) => self
+                .backend
+                .decoder_ctl(self.pointer, request.id(), argument),
+            None => self
+                .backend
+                .decoder_ctl_get(self.pointer, request.id(), &mut value),
+        };
+        try_map_opus_error(ffi_result)?;
+
+        Ok(value)
+    }
+
     /// Gets the duration (in samples) of the last packet successfully decoded
     /// or concealed.
     pub fn last_packet_duration(&self) -> Result<u32> {
@@ -220,30 +657,430 @@ impl Decoder {
         self.set_decoder_ctl_request(ffi::OPUS_SET_GAIN_REQUEST, gain)
     }
 
+    /// Configures the decoder's computational complexity from `0` to `10`.
+    ///
+    /// Opus 1.5 gates neural post-processing by the complexity, see
+    /// [`Enhancement`].
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidComplexity`] if `complexity` exceeds `10` and
+    /// [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`Enhancement`]: crate::Enhancement
+    /// [`Error::InvalidComplexity`]: crate::error::Error::InvalidComplexity
+    /// [Error::Opus]: crate::error::Error::Opus
+    #[cfg(feature = "opus_1_5")]
+    pub fn set_complexity(&mut self, complexity: u8) -> Result<()> {
+        if complexity > 10 {
+            return Err(Error::InvalidComplexity(i32::from(complexity)));
+        }
+
+        self.set_decoder_ctl_request(ffi::OPUS_SET_COMPLEXITY_REQUEST, i32::from(complexity))
+    }
+
+    /// Gets the decoder's configured computational complexity.
+    #[cfg(feature = "opus_1_5")]
+    pub fn complexity(&self) -> Result<u8> {
+        self.decoder_ctl_request(ffi::OPUS_GET_COMPLEXITY_REQUEST)
+            .map(|n| n as u8)
+    }
+
+    /// Enables `enhancement` and every less demanding one, or forbids
+    /// neural post-processing via [`Enhancement::None`], by configuring the
+    /// decoder's complexity.
+    ///
+    /// [`Enhancement::None`]: crate::Enhancement::None
+    #[cfg(feature = "opus_1_5")]
+    pub fn set_enhancement(&mut self, enhancement: Enhancement) -> Result<()> {
+        self.set_complexity(enhancement.complexity())
+    }
+
+    /// Gets the neural post-processing the decoder's complexity enables.
+    #[cfg(feature = "opus_1_5")]
+    pub fn enhancement(&self) -> Result<Enhancement> {
+        self.complexity().map(Enhancement::from_complexity)
+    }
+
+    /// Gets the decoder's configured gain in dB.
+    pub fn gain_db(&self) -> Result<f32> {
+        self.gain().map(|gain| gain as f32 / 256.0)
+    }
+
+    /// Configures decoder gain adjustment in dB, converted to the Q8 dB
+    /// units of [`set_gain`].
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidGain`] if `gain` is not between -32 and 32 dB
+    /// inclusive.
+    ///
+    /// [`set_gain`]: Decoder::set_gain
+    /// [`Error::InvalidGain`]: crate::error::Error::InvalidGain
+    pub fn set_gain_db(&self, gain: f32) -> Result<()> {
+        let q8_gain = (gain * 256.0).round() as i32;
+
+        if !(-MAX_GAIN_DB..=MAX_GAIN_DB).contains(&gain) {
+            return Err(Error::InvalidGain(q8_gain));
+        }
+
+        self.set_gain(q8_gain)
+    }
+
     /// Gets size of self's underlying Opus-decoder in bytes.
     pub fn size(&self) -> usize {
-        unsafe { ffi::opus_decoder_get_size(self.channels as i32) as usize }
+        unsafe { self.backend.decoder_get_size(self.channels as i32) as usize }
+    }
+
+    /// Gets a snapshot of the decoder's settings, e.g. for logging or to
+    /// restore them later via [`DecoderConfig`].
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`DecoderConfig`]: crate::coder::DecoderConfig
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn settings(&self) -> Result<DecoderSettings> {
+        Ok(DecoderSettings {
+            sample_rate: self.sample_rate()?,
+            gain: self.gain()?,
+            phase_inversion_disabled: self.phase_inversion_disabled()?,
+        })
+    }
+
+    /// Resets the decoder's state as after construction, e.g. after
+    /// seeking, while keeping its configuration.
+    ///
+    /// If `settings` are passed, they are applied after resetting,
+    /// e.g. a snapshot taken earlier via [`settings`].
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`settings`]: struct.Decoder.html#method.settings
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn reset(&mut self, settings: Option<DecoderSettings>) -> Result<()> {
+        self.reset_state()?;
+
+        match settings {
+            Some(settings) => DecoderConfig::from(settings).apply(self),
+            None => Ok(()),
+        }
     }
 }
 
-/// Gets size of an Opus-decoder in bytes.
+/// Gets size of an Opus-decoder in bytes, as created by [`Decoder::new`].
+///
+/// [`Decoder::new`]: struct.Decoder.html#method.new
 pub fn size(channels: Channels) -> usize {
-    unsafe { ffi::opus_decoder_get_size(channels as i32) as usize }
+    unsafe { backend::current().decoder_get_size(channels as i32) as usize }
 }
 
 impl Drop for Decoder {
     /// We have to ensure that the resource our wrapping Opus-struct is pointing
     /// to is deallocated properly.
     fn drop(&mut self) {
-        unsafe { ffi::opus_decoder_destroy(self.pointer) }
+        unsafe { self.backend.decoder_destroy(self.pointer) }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Decoder;
-    use crate::{Channels, Error, ErrorCode, SampleRate};
+    use super::{DecodeRequest, Decoder};
+    use crate::{
+        coder::{Encoder, GenericCtl},
+        packet::Packet,
+        Application, Channels, Error, ErrorCode, MutSignals, SampleRate,
+    };
     use matches::assert_matches;
+    use std::{convert::TryFrom, mem::MaybeUninit, time::Duration};
+
+    #[test]
+    fn decode_to_vec() {
+        let mut decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+
+        // A 20 ms stereo packet of silence.
+        let packet = [252_u8, 255, 254];
+
+        let output: Vec<i16> = decoder
+            .decode_to_vec(DecodeRequest::Normal(
+                Packet::try_from(&packet[..]).unwrap(),
+            ))
+            .unwrap();
+        assert_eq!(output.len(), 960 * 2);
+
+        let output: Vec<f32> = decoder
+            .decode_to_vec(DecodeRequest::Lost {
+                duration: Some(Duration::from_millis(20)),
+            })
+            .unwrap();
+        assert_eq!(output.len(), 960 * 2);
+
+        let mut decoder = Decoder::new(SampleRate::Hz8000, Channels::Mono).unwrap();
+        let output: Vec<i16> = decoder
+            .decode_to_vec(DecodeRequest::Normal(
+                Packet::try_from(&packet[..]).unwrap(),
+            ))
+            .unwrap();
+        assert_eq!(output.len(), 160);
+    }
+
+    #[test]
+    fn decode_uninit() {
+        let mut decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+
+        // A 20 ms stereo packet of silence.
+        let packet = [252_u8, 255, 254];
+        let mut output = [MaybeUninit::<i16>::uninit(); 4096];
+
+        let decoded = decoder
+            .decode_uninit(
+                DecodeRequest::Normal(Packet::try_from(&packet[..]).unwrap()),
+                &mut output,
+            )
+            .unwrap();
+        assert_eq!(decoded.len(), 960 * 2);
+        assert!(decoded.iter().all(|&sample| sample == 0));
+    }
+
+    #[test]
+    fn conceal_requested_duration() {
+        let mut decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+
+        // A 20 ms stereo packet of silence.
+        let packet = [252_u8, 255, 254];
+        let mut output = vec![0_i16; 5760 * 2];
+
+        let decoded = decoder
+            .decode(
+                DecodeRequest::Normal(Packet::try_from(&packet[..]).unwrap()),
+                MutSignals::try_from(&mut output).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(decoded, 960);
+
+        let concealed = decoder
+            .decode(
+                DecodeRequest::Lost {
+                    duration: Some(Duration::from_millis(10)),
+                },
+                MutSignals::try_from(&mut output).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(concealed, 480);
+
+        assert_matches!(
+            decoder.decode(
+                DecodeRequest::Lost {
+                    duration: Some(Duration::from_millis(120)),
+                },
+                MutSignals::try_from(&mut output[..960]).unwrap(),
+            ),
+            Err(Error::SignalsExpectedLen(11520))
+        );
+    }
+
+    #[test]
+    fn conceal_last_packet_duration() {
+        let mut decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+        assert_matches!(decoder.last_packet_duration(), Ok(0));
+
+        let mut output = vec![0_i16; 5760 * 2];
+        let lost = DecodeRequest::Lost { duration: None };
+
+        // Without a preceding packet, the whole output is concealed.
+        let concealed = decoder
+            .decode(lost, MutSignals::try_from(&mut output[..1920]).unwrap())
+            .unwrap();
+        assert_eq!(concealed, 960);
+
+        // A 10 ms stereo packet of silence.
+        let packet = [244_u8, 255, 254];
+
+        let decoded = decoder
+            .decode(
+                DecodeRequest::Normal(Packet::try_from(&packet[..]).unwrap()),
+                MutSignals::try_from(&mut output).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(decoded, 480);
+        assert_matches!(decoder.last_packet_duration(), Ok(480));
+
+        let concealed = decoder
+            .decode(lost, MutSignals::try_from(&mut output).unwrap())
+            .unwrap();
+        assert_eq!(concealed, 480);
+
+        let concealed: Vec<i16> = decoder.decode_to_vec(lost).unwrap();
+        assert_eq!(concealed.len(), 480 * 2);
+    }
+
+    #[test]
+    fn conceal_burst() {
+        let mut decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+
+        let concealed: Vec<i16> = decoder.conceal(Duration::from_millis(250)).unwrap();
+        assert_eq!(concealed.len(), 12000 * 2);
+
+        let concealed: Vec<f32> = decoder.conceal(Duration::from_micros(3500)).unwrap();
+        assert_eq!(concealed.len(), 120 * 2);
+
+        let concealed: Vec<i16> = decoder.conceal(Duration::from_millis(1)).unwrap();
+        assert!(concealed.is_empty());
+
+        // A 10 ms stereo packet of silence.
+        let packet = [244_u8, 255, 254];
+        decoder
+            .decode_to_vec::<i16>(DecodeRequest::Normal(
+                Packet::try_from(&packet[..]).unwrap(),
+            ))
+            .unwrap();
+
+        // Concealed in frames as long as the last packet.
+        let concealed: Vec<i16> = decoder.conceal(Duration::from_millis(20)).unwrap();
+        assert_eq!(concealed.len(), 960 * 2);
+        assert_matches!(decoder.last_packet_duration(), Ok(480));
+    }
+
+    #[test]
+    fn recover_from_fec() {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz16000, Channels::Mono, Application::Voip).unwrap();
+        encoder.set_inband_fec(true).unwrap();
+        encoder.set_packet_loss_perc(20).unwrap();
+        let mut decoder = Decoder::new(SampleRate::Hz16000, Channels::Mono).unwrap();
+
+        let input: Vec<i16> = (0..320)
+            .map(|i| ((i as f32 * 0.1).sin() * 8000.0) as i16)
+            .collect();
+        let mut packet = [0; 1275];
+        let len = encoder.encode(&input, &mut packet).unwrap();
+        let packet = Packet::try_from(&packet[..len]).unwrap();
+
+        let mut output = [0_i16; 320];
+        assert_matches!(
+            decoder.decode(
+                DecodeRequest::Fec(packet),
+                MutSignals::try_from(&mut output[..]).unwrap()
+            ),
+            Ok(320)
+        );
+        assert_matches!(
+            decoder.decode(
+                DecodeRequest::Normal(packet),
+                MutSignals::try_from(&mut output[..]).unwrap()
+            ),
+            Ok(320)
+        );
+    }
+
+    #[test]
+    fn reset_keeps_configuration() {
+        let mut decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+        decoder.set_gain(512).unwrap();
+        let settings = decoder.settings().unwrap();
+
+        let packet = [252_u8, 255, 254];
+        let _: Vec<i16> = decoder
+            .decode_to_vec(DecodeRequest::Normal(
+                Packet::try_from(&packet[..]).unwrap(),
+            ))
+            .unwrap();
+
+        decoder.reset(None).unwrap();
+        assert_matches!(decoder.gain(), Ok(512));
+        assert_matches!(decoder.last_packet_duration(), Ok(0));
+
+        decoder.set_gain(0).unwrap();
+        decoder.reset(Some(settings)).unwrap();
+        assert_matches!(decoder.gain(), Ok(512));
+    }
+
+    #[test]
+    fn reinit_in_place() {
+        let mut decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+        decoder.set_gain(512).unwrap();
+
+        decoder.reinit(SampleRate::Hz8000, Channels::Mono).unwrap();
+        assert_matches!(decoder.sample_rate(), Ok(SampleRate::Hz8000));
+        assert_matches!(decoder.gain(), Ok(0));
+
+        // A 20 ms stereo packet of silence, decoded as mono.
+        let packet = [252_u8, 255, 254];
+        let output: Vec<i16> = decoder
+            .decode_to_vec(DecodeRequest::Normal(
+                Packet::try_from(&packet[..]).unwrap(),
+            ))
+            .unwrap();
+        assert_eq!(output.len(), 160);
+
+        assert_matches!(
+            decoder.reinit(SampleRate::Hz48000, Channels::Stereo),
+            Err(Error::InvalidChannels(2))
+        );
+    }
+
+    #[test]
+    fn raw_handle_round_trip() {
+        let decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+        decoder.set_gain(512).unwrap();
+
+        let raw = decoder.into_raw().unwrap();
+        let mut decoder = unsafe { Decoder::from_raw(raw, Channels::Stereo) };
+        assert_eq!(decoder.as_mut_ptr(), raw);
+        assert_matches!(decoder.gain(), Ok(512));
+    }
+
+    #[test]
+    fn get_pitch() {
+        let mut decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+        assert_matches!(decoder.pitch(), Ok(0));
+
+        // Silence is not voiced, hence has no pitch.
+        let packet = [252_u8, 255, 254];
+        let _: Vec<i16> = decoder
+            .decode_to_vec(DecodeRequest::Normal(
+                Packet::try_from(&packet[..]).unwrap(),
+            ))
+            .unwrap();
+        assert_matches!(decoder.pitch(), Ok(0));
+    }
+
+    #[test]
+    fn set_get_phase_inversion_disabled() {
+        let mut decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+
+        assert_matches!(decoder.phase_inversion_disabled(), Ok(false));
+
+        decoder
+            .set_phase_inversion_disabled(true)
+            .expect("Could not disable phase inversion.");
+        assert_matches!(decoder.phase_inversion_disabled(), Ok(true));
+
+        decoder
+            .set_phase_inversion_disabled(false)
+            .expect("Could not enable phase inversion.");
+        assert_matches!(decoder.phase_inversion_disabled(), Ok(false));
+    }
+
+    #[test]
+    fn set_and_get_gain_db() {
+        let decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+
+        assert_matches!(decoder.set_gain_db(-6.0), Ok(()));
+        assert_matches!(decoder.gain(), Ok(-1536));
+        assert!((decoder.gain_db().unwrap() + 6.0).abs() < std::f32::EPSILON);
+
+        assert_matches!(decoder.set_gain_db(1.5), Ok(()));
+        assert_matches!(decoder.gain(), Ok(384));
+
+        assert_matches!(decoder.set_gain_db(32.0), Ok(()));
+        assert_matches!(decoder.set_gain_db(32.5), Err(Error::InvalidGain(8320)));
+        assert_matches!(decoder.set_gain_db(-40.0), Err(Error::InvalidGain(-10240)));
+        assert_matches!(
+            decoder.set_gain_db(std::f32::NAN),
+            Err(Error::InvalidGain(_))
+        );
+        assert_matches!(decoder.gain(), Ok(8192));
+    }
 
     #[test]
     fn set_and_get_gain() {
@@ -270,4 +1107,24 @@ mod tests {
             Err(Error::Opus(ErrorCode::BadArgument))
         );
     }
+
+    #[cfg(all(feature = "opus_1_5", opus_dred))]
+    #[test]
+    fn set_get_enhancement() {
+        use crate::Enhancement;
+
+        let mut decoder = Decoder::new(SampleRate::Hz16000, Channels::Mono).unwrap();
+
+        assert_matches!(decoder.set_enhancement(Enhancement::Lace), Ok(()));
+        assert_matches!(decoder.complexity(), Ok(6));
+        assert_matches!(decoder.enhancement(), Ok(Enhancement::Lace));
+
+        assert_matches!(decoder.set_enhancement(Enhancement::None), Ok(()));
+        assert_matches!(decoder.enhancement(), Ok(Enhancement::None));
+
+        assert_matches!(
+            decoder.set_complexity(11),
+            Err(Error::InvalidComplexity(11))
+        );
+    }
 }
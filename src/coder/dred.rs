@@ -0,0 +1,225 @@
+use crate::{error::try_map_opus_error, ffi, packet::Packet, ErrorCode, Result, SampleRate};
+use std::os::raw::{c_int, c_uchar};
+
+pub(crate) const OPUS_SET_DRED_DURATION_REQUEST: i32 = 4050;
+pub(crate) const OPUS_GET_DRED_DURATION_REQUEST: i32 = 4051;
+
+/// The opaque Opus DRED decoder state.
+#[repr(C)]
+pub struct OpusDREDDecoder {
+    _private: [u8; 0],
+}
+
+/// The opaque Opus DRED state.
+#[repr(C)]
+pub struct OpusDRED {
+    _private: [u8; 0],
+}
+
+extern "C" {
+    fn opus_dred_decoder_create(error: *mut c_int) -> *mut OpusDREDDecoder;
+    fn opus_dred_decoder_destroy(decoder: *mut OpusDREDDecoder);
+    fn opus_dred_alloc(error: *mut c_int) -> *mut OpusDRED;
+    fn opus_dred_free(dred: *mut OpusDRED);
+    fn opus_dred_parse(
+        decoder: *mut OpusDREDDecoder,
+        dred: *mut OpusDRED,
+        data: *const c_uchar,
+        len: i32,
+        max_dred_samples: i32,
+        sample_rate: i32,
+        dred_end: *mut c_int,
+        defer_processing: c_int,
+    ) -> c_int;
+    pub(crate) fn opus_decoder_dred_decode(
+        decoder: *mut ffi::OpusDecoder,
+        dred: *const OpusDRED,
+        dred_offset: i32,
+        pcm: *mut i16,
+        frame_size: i32,
+    ) -> c_int;
+    pub(crate) fn opus_decoder_dred_decode_float(
+        decoder: *mut ffi::OpusDecoder,
+        dred: *const OpusDRED,
+        dred_offset: i32,
+        pcm: *mut f32,
+        frame_size: i32,
+    ) -> c_int;
+}
+
+/// Deep redundancy (DRED) parsed from a packet by a [`DredDecoder`], used
+/// to recover lost audio preceding the packet via [`Decoder::decode_dred`].
+///
+/// [`DredDecoder`]: crate::coder::DredDecoder
+/// [`Decoder::decode_dred`]: crate::coder::Decoder::decode_dred
+#[derive(Debug)]
+pub struct Dred {
+    pointer: *mut OpusDRED,
+}
+
+unsafe impl Send for Dred {}
+
+impl Dred {
+    /// Allocates an empty DRED state.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn new() -> Result<Self> {
+        let mut opus_code = 0;
+
+        let pointer = unsafe { opus_dred_alloc(&mut opus_code) };
+
+        if opus_code == ffi::OPUS_OK && !pointer.is_null() {
+            return Ok(Self { pointer });
+        }
+
+        Err(ErrorCode::from(opus_code).into())
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const OpusDRED {
+        self.pointer
+    }
+}
+
+impl Drop for Dred {
+    fn drop(&mut self) {
+        unsafe { opus_dred_free(self.pointer) }
+    }
+}
+
+/// The result of parsing a packet's deep redundancy.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct DredParse {
+    /// Samples of audio preceding the packet the redundancy reaches back,
+    /// zero if the packet carries none.
+    pub available: usize,
+    /// Samples of silence between the packet's timestamp and the last
+    /// redundant sample, which need not be recovered.
+    pub end: usize,
+}
+
+/// `DredDecoder` extracts deep redundancy (DRED) from Opus 1.5 packets,
+/// allowing to recover up to a second of audio lost before a packet.
+#[derive(Debug)]
+pub struct DredDecoder {
+    pointer: *mut OpusDREDDecoder,
+}
+
+unsafe impl Send for DredDecoder {}
+
+impl DredDecoder {
+    /// Creates a new DRED decoder.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem, e.g.
+    /// [`ErrorCode::Unimplemented`] if Opus was built without DRED.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    /// [`ErrorCode::Unimplemented`]: crate::error::ErrorCode::Unimplemented
+    pub fn new() -> Result<Self> {
+        let mut opus_code = 0;
+
+        let pointer = unsafe { opus_dred_decoder_create(&mut opus_code) };
+
+        if opus_code == ffi::OPUS_OK && !pointer.is_null() {
+            return Ok(Self { pointer });
+        }
+
+        Err(ErrorCode::from(opus_code).into())
+    }
+
+    /// Parses the redundancy of `input` into `dred`, considering at most
+    /// `max_samples` preceding the packet at `sample_rate`.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn parse(
+        &mut self,
+        dred: &mut Dred,
+        input: Packet<'_>,
+        max_samples: u32,
+        sample_rate: SampleRate,
+    ) -> Result<DredParse> {
+        let mut end = 0;
+
+        let available = try_map_opus_error(unsafe {
+            opus_dred_parse(
+                self.pointer,
+                dred.pointer,
+                input.as_ptr(),
+                input.i32_len(),
+                max_samples.min(i32::MAX as u32) as i32,
+                sample_rate as i32,
+                &mut end,
+                0,
+            )
+        })?;
+
+        Ok(DredParse {
+            available: available as usize,
+            end: end.max(0) as usize,
+        })
+    }
+}
+
+impl Drop for DredDecoder {
+    fn drop(&mut self) {
+        unsafe { opus_dred_decoder_destroy(self.pointer) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dred, DredDecoder};
+    use crate::{
+        coder::{Decoder, Encoder},
+        packet::Packet,
+        Application, Channels, MutSignals, SampleRate, TryFrom,
+    };
+    use matches::assert_matches;
+    use std::time::Duration;
+
+    #[test]
+    fn recover_from_dred() {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz16000, Channels::Mono, Application::Voip).unwrap();
+        encoder
+            .set_dred_duration(Duration::from_millis(200))
+            .unwrap();
+        encoder.set_packet_loss_perc(20).unwrap();
+        assert_eq!(encoder.dred_duration().unwrap(), Duration::from_millis(200));
+
+        let mut dred_decoder = DredDecoder::new().unwrap();
+        let mut dred = Dred::new().unwrap();
+        let mut decoder = Decoder::new(SampleRate::Hz16000, Channels::Mono).unwrap();
+
+        let mut packet = [0; 1275];
+        let mut len = 0;
+
+        for frame in 0..50 {
+            let input: Vec<i16> = (0..320)
+                .map(|i| (((frame * 320 + i) as f32 * 0.05).sin() * 8000.0) as i16)
+                .collect();
+            len = encoder.encode(&input, &mut packet).unwrap();
+        }
+
+        let packet = Packet::try_from(&packet[..len]).unwrap();
+        let parsed = dred_decoder
+            .parse(&mut dred, packet, 16000, SampleRate::Hz16000)
+            .unwrap();
+
+        // After a second of audio the packets carry redundancy.
+        assert!(parsed.available > 0);
+
+        let offset = parsed.available.min(320) as u32;
+        let mut output = [0_i16; 320];
+        let signals = MutSignals::try_from(&mut output[..]).unwrap();
+
+        assert_matches!(decoder.decode_dred(&dred, offset, signals), Ok(320));
+        assert!(output.iter().any(|&sample| sample != 0));
+    }
+}
@@ -0,0 +1,122 @@
+use super::{Encoder, GenericCtl};
+use crate::{packet::Packet, Result, Sample};
+use std::{convert::TryFrom, time::Duration};
+
+/// Longest packet, in bytes, the encoder emits while in discontinuous
+/// transmission.
+const MAX_DTX_PACKET_LEN: usize = 2;
+
+/// The outcome of encoding a frame with a [`DtxEncoder`].
+///
+/// [`DtxEncoder`]: crate::coder::DtxEncoder
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EncodeOutput {
+    /// A packet of the given length that should be transmitted.
+    Packet(usize),
+    /// The encoder is in discontinuous transmission, nothing needs to be
+    /// transmitted for `duration`.
+    Silence { duration: Duration },
+}
+
+/// `DtxEncoder` wraps an [`Encoder`] with discontinuous transmission (DTX)
+/// enabled and tells apart packets worth transmitting from the
+/// no-transmission packets emitted during silence.
+///
+/// [`Encoder`]: crate::coder::Encoder
+#[derive(Debug)]
+pub struct DtxEncoder {
+    encoder: Encoder,
+}
+
+impl DtxEncoder {
+    /// Creates a new `DtxEncoder`, enabling DTX on `encoder`.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn new(mut encoder: Encoder) -> Result<Self> {
+        encoder.enable_dtx()?;
+
+        Ok(Self { encoder })
+    }
+
+    /// Gets the wrapped encoder.
+    pub fn encoder(&self) -> &Encoder {
+        &self.encoder
+    }
+
+    /// Gets the wrapped encoder mutably, e.g. to adjust its settings.
+    pub fn encoder_mut(&mut self) -> &mut Encoder {
+        &mut self.encoder
+    }
+
+    /// Unwraps the encoder.
+    pub fn into_inner(self) -> Encoder {
+        self.encoder
+    }
+
+    /// Encodes an Opus frame.
+    ///
+    /// The `input` signal (interleaved if 2 channels) will be encoded into the
+    /// `output` payload. On success, returns [`EncodeOutput::Packet`] with the
+    /// length of the encoded packet, or [`EncodeOutput::Silence`] with the
+    /// frame's duration if the packet need not be transmitted.
+    /// The samples are either `i16` or `f32`, see [`Sample`].
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`EncodeOutput::Packet`]: crate::coder::EncodeOutput::Packet
+    /// [`EncodeOutput::Silence`]: crate::coder::EncodeOutput::Silence
+    /// [`Sample`]: crate::sample::Sample
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn encode<S: Sample>(&self, input: &[S], output: &mut [u8]) -> Result<EncodeOutput> {
+        let len = self.encoder.encode(input, output)?;
+
+        if len > MAX_DTX_PACKET_LEN {
+            return Ok(EncodeOutput::Packet(len));
+        }
+
+        let duration = Packet::try_from(&output[..len])?.duration(self.encoder.sample_rate()?)?;
+
+        Ok(EncodeOutput::Silence { duration })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DtxEncoder, EncodeOutput};
+    use crate::{coder::Encoder, Application, Channels, SampleRate};
+    use matches::assert_matches;
+    use std::time::Duration;
+
+    #[test]
+    fn silence_markers() {
+        let encoder = Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Voip).unwrap();
+        let encoder = DtxEncoder::new(encoder).unwrap();
+        assert_matches!(encoder.encoder().dtx(), Ok(true));
+
+        let silence = [0_i16; 960];
+        let noise: Vec<i16> = (0..960).map(|i| ((i * 7919) % 20000) as i16).collect();
+        let mut output = [0; 256];
+
+        assert_matches!(
+            encoder.encode(&noise, &mut output),
+            Ok(EncodeOutput::Packet(_))
+        );
+
+        // Discontinuous transmission kicks in after some frames of silence.
+        let mut last = None;
+        for _ in 0..50 {
+            last = Some(encoder.encode(&silence, &mut output).unwrap());
+        }
+
+        assert_eq!(
+            last,
+            Some(EncodeOutput::Silence {
+                duration: Duration::from_millis(20)
+            })
+        );
+    }
+}
@@ -1,8 +1,22 @@
-use super::GenericCtl;
+#[cfg(all(feature = "opus_1_5", opus_dred))]
+use super::dred;
+use super::{CtlRequest, EncoderConfig, EncoderSettings, GenericCtl};
 use crate::{
-    error::try_map_opus_error, ffi, Application, Bandwidth, Bitrate, Channels, ErrorCode, Result,
+    backend::{self, OpusBackend, LINKED},
+    error::try_map_opus_error,
+    ffi,
+    packet::MAX_PACKET_SIZE,
+    Application, Bandwidth, Bitrate, Channels, Error, ErrorCode, FrameDuration, Result, Sample,
     SampleRate, Signal, TryFrom,
 };
+#[cfg(feature = "bytes")]
+use bytes::BytesMut;
+#[cfg(all(feature = "opus_1_5", opus_dred))]
+use std::time::Duration;
+use std::{mem::ManuallyDrop, ops::RangeInclusive};
+
+/// Signal depths in bits accepted by Opus.
+pub(crate) const LSB_DEPTHS: RangeInclusive<u8> = 8..=24;
 
 /// `Encoder` calls to Opus and offers method to encode and issue
 /// requests to Opus.
@@ -10,6 +24,14 @@ use crate::{
 pub struct Encoder {
     pointer: *mut ffi::OpusEncoder,
     channels: Channels,
+    /// The bitrate last configured, `None` if unknown. Opus reports the rate
+    /// it picked rather than [`Bitrate::Auto`] or [`Bitrate::Max`].
+    ///
+    /// [`Bitrate::Auto`]: crate::Bitrate::Auto
+    /// [`Bitrate::Max`]: crate::Bitrate::Max
+    bitrate: Option<Bitrate>,
+    /// The Opus implementation `pointer` stems from.
+    backend: &'static dyn OpusBackend,
 }
 
 /// The Opus encoder can be sent between threads unless the Opus library
@@ -30,7 +52,7 @@ impl GenericCtl for Encoder {
 
     /// Gets the encoder's configured phase inversion status.
     fn phase_inversion_disabled(&self) -> Result<bool> {
-        self.encoder_ctl_request(ffi::OPUS_SET_PHASE_INVERSION_DISABLED_REQUEST)
+        self.encoder_ctl_request(ffi::OPUS_GET_PHASE_INVERSION_DISABLED_REQUEST)
             .map(|b| b == 1)
     }
 
@@ -83,8 +105,10 @@ impl Encoder {
     pub fn new(sample_rate: SampleRate, channels: Channels, mode: Application) -> Result<Encoder> {
         let mut opus_code = 0;
 
+        let backend = backend::current();
+
         let pointer = unsafe {
-            ffi::opus_encoder_create(
+            backend.encoder_create(
                 sample_rate as i32,
                 channels as i32,
                 mode as i32,
@@ -93,12 +117,131 @@ impl Encoder {
         };
 
         if opus_code == ffi::OPUS_OK || !pointer.is_null() {
-            return Ok(Encoder { pointer, channels });
+            return Ok(Encoder {
+                pointer,
+                channels,
+                bitrate: Some(Bitrate::Auto),
+                backend,
+            });
         }
 
         Err(ErrorCode::from(opus_code).into())
     }
 
+    /// Wraps an existing Opus encoder state, e.g. one returned by
+    /// [`into_raw`].
+    ///
+    /// **Safety**:
+    /// `pointer` must point to a valid Opus encoder coding `channels`,
+    /// created by the Opus linked at build time.
+    /// The returned `Encoder` takes ownership and destroys the state via
+    /// `opus_encoder_destroy`, unless it is prevented from being dropped,
+    /// e.g. via [`ManuallyDrop`].
+    ///
+    /// [`into_raw`]: struct.Encoder.html#method.into_raw
+    /// [`ManuallyDrop`]: std::mem::ManuallyDrop
+    pub unsafe fn from_raw(pointer: *mut ffi::OpusEncoder, channels: Channels) -> Self {
+        Self::from_raw_with(pointer, channels, &LINKED)
+    }
+
+    /// Wraps an existing Opus encoder state created by `backend`.
+    ///
+    /// **Safety**:
+    /// Same as [`from_raw`], for states of `backend` instead of the linked
+    /// Opus.
+    ///
+    /// [`from_raw`]: struct.Encoder.html#method.from_raw
+    pub(crate) unsafe fn from_raw_with(
+        pointer: *mut ffi::OpusEncoder,
+        channels: Channels,
+        backend: &'static dyn OpusBackend,
+    ) -> Self {
+        Encoder {
+            pointer,
+            channels,
+            bitrate: None,
+            backend,
+        }
+    }
+
+    /// Releases ownership of the Opus encoder state, e.g. to hand it to
+    /// another C library.
+    ///
+    /// The state is no longer destroyed, reclaim it via [`from_raw`].
+    ///
+    /// **Errors**:
+    /// Returns [`ErrorCode::Unimplemented`] if the encoder runs on an Opus
+    /// loaded at runtime via the `dlopen`-feature, as [`from_raw`] can only
+    /// wrap states of the linked Opus. The encoder is destroyed in that case.
+    ///
+    /// [`from_raw`]: struct.Encoder.html#method.from_raw
+    /// [`ErrorCode::Unimplemented`]: crate::error::ErrorCode::Unimplemented
+    pub fn into_raw(self) -> Result<*mut ffi::OpusEncoder> {
+        if !self.backend.is_linked() {
+            return Err(Error::Opus(ErrorCode::Unimplemented));
+        }
+
+        Ok(ManuallyDrop::new(self).pointer)
+    }
+
+    /// Gets a pointer to the Opus encoder state.
+    pub fn as_ptr(&self) -> *const ffi::OpusEncoder {
+        self.pointer
+    }
+
+    /// Gets a mutable pointer to the Opus encoder state.
+    pub fn as_mut_ptr(&mut self) -> *mut ffi::OpusEncoder {
+        self.pointer
+    }
+
+    /// Gets the channels the encoder encodes.
+    pub fn channels(&self) -> Channels {
+        self.channels
+    }
+
+    /// Re-initialises the encoder in place as if constructed via
+    /// [`Encoder::new`], without reallocating its state.
+    ///
+    /// Every setting is reset to its default.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidChannels`] if `channels` need a larger state
+    /// than the current channels, e.g. stereo after mono, and [Error::Opus]
+    /// when Opus encountered a problem.
+    ///
+    /// [`Encoder::new`]: struct.Encoder.html#method.new
+    /// [`Error::InvalidChannels`]: crate::error::Error::InvalidChannels
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn reinit(
+        &mut self,
+        sample_rate: SampleRate,
+        channels: Channels,
+        mode: Application,
+    ) -> Result<()> {
+        let (size, current_size) = unsafe {
+            (
+                self.backend.encoder_get_size(channels as i32),
+                self.backend.encoder_get_size(self.channels as i32),
+            )
+        };
+
+        if size > current_size {
+            return Err(Error::InvalidChannels(channels as i32));
+        }
+
+        try_map_opus_error(unsafe {
+            self.backend.encoder_init(
+                self.pointer,
+                sample_rate as i32,
+                channels as i32,
+                mode as i32,
+            )
+        })?;
+        self.channels = channels;
+
+        Ok(())
+    }
+
     /// Issues a CTL get-`request` to Opus.
     /// If Opus returns a negative value it indicates an error.
     ///
@@ -110,7 +253,10 @@ impl Encoder {
     pub fn encoder_ctl_request(&self, request: i32) -> Result<i32> {
         let mut value = 0;
 
-        let ffi_result = unsafe { ffi::opus_encoder_ctl(self.pointer, request, &mut value) };
+        let ffi_result = unsafe {
+            self.backend
+                .encoder_ctl_get(self.pointer, request, &mut value)
+        };
         try_map_opus_error(ffi_result)?;
 
         Ok(value)
@@ -126,19 +272,69 @@ impl Encoder {
     ///
     /// [`Encoder`]: struct.Encoder.html
     pub fn set_encoder_ctl_request(&mut self, request: i32, value: i32) -> Result<()> {
-        try_map_opus_error(unsafe { ffi::opus_encoder_ctl(self.pointer, request, value) })?;
+        try_map_opus_error(unsafe { self.backend.encoder_ctl(self.pointer, request, value) })?;
+        self.track_bitrate(request, value);
 
         Ok(())
     }
 
+    /// Remembers the bitrate if `request` configured it to `value`.
+    fn track_bitrate(&mut self, request: i32, value: i32) {
+        if request == ffi::OPUS_SET_BITRATE_REQUEST {
+            self.bitrate = Bitrate::try_from(value).ok();
+        }
+    }
+
+    /// Issues a typed CTL `request` to Opus, e.g. a request this crate does
+    /// not wrap yet.
+    /// On success, returns the requested value for get-requests, otherwise
+    /// zero.
+    ///
+    /// **Safety**:
+    /// Opus must accept the argument of [`CtlRequest::Other`] as integer,
+    /// passing it to a request expecting a pointer is undefined behaviour.
+    /// Likewise, Opus must write a single integer to the pointer
+    /// [`CtlRequest::OtherGet`] passes.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`CtlRequest::Other`]: crate::coder::CtlRequest::Other
+    /// [`CtlRequest::OtherGet`]: crate::coder::CtlRequest::OtherGet
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub unsafe fn raw_ctl(&mut self, request: CtlRequest) -> Result<i32> {
+        let mut value = 0;
+
+        let ffi_result = match request.argument() {
+            Some(argument) => self
+                .backend
+                .encoder_ctl(self.pointer, request.id(), argument),
+            None => self
+                .backend
+                .encoder_ctl_get(self.pointer, request.id(), &mut value),
+        };
+        try_map_opus_error(ffi_result)?;
+
+        if let Some(argument) = request.argument() {
+            self.track_bitrate(request.id(), argument);
+        }
+
+        Ok(value)
+    }
+
     /// Encodes an Opus frame.
     ///
     /// The `input` signal (interleaved if 2 channels) will be encoded into the
     /// `output` payload and on success returns the length of the
     /// encoded packet.
-    pub fn encode(&self, input: &[i16], output: &mut [u8]) -> Result<usize> {
+    ///
+    /// The samples are either `i16` or `f32`, see [`Sample`].
+    ///
+    /// [`Sample`]: crate::sample::Sample
+    pub fn encode<S: Sample>(&self, input: &[S], output: &mut [u8]) -> Result<usize> {
         try_map_opus_error(unsafe {
-            ffi::opus_encode(
+            S::encode(
+                self.backend,
                 self.pointer,
                 input.as_ptr(),
                 input.len() as i32 / self.channels as i32,
@@ -149,22 +345,49 @@ impl Encoder {
         .map(|n| n as usize)
     }
 
+    /// Encodes an Opus frame into a newly allocated packet.
+    ///
+    /// The `input` signal (interleaved if 2 channels) will be encoded
+    /// into a buffer of the size Opus recommends, which is truncated to the
+    /// length of the encoded packet.
+    /// The samples are either `i16` or `f32`, see [`Sample`].
+    ///
+    /// [`Sample`]: crate::sample::Sample
+    pub fn encode_to_vec<S: Sample>(&self, input: &[S]) -> Result<Vec<u8>> {
+        let mut output = vec![0; MAX_PACKET_SIZE];
+        let len = self.encode(input, &mut output)?;
+        output.truncate(len);
+
+        Ok(output)
+    }
+
+    /// Encodes an Opus frame and appends the packet to `output`.
+    ///
+    /// The `input` signal (interleaved if 2 channels) will be encoded
+    /// and on success returns the length of the encoded packet, which can
+    /// be split off `output` without copying.
+    /// The samples are either `i16` or `f32`, see [`Sample`].
+    ///
+    /// [`Sample`]: crate::sample::Sample
+    #[cfg(feature = "bytes")]
+    pub fn encode_to_bytes<S: Sample>(&self, input: &[S], output: &mut BytesMut) -> Result<usize> {
+        let start = output.len();
+        output.resize(start + MAX_PACKET_SIZE, 0);
+
+        let result = self.encode(input, &mut output[start..]);
+        output.truncate(start + *result.as_ref().unwrap_or(&0));
+
+        result
+    }
+
     /// Encodes an Opus frame from floating point input.
     ///
     /// The `input` signal (interleaved if 2 channels) will be encoded into the
     /// `output` payload and on success, returns the length of the
     /// encoded packet.
+    #[deprecated(note = "use `encode` instead, it accepts `f32` input")]
     pub fn encode_float(&self, input: &[f32], output: &mut [u8]) -> Result<usize> {
-        try_map_opus_error(unsafe {
-            ffi::opus_encode_float(
-                self.pointer,
-                input.as_ptr(),
-                input.len() as i32 / self.channels as i32,
-                output.as_mut_ptr(),
-                output.len() as i32,
-            )
-        })
-        .map(|n| n as usize)
+        self.encode(input, output)
     }
 
     /// Gets the encoder's complexity configuration.
@@ -218,56 +441,66 @@ impl Encoder {
             .and_then(Bitrate::try_from)
     }
 
-    /// Enables variable bitrate (VBR) in the encoder.
+    /// Enables constrained variable bitrate (VBR) in the encoder.
     ///
-    /// The configured bitrate may not be met exactly because frames must be an
-    /// integer number of bytes in length.
+    /// See [`set_constrained_vbr`].
     ///
-    /// **Warning**:
-    /// Only the MDCT mode of Opus currently heeds the constraint.
-    /// Speech mode ignores it completely,
-    /// hybrid mode may fail to obey it if the LPC layer uses more bitrate
-    /// than the constraint would have permitted.
+    /// [`set_constrained_vbr`]: struct.Encoder.html#method.set_constrained_vbr
     pub fn enable_vbr_constraint(&mut self) -> Result<()> {
-        self.set_vbr_constraint(true)
+        self.set_constrained_vbr(true)
     }
 
-    /// Disables variable bitrate (VBR) in the encoder.
+    /// Disables constrained variable bitrate (VBR) in the encoder, allowing
+    /// unconstrained VBR.
     ///
-    /// The configured bitrate may not be met exactly because frames must be an
-    /// integer number of bytes in length.
+    /// See [`set_constrained_vbr`].
     ///
-    /// **Warning**:
-    /// Only the MDCT mode of Opus currently heeds the constraint.
-    /// Speech mode ignores it completely,
-    /// hybrid mode may fail to obey it if the LPC layer uses more bitrate
-    /// than the constraint would have permitted.
+    /// [`set_constrained_vbr`]: struct.Encoder.html#method.set_constrained_vbr
     pub fn disable_vbr_constraint(&mut self) -> Result<()> {
-        self.set_vbr_constraint(false)
+        self.set_constrained_vbr(false)
     }
 
-    /// Sets variable bitrate (VBR) in the encoder.
+    /// Sets constrained variable bitrate (VBR) in the encoder.
     ///
-    /// The configured bitrate may not be met exactly because frames must be an
-    /// integer number of bytes in length.
+    /// See [`set_constrained_vbr`].
+    ///
+    /// [`set_constrained_vbr`]: struct.Encoder.html#method.set_constrained_vbr
+    pub fn set_vbr_constraint(&mut self, enable: bool) -> Result<()> {
+        self.set_constrained_vbr(enable)
+    }
+
+    /// Determine if constrained VBR is enabled in the encoder.
+    pub fn vbr_constraint(&self) -> Result<bool> {
+        self.constrained_vbr()
+    }
+
+    /// Configures whether variable bitrate (VBR) is constrained in the
+    /// encoder.
+    ///
+    /// Constrained VBR behaves like a bit reservoir of one frame, keeping the
+    /// bitrate close to the configured one, which allows modelling buffers
+    /// as with CBR.
+    /// This setting is ignored unless VBR is enabled, see [`set_vbr`].
+    /// The default is `true`.
     ///
     /// **Warning**:
     /// Only the MDCT mode of Opus currently heeds the constraint.
     /// Speech mode ignores it completely,
     /// hybrid mode may fail to obey it if the LPC layer uses more bitrate
     /// than the constraint would have permitted.
-    pub fn set_vbr_constraint(&mut self, enable: bool) -> Result<()> {
-        let if_vbr_shall_be_enabled = if enable { 1 } else { 0 };
+    ///
+    /// [`set_vbr`]: struct.Encoder.html#method.set_vbr
+    pub fn set_constrained_vbr(&mut self, constrained: bool) -> Result<()> {
+        let vbr_shall_be_constrained = if constrained { 1 } else { 0 };
 
         self.set_encoder_ctl_request(
             ffi::OPUS_SET_VBR_CONSTRAINT_REQUEST,
-            if_vbr_shall_be_enabled,
+            vbr_shall_be_constrained,
         )
-        .map(|_| ())
     }
 
-    /// Determine if constrained VBR is enabled in the encoder.
-    pub fn vbr_constraint(&self) -> Result<bool> {
+    /// Gets whether variable bitrate (VBR) is constrained in the encoder.
+    pub fn constrained_vbr(&self) -> Result<bool> {
         self.encoder_ctl_request(ffi::OPUS_GET_VBR_CONSTRAINT_REQUEST)
             .map(|b| b == 1)
     }
@@ -351,6 +584,32 @@ impl Encoder {
         .map(|_| ())
     }
 
+    /// Configures how much audio preceding each packet is carried as deep
+    /// redundancy (DRED), up to about a second in 10 ms steps.
+    ///
+    /// Receivers recover lost audio from it via [`DredDecoder`], a zero
+    /// `duration` disables DRED.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem, e.g. if the
+    /// duration exceeds what Opus supports.
+    ///
+    /// [`DredDecoder`]: crate::coder::DredDecoder
+    /// [Error::Opus]: crate::error::Error::Opus
+    #[cfg(all(feature = "opus_1_5", opus_dred))]
+    pub fn set_dred_duration(&mut self, duration: Duration) -> Result<()> {
+        let frames = (duration.as_millis() / 10).min(i32::MAX as u128) as i32;
+
+        self.set_encoder_ctl_request(dred::OPUS_SET_DRED_DURATION_REQUEST, frames)
+    }
+
+    /// Gets the encoder's configured deep redundancy duration.
+    #[cfg(all(feature = "opus_1_5", opus_dred))]
+    pub fn dred_duration(&self) -> Result<Duration> {
+        self.encoder_ctl_request(dred::OPUS_GET_DRED_DURATION_REQUEST)
+            .map(|frames| Duration::from_millis(frames as u64 * 10))
+    }
+
     /// Gets the total samples of delay added by the entire codec.
     ///
     /// This can be queried by the encoder and then the provided number of
@@ -369,15 +628,62 @@ impl Encoder {
             .map(|n| n as u32)
     }
 
+    /// Gets the pre-skip as specified by RFC 7845, the amount of samples at
+    /// 48 kHz a decoder discards from the start of the stream.
+    ///
+    /// The pre-skip covers the encoder's [`lookahead`], if the first input
+    /// frame has been extended via [`prime`], `primed` must be `true` to
+    /// discard the prepended silence as well.
+    ///
+    /// [`lookahead`]: Encoder::lookahead
+    /// [`prime`]: Encoder::prime
+    pub fn pre_skip(&self, primed: bool) -> Result<u16> {
+        let lookahead = self.lookahead()? as usize;
+        let samples = if primed { 2 * lookahead } else { lookahead };
+        let sample_rate = self.sample_rate()?;
+
+        Ok((samples * SampleRate::Hz48000 as usize / sample_rate as usize) as u16)
+    }
+
+    /// Prepends silence covering the encoder's [`lookahead`] to `input`,
+    /// the interleaved first input frame, so the encoder's start-up is
+    /// spent on silence rather than on the beginning of the audio.
+    ///
+    /// The returned signal exceeds a frame, it must be split into frames
+    /// before encoding. The prepended silence has to be discarded by
+    /// decoders, see [`pre_skip`].
+    ///
+    /// [`lookahead`]: Encoder::lookahead
+    /// [`pre_skip`]: Encoder::pre_skip
+    pub fn prime<S: Sample>(&self, input: &[S]) -> Result<Vec<S>> {
+        let silence = self.lookahead()? as usize * self.channels as usize;
+
+        let mut primed = Vec::with_capacity(silence + input.len());
+        primed.resize(silence, S::default());
+        primed.extend_from_slice(input);
+
+        Ok(primed)
+    }
+
     /// Configures mono/stereo forcing in the encoder.
     ///
     /// This can force the encoder to produce packets encoded as either
     /// mono or stereo, regardless of the format of the input audio.
     /// This is useful when the caller knows that the input signal is
-    /// currently a mono source embedded in a stereo stream.
-    pub fn set_force_channels(&mut self, channels: Channels) -> Result<()> {
+    /// currently a mono source embedded in a stereo stream, e.g. to
+    /// force a stereo encoder to mono during low-bitrate periods without
+    /// recreating it.
+    ///
+    /// Passing `None`, or [`Channels::Auto`], lets the encoder choose again.
+    ///
+    /// [`Channels::Auto`]: ../enum.Channels.html#variant.Auto
+    pub fn set_force_channels<C>(&mut self, channels: C) -> Result<()>
+    where
+        C: Into<Option<Channels>>,
+    {
+        let channels = channels.into().unwrap_or(Channels::Auto);
+
         self.set_encoder_ctl_request(ffi::OPUS_SET_FORCE_CHANNELS_REQUEST, channels as i32)
-            .map(|_| ())
     }
 
     /// Gets the encoder's forced channel configuration.
@@ -386,6 +692,38 @@ impl Encoder {
             .and_then(Channels::try_from)
     }
 
+    /// Gets the encoder's configured frame duration, `None` if the duration
+    /// is determined by the input's length.
+    pub fn frame_duration(&self) -> Result<Option<FrameDuration>> {
+        match self.encoder_ctl_request(ffi::OPUS_GET_EXPERT_FRAME_DURATION_REQUEST)? {
+            ffi::OPUS_FRAMESIZE_ARG => Ok(None),
+            duration => FrameDuration::try_from(duration).map(Some),
+        }
+    }
+
+    /// Configures the encoder's use of variable duration frames.
+    ///
+    /// When set to a [`FrameDuration`], the encoder is restricted to
+    /// frames of that duration, e.g. to lock in 2.5 or 5 ms frames for low
+    /// latency, or to enable the 80 to 120 ms frames introduced in Opus 1.2.
+    /// The input must be at least as long as the frame, excess samples
+    /// are ignored.
+    ///
+    /// Passing `None`, the default, selects the frame duration by the
+    /// length of the input.
+    ///
+    /// [`FrameDuration`]: ../enum.FrameDuration.html
+    pub fn set_frame_duration<D>(&mut self, frame_duration: D) -> Result<()>
+    where
+        D: Into<Option<FrameDuration>>,
+    {
+        let frame_duration = frame_duration
+            .into()
+            .map_or(ffi::OPUS_FRAMESIZE_ARG, |duration| duration as i32);
+
+        self.set_encoder_ctl_request(ffi::OPUS_SET_EXPERT_FRAME_DURATION_REQUEST, frame_duration)
+    }
+
     /// Gets the encoder's configured maximum allowed bandpass.
     pub fn max_bandwidth(&self) -> Result<Bandwidth> {
         self.encoder_ctl_request(ffi::OPUS_GET_MAX_BANDWIDTH_REQUEST)
@@ -406,9 +744,9 @@ impl Encoder {
     /// [`Bandwidth::Auto`] will return [`BadArgument`] as it is not
     /// accepted by Opus as `bandwidth` value.
     ///
-    /// [`set_bandwidth`]: struct.Encoder.html#method.set_bandwidth.html
+    /// [`set_bandwidth`]: struct.Encoder.html#method.set_bandwidth
     /// [`Bandwidth::Auto`]: ../enum.Bandwidth.html#variant.Auto
-    /// [`BadArgument`]: ../error/enum.ErrorCode.html#variant.BadArgument.html
+    /// [`BadArgument`]: ../error/enum.ErrorCode.html#variant.BadArgument
     pub fn set_max_bandwidth(&mut self, bandwidth: Bandwidth) -> Result<()> {
         self.set_encoder_ctl_request(ffi::OPUS_SET_MAX_BANDWIDTH_REQUEST, bandwidth as i32)
     }
@@ -422,6 +760,10 @@ impl Encoder {
     /// If set `prediction_disabled` to `true`, disables almost all use of
     /// prediction, making frames almost completely independent.
     ///
+    /// This is useful when every packet must be decodable on its own,
+    /// e.g. for receivers joining a multicast stream in progress.
+    /// The default is `false`.
+    ///
     /// This reduces quality.
     pub fn set_prediction_disabled(&mut self, prediction_disabled: bool) -> Result<()> {
         let prediction_disabled = if prediction_disabled { 1 } else { 0 };
@@ -491,6 +833,23 @@ impl Encoder {
         self.set_dtx(false)
     }
 
+    /// Gets whether the last encoded frame was in discontinuous
+    /// transmission (DTX).
+    ///
+    /// While `true`, the encoder produces packets that need not be sent,
+    /// allowing streaming applications to suppress them or send keepalives
+    /// instead.
+    ///
+    /// **Info**:
+    /// This always returns `false` unless DTX has been enabled via
+    /// [`set_dtx`].
+    ///
+    /// [`set_dtx`]: struct.Encoder.html#method.set_dtx
+    pub fn in_dtx(&self) -> Result<bool> {
+        self.encoder_ctl_request(ffi::OPUS_GET_IN_DTX_REQUEST)
+            .map(|n| n == 1)
+    }
+
     /// Gets the encoder's configured signal depth.
     pub fn lsb_depth(&self) -> Result<u8> {
         self.encoder_ctl_request(ffi::OPUS_GET_LSB_DEPTH_REQUEST)
@@ -505,30 +864,98 @@ impl Encoder {
     ///
     /// For example, a depth of 14 would be an appropriate setting for G.711
     /// u-law input. A depth of 16 would be appropriate for 16-bit linear pcm
-    /// input with `encode_float()`.
+    /// input encoded as `f32`.
     ///
-    /// When using `encode()` instead of `encode_float()`, or when libopus is
+    /// When encoding `i16` instead of `f32` input, or when libopus is
     /// compiled for fixed-point, the encoder uses the minimum of the value set
     /// here and the value 16.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidLsbDepth`] if `lsb_depth` is not within
+    /// 8 to 24 inclusive.
+    ///
+    /// [`Error::InvalidLsbDepth`]: crate::error::Error::InvalidLsbDepth
     pub fn set_lsb_depth(&mut self, lsb_depth: u8) -> Result<()> {
+        if !LSB_DEPTHS.contains(&lsb_depth) {
+            return Err(Error::InvalidLsbDepth(lsb_depth));
+        }
+
         self.set_encoder_ctl_request(ffi::OPUS_SET_LSB_DEPTH_REQUEST, i32::from(lsb_depth))
             .map(|_| ())
     }
+
+    /// Gets a snapshot of the encoder's settings, e.g. for logging or to
+    /// restore them later via [`EncoderConfig`].
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`EncoderConfig`]: crate::coder::EncoderConfig
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn settings(&self) -> Result<EncoderSettings> {
+        Ok(EncoderSettings {
+            sample_rate: self.sample_rate()?,
+            application: self.application()?,
+            bitrate: self.bitrate.map_or_else(|| self.bitrate(), Ok)?,
+            complexity: self.complexity()?,
+            vbr: self.vbr()?,
+            vbr_constraint: self.constrained_vbr()?,
+            inband_fec: self.inband_fec()?,
+            packet_loss_perc: self.packet_loss_perc()?,
+            force_channels: self.force_channels()?,
+            max_bandwidth: self.max_bandwidth()?,
+            bandwidth: self.bandwidth()?,
+            signal: self.signal()?,
+            dtx: self.dtx()?,
+            lsb_depth: self.lsb_depth()?,
+            prediction_disabled: self.prediction_disabled()?,
+            phase_inversion_disabled: self.phase_inversion_disabled()?,
+            frame_duration: self.frame_duration()?,
+            #[cfg(all(feature = "opus_1_5", opus_dred))]
+            dred_duration: self.dred_duration()?,
+        })
+    }
+
+    /// Resets the encoder's state as after construction, e.g. when
+    /// restarting a stream, while keeping its configuration.
+    ///
+    /// If `settings` are passed, they are applied after resetting,
+    /// e.g. a snapshot taken earlier via [`settings`].
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`settings`]: struct.Encoder.html#method.settings
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn reset(&mut self, settings: Option<EncoderSettings>) -> Result<()> {
+        self.reset_state()?;
+
+        match settings {
+            Some(settings) => EncoderConfig::from(settings).apply(self),
+            None => Ok(()),
+        }
+    }
 }
 
 impl Drop for Encoder {
     /// We have to ensure that the resource our wrapping Opus-struct is pointing
     /// to is deallocated properly.
     fn drop(&mut self) {
-        unsafe { ffi::opus_encoder_destroy(self.pointer) }
+        unsafe { self.backend.encoder_destroy(self.pointer) }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Encoder;
-    use crate::{Application, Bandwidth, Bitrate, Channels, Error, ErrorCode, SampleRate, Signal};
+    use crate::{
+        coder::{DecodeRequest, Decoder, GenericCtl},
+        packet::Packet,
+        Application, Bandwidth, Bitrate, Channels, Error, ErrorCode, FrameDuration, SampleRate,
+        Signal,
+    };
     use matches::assert_matches;
+    use std::convert::TryFrom;
 
     #[test]
     fn set_get_inband_fec() {
@@ -586,6 +1013,25 @@ mod tests {
         assert_matches!(encoder.vbr_constraint(), Ok(false));
     }
 
+    #[test]
+    fn set_get_constrained_vbr() {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+
+        assert_matches!(encoder.constrained_vbr(), Ok(true));
+
+        encoder
+            .set_constrained_vbr(false)
+            .expect("Could not disable VBR constraint.");
+        assert_matches!(encoder.constrained_vbr(), Ok(false));
+        assert_matches!(encoder.vbr_constraint(), Ok(false));
+
+        encoder
+            .set_constrained_vbr(true)
+            .expect("Could not enable VBR constraint.");
+        assert_matches!(encoder.constrained_vbr(), Ok(true));
+    }
+
     #[test]
     fn set_get_vbr() {
         let mut encoder =
@@ -651,6 +1097,49 @@ mod tests {
             .set_force_channels(Channels::Auto)
             .expect("Could not set force channels to mono.");
         assert_matches!(encoder.force_channels(), Ok(Channels::Auto));
+
+        encoder
+            .set_force_channels(Some(Channels::Mono))
+            .expect("Could not set force channels to mono.");
+        assert_matches!(encoder.force_channels(), Ok(Channels::Mono));
+
+        encoder
+            .set_force_channels(None)
+            .expect("Could not stop forcing channels.");
+        assert_matches!(encoder.force_channels(), Ok(Channels::Auto));
+    }
+
+    #[test]
+    fn set_get_frame_duration() {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Audio).unwrap();
+
+        assert_matches!(encoder.frame_duration(), Ok(None));
+
+        encoder
+            .set_frame_duration(FrameDuration::Ms5)
+            .expect("Could not set frame duration to 5 ms.");
+        assert_matches!(encoder.frame_duration(), Ok(Some(FrameDuration::Ms5)));
+
+        let noise: Vec<i16> = (0..960).map(|i| ((i * 7919) % 20000) as i16).collect();
+        let mut output = [0; 4000];
+        let len = encoder.encode(&noise, &mut output).unwrap();
+        assert_matches!(
+            Packet::try_from(&output[..len])
+                .unwrap()
+                .nb_samples(SampleRate::Hz48000),
+            Ok(240)
+        );
+
+        encoder
+            .set_frame_duration(Some(FrameDuration::Ms120))
+            .expect("Could not set frame duration to 120 ms.");
+        assert_matches!(encoder.frame_duration(), Ok(Some(FrameDuration::Ms120)));
+
+        encoder
+            .set_frame_duration(None)
+            .expect("Could not reset frame duration.");
+        assert_matches!(encoder.frame_duration(), Ok(None));
     }
 
     #[test]
@@ -671,6 +1160,142 @@ mod tests {
         assert_matches!(encoder.prediction_disabled(), Ok(false));
     }
 
+    #[test]
+    fn join_in_progress_without_prediction() {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Audio).unwrap();
+        encoder
+            .set_prediction_disabled(true)
+            .expect("Could not set prediction disabled to true.");
+
+        let noise: Vec<i16> = (0..960).map(|i| ((i * 7919) % 20000) as i16).collect();
+        let mut output = [0; 4000];
+
+        for _ in 0..10 {
+            let len = encoder.encode(&noise, &mut output).unwrap();
+
+            // A decoder joining now decodes the packet as a whole.
+            let mut decoder = Decoder::new(SampleRate::Hz48000, Channels::Mono).unwrap();
+            let decoded: Vec<i16> = decoder
+                .decode_to_vec(DecodeRequest::Normal(
+                    Packet::try_from(&output[..len]).unwrap(),
+                ))
+                .unwrap();
+            assert_eq!(decoded.len(), 960);
+        }
+    }
+
+    #[test]
+    fn set_get_phase_inversion_disabled() {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+
+        assert_matches!(encoder.phase_inversion_disabled(), Ok(false));
+
+        encoder
+            .set_phase_inversion_disabled(true)
+            .expect("Could not disable phase inversion.");
+        assert_matches!(encoder.phase_inversion_disabled(), Ok(true));
+
+        encoder
+            .set_phase_inversion_disabled(false)
+            .expect("Could not enable phase inversion.");
+        assert_matches!(encoder.phase_inversion_disabled(), Ok(false));
+    }
+
+    #[test]
+    fn matching_final_range() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        let mut decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+
+        let noise: Vec<i16> = (0..960 * 2).map(|i| ((i * 7919) % 20000) as i16).collect();
+        let mut output = [0; 4000];
+
+        for _ in 0..5 {
+            let len = encoder.encode(&noise, &mut output).unwrap();
+            let _: Vec<i16> = decoder
+                .decode_to_vec(DecodeRequest::Normal(
+                    Packet::try_from(&output[..len]).unwrap(),
+                ))
+                .unwrap();
+
+            assert_eq!(
+                encoder.final_range().unwrap(),
+                decoder.final_range().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn reset_keeps_configuration() {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        encoder.set_complexity(3).unwrap();
+        let settings = encoder.settings().unwrap();
+
+        let noise: Vec<i16> = (0..960 * 2).map(|i| ((i * 7919) % 20000) as i16).collect();
+        let mut output = [0; 4000];
+        let first_len = encoder.encode(&noise, &mut output).unwrap();
+        let first = output[..first_len].to_vec();
+
+        encoder.reset(None).unwrap();
+        assert_matches!(encoder.complexity(), Ok(3));
+
+        let len = encoder.encode(&noise, &mut output).unwrap();
+        assert_eq!(&output[..len], &first[..]);
+
+        encoder.set_complexity(10).unwrap();
+        encoder.reset(Some(settings)).unwrap();
+        assert_matches!(encoder.complexity(), Ok(3));
+    }
+
+    #[test]
+    fn reinit_in_place() {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        encoder.set_complexity(3).unwrap();
+
+        encoder
+            .reinit(SampleRate::Hz16000, Channels::Mono, Application::Voip)
+            .unwrap();
+        assert_matches!(encoder.sample_rate(), Ok(SampleRate::Hz16000));
+        assert_matches!(encoder.application(), Ok(Application::Voip));
+        assert_matches!(encoder.complexity(), Ok(10));
+
+        let silence = [0_i16; 320];
+        let mut output = [0; 256];
+        assert_matches!(encoder.encode(&silence, &mut output), Ok(_));
+
+        assert_matches!(
+            encoder.reinit(SampleRate::Hz48000, Channels::Stereo, Application::Audio),
+            Err(Error::InvalidChannels(2))
+        );
+        assert_matches!(
+            encoder.reinit(SampleRate::Hz48000, Channels::Auto, Application::Audio),
+            Err(Error::Opus(ErrorCode::BadArgument))
+        );
+    }
+
+    #[test]
+    fn raw_handle_round_trip() {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Audio).unwrap();
+        encoder.set_complexity(3).unwrap();
+        let pointer = encoder.as_mut_ptr();
+
+        let raw = encoder.into_raw().unwrap();
+        assert_eq!(raw, pointer);
+
+        let encoder = unsafe { Encoder::from_raw(raw, Channels::Mono) };
+        assert_eq!(encoder.as_ptr(), raw as *const _);
+        assert_matches!(encoder.complexity(), Ok(3));
+
+        let silence = [0_i16; 960];
+        let mut output = [0; 256];
+        assert_matches!(encoder.encode(&silence, &mut output), Ok(_));
+    }
+
     #[test]
     fn set_get_signal() {
         let mut encoder =
@@ -708,6 +1333,25 @@ mod tests {
             .expect("Could not create mono audio encoder");
     }
 
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn encode_to_bytes() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Audio).unwrap();
+
+        // 48000Hz * 1 channel * 20 ms / 1000
+        const MONO_20MS: usize = 48000 * 1 * 20 / 1000;
+        let input = [0_i16; MONO_20MS];
+        let mut output = bytes::BytesMut::new();
+
+        let len = encoder.encode_to_bytes(&input, &mut output).unwrap();
+        assert_eq!(len, 3);
+        assert_eq!(&output[..], &[248, 255, 254]);
+
+        encoder.encode_to_bytes(&input, &mut output).unwrap();
+        assert_eq!(&output[..], &[248, 255, 254, 248, 255, 254]);
+    }
+
     #[test]
     fn encoding() {
         let stereo_encoder =
@@ -731,6 +1375,52 @@ mod tests {
 
         let len = mono_encoder.encode(&input, &mut output).unwrap();
         assert_eq!(&output[..len], &[248, 255, 254]);
+
+        let float_input = [0_f32; MONO_20MS];
+        let len = mono_encoder.encode(&float_input, &mut output).unwrap();
+        assert_eq!(&output[..len], &[248, 255, 254]);
+
+        assert_eq!(
+            mono_encoder.encode_to_vec(&input).unwrap(),
+            &[248, 255, 254]
+        );
+        assert_eq!(
+            mono_encoder.encode_to_vec(&float_input).unwrap(),
+            &[248, 255, 254]
+        );
+    }
+
+    #[test]
+    fn get_lookahead() {
+        // 2.5 ms of lookahead plus 4 ms delay compensation at 48000Hz.
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        assert_matches!(encoder.lookahead(), Ok(312));
+
+        // The low delay application does not compensate delay.
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::LowDelay).unwrap();
+        assert_matches!(encoder.lookahead(), Ok(120));
+
+        let encoder = Encoder::new(SampleRate::Hz16000, Channels::Mono, Application::Voip).unwrap();
+        assert_matches!(encoder.lookahead(), Ok(104));
+    }
+
+    #[test]
+    fn pre_skip_and_priming() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        assert_matches!(encoder.pre_skip(false), Ok(312));
+        assert_matches!(encoder.pre_skip(true), Ok(624));
+
+        let primed = encoder.prime(&[1_i16; 4]).unwrap();
+        assert_eq!(primed.len(), 312 * 2 + 4);
+        assert!(primed[..624].iter().all(|&sample| sample == 0));
+        assert_eq!(&primed[624..], &[1; 4]);
+
+        // Lookahead is scaled to 48000Hz.
+        let encoder = Encoder::new(SampleRate::Hz16000, Channels::Mono, Application::Voip).unwrap();
+        assert_matches!(encoder.pre_skip(false), Ok(312));
     }
 
     #[test]
@@ -746,6 +1436,34 @@ mod tests {
         let bandwidth_got = encoder.max_bandwidth().unwrap();
 
         assert_eq!(bandwidth_to_set, bandwidth_got);
+
+        assert_matches!(
+            encoder.set_max_bandwidth(Bandwidth::Auto),
+            Err(Error::Opus(ErrorCode::BadArgument))
+        );
+    }
+
+    #[test]
+    fn max_bandwidth_caps_selection() {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        encoder
+            .set_bitrate(Bitrate::BitsPerSecond(128_000))
+            .unwrap();
+        encoder.set_max_bandwidth(Bandwidth::Wideband).unwrap();
+
+        let noise: Vec<i16> = (0..960 * 2).map(|i| ((i * 7919) % 20000) as i16).collect();
+        let mut output = [0; 4000];
+
+        for _ in 0..10 {
+            let len = encoder.encode(&noise, &mut output).unwrap();
+            let bandwidth = Packet::try_from(&output[..len])
+                .unwrap()
+                .bandwidth()
+                .unwrap();
+
+            assert!(bandwidth as i32 <= Bandwidth::Wideband as i32);
+        }
     }
 
     #[test]
@@ -821,6 +1539,25 @@ mod tests {
         assert_matches!(encoder.dtx(), Ok(false));
     }
 
+    #[test]
+    fn get_in_dtx() {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Voip).unwrap();
+
+        assert_matches!(encoder.in_dtx(), Ok(false));
+
+        encoder.enable_dtx().expect("Could not set dtx to true.");
+
+        let silence = [0_i16; 960];
+        let mut output = [0; 256];
+
+        for _ in 0..50 {
+            encoder.encode(&silence, &mut output).unwrap();
+        }
+
+        assert_matches!(encoder.in_dtx(), Ok(true));
+    }
+
     #[test]
     fn set_get_lsb_depth() {
         let mut encoder =
@@ -838,15 +1575,8 @@ mod tests {
             .expect("Could not set lsb depth to 8.");
         assert_matches!(encoder.lsb_depth(), Ok(8));
 
-        assert_matches!(
-            encoder.set_lsb_depth(7),
-            Err(Error::Opus(ErrorCode::BadArgument))
-        );
-
-        assert_matches!(
-            encoder.set_lsb_depth(25),
-            Err(Error::Opus(ErrorCode::BadArgument))
-        );
+        assert_matches!(encoder.set_lsb_depth(7), Err(Error::InvalidLsbDepth(7)));
+        assert_matches!(encoder.set_lsb_depth(25), Err(Error::InvalidLsbDepth(25)));
 
         assert_matches!(encoder.lsb_depth(), Ok(8));
     }
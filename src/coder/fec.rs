@@ -0,0 +1,171 @@
+use super::{DecodeRequest, Decoder, Encoder, GenericCtl};
+use crate::{packet::Packet, Result, Sample};
+use std::time::Duration;
+
+/// Longest burst of lost packets concealed before a received packet, longer
+/// gaps are treated as a discontinuity of the stream.
+pub(crate) const MAX_CONCEALMENT: Duration = Duration::from_secs(1);
+
+/// `FecChannel` pairs an [`Encoder`] embedding inband forward error
+/// correction (FEC) with a [`Decoder`] recovering lost packets from it.
+///
+/// Received packets are passed along their sequence number. On a gap, the
+/// packet directly preceding the received one is recovered from the
+/// received packet's FEC data, earlier ones are concealed.
+///
+/// [`Encoder`]: crate::coder::Encoder
+/// [`Decoder`]: crate::coder::Decoder
+#[derive(Debug)]
+pub struct FecChannel {
+    encoder: Encoder,
+    decoder: Decoder,
+    next_sequence: Option<u16>,
+}
+
+impl FecChannel {
+    /// Creates a new `FecChannel`, enabling inband FEC on `encoder` and
+    /// configuring the expected packet loss to `packet_loss_perc`.
+    ///
+    /// Opus only embeds FEC data if the expected packet loss is non-zero.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn new(mut encoder: Encoder, decoder: Decoder, packet_loss_perc: u8) -> Result<Self> {
+        encoder.set_inband_fec(true)?;
+        encoder.set_packet_loss_perc(packet_loss_perc)?;
+
+        Ok(Self {
+            encoder,
+            decoder,
+            next_sequence: None,
+        })
+    }
+
+    /// Gets the wrapped encoder.
+    pub fn encoder(&self) -> &Encoder {
+        &self.encoder
+    }
+
+    /// Gets the wrapped encoder mutably, e.g. to adapt the expected packet
+    /// loss to the measured one.
+    pub fn encoder_mut(&mut self) -> &mut Encoder {
+        &mut self.encoder
+    }
+
+    /// Gets the wrapped decoder.
+    pub fn decoder(&self) -> &Decoder {
+        &self.decoder
+    }
+
+    /// Unwraps the encoder and decoder.
+    pub fn into_parts(self) -> (Encoder, Decoder) {
+        (self.encoder, self.decoder)
+    }
+
+    /// Encodes an Opus frame, see [`Encoder::encode`].
+    ///
+    /// [`Encoder::encode`]: crate::coder::Encoder::encode
+    pub fn encode<S: Sample>(&self, input: &[S], output: &mut [u8]) -> Result<usize> {
+        self.encoder.encode(input, output)
+    }
+
+    /// Decodes the received `packet` numbered `sequence`, preceded by the
+    /// audio of lost packets if packets were skipped since the last
+    /// received one.
+    ///
+    /// Lost packets are assumed to last as long as `packet`. Packets arriving
+    /// late or twice are dropped, returning no samples.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn receive<S: Sample>(&mut self, sequence: u16, packet: Packet<'_>) -> Result<Vec<S>> {
+        receive(&mut self.decoder, &mut self.next_sequence, sequence, packet)
+    }
+}
+
+/// Decodes `packet` numbered `sequence` via `decoder`, preceded by the
+/// audio of packets lost since `next_sequence`, see [`FecChannel::receive`].
+///
+/// [`FecChannel::receive`]: crate::coder::FecChannel::receive
+pub(crate) fn receive<S: Sample>(
+    decoder: &mut Decoder,
+    next_sequence: &mut Option<u16>,
+    sequence: u16,
+    packet: Packet<'_>,
+) -> Result<Vec<S>> {
+    let lost = match *next_sequence {
+        Some(expected) => sequence.wrapping_sub(expected),
+        None => 0,
+    };
+
+    // Sequence numbers behind the expected one wrap to large gaps.
+    if lost >= 0x8000 {
+        return Ok(Vec::new());
+    }
+
+    let mut output = Vec::new();
+
+    if lost > 0 {
+        let duration = packet.duration(decoder.sample_rate()?)?;
+        let concealment = duration * u32::from(lost - 1);
+
+        if concealment > MAX_CONCEALMENT {
+            decoder.reset_state()?;
+        } else {
+            output = decoder.conceal(concealment)?;
+            output.extend(decoder.decode_to_vec::<S>(DecodeRequest::Fec(packet))?);
+        }
+    }
+
+    output.extend(decoder.decode_to_vec::<S>(DecodeRequest::Normal(packet))?);
+    *next_sequence = Some(sequence.wrapping_add(1));
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FecChannel;
+    use crate::{
+        coder::{Decoder, Encoder},
+        packet::Packet,
+        Application, Channels, SampleRate, TryFrom,
+    };
+
+    #[test]
+    fn recover_lost_packets() {
+        let encoder = Encoder::new(SampleRate::Hz16000, Channels::Mono, Application::Voip).unwrap();
+        let decoder = Decoder::new(SampleRate::Hz16000, Channels::Mono).unwrap();
+        let mut channel = FecChannel::new(encoder, decoder, 20).unwrap();
+
+        let mut received = 0;
+
+        for sequence in 0..10_u16 {
+            let input: Vec<i16> = (0..320)
+                .map(|i| (((sequence as usize * 320 + i) as f32 * 0.1).sin() * 8000.0) as i16)
+                .collect();
+            let mut packet = [0; 1275];
+            let len = channel.encode(&input, &mut packet).unwrap();
+
+            if sequence == 4 || sequence == 6 || sequence == 7 {
+                continue;
+            }
+
+            let output: Vec<i16> = channel
+                .receive(sequence, Packet::try_from(&packet[..len]).unwrap())
+                .unwrap();
+            received += output.len();
+
+            let late: Vec<i16> = channel
+                .receive(sequence, Packet::try_from(&packet[..len]).unwrap())
+                .unwrap();
+            assert!(late.is_empty());
+        }
+
+        assert_eq!(received, 10 * 320);
+    }
+}
@@ -0,0 +1,129 @@
+use super::{Encoder, GenericCtl};
+use crate::{packet::PacketBuf, FrameDuration, Result, Sample, TryFrom};
+
+/// `EncodeFrames` encodes the samples of an iterator frame by frame,
+/// yielding one packet per frame, see [`Encoder::encode_frames`].
+///
+/// The samples are buffered until a frame is complete. Once the samples
+/// run out, a trailing partial frame is padded with silence and encoded.
+///
+/// [`Encoder::encode_frames`]: crate::coder::Encoder::encode_frames
+#[derive(Debug)]
+pub struct EncodeFrames<'a, I, S> {
+    encoder: &'a Encoder,
+    samples: I,
+    /// Interleaved samples per frame.
+    frame_len: usize,
+    frame: Vec<S>,
+}
+
+impl<'a, I, S> EncodeFrames<'a, I, S>
+where
+    I: Iterator<Item = S>,
+    S: Sample,
+{
+    /// Creates a new `EncodeFrames` encoding `samples`, interleaved if 2
+    /// channels, via `encoder` in frames of `frame_duration`.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn new(encoder: &'a Encoder, samples: I, frame_duration: FrameDuration) -> Result<Self> {
+        let frame_len = frame_duration.samples(encoder.sample_rate()?, encoder.channels() as usize);
+
+        Ok(Self {
+            encoder,
+            samples,
+            frame_len,
+            frame: Vec::with_capacity(frame_len),
+        })
+    }
+}
+
+impl<'a, I, S> Iterator for EncodeFrames<'a, I, S>
+where
+    I: Iterator<Item = S>,
+    S: Sample,
+{
+    type Item = Result<PacketBuf>;
+
+    fn next(&mut self) -> Option<Result<PacketBuf>> {
+        self.frame.clear();
+        self.frame
+            .extend(self.samples.by_ref().take(self.frame_len));
+
+        if self.frame.is_empty() {
+            return None;
+        }
+
+        self.frame.resize(self.frame_len, S::default());
+
+        let packet = self
+            .encoder
+            .encode_to_vec(&self.frame)
+            .and_then(PacketBuf::try_from);
+
+        Some(packet)
+    }
+}
+
+impl Encoder {
+    /// Encodes `samples`, interleaved if 2 channels, lazily in frames of
+    /// `frame_duration`, e.g. to transcode as an iterator chain.
+    ///
+    /// A trailing partial frame is padded with silence.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn encode_frames<S, I>(
+        &self,
+        samples: I,
+        frame_duration: FrameDuration,
+    ) -> Result<EncodeFrames<'_, I::IntoIter, S>>
+    where
+        S: Sample,
+        I: IntoIterator<Item = S>,
+    {
+        EncodeFrames::new(self, samples.into_iter(), frame_duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        coder::{DecodeRequest, Decoder, Encoder},
+        Application, Channels, FrameDuration, Result, SampleRate,
+    };
+
+    #[test]
+    fn encode_frame_by_frame() {
+        let encoder =
+            Encoder::new(SampleRate::Hz16000, Channels::Stereo, Application::Audio).unwrap();
+        let mut decoder = Decoder::new(SampleRate::Hz16000, Channels::Stereo).unwrap();
+
+        // 2.5 frames of 10 ms, the last padded.
+        let samples = std::iter::repeat(0_i16).take(800);
+        let packets = encoder
+            .encode_frames(samples, FrameDuration::Ms10)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(packets.len(), 3);
+
+        for packet in &packets {
+            let pcm: Vec<i16> = decoder
+                .decode_to_vec(DecodeRequest::Normal(packet.as_packet()))
+                .unwrap();
+            assert_eq!(pcm.len(), 320);
+        }
+
+        let empty: [f32; 0] = [];
+        let mut frames = encoder
+            .encode_frames(empty.iter().copied(), FrameDuration::Ms20)
+            .unwrap();
+        assert!(frames.next().is_none());
+    }
+}
@@ -0,0 +1,375 @@
+use super::{DecodeRequest, Decoder, DecoderConfig, Encoder, EncoderConfig, GenericCtl};
+use crate::{
+    backend, error::try_map_opus_error, ffi, Application, Bitrate, Channels, Error, ErrorCode,
+    MutSignals, Result, Sample, SampleRate,
+};
+use std::{
+    marker::PhantomData,
+    mem::{self, ManuallyDrop},
+    ops::Deref,
+};
+
+/// Alignment Opus states are placed at within caller-provided storage.
+const STATE_ALIGN: usize = 16;
+
+/// Gets the start of the aligned state of `size` bytes within `storage`.
+///
+/// **Errors**:
+/// Returns [`BufferTooSmall`] if `storage` cannot hold the aligned state.
+///
+/// [`BufferTooSmall`]: crate::error::ErrorCode::BufferTooSmall
+fn aligned_state(storage: &mut [u8], size: usize) -> Result<*mut u8> {
+    let offset = storage.as_ptr().align_offset(STATE_ALIGN);
+
+    if offset == usize::MAX || storage.len() < offset.saturating_add(size) {
+        return Err(Error::Opus(ErrorCode::BufferTooSmall));
+    }
+
+    Ok(unsafe { storage.as_mut_ptr().add(offset) })
+}
+
+/// An [`Encoder`] placed in caller-provided storage instead of being
+/// allocated by Opus, e.g. on the stack or in a pool.
+///
+/// The state lives in the storage and cannot outlive it.
+/// Getters are available via [`Deref`], setters are provided by the type
+/// itself, as moving the borrowed [`Encoder`] out must be prevented.
+///
+/// [`Encoder`]: crate::coder::Encoder
+/// [`Deref`]: std::ops::Deref
+#[derive(Debug)]
+pub struct EncoderInPlace<'a> {
+    encoder: ManuallyDrop<Encoder>,
+    storage: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> EncoderInPlace<'a> {
+    /// Gets the length in bytes storage must have to hold an encoder
+    /// coding `channels`, including room for alignment.
+    pub fn size(channels: Channels) -> usize {
+        unsafe { backend::current().encoder_get_size(channels as i32) as usize + STATE_ALIGN - 1 }
+    }
+
+    /// Creates a new Opus encoder within `storage`.
+    ///
+    /// **Errors**:
+    /// Returns [`BufferTooSmall`] if `storage` is shorter than
+    /// [`EncoderInPlace::size`] and [Error::Opus] when Opus encountered
+    /// a problem.
+    ///
+    /// [`BufferTooSmall`]: crate::error::ErrorCode::BufferTooSmall
+    /// [`EncoderInPlace::size`]: crate::coder::EncoderInPlace::size
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn new(
+        storage: &'a mut [u8],
+        sample_rate: SampleRate,
+        channels: Channels,
+        mode: Application,
+    ) -> Result<Self> {
+        let backend = backend::current();
+        let size = unsafe { backend.encoder_get_size(channels as i32) as usize };
+        let pointer = aligned_state(storage, size)? as *mut ffi::OpusEncoder;
+
+        try_map_opus_error(unsafe {
+            backend.encoder_init(pointer, sample_rate as i32, channels as i32, mode as i32)
+        })?;
+
+        // The state is owned by the storage, `ManuallyDrop` prevents
+        // Opus from freeing it.
+        let encoder = unsafe { Encoder::from_raw_with(pointer, channels, backend) };
+
+        Ok(Self {
+            encoder: ManuallyDrop::new(encoder),
+            storage: PhantomData,
+        })
+    }
+
+    /// Applies every configured setting of `config`, see
+    /// [`EncoderConfig::apply`].
+    ///
+    /// [`EncoderConfig::apply`]: crate::coder::EncoderConfig::apply
+    pub fn configure(&mut self, config: &EncoderConfig) -> Result<()> {
+        config.apply(&mut self.encoder)
+    }
+
+    /// Configures the bitrate of the encoder.
+    pub fn set_bitrate(&mut self, bitrate: Bitrate) -> Result<()> {
+        self.encoder.set_bitrate(bitrate)
+    }
+
+    /// Configures the computational complexity of the encoder.
+    pub fn set_complexity(&mut self, complexity: u8) -> Result<()> {
+        self.encoder.set_complexity(complexity)
+    }
+
+    /// Resets the encoder's state as after construction, while keeping its
+    /// configuration.
+    pub fn reset_state(&mut self) -> Result<()> {
+        self.encoder.reset_state()
+    }
+}
+
+impl Deref for EncoderInPlace<'_> {
+    type Target = Encoder;
+
+    fn deref(&self) -> &Encoder {
+        &self.encoder
+    }
+}
+
+/// A [`Decoder`] placed in caller-provided storage instead of being
+/// allocated by Opus, e.g. on the stack or in a pool.
+///
+/// The state lives in the storage and cannot outlive it.
+/// Getters are available via [`Deref`], decoding and setters are provided by
+/// the type itself, as moving the borrowed [`Decoder`] out must be prevented.
+///
+/// [`Decoder`]: crate::coder::Decoder
+/// [`Deref`]: std::ops::Deref
+#[derive(Debug)]
+pub struct DecoderInPlace<'a> {
+    decoder: ManuallyDrop<Decoder>,
+    storage: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> DecoderInPlace<'a> {
+    /// Gets the length in bytes storage must have to hold a decoder
+    /// decoding `channels`, including room for alignment.
+    pub fn size(channels: Channels) -> usize {
+        unsafe { backend::current().decoder_get_size(channels as i32) as usize + STATE_ALIGN - 1 }
+    }
+
+    /// Creates a new Opus decoder within `storage`.
+    ///
+    /// **Errors**:
+    /// Returns [`BufferTooSmall`] if `storage` is shorter than
+    /// [`DecoderInPlace::size`] and [Error::Opus] when Opus encountered
+    /// a problem.
+    ///
+    /// [`BufferTooSmall`]: crate::error::ErrorCode::BufferTooSmall
+    /// [`DecoderInPlace::size`]: crate::coder::DecoderInPlace::size
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn new(storage: &'a mut [u8], sample_rate: SampleRate, channels: Channels) -> Result<Self> {
+        let backend = backend::current();
+        let size = unsafe { backend.decoder_get_size(channels as i32) as usize };
+        let pointer = aligned_state(storage, size)? as *mut ffi::OpusDecoder;
+
+        try_map_opus_error(unsafe {
+            backend.decoder_init(pointer, sample_rate as i32, channels as i32)
+        })?;
+
+        // The state is owned by the storage, `ManuallyDrop` prevents
+        // Opus from freeing it.
+        let decoder = unsafe { Decoder::from_raw_with(pointer, channels, backend) };
+
+        Ok(Self {
+            decoder: ManuallyDrop::new(decoder),
+            storage: PhantomData,
+        })
+    }
+
+    /// Decodes an Opus packet, see [`Decoder::decode`].
+    ///
+    /// [`Decoder::decode`]: crate::coder::Decoder::decode
+    pub fn decode<S: Sample>(
+        &mut self,
+        request: DecodeRequest<'_>,
+        output: MutSignals<'_, S>,
+    ) -> Result<usize> {
+        self.decoder.decode(request, output)
+    }
+
+    /// Applies every configured setting of `config`, see
+    /// [`DecoderConfig::apply`].
+    ///
+    /// [`DecoderConfig::apply`]: crate::coder::DecoderConfig::apply
+    pub fn configure(&mut self, config: &DecoderConfig) -> Result<()> {
+        config.apply(&mut self.decoder)
+    }
+
+    /// Resets the decoder's state as after construction, while keeping its
+    /// configuration.
+    pub fn reset_state(&mut self) -> Result<()> {
+        self.decoder.reset_state()
+    }
+}
+
+impl Deref for DecoderInPlace<'_> {
+    type Target = Decoder;
+
+    fn deref(&self) -> &Decoder {
+        &self.decoder
+    }
+}
+
+/// `CoderArena` places the states of many encoders and decoders in a single
+/// caller-provided buffer, bounding the memory used by them up front.
+///
+/// Memory is handed out sequentially and returned only once the arena's
+/// storage is released.
+#[derive(Debug)]
+pub struct CoderArena<'a> {
+    free: &'a mut [u8],
+}
+
+impl<'a> CoderArena<'a> {
+    /// Creates a new arena handing out `storage`.
+    pub fn new(storage: &'a mut [u8]) -> Self {
+        Self { free: storage }
+    }
+
+    /// Gets the amount of bytes not handed out yet.
+    pub fn remaining(&self) -> usize {
+        self.free.len()
+    }
+
+    /// Creates a new Opus encoder within the arena, see
+    /// [`EncoderInPlace::new`].
+    ///
+    /// **Errors**:
+    /// Returns [`BufferTooSmall`] if the arena's remaining memory cannot
+    /// hold the encoder and [Error::Opus] when Opus encountered a problem,
+    /// the memory is consumed nonetheless.
+    ///
+    /// [`EncoderInPlace::new`]: crate::coder::EncoderInPlace::new
+    /// [`BufferTooSmall`]: crate::error::ErrorCode::BufferTooSmall
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn encoder(
+        &mut self,
+        sample_rate: SampleRate,
+        channels: Channels,
+        mode: Application,
+    ) -> Result<EncoderInPlace<'a>> {
+        let storage = self.take(EncoderInPlace::size(channels))?;
+
+        EncoderInPlace::new(storage, sample_rate, channels, mode)
+    }
+
+    /// Creates a new Opus decoder within the arena, see
+    /// [`DecoderInPlace::new`].
+    ///
+    /// **Errors**:
+    /// Returns [`BufferTooSmall`] if the arena's remaining memory cannot
+    /// hold the decoder and [Error::Opus] when Opus encountered a problem,
+    /// the memory is consumed nonetheless.
+    ///
+    /// [`DecoderInPlace::new`]: crate::coder::DecoderInPlace::new
+    /// [`BufferTooSmall`]: crate::error::ErrorCode::BufferTooSmall
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn decoder(
+        &mut self,
+        sample_rate: SampleRate,
+        channels: Channels,
+    ) -> Result<DecoderInPlace<'a>> {
+        let storage = self.take(DecoderInPlace::size(channels))?;
+
+        DecoderInPlace::new(storage, sample_rate, channels)
+    }
+
+    /// Splits `len` bytes off the remaining memory.
+    fn take(&mut self, len: usize) -> Result<&'a mut [u8]> {
+        if len > self.free.len() {
+            return Err(Error::Opus(ErrorCode::BufferTooSmall));
+        }
+
+        let (taken, free) = mem::take(&mut self.free).split_at_mut(len);
+        self.free = free;
+
+        Ok(taken)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CoderArena, DecoderInPlace, EncoderInPlace};
+    use crate::{
+        coder::{DecodeRequest, EncoderConfig},
+        packet::Packet,
+        Application, Bitrate, Channels, Error, ErrorCode, MutSignals, SampleRate,
+    };
+    use matches::assert_matches;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn coding_in_place() {
+        let mut encoder_storage = vec![0; EncoderInPlace::size(Channels::Stereo)];
+        let mut encoder = EncoderInPlace::new(
+            &mut encoder_storage,
+            SampleRate::Hz48000,
+            Channels::Stereo,
+            Application::Audio,
+        )
+        .unwrap();
+
+        encoder
+            .configure(&EncoderConfig {
+                bitrate: Some(Bitrate::BitsPerSecond(64000)),
+                ..EncoderConfig::default()
+            })
+            .unwrap();
+        assert_matches!(encoder.bitrate(), Ok(Bitrate::BitsPerSecond(64000)));
+
+        let mut decoder_storage = vec![0; DecoderInPlace::size(Channels::Stereo)];
+        let mut decoder =
+            DecoderInPlace::new(&mut decoder_storage, SampleRate::Hz48000, Channels::Stereo)
+                .unwrap();
+
+        let silence = [0_i16; 960 * 2];
+        let mut packet = [0; 4000];
+        let mut output = [0_i16; 960 * 2];
+
+        let len = encoder.encode(&silence, &mut packet).unwrap();
+        let decoded = decoder
+            .decode(
+                DecodeRequest::Normal(Packet::try_from(&packet[..len]).unwrap()),
+                MutSignals::try_from(&mut output[..]).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(decoded, 960);
+    }
+
+    #[test]
+    fn storage_too_small() {
+        let mut storage = vec![0; 64];
+
+        assert_matches!(
+            EncoderInPlace::new(
+                &mut storage,
+                SampleRate::Hz48000,
+                Channels::Stereo,
+                Application::Audio,
+            ),
+            Err(Error::Opus(ErrorCode::BufferTooSmall))
+        );
+        assert_matches!(
+            DecoderInPlace::new(&mut storage, SampleRate::Hz48000, Channels::Stereo),
+            Err(Error::Opus(ErrorCode::BufferTooSmall))
+        );
+    }
+
+    #[test]
+    fn arena_allocation() {
+        let encoder_size = EncoderInPlace::size(Channels::Mono);
+        let decoder_size = DecoderInPlace::size(Channels::Mono);
+        let mut storage = vec![0; encoder_size * 2 + decoder_size];
+        let mut arena = CoderArena::new(&mut storage);
+
+        let first = arena
+            .encoder(SampleRate::Hz48000, Channels::Mono, Application::Voip)
+            .unwrap();
+        let second = arena
+            .encoder(SampleRate::Hz16000, Channels::Mono, Application::Audio)
+            .unwrap();
+        let decoder = arena.decoder(SampleRate::Hz48000, Channels::Mono).unwrap();
+        assert_eq!(arena.remaining(), 0);
+
+        assert_matches!(first.application(), Ok(Application::Voip));
+        assert_matches!(second.application(), Ok(Application::Audio));
+        assert_matches!(decoder.gain(), Ok(0));
+
+        assert_matches!(
+            arena.decoder(SampleRate::Hz48000, Channels::Mono),
+            Err(Error::Opus(ErrorCode::BufferTooSmall))
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Multistream coders combine several Opus streams into a single packet,
+//! allowing more than two channels to be coded, e.g. for surround sound.
+//!
+//! Each stream is either coupled (stereo) or uncoupled (mono). A
+//! [`ChannelMapping`] assigns every channel to a stream channel.
+//!
+//! [`ChannelMapping`]: crate::coder::multistream::ChannelMapping
+
+pub use self::{
+    decoder::{DecoderStreamHandle, MultistreamDecoder},
+    encoder::{EncoderStreamHandle, MultistreamEncoder},
+};
+
+mod decoder;
+mod encoder;
+
+use crate::{Error, Result, TryFrom};
+
+/// Represents the channel mapping families defined by RFC 7845 and
+/// the ambisonics extension RFC 8486.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum MappingFamily {
+    /// Mono or stereo as used by RTP, one stream.
+    Rtp = 0,
+    /// Up to eight channels in Vorbis channel order.
+    Vorbis = 1,
+    /// Ambisonics with each channel coded individually.
+    Ambisonics = 2,
+    /// Ambisonics coded via a mixing matrix, used by projection coders.
+    AmbisonicsProjection = 3,
+    /// Any amount of uncoupled channels without a defined meaning.
+    Undefined = 255,
+}
+
+impl TryFrom<u8> for MappingFamily {
+    type Error = Error;
+
+    /// Fails if a value does not match a defined mapping family.
+    fn try_from(value: u8) -> Result<Self> {
+        Ok(match value {
+            0 => MappingFamily::Rtp,
+            1 => MappingFamily::Vorbis,
+            2 => MappingFamily::Ambisonics,
+            3 => MappingFamily::AmbisonicsProjection,
+            255 => MappingFamily::Undefined,
+            _ => return Err(Error::InvalidMappingFamily(value)),
+        })
+    }
+}
+
+impl From<MappingFamily> for u8 {
+    fn from(family: MappingFamily) -> u8 {
+        family as u8
+    }
+}
+
+/// A mapping table assigning each channel to a stream channel, alongside the
+/// stream layout the table refers to.
+///
+/// The construction verifies Opus' requirements:
+/// - The table contains exactly one entry per channel.
+/// - At least one stream exists and coupled streams do not exceed streams.
+/// - Every entry refers to an existing stream channel or is `255`,
+///   marking the channel as silent.
+///
+/// Coupled streams occupy the first `2 * coupled_streams` stream channels.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ChannelMapping {
+    streams: u8,
+    coupled_streams: u8,
+    mapping: Vec<u8>,
+}
+
+impl ChannelMapping {
+    /// Creates a new mapping of `channels` channels onto `streams` streams,
+    /// of which `coupled_streams` are coupled.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::MappingExpectedLen`] when `mapping`'s length does not
+    /// equal `channels`, [`Error::InvalidChannels`] when no channel is
+    /// requested, [`Error::InvalidStreams`] when the stream counts cannot be
+    /// represented by Opus, and [`Error::InvalidMappingIndex`] if an entry
+    /// refers to a non-existent stream channel.
+    ///
+    /// [`Error::MappingExpectedLen`]: crate::error::Error::MappingExpectedLen
+    /// [`Error::InvalidChannels`]: crate::error::Error::InvalidChannels
+    /// [`Error::InvalidStreams`]: crate::error::Error::InvalidStreams
+    /// [`Error::InvalidMappingIndex`]: crate::error::Error::InvalidMappingIndex
+    pub fn new(channels: u8, streams: u8, coupled_streams: u8, mapping: &[u8]) -> Result<Self> {
+        if channels == 0 {
+            return Err(Error::InvalidChannels(0));
+        }
+
+        // Coupled streams contribute two channels, uncoupled streams only one.
+        let stream_channels = usize::from(streams) + usize::from(coupled_streams);
+
+        if streams == 0 || coupled_streams > streams || stream_channels > 255 {
+            return Err(Error::InvalidStreams {
+                streams,
+                coupled_streams,
+            });
+        }
+
+        if mapping.len() != usize::from(channels) {
+            return Err(Error::MappingExpectedLen(usize::from(channels)));
+        }
+
+        if let Some(&index) = mapping
+            .iter()
+            .find(|&&index| index != 255 && usize::from(index) >= stream_channels)
+        {
+            return Err(Error::InvalidMappingIndex(index));
+        }
+
+        Ok(Self {
+            streams,
+            coupled_streams,
+            mapping: mapping.to_vec(),
+        })
+    }
+
+    /// Creates the mono mapping of [`MappingFamily::Vorbis`], one
+    /// uncoupled stream.
+    ///
+    /// [`MappingFamily::Vorbis`]: crate::coder::multistream::MappingFamily::Vorbis
+    pub fn mono() -> Self {
+        Self::vorbis(1, 0, &[0])
+    }
+
+    /// Creates the stereo mapping of [`MappingFamily::Vorbis`], one
+    /// coupled stream carrying left and right.
+    ///
+    /// [`MappingFamily::Vorbis`]: crate::coder::multistream::MappingFamily::Vorbis
+    pub fn stereo() -> Self {
+        Self::vorbis(1, 1, &[0, 1])
+    }
+
+    /// Creates the quadraphonic mapping of [`MappingFamily::Vorbis`].
+    ///
+    /// The channel order is front left, front right, rear left, rear right,
+    /// coded as two coupled streams.
+    ///
+    /// [`MappingFamily::Vorbis`]: crate::coder::multistream::MappingFamily::Vorbis
+    pub fn quad() -> Self {
+        Self::vorbis(2, 2, &[0, 1, 2, 3])
+    }
+
+    /// Creates the 5.1 surround mapping of [`MappingFamily::Vorbis`].
+    ///
+    /// The channel order is front left, front centre, front right,
+    /// rear left, rear right, LFE. Both front and rear pairs are coupled,
+    /// centre and LFE are coded as uncoupled streams.
+    ///
+    /// [`MappingFamily::Vorbis`]: crate::coder::multistream::MappingFamily::Vorbis
+    pub fn surround_5_1() -> Self {
+        Self::vorbis(4, 2, &[0, 4, 1, 2, 3, 5])
+    }
+
+    /// Creates the 7.1 surround mapping of [`MappingFamily::Vorbis`].
+    ///
+    /// The channel order is front left, front centre, front right,
+    /// side left, side right, rear left, rear right, LFE. The front, side, and
+    /// rear pairs are coupled, centre and LFE are coded as uncoupled streams.
+    ///
+    /// [`MappingFamily::Vorbis`]: crate::coder::multistream::MappingFamily::Vorbis
+    pub fn surround_7_1() -> Self {
+        Self::vorbis(5, 3, &[0, 6, 1, 2, 3, 4, 5, 7])
+    }
+
+    /// Builds one of the fixed tables of RFC 7845, section 5.1.1.2.
+    ///
+    /// These tables are known to be valid, hence no verification is required.
+    fn vorbis(streams: u8, coupled_streams: u8, mapping: &[u8]) -> Self {
+        Self {
+            streams,
+            coupled_streams,
+            mapping: mapping.to_vec(),
+        }
+    }
+
+    /// Gets the amount of channels, equal to the table's length.
+    ///
+    /// The length has been verified upon construction to fit into `u8`.
+    pub fn channels(&self) -> u8 {
+        self.mapping.len() as u8
+    }
+
+    /// Gets the total amount of streams.
+    pub fn streams(&self) -> u8 {
+        self.streams
+    }
+
+    /// Gets how many of the streams are coupled (stereo) streams.
+    pub fn coupled_streams(&self) -> u8 {
+        self.coupled_streams
+    }
+
+    /// Gets the mapping table.
+    pub fn mapping(&self) -> &[u8] {
+        &self.mapping
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChannelMapping, MappingFamily};
+    use crate::{Error, TryFrom};
+    use matches::assert_matches;
+
+    #[test]
+    fn mapping_family_try_from() {
+        assert_matches!(MappingFamily::try_from(0), Ok(MappingFamily::Rtp));
+        assert_matches!(MappingFamily::try_from(1), Ok(MappingFamily::Vorbis));
+        assert_matches!(MappingFamily::try_from(255), Ok(MappingFamily::Undefined));
+        assert_matches!(
+            MappingFamily::try_from(4),
+            Err(Error::InvalidMappingFamily(4))
+        );
+    }
+
+    #[test]
+    fn channel_mapping_construction() {
+        assert_matches!(
+            ChannelMapping::new(0, 1, 0, &[]),
+            Err(Error::InvalidChannels(0))
+        );
+
+        assert_matches!(
+            ChannelMapping::new(3, 2, 1, &[0, 1]),
+            Err(Error::MappingExpectedLen(3))
+        );
+
+        assert_matches!(
+            ChannelMapping::new(3, 2, 1, &[0, 1, 3]),
+            Err(Error::InvalidMappingIndex(3))
+        );
+
+        assert_matches!(
+            ChannelMapping::new(3, 1, 2, &[0, 1, 2]),
+            Err(Error::InvalidStreams { .. })
+        );
+
+        let mapping = ChannelMapping::new(3, 2, 1, &[0, 1, 255]).unwrap();
+        assert_eq!(mapping.channels(), 3);
+        assert_eq!(mapping.mapping(), &[0, 1, 255]);
+    }
+
+    #[test]
+    fn channel_mapping_presets() {
+        let presets = [
+            ChannelMapping::mono(),
+            ChannelMapping::stereo(),
+            ChannelMapping::quad(),
+            ChannelMapping::surround_5_1(),
+            ChannelMapping::surround_7_1(),
+        ];
+
+        for preset in presets.iter() {
+            let verified = ChannelMapping::new(
+                preset.channels(),
+                preset.streams(),
+                preset.coupled_streams(),
+                preset.mapping(),
+            );
+
+            assert_eq!(verified.as_ref(), Ok(preset));
+        }
+
+        assert_eq!(ChannelMapping::surround_5_1().channels(), 6);
+        assert_eq!(ChannelMapping::surround_7_1().channels(), 8);
+    }
+}
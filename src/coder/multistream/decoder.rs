@@ -0,0 +1,465 @@
+use super::ChannelMapping;
+use crate::{
+    buffers,
+    coder::{DecodeRequest, Decoder, GenericCtl},
+    error::try_map_opus_error,
+    ffi, Channels, Error, ErrorCode, MutSignals, Result, Sample, SampleRate, TryFrom,
+};
+use std::{marker::PhantomData, mem::ManuallyDrop};
+
+/// `MultistreamDecoder` to decode multistream packets into more than two
+/// channels.
+#[derive(Debug)]
+pub struct MultistreamDecoder {
+    pointer: *mut ffi::OpusMSDecoder,
+    channels: u8,
+    streams: u8,
+    coupled_streams: u8,
+}
+
+/// The Opus multistream decoder can be sent between threads unless the Opus
+/// library has been compiled with `NONTHREADSAFE_PSEUDOSTACK` to disallow
+/// decoding in parallel.
+unsafe impl Send for MultistreamDecoder {}
+
+impl GenericCtl for MultistreamDecoder {
+    fn final_range(&self) -> Result<u32> {
+        self.decoder_ctl_request(ffi::OPUS_GET_FINAL_RANGE_REQUEST)
+            .map(|v| v as u32)
+    }
+
+    fn phase_inversion_disabled(&self) -> Result<bool> {
+        self.decoder_ctl_request(ffi::OPUS_GET_PHASE_INVERSION_DISABLED_REQUEST)
+            .map(|b| b == 1)
+    }
+
+    fn set_phase_inversion_disabled(&mut self, disabled: bool) -> Result<()> {
+        let disable_phase_inversion = if disabled { 1 } else { 0 };
+        self.set_decoder_ctl_request(
+            ffi::OPUS_SET_PHASE_INVERSION_DISABLED_REQUEST,
+            disable_phase_inversion,
+        )
+    }
+
+    fn sample_rate(&self) -> Result<SampleRate> {
+        self.decoder_ctl_request(ffi::OPUS_GET_SAMPLE_RATE_REQUEST)
+            .and_then(SampleRate::try_from)
+    }
+
+    fn reset_state(&mut self) -> Result<()> {
+        self.decoder_ctl_request(ffi::OPUS_RESET_STATE).map(|_| ())
+    }
+}
+
+impl MultistreamDecoder {
+    /// Creates a new Opus multistream decoder restoring the channels from
+    /// streams as described by `mapping`.
+    pub fn new(sample_rate: SampleRate, mapping: &ChannelMapping) -> Result<MultistreamDecoder> {
+        let channels = mapping.channels();
+        let mut opus_code = 0;
+
+        // The `mapping` has been verified to contain exactly `channels`
+        // entries, hence Opus will not read past its end.
+        let pointer = unsafe {
+            ffi::opus_multistream_decoder_create(
+                sample_rate as i32,
+                i32::from(channels),
+                i32::from(mapping.streams()),
+                i32::from(mapping.coupled_streams()),
+                mapping.mapping().as_ptr(),
+                &mut opus_code,
+            )
+        };
+
+        if opus_code == ffi::OPUS_OK && !pointer.is_null() {
+            return Ok(MultistreamDecoder {
+                pointer,
+                channels,
+                streams: mapping.streams(),
+                coupled_streams: mapping.coupled_streams(),
+            });
+        }
+
+        Err(ErrorCode::from(opus_code).into())
+    }
+
+    /// Gets the amount of channels the decoder writes as output.
+    pub fn channels(&self) -> u8 {
+        self.channels
+    }
+
+    /// Gets a pointer to the Opus multistream decoder state, e.g. to hand it to
+    /// another C library.
+    pub fn as_ptr(&self) -> *const ffi::OpusMSDecoder {
+        self.pointer
+    }
+
+    /// Gets a mutable pointer to the Opus multistream decoder state.
+    pub fn as_mut_ptr(&mut self) -> *mut ffi::OpusMSDecoder {
+        self.pointer
+    }
+
+    /// Borrows the decoder state of the stream at `stream_id`.
+    ///
+    /// Coupled streams come first and are stereo, the remaining streams
+    /// are mono.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidStreamId`] if `stream_id` is not lower than
+    /// the amount of streams.
+    ///
+    /// [`Error::InvalidStreamId`]: crate::error::Error::InvalidStreamId
+    pub fn stream_decoder(&mut self, stream_id: u8) -> Result<DecoderStreamHandle<'_>> {
+        if stream_id >= self.streams {
+            return Err(Error::InvalidStreamId(stream_id));
+        }
+
+        let mut stream_pointer: *mut ffi::OpusDecoder = std::ptr::null_mut();
+
+        try_map_opus_error(unsafe {
+            ffi::opus_multistream_decoder_ctl(
+                self.pointer,
+                ffi::OPUS_MULTISTREAM_GET_DECODER_STATE_REQUEST,
+                i32::from(stream_id),
+                &mut stream_pointer,
+            )
+        })?;
+
+        let channels = if stream_id < self.coupled_streams {
+            Channels::Stereo
+        } else {
+            Channels::Mono
+        };
+
+        // Opus returned a pointer to a valid decoder state owned by the
+        // multistream decoder, `ManuallyDrop` prevents destroying it.
+        let decoder = unsafe { Decoder::from_raw(stream_pointer, channels) };
+
+        Ok(DecoderStreamHandle {
+            decoder: ManuallyDrop::new(decoder),
+            parent: PhantomData,
+        })
+    }
+
+    /// Decodes the multistream packet or recovers the loss described by
+    /// `request` and writes interleaved decoded data into `output`.
+    ///
+    /// The samples are either `i16` or `f32`, see [`Sample`].
+    ///
+    /// **Errors**:
+    /// Returns [`Error::SignalsExpectedLen`] if `output` cannot hold the
+    /// duration of a [`DecodeRequest::Lost`] and [Error::Opus] when Opus
+    /// encountered a problem.
+    ///
+    /// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+    /// [`DecodeRequest::Lost`]: crate::coder::DecodeRequest::Lost
+    /// [Error::Opus]: crate::error::Error::Opus
+    /// [`Sample`]: crate::sample::Sample
+    pub fn decode<S: Sample>(
+        &mut self,
+        request: DecodeRequest<'_>,
+        mut output: MutSignals<'_, S>,
+    ) -> Result<usize> {
+        let frame_size = request.frame_size(
+            usize::from(self.channels),
+            output.i32_len(),
+            || self.sample_rate(),
+            || self.last_packet_duration(),
+        )?;
+
+        let (input_pointer, input_len) = if let Some(value) = request.packet() {
+            (value.as_ptr(), value.i32_len())
+        } else {
+            (std::ptr::null(), 0)
+        };
+
+        try_map_opus_error(unsafe {
+            S::multistream_decode(
+                self.pointer,
+                input_pointer,
+                input_len,
+                output.as_mut_ptr(),
+                frame_size,
+                request.fec() as i32,
+            )
+        })
+        .map(|n| n as usize)
+    }
+
+    /// Decodes the multistream packet or recovers the loss described by
+    /// `request` and writes the decoded data into the planar `output`, one
+    /// slice per channel.
+    ///
+    /// The packet is decoded into an interleaved scratch buffer first, on
+    /// success returns the amount of decoded samples per channel.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidChannels`] if `output` does not contain a
+    /// slice for every channel, [`Error::SignalsExpectedLen`] if it cannot
+    /// hold the duration of a [`DecodeRequest::Lost`], and [Error::Opus] when
+    /// Opus encountered a problem.
+    ///
+    /// [`Error::InvalidChannels`]: crate::error::Error::InvalidChannels
+    /// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+    /// [`DecodeRequest::Lost`]: crate::coder::DecodeRequest::Lost
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn decode_planar<S: Sample>(
+        &mut self,
+        request: DecodeRequest<'_>,
+        output: &mut [&mut [S]],
+    ) -> Result<usize> {
+        let channels = usize::from(self.channels);
+
+        if output.len() != channels {
+            return Err(Error::InvalidChannels(output.len() as i32));
+        }
+
+        let samples = output
+            .iter()
+            .map(|channel| channel.len())
+            .min()
+            .unwrap_or(0);
+        let mut interleaved = vec![S::default(); samples * channels];
+
+        let decoded = self.decode(request, MutSignals::try_from(&mut interleaved)?)?;
+        buffers::deinterleave(&interleaved[..decoded * channels], output)?;
+
+        Ok(decoded)
+    }
+
+    /// Issues a CTL `request` to Opus without argument used to
+    /// request a value.
+    /// If Opus returns a value smaller than 0, it indicates an error.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    fn decoder_ctl_request(&self, request: i32) -> Result<i32> {
+        let mut value = 0;
+
+        let ffi_result =
+            unsafe { ffi::opus_multistream_decoder_ctl(self.pointer, request, &mut value) };
+
+        try_map_opus_error(ffi_result)?;
+
+        Ok(value)
+    }
+
+    /// Issues a CTL `request` to Opus accepting an additional argument used
+    /// to set the `decoder`'s setting to `value`.
+    /// If Opus returns a value smaller than 0, it indicates an error.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    fn set_decoder_ctl_request(&self, request: i32, value: i32) -> Result<()> {
+        try_map_opus_error(unsafe {
+            ffi::opus_multistream_decoder_ctl(self.pointer, request, value)
+        })?;
+
+        Ok(())
+    }
+
+    /// Gets the duration (in samples) of the last packet successfully decoded
+    /// or concealed.
+    pub fn last_packet_duration(&self) -> Result<u32> {
+        self.decoder_ctl_request(ffi::OPUS_GET_LAST_PACKET_DURATION_REQUEST)
+            .map(|v| v as u32)
+    }
+
+    /// Gets the decoder's configured amount to scale PCM signal by
+    /// in Q8 dB units.
+    pub fn gain(&self) -> Result<i32> {
+        self.decoder_ctl_request(ffi::OPUS_GET_GAIN_REQUEST)
+    }
+
+    /// Configures decoder gain adjustment of all streams.
+    ///
+    /// Scales the decoded output by a factor of `gain` specified in
+    /// Q8 dB units.
+    ///
+    /// **Warning**:
+    /// This has a maximum range of -32768 to 32767 inclusive, and returns
+    /// [`BadArgument`] otherwise.
+    ///
+    /// [`BadArgument`]: crate::error::ErrorCode::BadArgument
+    pub fn set_gain(&self, gain: i32) -> Result<()> {
+        self.set_decoder_ctl_request(ffi::OPUS_SET_GAIN_REQUEST, gain)
+    }
+
+    /// Gets size of an Opus multistream decoder with the given stream layout
+    /// in bytes.
+    pub fn size(streams: u8, coupled_streams: u8) -> usize {
+        unsafe {
+            ffi::opus_multistream_decoder_get_size(i32::from(streams), i32::from(coupled_streams))
+                as usize
+        }
+    }
+}
+
+/// A [`Decoder`] borrowed from a [`MultistreamDecoder`], decoding a single
+/// stream.
+///
+/// The state is owned by the multistream decoder and cannot outlive it.
+/// Only the stream's CTLs are exposed, decoding must go through the
+/// [`MultistreamDecoder`] to keep its state consistent.
+///
+/// [`Decoder`]: crate::coder::Decoder
+/// [`MultistreamDecoder`]: crate::coder::multistream::MultistreamDecoder
+#[derive(Debug)]
+pub struct DecoderStreamHandle<'a> {
+    decoder: ManuallyDrop<Decoder>,
+    parent: PhantomData<&'a mut MultistreamDecoder>,
+}
+
+impl DecoderStreamHandle<'_> {
+    /// Gets the channels of this stream, stereo if coupled.
+    pub fn channels(&self) -> Channels {
+        self.decoder.channels()
+    }
+
+    /// Gets the final state of this stream's entropy coder.
+    pub fn final_range(&self) -> Result<u32> {
+        self.decoder.final_range()
+    }
+
+    /// Gets the duration in samples of the last packet of this stream
+    /// decoded or concealed.
+    pub fn last_packet_duration(&self) -> Result<u32> {
+        self.decoder.last_packet_duration()
+    }
+
+    /// Gets the pitch period at 48 kHz of the last frame of this stream
+    /// decoded, zero if unavailable.
+    pub fn pitch(&self) -> Result<i32> {
+        self.decoder.pitch()
+    }
+
+    /// Gets the gain of this stream in Q8 dB units.
+    pub fn gain(&self) -> Result<i32> {
+        self.decoder.gain()
+    }
+
+    /// Configures the gain of this stream in Q8 dB units.
+    pub fn set_gain(&mut self, gain: i32) -> Result<()> {
+        self.decoder.set_gain(gain)
+    }
+}
+
+impl Drop for MultistreamDecoder {
+    /// We have to ensure that the resource our wrapping Opus-struct is pointing
+    /// to is deallocated properly.
+    fn drop(&mut self) {
+        unsafe { ffi::opus_multistream_decoder_destroy(self.pointer) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChannelMapping, MultistreamDecoder};
+    use crate::{
+        coder::{multistream::MultistreamEncoder, DecodeRequest},
+        packet::Packet,
+        Application, Error, MutSignals, SampleRate,
+    };
+    use std::{convert::TryFrom, time::Duration};
+
+    #[test]
+    fn multistream_decoder_construction() {
+        let mapping = ChannelMapping::new(3, 2, 1, &[0, 1, 255]).unwrap();
+
+        let decoder = MultistreamDecoder::new(SampleRate::Hz48000, &mapping)
+            .expect("Could not create three channel decoder with a silent channel.");
+        assert_eq!(decoder.channels(), 3);
+    }
+
+    #[test]
+    fn stream_decoder_access() {
+        let mut decoder =
+            MultistreamDecoder::new(SampleRate::Hz48000, &ChannelMapping::surround_5_1()).unwrap();
+
+        assert!(decoder.stream_decoder(4).is_err());
+
+        let mut front = decoder.stream_decoder(0).unwrap();
+        assert_eq!(front.gain().unwrap(), 0);
+        front.set_gain(256).unwrap();
+        assert_eq!(front.gain().unwrap(), 256);
+    }
+
+    #[test]
+    fn multistream_round_trip() {
+        let mapping = ChannelMapping::new(3, 2, 1, &[0, 1, 2]).unwrap();
+        let mut encoder =
+            MultistreamEncoder::new(SampleRate::Hz48000, &mapping, Application::Audio).unwrap();
+        let mut decoder = MultistreamDecoder::new(SampleRate::Hz48000, &mapping).unwrap();
+
+        // 48000Hz * 3 channels * 20 ms / 1000
+        const THREE_CHANNELS_20MS: usize = 48000 * 3 * 20 / 1000;
+        let input = [0_i16; THREE_CHANNELS_20MS];
+        let mut packet = [0; 512];
+
+        let len = encoder.encode(&input, &mut packet).unwrap();
+
+        let mut output = vec![0_i16; THREE_CHANNELS_20MS];
+        let samples = decoder
+            .decode(
+                DecodeRequest::Normal(Packet::try_from(&packet[..len]).unwrap()),
+                MutSignals::try_from(&mut output).unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(samples, THREE_CHANNELS_20MS / 3);
+
+        let lost = DecodeRequest::Lost {
+            duration: Some(Duration::from_millis(10)),
+        };
+        assert_eq!(
+            decoder
+                .decode(lost, MutSignals::try_from(&mut output).unwrap())
+                .unwrap(),
+            THREE_CHANNELS_20MS / 6
+        );
+
+        let mut short = vec![0_i16; 3];
+        assert_eq!(
+            decoder.decode(lost, MutSignals::try_from(&mut short).unwrap()),
+            Err(Error::SignalsExpectedLen(THREE_CHANNELS_20MS / 2))
+        );
+    }
+
+    #[test]
+    fn multistream_planar_round_trip() {
+        let mapping = ChannelMapping::new(3, 2, 1, &[0, 1, 2]).unwrap();
+        let mut encoder =
+            MultistreamEncoder::new(SampleRate::Hz48000, &mapping, Application::Audio).unwrap();
+        let mut decoder = MultistreamDecoder::new(SampleRate::Hz48000, &mapping).unwrap();
+
+        // 48000Hz * 20 ms / 1000
+        const CHANNEL_20MS: usize = 48000 * 20 / 1000;
+        let channel = [0_f32; CHANNEL_20MS];
+        let mut packet = [0; 512];
+
+        let len = encoder
+            .encode_planar(&[&channel[..], &channel[..], &channel[..]], &mut packet)
+            .unwrap();
+
+        assert!(encoder
+            .encode_planar(&[&channel[..], &channel[..]], &mut packet)
+            .is_err());
+
+        let mut first = vec![1_f32; CHANNEL_20MS];
+        let mut second = vec![1_f32; CHANNEL_20MS];
+        let mut third = vec![1_f32; CHANNEL_20MS];
+
+        let samples = decoder
+            .decode_planar(
+                DecodeRequest::Normal(Packet::try_from(&packet[..len]).unwrap()),
+                &mut [&mut first[..], &mut second[..], &mut third[..]],
+            )
+            .unwrap();
+
+        assert_eq!(samples, CHANNEL_20MS);
+    }
+}
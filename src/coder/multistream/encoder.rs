@@ -0,0 +1,742 @@
+use super::{ChannelMapping, MappingFamily};
+use crate::{
+    buffers,
+    coder::{encoder::LSB_DEPTHS, Encoder, GenericCtl},
+    error::try_map_opus_error,
+    ffi, Application, Bandwidth, Bitrate, Channels, Error, ErrorCode, Result, Sample, SampleRate,
+    Signal, TryFrom,
+};
+use std::{marker::PhantomData, mem::ManuallyDrop};
+
+/// `MultistreamEncoder` calls to Opus and offers methods to encode more than
+/// two channels into a single multistream packet and issue requests to Opus.
+#[derive(Debug)]
+pub struct MultistreamEncoder {
+    pointer: *mut ffi::OpusMSEncoder,
+    channels: u8,
+    streams: u8,
+    coupled_streams: u8,
+}
+
+/// The Opus multistream encoder can be sent between threads unless the Opus
+/// library has been compiled with `NONTHREADSAFE_PSEUDOSTACK` to disallow
+/// encoding in parallel.
+unsafe impl Send for MultistreamEncoder {}
+
+impl GenericCtl for MultistreamEncoder {
+    /// Gets the final state of the codec's entropy coder.
+    fn final_range(&self) -> Result<u32> {
+        self.encoder_ctl_request(ffi::OPUS_GET_FINAL_RANGE_REQUEST)
+            .map(|v| v as u32)
+    }
+
+    /// Gets the encoder's configured phase inversion status.
+    fn phase_inversion_disabled(&self) -> Result<bool> {
+        self.encoder_ctl_request(ffi::OPUS_GET_PHASE_INVERSION_DISABLED_REQUEST)
+            .map(|b| b == 1)
+    }
+
+    /// If set to `true`, disables the use of phase inversion for intensity
+    /// stereo in all coupled streams.
+    fn set_phase_inversion_disabled(&mut self, disabled: bool) -> Result<()> {
+        let disable_phase_inversion = if disabled { 1 } else { 0 };
+        self.set_encoder_ctl_request(
+            ffi::OPUS_SET_PHASE_INVERSION_DISABLED_REQUEST,
+            disable_phase_inversion,
+        )
+    }
+
+    /// Gets the sampling rate the encoder was initialized with.
+    fn sample_rate(&self) -> Result<SampleRate> {
+        self.encoder_ctl_request(ffi::OPUS_GET_SAMPLE_RATE_REQUEST)
+            .and_then(SampleRate::try_from)
+    }
+
+    /// Resets the codec state of every stream to be equivalent to a freshly
+    /// initialized state.
+    fn reset_state(&mut self) -> Result<()> {
+        self.encoder_ctl_request(ffi::OPUS_RESET_STATE).map(|_| ())
+    }
+}
+
+impl MultistreamEncoder {
+    /// Creates a new Opus multistream encoder encoding the channels into
+    /// streams as described by `mapping`.
+    pub fn new(
+        sample_rate: SampleRate,
+        mapping: &ChannelMapping,
+        mode: Application,
+    ) -> Result<MultistreamEncoder> {
+        let channels = mapping.channels();
+        let mut opus_code = 0;
+
+        // The `mapping` has been verified to contain exactly `channels`
+        // entries, hence Opus will not read past its end.
+        let pointer = unsafe {
+            ffi::opus_multistream_encoder_create(
+                sample_rate as i32,
+                i32::from(channels),
+                i32::from(mapping.streams()),
+                i32::from(mapping.coupled_streams()),
+                mapping.mapping().as_ptr(),
+                mode as i32,
+                &mut opus_code,
+            )
+        };
+
+        if opus_code == ffi::OPUS_OK && !pointer.is_null() {
+            return Ok(MultistreamEncoder {
+                pointer,
+                channels,
+                streams: mapping.streams(),
+                coupled_streams: mapping.coupled_streams(),
+            });
+        }
+
+        Err(ErrorCode::from(opus_code).into())
+    }
+
+    /// Creates a new Opus multistream encoder for surround sound, letting Opus
+    /// pick the stream layout for `channels` according to `mapping_family`.
+    ///
+    /// [`MappingFamily::Rtp`] accepts one or two channels,
+    /// [`MappingFamily::Vorbis`] up to eight channels,
+    /// [`MappingFamily::Ambisonics`] ambisonics channel counts, and
+    /// [`MappingFamily::Undefined`] any amount of uncoupled channels.
+    ///
+    /// On success, returns the encoder and the [`ChannelMapping`] Opus picked,
+    /// which is required to construct a matching decoder.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidChannels`] if `channels` is zero and
+    /// [`Unimplemented`] if Opus cannot map the channels with the given family.
+    ///
+    /// [`MappingFamily::Rtp`]: crate::coder::multistream::MappingFamily::Rtp
+    /// [`MappingFamily::Vorbis`]: crate::coder::multistream::MappingFamily::Vorbis
+    /// [`MappingFamily::Ambisonics`]: crate::coder::multistream::MappingFamily::Ambisonics
+    /// [`MappingFamily::Undefined`]: crate::coder::multistream::MappingFamily::Undefined
+    /// [`ChannelMapping`]: crate::coder::multistream::ChannelMapping
+    /// [`Error::InvalidChannels`]: crate::error::Error::InvalidChannels
+    /// [`Unimplemented`]: crate::error::ErrorCode::Unimplemented
+    pub fn new_surround(
+        sample_rate: SampleRate,
+        channels: u8,
+        mapping_family: MappingFamily,
+        mode: Application,
+    ) -> Result<(MultistreamEncoder, ChannelMapping)> {
+        if channels == 0 {
+            return Err(Error::InvalidChannels(0));
+        }
+
+        let mut opus_code = 0;
+        let mut streams = 0;
+        let mut coupled_streams = 0;
+        let mut mapping = vec![0_u8; usize::from(channels)];
+
+        // Opus writes exactly `channels` entries into `mapping`, which has
+        // been allocated with that length.
+        let pointer = unsafe {
+            ffi::opus_multistream_surround_encoder_create(
+                sample_rate as i32,
+                i32::from(channels),
+                i32::from(u8::from(mapping_family)),
+                &mut streams,
+                &mut coupled_streams,
+                mapping.as_mut_ptr(),
+                mode as i32,
+                &mut opus_code,
+            )
+        };
+
+        if opus_code == ffi::OPUS_OK && !pointer.is_null() {
+            let mapping = ChannelMapping {
+                streams: streams as u8,
+                coupled_streams: coupled_streams as u8,
+                mapping,
+            };
+
+            let encoder = MultistreamEncoder {
+                pointer,
+                channels,
+                streams: mapping.streams,
+                coupled_streams: mapping.coupled_streams,
+            };
+
+            return Ok((encoder, mapping));
+        }
+
+        Err(ErrorCode::from(opus_code).into())
+    }
+
+    /// Gets the amount of channels the encoder expects as input.
+    pub fn channels(&self) -> u8 {
+        self.channels
+    }
+
+    /// Gets a pointer to the Opus multistream encoder state, e.g. to hand it to
+    /// another C library.
+    pub fn as_ptr(&self) -> *const ffi::OpusMSEncoder {
+        self.pointer
+    }
+
+    /// Gets a mutable pointer to the Opus multistream encoder state.
+    pub fn as_mut_ptr(&mut self) -> *mut ffi::OpusMSEncoder {
+        self.pointer
+    }
+
+    /// Borrows the encoder state of the stream at `stream_id`.
+    ///
+    /// This allows applying CTLs to a single stream, e.g. forcing the LFE
+    /// stream to mono. Coupled streams come first and are stereo, the
+    /// remaining streams are mono.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidStreamId`] if `stream_id` is not lower than
+    /// the amount of streams.
+    ///
+    /// [`Error::InvalidStreamId`]: crate::error::Error::InvalidStreamId
+    pub fn stream_encoder(&mut self, stream_id: u8) -> Result<EncoderStreamHandle<'_>> {
+        if stream_id >= self.streams {
+            return Err(Error::InvalidStreamId(stream_id));
+        }
+
+        let mut stream_pointer: *mut ffi::OpusEncoder = std::ptr::null_mut();
+
+        try_map_opus_error(unsafe {
+            ffi::opus_multistream_encoder_ctl(
+                self.pointer,
+                ffi::OPUS_MULTISTREAM_GET_ENCODER_STATE_REQUEST,
+                i32::from(stream_id),
+                &mut stream_pointer,
+            )
+        })?;
+
+        let channels = if stream_id < self.coupled_streams {
+            Channels::Stereo
+        } else {
+            Channels::Mono
+        };
+
+        // Opus returned a pointer to a valid encoder state owned by the
+        // multistream encoder, `ManuallyDrop` prevents destroying it.
+        let encoder = unsafe { Encoder::from_raw(stream_pointer, channels) };
+
+        Ok(EncoderStreamHandle {
+            encoder: ManuallyDrop::new(encoder),
+            parent: PhantomData,
+        })
+    }
+
+    /// Issues a CTL get-`request` to Opus.
+    /// If Opus returns a negative value it indicates an error.
+    ///
+    /// **Info**:
+    /// As [`MultistreamEncoder`]'s methods cover the standard CTLs, it is
+    /// recommended to use them instead.
+    ///
+    /// [`MultistreamEncoder`]: crate::coder::multistream::MultistreamEncoder
+    pub fn encoder_ctl_request(&self, request: i32) -> Result<i32> {
+        let mut value = 0;
+
+        let ffi_result =
+            unsafe { ffi::opus_multistream_encoder_ctl(self.pointer, request, &mut value) };
+        try_map_opus_error(ffi_result)?;
+
+        Ok(value)
+    }
+
+    /// Issues a CTL set-`request` to Opus and sets the `MultistreamEncoder`'s
+    /// setting to `value` based on sent `request`.
+    /// If Opus returns a negative value it indicates an error.
+    ///
+    /// **Info**:
+    /// As [`MultistreamEncoder`]'s methods cover the standard CTLs, it is
+    /// recommended to use them instead.
+    ///
+    /// [`MultistreamEncoder`]: crate::coder::multistream::MultistreamEncoder
+    pub fn set_encoder_ctl_request(&mut self, request: i32, value: i32) -> Result<()> {
+        try_map_opus_error(unsafe {
+            ffi::opus_multistream_encoder_ctl(self.pointer, request, value)
+        })?;
+
+        Ok(())
+    }
+
+    /// Encodes a multistream Opus frame.
+    ///
+    /// The interleaved `input` signal will be encoded into the `output`
+    /// payload and on success returns the length of the encoded packet.
+    ///
+    /// The samples are either `i16` or `f32`, see [`Sample`].
+    ///
+    /// [`Sample`]: crate::sample::Sample
+    pub fn encode<S: Sample>(&mut self, input: &[S], output: &mut [u8]) -> Result<usize> {
+        try_map_opus_error(unsafe {
+            S::multistream_encode(
+                self.pointer,
+                input.as_ptr(),
+                input.len() as i32 / i32::from(self.channels),
+                output.as_mut_ptr(),
+                output.len() as i32,
+            )
+        })
+        .map(|n| n as usize)
+    }
+
+    /// Encodes a multistream Opus frame from planar `input`, one slice per
+    /// channel.
+    ///
+    /// The channels are interleaved into a scratch buffer before encoding
+    /// them into the `output` payload, on success returns the length of the
+    /// encoded packet.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidChannels`] if `input` does not contain a slice
+    /// for every channel and [`Error::SignalsExpectedLen`] if the slices
+    /// differ in length.
+    ///
+    /// [`Error::InvalidChannels`]: crate::error::Error::InvalidChannels
+    /// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+    pub fn encode_planar<S: Sample>(&mut self, input: &[&[S]], output: &mut [u8]) -> Result<usize> {
+        if input.len() != usize::from(self.channels) {
+            return Err(Error::InvalidChannels(input.len() as i32));
+        }
+
+        let samples = input.first().map_or(0, |channel| channel.len());
+        let mut interleaved = vec![S::default(); samples * input.len()];
+        buffers::interleave(input, &mut interleaved)?;
+
+        self.encode(&interleaved, output)
+    }
+
+    /// Gets the encoder's complexity configuration.
+    pub fn complexity(&self) -> Result<u8> {
+        self.encoder_ctl_request(ffi::OPUS_GET_COMPLEXITY_REQUEST)
+            .map(|v| v as u8)
+    }
+
+    /// Configures the encoder's computational complexity.
+    ///
+    /// **Warning**:
+    /// If `complexity` exceeds 10, [`BadArgument`] will be returned.
+    ///
+    /// [`BadArgument`]: crate::error::ErrorCode::BadArgument
+    pub fn set_complexity(&mut self, complexity: u8) -> Result<()> {
+        self.set_encoder_ctl_request(ffi::OPUS_SET_COMPLEXITY_REQUEST, i32::from(complexity))
+    }
+
+    /// Gets the encoder's configured application.
+    pub fn application(&self) -> Result<Application> {
+        self.encoder_ctl_request(ffi::OPUS_GET_APPLICATION_REQUEST)
+            .and_then(Application::try_from)
+    }
+
+    /// Configures the encoder's intended application.
+    pub fn set_application(&mut self, application: Application) -> Result<()> {
+        self.set_encoder_ctl_request(ffi::OPUS_SET_APPLICATION_REQUEST, application as i32)
+    }
+
+    /// Configures the total bitrate shared by all streams.
+    pub fn set_bitrate(&mut self, bitrate: Bitrate) -> Result<()> {
+        self.set_encoder_ctl_request(ffi::OPUS_SET_BITRATE_REQUEST, bitrate.into())
+    }
+
+    /// Gets the encoder's configured total bitrate.
+    pub fn bitrate(&self) -> Result<Bitrate> {
+        self.encoder_ctl_request(ffi::OPUS_GET_BITRATE_REQUEST)
+            .and_then(Bitrate::try_from)
+    }
+
+    /// Sets variable bitrate (VBR) in the encoder.
+    pub fn set_vbr(&mut self, enable: bool) -> Result<()> {
+        let if_vbr_shall_be_enabled = if enable { 1 } else { 0 };
+
+        self.set_encoder_ctl_request(ffi::OPUS_SET_VBR_REQUEST, if_vbr_shall_be_enabled)
+    }
+
+    /// Determine if variable bitrate (VBR) is enabled in the encoder.
+    pub fn vbr(&self) -> Result<bool> {
+        self.encoder_ctl_request(ffi::OPUS_GET_VBR_REQUEST)
+            .map(|b| b == 1)
+    }
+
+    /// Sets constrained variable bitrate (VBR) in the encoder.
+    pub fn set_vbr_constraint(&mut self, enable: bool) -> Result<()> {
+        let if_vbr_shall_be_enabled = if enable { 1 } else { 0 };
+
+        self.set_encoder_ctl_request(
+            ffi::OPUS_SET_VBR_CONSTRAINT_REQUEST,
+            if_vbr_shall_be_enabled,
+        )
+    }
+
+    /// Determine if constrained VBR is enabled in the encoder.
+    pub fn vbr_constraint(&self) -> Result<bool> {
+        self.encoder_ctl_request(ffi::OPUS_GET_VBR_CONSTRAINT_REQUEST)
+            .map(|b| b == 1)
+    }
+
+    /// Configures the encoder's use of inband forward error correction (FEC).
+    pub fn set_inband_fec(&mut self, enable: bool) -> Result<()> {
+        let if_inband_fec_shall_be_enabled = if enable { 1 } else { 0 };
+
+        self.set_encoder_ctl_request(
+            ffi::OPUS_SET_INBAND_FEC_REQUEST,
+            if_inband_fec_shall_be_enabled,
+        )
+    }
+
+    /// Gets encoder's configured use of inband forward error correction.
+    pub fn inband_fec(&self) -> Result<bool> {
+        self.encoder_ctl_request(ffi::OPUS_GET_INBAND_FEC_REQUEST)
+            .map(|n| n == 1)
+    }
+
+    /// Gets the encoder's configured packet loss percentage.
+    pub fn packet_loss_perc(&self) -> Result<u8> {
+        self.encoder_ctl_request(ffi::OPUS_GET_PACKET_LOSS_PERC_REQUEST)
+            .map(|n| n as u8)
+    }
+
+    /// Configures the encoder's expected packet loss percentage.
+    pub fn set_packet_loss_perc(&mut self, percentage: u8) -> Result<()> {
+        self.set_encoder_ctl_request(
+            ffi::OPUS_SET_PACKET_LOSS_PERC_REQUEST,
+            i32::from(percentage),
+        )
+    }
+
+    /// Gets encoder's configured use of discontinuous transmission.
+    pub fn dtx(&self) -> Result<bool> {
+        self.encoder_ctl_request(ffi::OPUS_GET_DTX_REQUEST)
+            .map(|n| n == 1)
+    }
+
+    /// Configures the encoder's use of discontinuous transmission (DTX).
+    pub fn set_dtx(&mut self, dtx: bool) -> Result<()> {
+        let dtx_shall_be_enabled = if dtx { 1 } else { 0 };
+
+        self.set_encoder_ctl_request(ffi::OPUS_SET_DTX_REQUEST, dtx_shall_be_enabled)
+    }
+
+    /// Gets the encoder's configured signal type.
+    pub fn signal(&self) -> Result<Signal> {
+        self.encoder_ctl_request(ffi::OPUS_GET_SIGNAL_REQUEST)
+            .and_then(Signal::try_from)
+    }
+
+    /// Configures the type of signal being encoded.
+    pub fn set_signal(&mut self, signal: Signal) -> Result<()> {
+        self.set_encoder_ctl_request(ffi::OPUS_SET_SIGNAL_REQUEST, signal as i32)
+    }
+
+    /// Gets the encoder's configured bandpass.
+    pub fn bandwidth(&self) -> Result<Bandwidth> {
+        self.encoder_ctl_request(ffi::OPUS_GET_BANDWIDTH_REQUEST)
+            .and_then(Bandwidth::try_from)
+    }
+
+    /// Sets the encoder's bandpass to a specific value.
+    pub fn set_bandwidth(&mut self, bandwidth: Bandwidth) -> Result<()> {
+        self.set_encoder_ctl_request(ffi::OPUS_SET_BANDWIDTH_REQUEST, bandwidth as i32)
+    }
+
+    /// Gets the encoder's configured maximum allowed bandpass.
+    pub fn max_bandwidth(&self) -> Result<Bandwidth> {
+        self.encoder_ctl_request(ffi::OPUS_GET_MAX_BANDWIDTH_REQUEST)
+            .and_then(Bandwidth::try_from)
+    }
+
+    /// Configures the maximum bandpass that the encoder will select
+    /// automatically.
+    pub fn set_max_bandwidth(&mut self, bandwidth: Bandwidth) -> Result<()> {
+        self.set_encoder_ctl_request(ffi::OPUS_SET_MAX_BANDWIDTH_REQUEST, bandwidth as i32)
+    }
+
+    /// Gets the encoder's configured signal depth.
+    pub fn lsb_depth(&self) -> Result<u8> {
+        self.encoder_ctl_request(ffi::OPUS_GET_LSB_DEPTH_REQUEST)
+            .map(|n| n as u8)
+    }
+
+    /// Configures the depth of signal being encoded.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidLsbDepth`] if `lsb_depth` is not within
+    /// 8 to 24 inclusive.
+    ///
+    /// [`Error::InvalidLsbDepth`]: crate::error::Error::InvalidLsbDepth
+    pub fn set_lsb_depth(&mut self, lsb_depth: u8) -> Result<()> {
+        if !LSB_DEPTHS.contains(&lsb_depth) {
+            return Err(Error::InvalidLsbDepth(lsb_depth));
+        }
+
+        self.set_encoder_ctl_request(ffi::OPUS_SET_LSB_DEPTH_REQUEST, i32::from(lsb_depth))
+    }
+
+    /// Gets the total samples of delay added by the entire codec.
+    pub fn lookahead(&self) -> Result<u32> {
+        self.encoder_ctl_request(ffi::OPUS_GET_LOOKAHEAD_REQUEST)
+            .map(|n| n as u32)
+    }
+
+    /// Gets the encoder's configured prediction status.
+    pub fn prediction_disabled(&self) -> Result<bool> {
+        self.encoder_ctl_request(ffi::OPUS_GET_PREDICTION_DISABLED_REQUEST)
+            .map(|n| n == 1)
+    }
+
+    /// If set `prediction_disabled` to `true`, disables almost all use of
+    /// prediction, making frames almost completely independent, so that
+    /// receivers can join a stream in progress.
+    ///
+    /// This reduces quality.
+    pub fn set_prediction_disabled(&mut self, prediction_disabled: bool) -> Result<()> {
+        let prediction_disabled = if prediction_disabled { 1 } else { 0 };
+
+        self.set_encoder_ctl_request(
+            ffi::OPUS_SET_PREDICTION_DISABLED_REQUEST,
+            prediction_disabled,
+        )
+    }
+
+    /// Gets size of an Opus multistream encoder with the given stream layout
+    /// in bytes.
+    pub fn size(streams: u8, coupled_streams: u8) -> usize {
+        unsafe {
+            ffi::opus_multistream_encoder_get_size(i32::from(streams), i32::from(coupled_streams))
+                as usize
+        }
+    }
+}
+
+/// An [`Encoder`] borrowed from a [`MultistreamEncoder`], coding a single
+/// stream.
+///
+/// The state is owned by the multistream encoder and cannot outlive it.
+/// Only the stream's CTLs are exposed, encoding must go through the
+/// [`MultistreamEncoder`] to keep its state consistent.
+///
+/// [`Encoder`]: crate::coder::Encoder
+/// [`MultistreamEncoder`]: crate::coder::multistream::MultistreamEncoder
+#[derive(Debug)]
+pub struct EncoderStreamHandle<'a> {
+    encoder: ManuallyDrop<Encoder>,
+    parent: PhantomData<&'a mut MultistreamEncoder>,
+}
+
+impl EncoderStreamHandle<'_> {
+    /// Gets the channels of this stream, stereo if coupled.
+    pub fn channels(&self) -> Channels {
+        self.encoder.channels()
+    }
+
+    /// Gets the final state of this stream's entropy coder.
+    pub fn final_range(&self) -> Result<u32> {
+        self.encoder.final_range()
+    }
+
+    /// Issues a CTL get-`request` to Opus for this stream.
+    /// If Opus returns a negative value it indicates an error.
+    pub fn encoder_ctl_request(&self, request: i32) -> Result<i32> {
+        self.encoder.encoder_ctl_request(request)
+    }
+
+    /// Issues a CTL set-`request` to Opus for this stream.
+    /// If Opus returns a negative value it indicates an error.
+    pub fn set_encoder_ctl_request(&mut self, request: i32, value: i32) -> Result<()> {
+        self.encoder.set_encoder_ctl_request(request, value)
+    }
+
+    /// Gets the mono/stereo forcing of this stream.
+    pub fn force_channels(&self) -> Result<Channels> {
+        self.encoder.force_channels()
+    }
+
+    /// Configures mono/stereo forcing of this stream, `None` lets the
+    /// encoder choose.
+    pub fn set_force_channels<C>(&mut self, channels: C) -> Result<()>
+    where
+        C: Into<Option<Channels>>,
+    {
+        self.encoder.set_force_channels(channels)
+    }
+
+    /// Gets the bitrate of this stream.
+    pub fn bitrate(&self) -> Result<Bitrate> {
+        self.encoder.bitrate()
+    }
+
+    /// Configures the bitrate of this stream.
+    pub fn set_bitrate(&mut self, bitrate: Bitrate) -> Result<()> {
+        self.encoder.set_bitrate(bitrate)
+    }
+
+    /// Gets the computational complexity of this stream.
+    pub fn complexity(&self) -> Result<u8> {
+        self.encoder.complexity()
+    }
+
+    /// Configures the computational complexity of this stream.
+    pub fn set_complexity(&mut self, complexity: u8) -> Result<()> {
+        self.encoder.set_complexity(complexity)
+    }
+
+    /// Gets the bandpass of this stream.
+    pub fn bandwidth(&self) -> Result<Bandwidth> {
+        self.encoder.bandwidth()
+    }
+
+    /// Sets the bandpass of this stream to a specific value.
+    pub fn set_bandwidth(&mut self, bandwidth: Bandwidth) -> Result<()> {
+        self.encoder.set_bandwidth(bandwidth)
+    }
+
+    /// Gets the maximum bandpass this stream selects automatically.
+    pub fn max_bandwidth(&self) -> Result<Bandwidth> {
+        self.encoder.max_bandwidth()
+    }
+
+    /// Configures the maximum bandpass this stream will select automatically.
+    pub fn set_max_bandwidth(&mut self, bandwidth: Bandwidth) -> Result<()> {
+        self.encoder.set_max_bandwidth(bandwidth)
+    }
+
+    /// Gets the type of signal being encoded in this stream.
+    pub fn signal(&self) -> Result<Signal> {
+        self.encoder.signal()
+    }
+
+    /// Configures the type of signal being encoded in this stream.
+    pub fn set_signal(&mut self, signal: Signal) -> Result<()> {
+        self.encoder.set_signal(signal)
+    }
+
+    /// Gets whether variable bitrate (VBR) of this stream is enabled.
+    pub fn vbr(&self) -> Result<bool> {
+        self.encoder.vbr()
+    }
+
+    /// Sets variable bitrate (VBR) of this stream.
+    pub fn set_vbr(&mut self, enable: bool) -> Result<()> {
+        self.encoder.set_vbr(enable)
+    }
+
+    /// Gets whether inband forward error correction (FEC) of this stream
+    /// is enabled.
+    pub fn inband_fec(&self) -> Result<bool> {
+        self.encoder.inband_fec()
+    }
+
+    /// Configures the use of inband forward error correction (FEC) of this
+    /// stream.
+    pub fn set_inband_fec(&mut self, enable: bool) -> Result<()> {
+        self.encoder.set_inband_fec(enable)
+    }
+
+    /// Gets the expected packet loss percentage of this stream.
+    pub fn packet_loss_perc(&self) -> Result<u8> {
+        self.encoder.packet_loss_perc()
+    }
+
+    /// Configures the expected packet loss percentage of this stream.
+    pub fn set_packet_loss_perc(&mut self, percentage: u8) -> Result<()> {
+        self.encoder.set_packet_loss_perc(percentage)
+    }
+}
+
+impl Drop for MultistreamEncoder {
+    /// We have to ensure that the resource our wrapping Opus-struct is pointing
+    /// to is deallocated properly.
+    fn drop(&mut self) {
+        unsafe { ffi::opus_multistream_encoder_destroy(self.pointer) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChannelMapping, MappingFamily, MultistreamEncoder};
+    use crate::{Application, Bitrate, Channels, Error, ErrorCode, SampleRate};
+    use matches::assert_matches;
+
+    #[test]
+    fn multistream_encoder_construction() {
+        let mapping = ChannelMapping::new(3, 2, 1, &[0, 1, 2]).unwrap();
+
+        let encoder = MultistreamEncoder::new(SampleRate::Hz48000, &mapping, Application::Audio)
+            .expect("Could not create three channel encoder.");
+        assert_eq!(encoder.channels(), 3);
+    }
+
+    #[test]
+    fn stream_encoder_access() {
+        let (mut encoder, _) = MultistreamEncoder::new_surround(
+            SampleRate::Hz48000,
+            6,
+            MappingFamily::Vorbis,
+            Application::Audio,
+        )
+        .unwrap();
+
+        assert_matches!(encoder.stream_encoder(4), Err(Error::InvalidStreamId(4)));
+
+        let mut lfe = encoder.stream_encoder(3).unwrap();
+        lfe.set_force_channels(Channels::Mono)
+            .expect("Could not force LFE stream to mono.");
+        assert_matches!(lfe.force_channels(), Ok(Channels::Mono));
+    }
+
+    #[test]
+    fn surround_encoder_construction() {
+        assert_matches!(
+            MultistreamEncoder::new_surround(
+                SampleRate::Hz48000,
+                0,
+                MappingFamily::Vorbis,
+                Application::Audio
+            ),
+            Err(Error::InvalidChannels(0))
+        );
+
+        assert_matches!(
+            MultistreamEncoder::new_surround(
+                SampleRate::Hz48000,
+                3,
+                MappingFamily::Rtp,
+                Application::Audio
+            ),
+            Err(Error::Opus(ErrorCode::Unimplemented))
+        );
+
+        let (_, mapping) = MultistreamEncoder::new_surround(
+            SampleRate::Hz48000,
+            6,
+            MappingFamily::Vorbis,
+            Application::Audio,
+        )
+        .expect("Could not create 5.1 surround encoder.");
+
+        assert_eq!(mapping.streams(), 4);
+        assert_eq!(mapping.coupled_streams(), 2);
+        assert_eq!(mapping.mapping(), &[0, 4, 1, 2, 3, 5]);
+    }
+
+    #[test]
+    fn multistream_encoding() {
+        let mapping = ChannelMapping::new(3, 2, 1, &[0, 1, 2]).unwrap();
+        let mut encoder =
+            MultistreamEncoder::new(SampleRate::Hz48000, &mapping, Application::Audio).unwrap();
+
+        encoder
+            .set_bitrate(Bitrate::BitsPerSecond(128000))
+            .expect("Could not set bitrate.");
+
+        // 48000Hz * 3 channels * 20 ms / 1000
+        const THREE_CHANNELS_20MS: usize = 48000 * 3 * 20 / 1000;
+        let input = [0_i16; THREE_CHANNELS_20MS];
+        let mut output = [0; 512];
+
+        let len = encoder.encode(&input, &mut output).unwrap();
+        assert!(len > 0);
+    }
+}
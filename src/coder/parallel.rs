@@ -0,0 +1,54 @@
+use super::Encoder;
+use crate::{packet::PacketBuf, Result, Sample, TryFrom};
+use rayon::prelude::*;
+
+/// Encodes independent streams in parallel on rayon's thread pool, e.g. a
+/// mixer's frames for each listener.
+///
+/// Each encoder encodes the frame at the same index, surplus encoders or
+/// frames are left out. Returns the packets in the order of `encoders`.
+///
+/// **Errors**:
+/// Each stream fails on its own, see [`Encoder::encode_to_vec`].
+///
+/// [`Encoder::encode_to_vec`]: crate::coder::Encoder::encode_to_vec
+pub fn encode_streams<S, F>(encoders: &mut [Encoder], frames: &[F]) -> Vec<Result<PacketBuf>>
+where
+    S: Sample + Sync,
+    F: AsRef<[S]> + Sync,
+{
+    encoders
+        .par_iter_mut()
+        .zip(frames.par_iter())
+        .map(|(encoder, frame)| {
+            let packet = encoder.encode_to_vec(frame.as_ref())?;
+
+            PacketBuf::try_from(packet)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encode_streams;
+    use crate::{coder::Encoder, Application, Channels, Error, SampleRate};
+    use matches::assert_matches;
+
+    #[test]
+    fn encode_in_parallel() {
+        let mut encoders: Vec<Encoder> = (0..8)
+            .map(|_| Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Audio).unwrap())
+            .collect();
+        let mut frames = vec![vec![0_i16; 960]; 8];
+        frames[3].truncate(100);
+
+        let packets = encode_streams(&mut encoders, &frames);
+
+        assert_eq!(packets.len(), 8);
+        assert_matches!(packets[3], Err(Error::Opus(_)));
+        assert!(packets
+            .iter()
+            .enumerate()
+            .all(|(i, packet)| i == 3 || packet.is_ok()));
+    }
+}
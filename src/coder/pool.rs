@@ -0,0 +1,235 @@
+use super::{DecodeRequest, Decoder};
+use crate::{Channels, MutSignals, Result, SampleRate, TryFrom};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+/// The most samples per channel an Opus packet decodes to, 120 ms at
+/// 48 kHz.
+const MAX_FRAME_SIZE: usize = 5760;
+
+/// Statistics of one stream decoded by a [`DecoderPool`].
+///
+/// [`DecoderPool`]: crate::coder::DecoderPool
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct StreamStats {
+    /// Packets decoded, including FEC recoveries.
+    pub packets: u64,
+    /// Losses concealed.
+    pub concealed: u64,
+    /// Requests Opus failed to decode.
+    pub errors: u64,
+    /// Samples per channel output.
+    pub samples: u64,
+}
+
+#[derive(Debug)]
+struct Stream {
+    decoder: Decoder,
+    stats: StreamStats,
+    last_used: Instant,
+}
+
+/// `DecoderPool` manages one [`Decoder`] per stream of e.g. a conference,
+/// keyed by an arbitrary ID such as the SSRC.
+///
+/// Decoders are created on a stream's first packet. Once the pool is full,
+/// the decoder used least recently is evicted, idle decoders can be evicted
+/// via [`evict_idle`]. All streams decode into one shared scratch buffer.
+///
+/// [`Decoder`]: crate::coder::Decoder
+/// [`evict_idle`]: crate::coder::DecoderPool::evict_idle
+#[derive(Debug)]
+pub struct DecoderPool<K> {
+    sample_rate: SampleRate,
+    channels: Channels,
+    capacity: usize,
+    streams: HashMap<K, Stream>,
+    scratch: Vec<f32>,
+}
+
+impl<K: Eq + Hash + Clone> DecoderPool<K> {
+    /// Creates a new `DecoderPool` holding at most `capacity` decoders, each
+    /// decoding to `sample_rate` and `channels`.
+    pub fn new(sample_rate: SampleRate, channels: Channels, capacity: usize) -> Self {
+        Self {
+            sample_rate,
+            channels,
+            capacity: capacity.max(1),
+            streams: HashMap::new(),
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Gets the number of decoders alive.
+    pub fn len(&self) -> usize {
+        self.streams.len()
+    }
+
+    /// Whether no decoder is alive.
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    /// Whether a decoder for `id` is alive.
+    pub fn contains(&self, id: &K) -> bool {
+        self.streams.contains_key(id)
+    }
+
+    /// Gets the statistics of the stream `id`, `None` if it has no decoder.
+    pub fn stats(&self, id: &K) -> Option<StreamStats> {
+        self.streams.get(id).map(|stream| stream.stats)
+    }
+
+    /// Decodes `request` of the stream `id` into the shared scratch buffer,
+    /// creating the stream's decoder if needed.
+    ///
+    /// Returns the decoded samples, interleaved if 2 channels, valid until
+    /// the next call.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn decode(&mut self, id: K, request: DecodeRequest<'_>) -> Result<&[f32]> {
+        let channels = self.channels as usize;
+
+        // Taken to decode into while a stream is borrowed.
+        let mut scratch = std::mem::take(&mut self.scratch);
+        scratch.resize(MAX_FRAME_SIZE * channels, 0.0);
+
+        let result = self.decode_into(id, request, &mut scratch);
+        self.scratch = scratch;
+        let samples = result?;
+
+        Ok(&self.scratch[..samples * channels])
+    }
+
+    /// Removes the decoder of the stream `id`, returning its statistics.
+    pub fn remove(&mut self, id: &K) -> Option<StreamStats> {
+        self.streams.remove(id).map(|stream| stream.stats)
+    }
+
+    /// Evicts the decoders unused for `max_idle` or longer, returning how
+    /// many were evicted.
+    pub fn evict_idle(&mut self, max_idle: Duration) -> usize {
+        let len = self.streams.len();
+        let now = Instant::now();
+
+        self.streams
+            .retain(|_, stream| now.saturating_duration_since(stream.last_used) < max_idle);
+
+        len - self.streams.len()
+    }
+
+    /// Decodes `request` of the stream `id` into `output`, tracking the
+    /// stream's statistics.
+    fn decode_into(
+        &mut self,
+        id: K,
+        request: DecodeRequest<'_>,
+        output: &mut [f32],
+    ) -> Result<usize> {
+        let stream = self.stream(id)?;
+        let output = MutSignals::try_from(output)?;
+
+        let samples = match stream.decoder.decode(request, output) {
+            Ok(samples) => samples,
+            Err(error) => {
+                stream.stats.errors += 1;
+
+                return Err(error);
+            }
+        };
+
+        match request {
+            DecodeRequest::Lost { .. } => stream.stats.concealed += 1,
+            _ => stream.stats.packets += 1,
+        }
+
+        stream.stats.samples += samples as u64;
+
+        Ok(samples)
+    }
+
+    /// Gets the stream `id`, creating its decoder and evicting the least
+    /// recently used one if the pool is full.
+    fn stream(&mut self, id: K) -> Result<&mut Stream> {
+        if !self.streams.contains_key(&id) && self.streams.len() >= self.capacity {
+            let oldest = self
+                .streams
+                .iter()
+                .min_by_key(|(_, stream)| stream.last_used)
+                .map(|(id, _)| id.clone());
+
+            if let Some(oldest) = oldest {
+                self.streams.remove(&oldest);
+            }
+        }
+
+        let stream = match self.streams.entry(id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(Stream {
+                decoder: Decoder::new(self.sample_rate, self.channels)?,
+                stats: StreamStats::default(),
+                last_used: Instant::now(),
+            }),
+        };
+
+        stream.last_used = Instant::now();
+
+        Ok(stream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DecoderPool;
+    use crate::{
+        coder::{DecodeRequest, Encoder},
+        packet::Packet,
+        Application, Channels, SampleRate, TryFrom,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn decode_per_stream() {
+        let encoder = Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Voip).unwrap();
+        let packet = encoder.encode_to_vec(&[0_i16; 960]).unwrap();
+        let packet = Packet::try_from(&packet).unwrap();
+
+        let mut pool = DecoderPool::new(SampleRate::Hz48000, Channels::Mono, 2);
+
+        assert_eq!(
+            pool.decode(1_u32, DecodeRequest::Normal(packet))
+                .unwrap()
+                .len(),
+            960
+        );
+        assert_eq!(
+            pool.decode(2, DecodeRequest::Normal(packet)).unwrap().len(),
+            960
+        );
+        let lost = DecodeRequest::Lost {
+            duration: Some(Duration::from_millis(20)),
+        };
+        assert_eq!(pool.decode(1, lost).unwrap().len(), 960);
+
+        let stats = pool.stats(&1).unwrap();
+        assert_eq!(
+            (stats.packets, stats.concealed, stats.samples),
+            (1, 1, 1920)
+        );
+
+        // Stream 2 was used least recently and makes room for stream 3.
+        pool.decode(3, DecodeRequest::Normal(packet)).unwrap();
+        assert_eq!(pool.len(), 2);
+        assert!(!pool.contains(&2));
+
+        assert!(pool.remove(&3).is_some());
+        assert_eq!(pool.evict_idle(Duration::from_secs(0)), 1);
+        assert!(pool.is_empty());
+    }
+}
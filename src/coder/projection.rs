@@ -0,0 +1,10 @@
+//! Projection coders encode ambisonics content by mixing the input channels
+//! into a set of Opus streams with a mixing matrix.
+//!
+//! The decoder requires the demixing matrix produced by the encoder to
+//! restore the original channels.
+
+pub use self::{decoder::ProjectionDecoder, encoder::ProjectionEncoder};
+
+mod decoder;
+mod encoder;
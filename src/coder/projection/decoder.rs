@@ -0,0 +1,289 @@
+use crate::{
+    coder::{DecodeRequest, GenericCtl},
+    error::try_map_opus_error,
+    ffi, Error, ErrorCode, MutSignals, Result, Sample, SampleRate, TryFrom,
+};
+
+/// `ProjectionDecoder` to decode projection packets back into ambisonics
+/// channels.
+#[derive(Debug)]
+pub struct ProjectionDecoder {
+    pointer: *mut ffi::OpusProjectionDecoder,
+    channels: u8,
+}
+
+/// The Opus projection decoder can be sent between threads unless the Opus
+/// library has been compiled with `NONTHREADSAFE_PSEUDOSTACK` to disallow
+/// decoding in parallel.
+unsafe impl Send for ProjectionDecoder {}
+
+impl GenericCtl for ProjectionDecoder {
+    fn final_range(&self) -> Result<u32> {
+        self.decoder_ctl_request(ffi::OPUS_GET_FINAL_RANGE_REQUEST)
+            .map(|v| v as u32)
+    }
+
+    fn phase_inversion_disabled(&self) -> Result<bool> {
+        self.decoder_ctl_request(ffi::OPUS_GET_PHASE_INVERSION_DISABLED_REQUEST)
+            .map(|b| b == 1)
+    }
+
+    fn set_phase_inversion_disabled(&mut self, disabled: bool) -> Result<()> {
+        let disable_phase_inversion = if disabled { 1 } else { 0 };
+        self.set_decoder_ctl_request(
+            ffi::OPUS_SET_PHASE_INVERSION_DISABLED_REQUEST,
+            disable_phase_inversion,
+        )
+    }
+
+    fn sample_rate(&self) -> Result<SampleRate> {
+        self.decoder_ctl_request(ffi::OPUS_GET_SAMPLE_RATE_REQUEST)
+            .and_then(SampleRate::try_from)
+    }
+
+    fn reset_state(&mut self) -> Result<()> {
+        self.decoder_ctl_request(ffi::OPUS_RESET_STATE).map(|_| ())
+    }
+}
+
+impl ProjectionDecoder {
+    /// Creates a new Opus projection decoder.
+    ///
+    /// `streams` and `coupled_streams` must match the layout of the
+    /// [`ProjectionEncoder`] and `demixing_matrix` must be the matrix
+    /// retrieved via [`ProjectionEncoder::demixing_matrix`].
+    ///
+    /// **Errors**:
+    /// Returns [`Error::DemixingMatrixExpectedLen`] if the matrix' length does
+    /// not match `channels` and the stream layout.
+    ///
+    /// [`ProjectionEncoder`]: crate::coder::projection::ProjectionEncoder
+    /// [`ProjectionEncoder::demixing_matrix`]: crate::coder::projection::ProjectionEncoder::demixing_matrix
+    /// [`Error::DemixingMatrixExpectedLen`]: crate::error::Error::DemixingMatrixExpectedLen
+    pub fn new(
+        sample_rate: SampleRate,
+        channels: u8,
+        streams: u8,
+        coupled_streams: u8,
+        demixing_matrix: &[u8],
+    ) -> Result<ProjectionDecoder> {
+        if channels == 0 {
+            return Err(Error::InvalidChannels(0));
+        }
+
+        if streams == 0 || coupled_streams > streams {
+            return Err(Error::InvalidStreams {
+                streams,
+                coupled_streams,
+            });
+        }
+
+        // Every matrix entry is a 16-bit value mapping one stream channel
+        // to one output channel.
+        let expected_len =
+            usize::from(channels) * (usize::from(streams) + usize::from(coupled_streams)) * 2;
+
+        if demixing_matrix.len() != expected_len {
+            return Err(Error::DemixingMatrixExpectedLen(expected_len));
+        }
+
+        let mut opus_code = 0;
+
+        // Opus only reads from the demixing matrix despite expecting a
+        // mutable pointer and its length has been verified above.
+        let pointer = unsafe {
+            ffi::opus_projection_decoder_create(
+                sample_rate as i32,
+                i32::from(channels),
+                i32::from(streams),
+                i32::from(coupled_streams),
+                demixing_matrix.as_ptr() as *mut u8,
+                demixing_matrix.len() as i32,
+                &mut opus_code,
+            )
+        };
+
+        if opus_code == ffi::OPUS_OK && !pointer.is_null() {
+            return Ok(ProjectionDecoder { pointer, channels });
+        }
+
+        Err(ErrorCode::from(opus_code).into())
+    }
+
+    /// Gets the amount of channels the decoder writes as output.
+    pub fn channels(&self) -> u8 {
+        self.channels
+    }
+
+    /// Gets a pointer to the Opus projection decoder state, e.g. to hand it to
+    /// another C library.
+    pub fn as_ptr(&self) -> *const ffi::OpusProjectionDecoder {
+        self.pointer
+    }
+
+    /// Gets a mutable pointer to the Opus projection decoder state.
+    pub fn as_mut_ptr(&mut self) -> *mut ffi::OpusProjectionDecoder {
+        self.pointer
+    }
+
+    /// Decodes the projection packet or recovers the loss described by
+    /// `request` and writes interleaved decoded data into `output`.
+    ///
+    /// The samples are either `i16` or `f32`, see [`Sample`].
+    ///
+    /// **Errors**:
+    /// Returns [`Error::SignalsExpectedLen`] if `output` cannot hold the
+    /// duration of a [`DecodeRequest::Lost`] and [Error::Opus] when Opus
+    /// encountered a problem.
+    ///
+    /// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+    /// [`DecodeRequest::Lost`]: crate::coder::DecodeRequest::Lost
+    /// [Error::Opus]: crate::error::Error::Opus
+    /// [`Sample`]: crate::sample::Sample
+    pub fn decode<S: Sample>(
+        &mut self,
+        request: DecodeRequest<'_>,
+        mut output: MutSignals<'_, S>,
+    ) -> Result<usize> {
+        let frame_size = request.frame_size(
+            usize::from(self.channels),
+            output.i32_len(),
+            || self.sample_rate(),
+            || {
+                self.decoder_ctl_request(ffi::OPUS_GET_LAST_PACKET_DURATION_REQUEST)
+                    .map(|v| v as u32)
+            },
+        )?;
+
+        let (input_pointer, input_len) = if let Some(value) = request.packet() {
+            (value.as_ptr(), value.i32_len())
+        } else {
+            (std::ptr::null(), 0)
+        };
+
+        try_map_opus_error(unsafe {
+            S::projection_decode(
+                self.pointer,
+                input_pointer,
+                input_len,
+                output.as_mut_ptr(),
+                frame_size,
+                request.fec() as i32,
+            )
+        })
+        .map(|n| n as usize)
+    }
+
+    /// Issues a CTL `request` to Opus without argument used to
+    /// request a value.
+    /// If Opus returns a value smaller than 0, it indicates an error.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    fn decoder_ctl_request(&self, request: i32) -> Result<i32> {
+        let mut value = 0;
+
+        let ffi_result =
+            unsafe { ffi::opus_projection_decoder_ctl(self.pointer, request, &mut value) };
+
+        try_map_opus_error(ffi_result)?;
+
+        Ok(value)
+    }
+
+    /// Issues a CTL `request` to Opus accepting an additional argument used
+    /// to set the `decoder`'s setting to `value`.
+    /// If Opus returns a value smaller than 0, it indicates an error.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    fn set_decoder_ctl_request(&self, request: i32, value: i32) -> Result<()> {
+        try_map_opus_error(unsafe {
+            ffi::opus_projection_decoder_ctl(self.pointer, request, value)
+        })?;
+
+        Ok(())
+    }
+
+    /// Gets size of an Opus projection decoder with the given layout in
+    /// bytes.
+    pub fn size(channels: u8, streams: u8, coupled_streams: u8) -> usize {
+        unsafe {
+            ffi::opus_projection_decoder_get_size(
+                i32::from(channels),
+                i32::from(streams),
+                i32::from(coupled_streams),
+            ) as usize
+        }
+    }
+}
+
+impl Drop for ProjectionDecoder {
+    /// We have to ensure that the resource our wrapping Opus-struct is pointing
+    /// to is deallocated properly.
+    fn drop(&mut self) {
+        unsafe { ffi::opus_projection_decoder_destroy(self.pointer) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProjectionDecoder;
+    use crate::{
+        coder::{multistream::MappingFamily, projection::ProjectionEncoder, DecodeRequest},
+        packet::Packet,
+        Application, Error, MutSignals, SampleRate,
+    };
+    use matches::assert_matches;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn projection_decoder_construction() {
+        assert_matches!(
+            ProjectionDecoder::new(SampleRate::Hz48000, 4, 2, 2, &[0; 3]),
+            Err(Error::DemixingMatrixExpectedLen(32))
+        );
+    }
+
+    #[test]
+    fn projection_round_trip() {
+        let mut encoder = ProjectionEncoder::new(
+            SampleRate::Hz48000,
+            4,
+            MappingFamily::AmbisonicsProjection,
+            Application::Audio,
+        )
+        .unwrap();
+        let matrix = encoder.demixing_matrix().unwrap();
+
+        let mut decoder = ProjectionDecoder::new(
+            SampleRate::Hz48000,
+            encoder.channels(),
+            encoder.streams(),
+            encoder.coupled_streams(),
+            &matrix,
+        )
+        .unwrap();
+
+        // 48000Hz * 4 channels * 20 ms / 1000
+        const FOUR_CHANNELS_20MS: usize = 48000 * 4 * 20 / 1000;
+        let input = [0_i16; FOUR_CHANNELS_20MS];
+        let mut packet = [0; 512];
+
+        let len = encoder.encode(&input, &mut packet).unwrap();
+
+        let mut output = vec![0_f32; FOUR_CHANNELS_20MS];
+        let samples = decoder
+            .decode(
+                DecodeRequest::Normal(Packet::try_from(&packet[..len]).unwrap()),
+                MutSignals::try_from(&mut output).unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(samples, FOUR_CHANNELS_20MS / 4);
+    }
+}
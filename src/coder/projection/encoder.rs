@@ -0,0 +1,334 @@
+use crate::{
+    coder::{multistream::MappingFamily, GenericCtl},
+    error::try_map_opus_error,
+    ffi, Application, Bitrate, Error, ErrorCode, Result, Sample, SampleRate, TryFrom,
+};
+
+/// `ProjectionEncoder` calls to Opus and offers methods to encode ambisonics
+/// content and issue requests to Opus.
+#[derive(Debug)]
+pub struct ProjectionEncoder {
+    pointer: *mut ffi::OpusProjectionEncoder,
+    channels: u8,
+    streams: u8,
+    coupled_streams: u8,
+}
+
+/// The Opus projection encoder can be sent between threads unless the Opus
+/// library has been compiled with `NONTHREADSAFE_PSEUDOSTACK` to disallow
+/// encoding in parallel.
+unsafe impl Send for ProjectionEncoder {}
+
+impl GenericCtl for ProjectionEncoder {
+    /// Gets the final state of the codec's entropy coder.
+    fn final_range(&self) -> Result<u32> {
+        self.encoder_ctl_request(ffi::OPUS_GET_FINAL_RANGE_REQUEST)
+            .map(|v| v as u32)
+    }
+
+    /// Gets the encoder's configured phase inversion status.
+    fn phase_inversion_disabled(&self) -> Result<bool> {
+        self.encoder_ctl_request(ffi::OPUS_GET_PHASE_INVERSION_DISABLED_REQUEST)
+            .map(|b| b == 1)
+    }
+
+    /// If set to `true`, disables the use of phase inversion for intensity
+    /// stereo in all coupled streams.
+    fn set_phase_inversion_disabled(&mut self, disabled: bool) -> Result<()> {
+        let disable_phase_inversion = if disabled { 1 } else { 0 };
+        self.set_encoder_ctl_request(
+            ffi::OPUS_SET_PHASE_INVERSION_DISABLED_REQUEST,
+            disable_phase_inversion,
+        )
+    }
+
+    /// Gets the sampling rate the encoder was initialized with.
+    fn sample_rate(&self) -> Result<SampleRate> {
+        self.encoder_ctl_request(ffi::OPUS_GET_SAMPLE_RATE_REQUEST)
+            .and_then(SampleRate::try_from)
+    }
+
+    /// Resets the codec state to be equivalent to a freshly initialized
+    /// state.
+    fn reset_state(&mut self) -> Result<()> {
+        self.encoder_ctl_request(ffi::OPUS_RESET_STATE).map(|_| ())
+    }
+}
+
+impl ProjectionEncoder {
+    /// Creates a new Opus projection encoder for ambisonics input.
+    ///
+    /// `channels` must be a valid ambisonics channel count, i.e.
+    /// `(order + 1)^2` optionally followed by two non-diegetic channels.
+    /// Opus only supports [`MappingFamily::AmbisonicsProjection`] as
+    /// `mapping_family`.
+    /// Opus picks the amount of streams, which can be queried via
+    /// [`streams`] and [`coupled_streams`] afterwards.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidChannels`] if `channels` is zero and
+    /// [`BadArgument`] if Opus rejects the channel count.
+    ///
+    /// [`MappingFamily::AmbisonicsProjection`]: crate::coder::multistream::MappingFamily::AmbisonicsProjection
+    /// [`streams`]: crate::coder::projection::ProjectionEncoder::streams
+    /// [`coupled_streams`]: crate::coder::projection::ProjectionEncoder::coupled_streams
+    /// [`Error::InvalidChannels`]: crate::error::Error::InvalidChannels
+    /// [`BadArgument`]: crate::error::ErrorCode::BadArgument
+    pub fn new(
+        sample_rate: SampleRate,
+        channels: u8,
+        mapping_family: MappingFamily,
+        mode: Application,
+    ) -> Result<ProjectionEncoder> {
+        if channels == 0 {
+            return Err(Error::InvalidChannels(0));
+        }
+
+        let mut opus_code = 0;
+        let mut streams = 0;
+        let mut coupled_streams = 0;
+
+        let pointer = unsafe {
+            ffi::opus_projection_ambisonics_encoder_create(
+                sample_rate as i32,
+                i32::from(channels),
+                i32::from(u8::from(mapping_family)),
+                &mut streams,
+                &mut coupled_streams,
+                mode as i32,
+                &mut opus_code,
+            )
+        };
+
+        if opus_code == ffi::OPUS_OK && !pointer.is_null() {
+            return Ok(ProjectionEncoder {
+                pointer,
+                channels,
+                streams: streams as u8,
+                coupled_streams: coupled_streams as u8,
+            });
+        }
+
+        Err(ErrorCode::from(opus_code).into())
+    }
+
+    /// Gets the amount of channels the encoder expects as input.
+    pub fn channels(&self) -> u8 {
+        self.channels
+    }
+
+    /// Gets a pointer to the Opus projection encoder state, e.g. to hand it to
+    /// another C library.
+    pub fn as_ptr(&self) -> *const ffi::OpusProjectionEncoder {
+        self.pointer
+    }
+
+    /// Gets a mutable pointer to the Opus projection encoder state.
+    pub fn as_mut_ptr(&mut self) -> *mut ffi::OpusProjectionEncoder {
+        self.pointer
+    }
+
+    /// Gets the total amount of streams Opus picked for the encoder.
+    pub fn streams(&self) -> u8 {
+        self.streams
+    }
+
+    /// Gets the amount of coupled (stereo) streams Opus picked for the
+    /// encoder.
+    pub fn coupled_streams(&self) -> u8 {
+        self.coupled_streams
+    }
+
+    /// Issues a CTL get-`request` to Opus.
+    /// If Opus returns a negative value it indicates an error.
+    pub fn encoder_ctl_request(&self, request: i32) -> Result<i32> {
+        let mut value = 0;
+
+        let ffi_result =
+            unsafe { ffi::opus_projection_encoder_ctl(self.pointer, request, &mut value) };
+        try_map_opus_error(ffi_result)?;
+
+        Ok(value)
+    }
+
+    /// Issues a CTL set-`request` to Opus and sets the `ProjectionEncoder`'s
+    /// setting to `value` based on sent `request`.
+    /// If Opus returns a negative value it indicates an error.
+    pub fn set_encoder_ctl_request(&mut self, request: i32, value: i32) -> Result<()> {
+        try_map_opus_error(unsafe {
+            ffi::opus_projection_encoder_ctl(self.pointer, request, value)
+        })?;
+
+        Ok(())
+    }
+
+    /// Encodes a projection Opus frame.
+    ///
+    /// The interleaved `input` signal will be encoded into the `output`
+    /// payload and on success returns the length of the encoded packet.
+    ///
+    /// The samples are either `i16` or `f32`, see [`Sample`].
+    ///
+    /// [`Sample`]: crate::sample::Sample
+    pub fn encode<S: Sample>(&mut self, input: &[S], output: &mut [u8]) -> Result<usize> {
+        try_map_opus_error(unsafe {
+            S::projection_encode(
+                self.pointer,
+                input.as_ptr(),
+                input.len() as i32 / i32::from(self.channels),
+                output.as_mut_ptr(),
+                output.len() as i32,
+            )
+        })
+        .map(|n| n as usize)
+    }
+
+    /// Configures the total bitrate shared by all streams.
+    pub fn set_bitrate(&mut self, bitrate: Bitrate) -> Result<()> {
+        self.set_encoder_ctl_request(ffi::OPUS_SET_BITRATE_REQUEST, bitrate.into())
+    }
+
+    /// Gets the encoder's configured total bitrate.
+    pub fn bitrate(&self) -> Result<Bitrate> {
+        self.encoder_ctl_request(ffi::OPUS_GET_BITRATE_REQUEST)
+            .and_then(Bitrate::try_from)
+    }
+
+    /// Gets the encoder's complexity configuration.
+    pub fn complexity(&self) -> Result<u8> {
+        self.encoder_ctl_request(ffi::OPUS_GET_COMPLEXITY_REQUEST)
+            .map(|v| v as u8)
+    }
+
+    /// Configures the encoder's computational complexity.
+    ///
+    /// **Warning**:
+    /// If `complexity` exceeds 10, [`BadArgument`] will be returned.
+    ///
+    /// [`BadArgument`]: crate::error::ErrorCode::BadArgument
+    pub fn set_complexity(&mut self, complexity: u8) -> Result<()> {
+        self.set_encoder_ctl_request(ffi::OPUS_SET_COMPLEXITY_REQUEST, i32::from(complexity))
+    }
+
+    /// Gets the total samples of delay added by the entire codec.
+    pub fn lookahead(&self) -> Result<u32> {
+        self.encoder_ctl_request(ffi::OPUS_GET_LOOKAHEAD_REQUEST)
+            .map(|n| n as u32)
+    }
+
+    /// Gets the gain of the demixing matrix in Q8 dB units.
+    pub fn demixing_matrix_gain(&self) -> Result<i32> {
+        self.encoder_ctl_request(ffi::OPUS_PROJECTION_GET_DEMIXING_MATRIX_GAIN_REQUEST)
+    }
+
+    /// Gets the size of the demixing matrix in bytes.
+    pub fn demixing_matrix_size(&self) -> Result<usize> {
+        self.encoder_ctl_request(ffi::OPUS_PROJECTION_GET_DEMIXING_MATRIX_SIZE_REQUEST)
+            .map(|n| n as usize)
+    }
+
+    /// Gets a copy of the demixing matrix.
+    ///
+    /// The matrix is required to construct a [`ProjectionDecoder`] decoding
+    /// this encoder's packets.
+    ///
+    /// [`ProjectionDecoder`]: crate::coder::projection::ProjectionDecoder
+    pub fn demixing_matrix(&self) -> Result<Vec<u8>> {
+        let size = self.demixing_matrix_size()?;
+        let mut matrix = vec![0_u8; size];
+
+        // Opus writes exactly `size` bytes into `matrix`, which has been
+        // allocated with that length.
+        try_map_opus_error(unsafe {
+            ffi::opus_projection_encoder_ctl(
+                self.pointer,
+                ffi::OPUS_PROJECTION_GET_DEMIXING_MATRIX_REQUEST,
+                matrix.as_mut_ptr(),
+                size as i32,
+            )
+        })?;
+
+        Ok(matrix)
+    }
+
+    /// Gets size of an Opus projection encoder in bytes.
+    ///
+    /// Returns `0` if Opus does not support the channel count and mapping
+    /// family.
+    pub fn size(channels: u8, mapping_family: MappingFamily) -> usize {
+        unsafe {
+            ffi::opus_projection_ambisonics_encoder_get_size(
+                i32::from(channels),
+                i32::from(u8::from(mapping_family)),
+            ) as usize
+        }
+    }
+}
+
+impl Drop for ProjectionEncoder {
+    /// We have to ensure that the resource our wrapping Opus-struct is pointing
+    /// to is deallocated properly.
+    fn drop(&mut self) {
+        unsafe { ffi::opus_projection_encoder_destroy(self.pointer) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProjectionEncoder;
+    use crate::{coder::multistream::MappingFamily, Application, Error, SampleRate};
+    use matches::assert_matches;
+
+    #[test]
+    fn projection_encoder_construction() {
+        assert_matches!(
+            ProjectionEncoder::new(
+                SampleRate::Hz48000,
+                0,
+                MappingFamily::AmbisonicsProjection,
+                Application::Audio
+            ),
+            Err(Error::InvalidChannels(0))
+        );
+
+        assert_matches!(
+            ProjectionEncoder::new(
+                SampleRate::Hz48000,
+                5,
+                MappingFamily::AmbisonicsProjection,
+                Application::Audio
+            ),
+            Err(Error::Opus(_))
+        );
+
+        let encoder = ProjectionEncoder::new(
+            SampleRate::Hz48000,
+            4,
+            MappingFamily::AmbisonicsProjection,
+            Application::Audio,
+        )
+        .expect("Could not create first order ambisonics encoder.");
+
+        let matrix = encoder.demixing_matrix().unwrap();
+        assert_eq!(matrix.len(), encoder.demixing_matrix_size().unwrap());
+    }
+
+    #[test]
+    fn projection_encoding() {
+        let mut encoder = ProjectionEncoder::new(
+            SampleRate::Hz48000,
+            4,
+            MappingFamily::AmbisonicsProjection,
+            Application::Audio,
+        )
+        .unwrap();
+
+        // 48000Hz * 4 channels * 20 ms / 1000
+        const FOUR_CHANNELS_20MS: usize = 48000 * 4 * 20 / 1000;
+        let input = [0_i16; FOUR_CHANNELS_20MS];
+        let mut output = [0; 512];
+
+        let len = encoder.encode(&input, &mut output).unwrap();
+        assert!(len > 0);
+    }
+}
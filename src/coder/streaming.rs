@@ -0,0 +1,318 @@
+use super::{fec::MAX_CONCEALMENT, DecodeRequest, Decoder, Encoder, GenericCtl};
+use crate::{
+    packet::{Packet, PacketBuf},
+    FrameDuration, Result, Sample, SampleRate, TryFrom,
+};
+use std::{collections::VecDeque, marker::PhantomData};
+
+/// `StreamEncoder` accepts PCM of any length, e.g. the odd-sized buffers of
+/// capture callbacks, and encodes it once complete frames accumulate.
+#[derive(Debug)]
+pub struct StreamEncoder<S = i16> {
+    encoder: Encoder,
+    /// Interleaved samples per frame.
+    frame_len: usize,
+    pending: VecDeque<S>,
+}
+
+impl<S: Sample> StreamEncoder<S> {
+    /// Creates a new `StreamEncoder` encoding via `encoder` in frames of
+    /// `frame_duration`.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn new(encoder: Encoder, frame_duration: FrameDuration) -> Result<Self> {
+        let frame_len = frame_duration.samples(encoder.sample_rate()?, encoder.channels() as usize);
+
+        Ok(Self {
+            encoder,
+            frame_len,
+            pending: VecDeque::with_capacity(frame_len * 2),
+        })
+    }
+
+    /// Gets the encoder.
+    pub fn encoder(&self) -> &Encoder {
+        &self.encoder
+    }
+
+    /// Gets the encoder mutably, e.g. to change the bitrate mid-stream.
+    pub fn encoder_mut(&mut self) -> &mut Encoder {
+        &mut self.encoder
+    }
+
+    /// Unwraps the encoder, discarding the PCM pending.
+    pub fn into_inner(self) -> Encoder {
+        self.encoder
+    }
+
+    /// Gets the amount of interleaved samples pending, less than a frame.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Appends `pcm`, interleaved if 2 channels, and encodes every frame
+    /// completed, appending the packets in order to `packets`.
+    ///
+    /// On success, returns the amount of packets appended, none if no frame
+    /// was completed.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem, the frame
+    /// failing is dropped. The packets encoded before it have been
+    /// appended, the frames after it stay pending.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn push(&mut self, pcm: &[S], packets: &mut Vec<PacketBuf>) -> Result<usize> {
+        self.pending.extend(pcm);
+
+        let len = packets.len();
+        packets.reserve(self.pending.len() / self.frame_len);
+
+        while self.pending.len() >= self.frame_len {
+            let packet = self.encode_frame();
+            self.pending.drain(..self.frame_len);
+            packets.push(packet?);
+        }
+
+        Ok(packets.len() - len)
+    }
+
+    /// Encodes the PCM pending padded with silence to a complete frame,
+    /// e.g. at the end of a stream.
+    ///
+    /// Returns `None` if no PCM is pending.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn flush(&mut self) -> Result<Option<PacketBuf>> {
+        if self.pending.is_empty() {
+            return Ok(None);
+        }
+
+        self.pending.resize(self.frame_len, S::default());
+        let packet = self.encode_frame();
+        self.pending.clear();
+
+        packet.map(Some)
+    }
+
+    /// Encodes the first frame pending.
+    fn encode_frame(&mut self) -> Result<PacketBuf> {
+        let frame = &self.pending.make_contiguous()[..self.frame_len];
+
+        PacketBuf::try_from(self.encoder.encode_to_vec(frame)?)
+    }
+}
+
+/// How a [`StreamDecoder`] fills gaps in the stream.
+///
+/// [`StreamDecoder`]: crate::coder::StreamDecoder
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum GapFill {
+    /// Packet loss concealment (PLC).
+    Conceal,
+    /// Silence.
+    Silence,
+}
+
+impl Default for GapFill {
+    fn default() -> Self {
+        GapFill::Conceal
+    }
+}
+
+/// `StreamDecoder` decodes timestamped packets into continuous PCM, filling
+/// gaps between them according to its [`GapFill`].
+///
+/// Timestamps count samples per channel at the decoder's sample rate.
+/// Packets older than the PCM already output are dropped. Timestamps more
+/// than a second off, e.g. after the sender restarted, are a discontinuity:
+/// the decoder is reset and continues at the new timestamp without filling
+/// a gap.
+///
+/// [`GapFill`]: crate::coder::GapFill
+#[derive(Debug)]
+pub struct StreamDecoder<S = i16> {
+    decoder: Decoder,
+    sample_rate: SampleRate,
+    gap_fill: GapFill,
+    /// The timestamp following the PCM output, `None` before the first
+    /// packet.
+    next_timestamp: Option<u64>,
+    /// Samples per channel of the last packet, assumed for lost ones.
+    last_samples: usize,
+    _sample: PhantomData<S>,
+}
+
+impl<S: Sample> StreamDecoder<S> {
+    /// Creates a new `StreamDecoder` decoding via `decoder` and filling gaps
+    /// according to `gap_fill`.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn new(decoder: Decoder, gap_fill: GapFill) -> Result<Self> {
+        let sample_rate = decoder.sample_rate()?;
+
+        Ok(Self {
+            decoder,
+            sample_rate,
+            gap_fill,
+            next_timestamp: None,
+            last_samples: FrameDuration::Ms20.samples_per_channel(sample_rate),
+            _sample: PhantomData,
+        })
+    }
+
+    /// Gets the decoder.
+    pub fn decoder(&self) -> &Decoder {
+        &self.decoder
+    }
+
+    /// Unwraps the decoder.
+    pub fn into_inner(self) -> Decoder {
+        self.decoder
+    }
+
+    /// Gets the timestamp following the PCM output so far, `None` before the
+    /// first packet.
+    pub fn next_timestamp(&self) -> Option<u64> {
+        self.next_timestamp
+    }
+
+    /// Decodes the packet starting at `timestamp`, `None` if it was lost.
+    ///
+    /// Returns the PCM, interleaved if 2 channels, continuing the PCM
+    /// output before: a gap up to `timestamp` is filled first, a lost
+    /// packet is assumed to last as long as the one before it. Returns
+    /// nothing for packets older than the PCM output.
+    ///
+    /// A `timestamp` more than a second off the PCM output resets the
+    /// decoder and starts over at it.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn decode(&mut self, timestamp: u64, packet: Option<Packet<'_>>) -> Result<Vec<S>> {
+        let mut next_timestamp = *self.next_timestamp.get_or_insert(timestamp);
+        let max_gap = self.sample_rate as u64 * MAX_CONCEALMENT.as_secs();
+
+        // Filling a jump, e.g. of a wrapped or restarted clock, would
+        // allocate without bound.
+        if timestamp.max(next_timestamp) - timestamp.min(next_timestamp) > max_gap {
+            self.decoder.reset_state()?;
+            next_timestamp = timestamp;
+        }
+
+        if timestamp < next_timestamp {
+            return Ok(Vec::new());
+        }
+
+        let gap = (timestamp - next_timestamp) as usize;
+
+        match packet {
+            Some(packet) => {
+                let mut output = self.fill(gap)?;
+                let decoded: Vec<S> = self.decoder.decode_to_vec(DecodeRequest::Normal(packet))?;
+                let samples = decoded.len() / self.decoder.channels() as usize;
+
+                output.extend(decoded);
+                self.last_samples = samples;
+                self.next_timestamp = Some(timestamp.saturating_add(samples as u64));
+
+                Ok(output)
+            }
+            None => {
+                let output = self.fill(gap + self.last_samples)?;
+                self.next_timestamp = Some(timestamp.saturating_add(self.last_samples as u64));
+
+                Ok(output)
+            }
+        }
+    }
+
+    /// Fills a gap of `samples` per channel.
+    fn fill(&mut self, samples: usize) -> Result<Vec<S>> {
+        let channels = self.decoder.channels() as usize;
+
+        let mut output = match self.gap_fill {
+            GapFill::Conceal if samples > 0 => {
+                let duration = self.sample_rate.duration_of(samples).min(MAX_CONCEALMENT);
+
+                self.decoder.conceal(duration)?
+            }
+            _ => Vec::new(),
+        };
+
+        // Concealment covers multiples of 2.5 ms, silence the remainder.
+        output.resize(samples * channels, S::default());
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GapFill, StreamDecoder, StreamEncoder};
+    use crate::{
+        coder::{Decoder, Encoder},
+        packet::Packet,
+        Application, Channels, FrameDuration, SampleRate,
+    };
+
+    #[test]
+    fn encode_odd_sized_chunks() {
+        let encoder = Encoder::new(SampleRate::Hz16000, Channels::Mono, Application::Voip).unwrap();
+        let mut encoder = StreamEncoder::new(encoder, FrameDuration::Ms20).unwrap();
+
+        let mut packets = Vec::new();
+        assert_eq!(encoder.push(&[0_i16; 317], &mut packets).unwrap(), 0);
+        assert_eq!(encoder.push(&[0_i16; 700], &mut packets).unwrap(), 3);
+        assert_eq!(packets.len(), 3);
+        assert_eq!(encoder.pending(), 57);
+
+        assert!(encoder.flush().unwrap().is_some());
+        assert_eq!(encoder.pending(), 0);
+        assert!(encoder.flush().unwrap().is_none());
+    }
+
+    #[test]
+    fn decode_with_gaps() {
+        let encoder = Encoder::new(SampleRate::Hz16000, Channels::Mono, Application::Voip).unwrap();
+        let packet = encoder.encode_to_vec(&[0_i16; 320]).unwrap();
+        let packet = Packet::new(&packet).unwrap();
+
+        let decoder = Decoder::new(SampleRate::Hz16000, Channels::Mono).unwrap();
+        let mut decoder = StreamDecoder::<i16>::new(decoder, GapFill::Conceal).unwrap();
+
+        assert_eq!(decoder.decode(1000, Some(packet)).unwrap().len(), 320);
+        // A lost packet lasts as long as the last one.
+        assert_eq!(decoder.decode(1320, None).unwrap().len(), 320);
+        // The gap of 100 samples is filled first.
+        assert_eq!(decoder.decode(1740, Some(packet)).unwrap().len(), 420);
+        assert_eq!(decoder.next_timestamp(), Some(2060));
+        // Late packets are dropped.
+        assert!(decoder.decode(1740, Some(packet)).unwrap().is_empty());
+        // Jumps beyond a second start over instead of being filled.
+        assert_eq!(
+            decoder.decode(u64::MAX / 2, Some(packet)).unwrap().len(),
+            320
+        );
+        assert_eq!(decoder.next_timestamp(), Some(u64::MAX / 2 + 320));
+        assert_eq!(decoder.decode(0, Some(packet)).unwrap().len(), 320);
+        assert_eq!(decoder.next_timestamp(), Some(320));
+
+        let decoder = Decoder::new(SampleRate::Hz16000, Channels::Mono).unwrap();
+        let mut decoder = StreamDecoder::<f32>::new(decoder, GapFill::Silence).unwrap();
+        decoder.decode(0, Some(packet)).unwrap();
+        let output = decoder.decode(640, Some(packet)).unwrap();
+        assert!(output[..320].iter().all(|sample| *sample == 0.0));
+    }
+}
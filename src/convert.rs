@@ -0,0 +1,299 @@
+//! Conversions between sample formats, e.g. to feed the `f32` output of a
+//! DSP graph to an `i16` encoder.
+//!
+//! Converting `f32` to `i16` loses precision, [`Quantizer`] optionally
+//! dithers to decorrelate the error from the signal and counts the samples
+//! clipped.
+//!
+//! Capture formats wider or narrower than the encoder accepts, `i32`,
+//! packed 24-bit and offset-binary `u8`, convert to `i16` or `f32` via
+//! [`from_i32`], [`from_packed_i24`] and [`from_u8`].
+//!
+//! [`Quantizer`]: crate::convert::Quantizer
+//! [`from_i32`]: crate::convert::from_i32
+//! [`from_packed_i24`]: crate::convert::from_packed_i24
+//! [`from_u8`]: crate::convert::from_u8
+
+use crate::{Error, Result, Sample};
+
+/// The scale between `i16` and `f32` samples in the range of `-1.0` to
+/// `1.0`.
+const I16_SCALE: f32 = 32768.0;
+
+/// Converts `input` to `f32` samples in the range of `-1.0` to `1.0` into
+/// `output`, losslessly.
+///
+/// **Errors**:
+/// Returns [`Error::SignalsExpectedLen`] if `output` cannot hold all
+/// samples.
+///
+/// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+pub fn i16_to_f32(input: &[i16], output: &mut [f32]) -> Result<()> {
+    if output.len() < input.len() {
+        return Err(Error::SignalsExpectedLen(input.len()));
+    }
+
+    for (sample, &input) in output.iter_mut().zip(input) {
+        *sample = f32::from(input) / I16_SCALE;
+    }
+
+    Ok(())
+}
+
+/// Converts the full scale `i32` `input` into `output`, rounding to the
+/// nearest `i16` and saturating at its range.
+///
+/// **Errors**:
+/// Returns [`Error::SignalsExpectedLen`] if `output` cannot hold all
+/// samples.
+///
+/// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+pub fn from_i32<S: Sample>(input: &[i32], output: &mut [S]) -> Result<()> {
+    if output.len() < input.len() {
+        return Err(Error::SignalsExpectedLen(input.len()));
+    }
+
+    for (sample, &input) in output.iter_mut().zip(input) {
+        *sample = S::from_f32((f64::from(input) / 2_147_483_648.0) as f32);
+    }
+
+    Ok(())
+}
+
+/// Converts the packed little-endian 24-bit `input`, three bytes per
+/// sample, into `output`, rounding to the nearest `i16` and saturating at
+/// its range.
+///
+/// On success, returns the amount of samples.
+///
+/// **Errors**:
+/// Returns [`Error::SignalsExpectedLen`] if `input` contains a partial
+/// sample or `output` cannot hold all samples.
+///
+/// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+pub fn from_packed_i24<S: Sample>(input: &[u8], output: &mut [S]) -> Result<usize> {
+    let samples = input.len() / 3;
+
+    if input.len() % 3 != 0 || output.len() < samples {
+        return Err(Error::SignalsExpectedLen(samples));
+    }
+
+    for (sample, bytes) in output.iter_mut().zip(input.chunks_exact(3)) {
+        // Shifting the sign bit to the top sign-extends the sample.
+        let value = i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8;
+        *sample = S::from_f32(value as f32 / 8_388_608.0);
+    }
+
+    Ok(samples)
+}
+
+/// Converts the offset-binary `u8` `input`, silence at `128`, into
+/// `output`.
+///
+/// **Errors**:
+/// Returns [`Error::SignalsExpectedLen`] if `output` cannot hold all
+/// samples.
+///
+/// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+pub fn from_u8<S: Sample>(input: &[u8], output: &mut [S]) -> Result<()> {
+    if output.len() < input.len() {
+        return Err(Error::SignalsExpectedLen(input.len()));
+    }
+
+    for (sample, &input) in output.iter_mut().zip(input) {
+        *sample = S::from_f32((f32::from(input) - 128.0) / 128.0);
+    }
+
+    Ok(())
+}
+
+/// How a [`Quantizer`] treats the rounding error.
+///
+/// [`Quantizer`]: crate::convert::Quantizer
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Dither {
+    /// Rounding to the nearest value.
+    None,
+    /// Triangular probability density function (TPDF) dither of ±1 LSB
+    /// added before rounding, turning the error into constant noise.
+    Tpdf,
+}
+
+impl Default for Dither {
+    fn default() -> Self {
+        Dither::None
+    }
+}
+
+/// The samples a conversion clipped, those exceeding the target range.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct ClipStats {
+    /// The samples converted.
+    pub samples: usize,
+    /// The samples clipped to the target range.
+    pub clipped: usize,
+}
+
+impl ClipStats {
+    /// Gets the ratio of samples clipped, `0.0` if none were converted.
+    pub fn clipped_ratio(&self) -> f64 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            self.clipped as f64 / self.samples as f64
+        }
+    }
+}
+
+/// `Quantizer` converts `f32` samples in the range of `-1.0` to `1.0` to
+/// `i16`, optionally dithered, saturating those beyond the range.
+///
+/// The dither's random state carries across calls, the [`ClipStats`] of
+/// all calls are summed up.
+///
+/// [`ClipStats`]: crate::convert::ClipStats
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Quantizer {
+    dither: Dither,
+    /// The state of a xorshift generator, never zero.
+    random: u32,
+    stats: ClipStats,
+}
+
+impl Quantizer {
+    /// Creates a new `Quantizer` treating the rounding error per `dither`.
+    pub fn new(dither: Dither) -> Self {
+        Self {
+            dither,
+            random: 0x9e37_79b9,
+            stats: ClipStats::default(),
+        }
+    }
+
+    /// Gets the dither.
+    pub fn dither(&self) -> Dither {
+        self.dither
+    }
+
+    /// Gets the statistics summed over all conversions.
+    pub fn stats(&self) -> ClipStats {
+        self.stats
+    }
+
+    /// Resets the statistics, e.g. to measure the next buffer alone.
+    pub fn reset_stats(&mut self) {
+        self.stats = ClipStats::default();
+    }
+
+    /// Converts `input` to `i16` samples into `output`.
+    ///
+    /// On success, returns the statistics of this conversion.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::SignalsExpectedLen`] if `output` cannot hold all
+    /// samples.
+    ///
+    /// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+    pub fn convert(&mut self, input: &[f32], output: &mut [i16]) -> Result<ClipStats> {
+        if output.len() < input.len() {
+            return Err(Error::SignalsExpectedLen(input.len()));
+        }
+
+        let mut stats = ClipStats {
+            samples: input.len(),
+            clipped: 0,
+        };
+
+        for (sample, &input) in output.iter_mut().zip(input) {
+            let noise = match self.dither {
+                Dither::None => 0.0,
+                Dither::Tpdf => self.uniform() + self.uniform(),
+            };
+            let value = (input * I16_SCALE + noise).round();
+
+            if value > f32::from(i16::MAX) || value < f32::from(i16::MIN) || value.is_nan() {
+                stats.clipped += 1;
+            }
+
+            // Float to integer casts saturate, NaN converting to zero.
+            *sample = value as i16;
+        }
+
+        self.stats.samples += stats.samples;
+        self.stats.clipped += stats.clipped;
+
+        Ok(stats)
+    }
+
+    /// Converts `input` to a new `Vec` of `i16` samples, returning it with
+    /// the statistics of this conversion.
+    pub fn convert_to_vec(&mut self, input: &[f32]) -> (Vec<i16>, ClipStats) {
+        let mut output = vec![0; input.len()];
+        let stats = self.convert(input, &mut output).unwrap_or_default();
+
+        (output, stats)
+    }
+
+    /// Draws a uniformly distributed value of -0.5 to 0.5 LSB.
+    fn uniform(&mut self) -> f32 {
+        self.random ^= self.random << 13;
+        self.random ^= self.random >> 17;
+        self.random ^= self.random << 5;
+
+        (self.random >> 8) as f32 / 16_777_216.0 - 0.5
+    }
+}
+
+impl Default for Quantizer {
+    fn default() -> Self {
+        Self::new(Dither::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_i32, from_packed_i24, from_u8, i16_to_f32, Dither, Quantizer};
+
+    #[test]
+    fn quantize_samples() {
+        let mut quantizer = Quantizer::new(Dither::None);
+        let (samples, stats) = quantizer.convert_to_vec(&[0.0, 0.5, -1.0, 1.0, -2.0, f32::NAN]);
+        assert_eq!(samples, [0, 16384, -32768, 32767, -32768, 0]);
+        assert_eq!((stats.samples, stats.clipped), (6, 3));
+
+        let mut restored = [0.0; 3];
+        i16_to_f32(&[0, 16384, -32768], &mut restored).unwrap();
+        assert_eq!(restored, [0.0, 0.5, -1.0]);
+
+        // Dither stays within one LSB of the exact value.
+        let mut quantizer = Quantizer::new(Dither::Tpdf);
+        let (samples, _) = quantizer.convert_to_vec(&[0.25; 1000]);
+        assert!(samples
+            .iter()
+            .all(|&sample| (8191..=8193).contains(&sample)));
+        assert!(samples.iter().any(|&sample| sample != 8192));
+        assert_eq!(quantizer.stats().clipped, 0);
+
+        assert!(quantizer.convert(&[0.0; 2], &mut [0; 1]).is_err());
+    }
+
+    #[test]
+    fn convert_capture_formats() {
+        let mut samples = [0_i16; 4];
+        from_i32(&[0, 1 << 30, i32::MIN, i32::MAX], &mut samples).unwrap();
+        assert_eq!(samples, [0, 16384, -32768, 32767]);
+
+        let mut floats = [0.0_f32; 2];
+        from_i32(&[1 << 30, -(1 << 29)], &mut floats).unwrap();
+        assert_eq!(floats, [0.5, -0.25]);
+
+        let packed = [0x00, 0x00, 0x40, 0x00, 0x00, 0x80, 0xff, 0xff, 0x7f];
+        assert_eq!(from_packed_i24(&packed, &mut samples).unwrap(), 3);
+        assert_eq!(samples[..3], [16384, -32768, 32767]);
+        assert!(from_packed_i24(&packed[..4], &mut samples).is_err());
+
+        from_u8(&[128, 0, 255, 192], &mut samples).unwrap();
+        assert_eq!(samples, [0, -32768, 32512, 16384]);
+        assert!(from_u8(&[128; 5], &mut samples).is_err());
+    }
+}
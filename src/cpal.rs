@@ -0,0 +1,221 @@
+//! Bridges between cpal's audio streams and the streaming coders: see
+//! [`capture`] to encode a microphone and [`playback`] to play decoded
+//! packets.
+//!
+//! Both streams run at the coder's sample rate and channels, the device
+//! must support them. Call `play` on the stream returned to start it.
+//!
+//! [`capture`]: crate::cpal::capture
+//! [`playback`]: crate::cpal::playback
+
+use crate::{
+    coder::{GenericCtl, StreamDecoder, StreamEncoder},
+    packet::{Packet, PacketBuf},
+    Result, SampleRate,
+};
+use cpal::{
+    traits::DeviceTrait, BackendSpecificError, BufferSize, BuildStreamError, Device,
+    InputCallbackInfo, OutputCallbackInfo, Stream, StreamConfig,
+};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+/// The most PCM a [`PlaybackSink`] buffers before dropping the oldest,
+/// bounding the latency.
+///
+/// [`PlaybackSink`]: crate::cpal::PlaybackSink
+pub const MAX_BUFFERED: Duration = Duration::from_millis(500);
+
+/// Builds an input stream on `device` that feeds the captured PCM to
+/// `encoder`, passing every packet encoded to `on_packet`.
+///
+/// Frames failing to encode and errors of the stream are dropped.
+///
+/// **Errors**:
+/// Returns cpal's error if the device fails to build the stream, e.g. if
+/// it does not support the encoder's sample rate or channels.
+pub fn capture<F>(
+    device: &Device,
+    mut encoder: StreamEncoder<f32>,
+    mut on_packet: F,
+) -> std::result::Result<Stream, BuildStreamError>
+where
+    F: FnMut(PacketBuf) + Send + 'static,
+{
+    let config = stream_config(
+        encoder.encoder().sample_rate(),
+        encoder.encoder().channels() as u16,
+    )?;
+
+    let mut packets = Vec::new();
+
+    device.build_input_stream(
+        &config,
+        move |pcm: &[f32], _: &InputCallbackInfo| {
+            // The packets encoded before a failing frame are still passed.
+            let _ = encoder.push(pcm, &mut packets);
+            packets.drain(..).for_each(&mut on_packet);
+        },
+        |_| {},
+        None,
+    )
+}
+
+/// Builds an output stream on `device` playing the PCM `decoder` decodes,
+/// returning it with the [`PlaybackSink`] to decode the packets with.
+///
+/// The stream plays silence while the sink's buffer runs dry.
+///
+/// **Errors**:
+/// Returns cpal's error if the device fails to build the stream, e.g. if
+/// it does not support the decoder's sample rate or channels.
+///
+/// [`PlaybackSink`]: crate::cpal::PlaybackSink
+pub fn playback(
+    device: &Device,
+    decoder: StreamDecoder<f32>,
+) -> std::result::Result<(Stream, PlaybackSink), BuildStreamError> {
+    let sample_rate = decoder.decoder().sample_rate();
+    let channels = decoder.decoder().channels() as u16;
+    let config = stream_config(sample_rate, channels)?;
+
+    let max_len = sample_rate.map_or(0, |sample_rate| sample_rate.samples_in(MAX_BUFFERED))
+        * usize::from(channels);
+    let sink = PlaybackSink::new(decoder, max_len);
+    let buffer = Arc::clone(&sink.buffer);
+
+    let stream = device.build_output_stream(
+        &config,
+        move |output: &mut [f32], _: &OutputCallbackInfo| {
+            let mut buffer = lock(&buffer);
+            let len = output.len().min(buffer.len());
+
+            for (sample, buffered) in output.iter_mut().zip(buffer.drain(..len)) {
+                *sample = buffered;
+            }
+
+            output[len..].iter_mut().for_each(|sample| *sample = 0.0);
+        },
+        |_| {},
+        None,
+    )?;
+
+    Ok((stream, sink))
+}
+
+/// `PlaybackSink` decodes timestamped packets via a [`StreamDecoder`] into
+/// the buffer an output stream built by [`playback`] plays.
+///
+/// [`StreamDecoder`]: crate::coder::StreamDecoder
+/// [`playback`]: crate::cpal::playback
+#[derive(Debug)]
+pub struct PlaybackSink {
+    decoder: StreamDecoder<f32>,
+    buffer: Arc<Mutex<VecDeque<f32>>>,
+    /// The most interleaved samples buffered.
+    max_len: usize,
+    channels: usize,
+}
+
+impl PlaybackSink {
+    fn new(decoder: StreamDecoder<f32>, max_len: usize) -> Self {
+        let channels = decoder.decoder().channels() as usize;
+
+        Self {
+            decoder,
+            buffer: Arc::new(Mutex::new(VecDeque::with_capacity(max_len))),
+            max_len,
+            channels,
+        }
+    }
+
+    /// Gets the decoder.
+    pub fn decoder(&self) -> &StreamDecoder<f32> {
+        &self.decoder
+    }
+
+    /// Gets the amount of interleaved samples waiting to be played.
+    pub fn buffered(&self) -> usize {
+        lock(&self.buffer).len()
+    }
+
+    /// Decodes the packet starting at `timestamp`, `None` if it was lost,
+    /// as [`StreamDecoder::decode`] does and queues the PCM for playback.
+    ///
+    /// The oldest PCM beyond [`MAX_BUFFERED`] is dropped.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`StreamDecoder::decode`]: crate::coder::StreamDecoder::decode
+    /// [`MAX_BUFFERED`]: crate::cpal::MAX_BUFFERED
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn decode(&mut self, timestamp: u64, packet: Option<Packet<'_>>) -> Result<()> {
+        let pcm = self.decoder.decode(timestamp, packet)?;
+
+        let mut buffer = lock(&self.buffer);
+        buffer.extend(pcm);
+
+        // Drop whole frames only to keep the channels interleaved.
+        let excess = buffer.len().saturating_sub(self.max_len);
+        let excess =
+            ((excess + self.channels - 1) / self.channels * self.channels).min(buffer.len());
+        buffer.drain(..excess);
+
+        Ok(())
+    }
+}
+
+/// Creates the configuration of a stream at `sample_rate` with `channels`.
+fn stream_config(
+    sample_rate: Result<SampleRate>,
+    channels: u16,
+) -> std::result::Result<StreamConfig, BuildStreamError> {
+    let sample_rate = sample_rate.map_err(|error| BuildStreamError::BackendSpecific {
+        err: BackendSpecificError {
+            description: error.to_string(),
+        },
+    })?;
+
+    Ok(StreamConfig {
+        channels,
+        sample_rate: cpal::SampleRate(sample_rate as u32),
+        buffer_size: BufferSize::Default,
+    })
+}
+
+/// Locks `buffer`, which cannot hold invalid state even if poisoned.
+fn lock(buffer: &Mutex<VecDeque<f32>>) -> MutexGuard<'_, VecDeque<f32>> {
+    buffer.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PlaybackSink;
+    use crate::{
+        coder::{Decoder, Encoder, GapFill, StreamDecoder},
+        packet::Packet,
+        Application, Channels, SampleRate,
+    };
+
+    #[test]
+    fn buffer_playback() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        let decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+        let decoder = StreamDecoder::new(decoder, GapFill::Silence).unwrap();
+        let mut sink = PlaybackSink::new(decoder, 3000);
+
+        let data = encoder.encode_to_vec(&[0_i16; 1920]).unwrap();
+        sink.decode(0, Some(Packet::new(&data).unwrap())).unwrap();
+        assert_eq!(sink.buffered(), 1920);
+
+        // The oldest frames beyond the limit are dropped.
+        sink.decode(960, Some(Packet::new(&data).unwrap())).unwrap();
+        assert_eq!(sink.buffered(), 3000);
+        assert_eq!(sink.decoder().next_timestamp(), Some(1920));
+    }
+}
@@ -0,0 +1,464 @@
+//! Opus Custom, CELT-only coding at non-standard sample rates and frame
+//! sizes, e.g. for ultra-low-latency links where standard Opus framing does
+//! not apply.
+//!
+//! Packets coded via custom modes are not standard Opus packets, both ends
+//! must use the same [`CustomMode`].
+//!
+//! **Info**:
+//! Requires the linked Opus to be built with custom modes enabled, e.g.
+//! `--enable-custom-modes` or `-DOPUS_CUSTOM_MODES=ON`.
+//!
+//! [`CustomMode`]: crate::custom::CustomMode
+
+use crate::{
+    error::try_map_opus_error, ffi, packet::Packet, Bitrate, Channels, Error, ErrorCode,
+    MutSignals, Result, Sample, TryFrom,
+};
+use std::os::raw::{c_int, c_uchar};
+
+/// The opaque Opus Custom mode.
+#[repr(C)]
+pub struct OpusCustomMode {
+    _private: [u8; 0],
+}
+
+/// The opaque Opus Custom encoder state.
+#[repr(C)]
+pub struct OpusCustomEncoder {
+    _private: [u8; 0],
+}
+
+/// The opaque Opus Custom decoder state.
+#[repr(C)]
+pub struct OpusCustomDecoder {
+    _private: [u8; 0],
+}
+
+extern "C" {
+    fn opus_custom_mode_create(
+        sample_rate: i32,
+        frame_size: c_int,
+        error: *mut c_int,
+    ) -> *mut OpusCustomMode;
+    fn opus_custom_mode_destroy(mode: *mut OpusCustomMode);
+
+    fn opus_custom_encoder_create(
+        mode: *const OpusCustomMode,
+        channels: c_int,
+        error: *mut c_int,
+    ) -> *mut OpusCustomEncoder;
+    fn opus_custom_encoder_destroy(encoder: *mut OpusCustomEncoder);
+    fn opus_custom_encoder_ctl(encoder: *mut OpusCustomEncoder, request: c_int, ...) -> c_int;
+    pub(crate) fn opus_custom_encode(
+        encoder: *mut OpusCustomEncoder,
+        pcm: *const i16,
+        frame_size: c_int,
+        data: *mut c_uchar,
+        max_data_bytes: c_int,
+    ) -> c_int;
+    pub(crate) fn opus_custom_encode_float(
+        encoder: *mut OpusCustomEncoder,
+        pcm: *const f32,
+        frame_size: c_int,
+        data: *mut c_uchar,
+        max_data_bytes: c_int,
+    ) -> c_int;
+
+    fn opus_custom_decoder_create(
+        mode: *const OpusCustomMode,
+        channels: c_int,
+        error: *mut c_int,
+    ) -> *mut OpusCustomDecoder;
+    fn opus_custom_decoder_destroy(decoder: *mut OpusCustomDecoder);
+    fn opus_custom_decoder_ctl(decoder: *mut OpusCustomDecoder, request: c_int, ...) -> c_int;
+    pub(crate) fn opus_custom_decode(
+        decoder: *mut OpusCustomDecoder,
+        data: *const c_uchar,
+        len: c_int,
+        pcm: *mut i16,
+        frame_size: c_int,
+    ) -> c_int;
+    pub(crate) fn opus_custom_decode_float(
+        decoder: *mut OpusCustomDecoder,
+        data: *const c_uchar,
+        len: c_int,
+        pcm: *mut f32,
+        frame_size: c_int,
+    ) -> c_int;
+}
+
+/// A custom mode describing the sample rate and frame size encoders and
+/// decoders created from it operate at.
+#[derive(Debug)]
+pub struct CustomMode {
+    pointer: *mut OpusCustomMode,
+    sample_rate: i32,
+    frame_size: usize,
+}
+
+/// The mode is immutable after creation.
+unsafe impl Send for CustomMode {}
+unsafe impl Sync for CustomMode {}
+
+impl CustomMode {
+    /// Creates a new custom mode coding frames of `frame_size` samples per
+    /// channel at `sample_rate`.
+    ///
+    /// Opus accepts sample rates from 8000 to 96000 Hz and even frame sizes
+    /// from 40 to 1024 samples.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] if Opus rejects the mode, e.g. due to an
+    /// unsupported frame size.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn new(sample_rate: i32, frame_size: usize) -> Result<Self> {
+        let frame_size_arg =
+            c_int::try_from(frame_size).map_err(|_| Error::Opus(ErrorCode::BadArgument))?;
+        let mut opus_code = 0;
+
+        let pointer =
+            unsafe { opus_custom_mode_create(sample_rate, frame_size_arg, &mut opus_code) };
+
+        if opus_code == ffi::OPUS_OK && !pointer.is_null() {
+            return Ok(Self {
+                pointer,
+                sample_rate,
+                frame_size,
+            });
+        }
+
+        Err(ErrorCode::from(opus_code).into())
+    }
+
+    /// Gets the sample rate the mode was created with.
+    pub fn sample_rate(&self) -> i32 {
+        self.sample_rate
+    }
+
+    /// Gets the samples per channel every frame consists of.
+    pub fn frame_size(&self) -> usize {
+        self.frame_size
+    }
+
+    /// Gets a pointer to the Opus Custom mode.
+    pub fn as_ptr(&self) -> *const OpusCustomMode {
+        self.pointer
+    }
+}
+
+impl Drop for CustomMode {
+    fn drop(&mut self) {
+        unsafe { opus_custom_mode_destroy(self.pointer) }
+    }
+}
+
+/// `CustomEncoder` encodes frames of a [`CustomMode`], which must outlive
+/// it.
+///
+/// [`CustomMode`]: crate::custom::CustomMode
+#[derive(Debug)]
+pub struct CustomEncoder<'m> {
+    pointer: *mut OpusCustomEncoder,
+    mode: &'m CustomMode,
+    channels: Channels,
+}
+
+/// The Opus Custom encoder can be sent between threads unless the Opus
+/// library has been compiled with `NONTHREADSAFE_PSEUDOSTACK` to disallow
+/// encoding in parallel.
+unsafe impl Send for CustomEncoder<'_> {}
+
+impl<'m> CustomEncoder<'m> {
+    /// Creates a new encoder for `mode`.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem, e.g. if
+    /// `channels` is [`Channels::Auto`].
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    /// [`Channels::Auto`]: crate::Channels::Auto
+    pub fn new(mode: &'m CustomMode, channels: Channels) -> Result<Self> {
+        let mut opus_code = 0;
+
+        let pointer =
+            unsafe { opus_custom_encoder_create(mode.pointer, channels as c_int, &mut opus_code) };
+
+        if opus_code == ffi::OPUS_OK && !pointer.is_null() {
+            return Ok(Self {
+                pointer,
+                mode,
+                channels,
+            });
+        }
+
+        Err(ErrorCode::from(opus_code).into())
+    }
+
+    /// Gets the mode the encoder was created for.
+    pub fn mode(&self) -> &'m CustomMode {
+        self.mode
+    }
+
+    /// Gets the channels the encoder was created with.
+    pub fn channels(&self) -> Channels {
+        self.channels
+    }
+
+    /// Encodes exactly one frame of the mode's frame size.
+    ///
+    /// The `input` signal (interleaved if 2 channels) will be encoded into the
+    /// `output` payload and on success returns the length of the encoded
+    /// packet.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::SignalsExpectedLen`] if `input` does not hold exactly
+    /// one frame and [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn encode<S: Sample>(&mut self, input: &[S], output: &mut [u8]) -> Result<usize> {
+        let expected = self.mode.frame_size * self.channels as usize;
+
+        if input.len() != expected {
+            return Err(Error::SignalsExpectedLen(expected));
+        }
+
+        let max_data_bytes = output.len().min(c_int::MAX as usize) as c_int;
+
+        try_map_opus_error(unsafe {
+            S::custom_encode(
+                self.pointer,
+                input.as_ptr(),
+                self.mode.frame_size as c_int,
+                output.as_mut_ptr(),
+                max_data_bytes,
+            )
+        })
+        .map(|n| n as usize)
+    }
+
+    /// Configures the bitrate of the encoder.
+    pub fn set_bitrate(&mut self, bitrate: Bitrate) -> Result<()> {
+        self.set_encoder_ctl_request(ffi::OPUS_SET_BITRATE_REQUEST, bitrate.into())
+    }
+
+    /// Configures the computational complexity of the encoder from `0` to
+    /// `10`.
+    pub fn set_complexity(&mut self, complexity: u8) -> Result<()> {
+        self.set_encoder_ctl_request(ffi::OPUS_SET_COMPLEXITY_REQUEST, i32::from(complexity))
+    }
+
+    /// Enables or disables variable bitrate.
+    pub fn set_vbr(&mut self, vbr: bool) -> Result<()> {
+        self.set_encoder_ctl_request(ffi::OPUS_SET_VBR_REQUEST, vbr as i32)
+    }
+
+    /// Configures the expected packet loss in percent, making the encoder
+    /// more robust against it.
+    pub fn set_packet_loss_perc(&mut self, percentage: u8) -> Result<()> {
+        self.set_encoder_ctl_request(
+            ffi::OPUS_SET_PACKET_LOSS_PERC_REQUEST,
+            i32::from(percentage),
+        )
+    }
+
+    /// Gets the final state of the codec's entropy coder.
+    pub fn final_range(&self) -> Result<u32> {
+        let mut value: u32 = 0;
+
+        try_map_opus_error(unsafe {
+            opus_custom_encoder_ctl(
+                self.pointer,
+                ffi::OPUS_GET_FINAL_RANGE_REQUEST,
+                &mut value as *mut u32,
+            )
+        })?;
+
+        Ok(value)
+    }
+
+    /// Resets the encoder's state as after construction, while keeping its
+    /// configuration.
+    pub fn reset_state(&mut self) -> Result<()> {
+        try_map_opus_error(unsafe { opus_custom_encoder_ctl(self.pointer, ffi::OPUS_RESET_STATE) })
+            .map(|_| ())
+    }
+
+    fn set_encoder_ctl_request(&mut self, request: i32, value: i32) -> Result<()> {
+        try_map_opus_error(unsafe { opus_custom_encoder_ctl(self.pointer, request, value) })
+            .map(|_| ())
+    }
+}
+
+impl Drop for CustomEncoder<'_> {
+    fn drop(&mut self) {
+        unsafe { opus_custom_encoder_destroy(self.pointer) }
+    }
+}
+
+/// `CustomDecoder` decodes packets of a [`CustomMode`], which must outlive
+/// it.
+///
+/// [`CustomMode`]: crate::custom::CustomMode
+#[derive(Debug)]
+pub struct CustomDecoder<'m> {
+    pointer: *mut OpusCustomDecoder,
+    mode: &'m CustomMode,
+    channels: Channels,
+}
+
+/// The Opus Custom decoder can be sent between threads unless the Opus
+/// library has been compiled with `NONTHREADSAFE_PSEUDOSTACK` to disallow
+/// decoding in parallel.
+unsafe impl Send for CustomDecoder<'_> {}
+
+impl<'m> CustomDecoder<'m> {
+    /// Creates a new decoder for `mode`.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem, e.g. if
+    /// `channels` is [`Channels::Auto`].
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    /// [`Channels::Auto`]: crate::Channels::Auto
+    pub fn new(mode: &'m CustomMode, channels: Channels) -> Result<Self> {
+        let mut opus_code = 0;
+
+        let pointer =
+            unsafe { opus_custom_decoder_create(mode.pointer, channels as c_int, &mut opus_code) };
+
+        if opus_code == ffi::OPUS_OK && !pointer.is_null() {
+            return Ok(Self {
+                pointer,
+                mode,
+                channels,
+            });
+        }
+
+        Err(ErrorCode::from(opus_code).into())
+    }
+
+    /// Gets the mode the decoder was created for.
+    pub fn mode(&self) -> &'m CustomMode {
+        self.mode
+    }
+
+    /// Gets the channels the decoder was created with.
+    pub fn channels(&self) -> Channels {
+        self.channels
+    }
+
+    /// Decodes a packet as `input` into one frame of the mode's frame size
+    /// in `output`.
+    /// Passing `None` as `input` indicates a packet loss.
+    ///
+    /// On success, returns the decoded samples per channel.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::SignalsExpectedLen`] if `output` cannot hold a frame
+    /// and [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn decode<S: Sample>(
+        &mut self,
+        input: Option<Packet<'_>>,
+        mut output: MutSignals<'_, S>,
+    ) -> Result<usize> {
+        let frame_size = self.mode.frame_size;
+        let expected = frame_size * self.channels as usize;
+
+        if (output.i32_len() as usize) < expected {
+            return Err(Error::SignalsExpectedLen(expected));
+        }
+
+        let (input_pointer, input_len) = if let Some(value) = input {
+            (value.as_ptr(), value.i32_len())
+        } else {
+            (std::ptr::null(), 0)
+        };
+
+        try_map_opus_error(unsafe {
+            S::custom_decode(
+                self.pointer,
+                input_pointer,
+                input_len,
+                output.as_mut_ptr(),
+                frame_size as c_int,
+            )
+        })
+        .map(|n| n as usize)
+    }
+
+    /// Gets the final state of the codec's entropy coder.
+    pub fn final_range(&self) -> Result<u32> {
+        let mut value: u32 = 0;
+
+        try_map_opus_error(unsafe {
+            opus_custom_decoder_ctl(
+                self.pointer,
+                ffi::OPUS_GET_FINAL_RANGE_REQUEST,
+                &mut value as *mut u32,
+            )
+        })?;
+
+        Ok(value)
+    }
+
+    /// Resets the decoder's state as after construction.
+    pub fn reset_state(&mut self) -> Result<()> {
+        try_map_opus_error(unsafe { opus_custom_decoder_ctl(self.pointer, ffi::OPUS_RESET_STATE) })
+            .map(|_| ())
+    }
+}
+
+impl Drop for CustomDecoder<'_> {
+    fn drop(&mut self) {
+        unsafe { opus_custom_decoder_destroy(self.pointer) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CustomDecoder, CustomEncoder, CustomMode};
+    use crate::{packet::Packet, Bitrate, Channels, Error, ErrorCode, MutSignals, TryFrom};
+    use matches::assert_matches;
+
+    #[test]
+    fn invalid_mode() {
+        assert_matches!(
+            CustomMode::new(48000, 41),
+            Err(Error::Opus(ErrorCode::BadArgument))
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mode = CustomMode::new(48000, 128).unwrap();
+        let mut encoder = CustomEncoder::new(&mode, Channels::Stereo).unwrap();
+        let mut decoder = CustomDecoder::new(&mode, Channels::Stereo).unwrap();
+
+        encoder.set_bitrate(Bitrate::BitsPerSecond(96_000)).unwrap();
+
+        assert_matches!(
+            encoder.encode(&[0_i16; 128], &mut [0; 256]),
+            Err(Error::SignalsExpectedLen(256))
+        );
+
+        let input = [0.25_f32; 256];
+        let mut packet = [0; 256];
+        let len = encoder.encode(&input, &mut packet).unwrap();
+
+        let mut output = [0_f32; 256];
+        let packet = Packet::try_from(&packet[..len]).unwrap();
+        let signals = MutSignals::try_from(&mut output[..]).unwrap();
+
+        assert_matches!(decoder.decode(Some(packet), signals), Ok(128));
+        assert_eq!(
+            encoder.final_range().unwrap(),
+            decoder.final_range().unwrap()
+        );
+    }
+}
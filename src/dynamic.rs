@@ -0,0 +1,335 @@
+//! Resolves Opus at runtime instead of at link time, see [`OpusLibrary`].
+//!
+//! [`OpusLibrary`]: crate::dynamic::OpusLibrary
+
+use crate::{
+    backend::{self, OpusBackend},
+    ffi, Error, Result,
+};
+use libloading::Library;
+use std::{
+    ffi::{CStr, OsStr},
+    fmt::{Debug, Formatter, Result as FmtResult},
+    os::raw::{c_char, c_int, c_uchar},
+};
+
+/// Names Opus is commonly installed as on the current platform.
+#[cfg(target_os = "windows")]
+const LIBRARY_NAMES: &[&str] = &["opus.dll", "libopus-0.dll", "libopus.dll"];
+#[cfg(target_os = "macos")]
+const LIBRARY_NAMES: &[&str] = &["libopus.0.dylib", "libopus.dylib"];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const LIBRARY_NAMES: &[&str] = &["libopus.so.0", "libopus.so"];
+
+/// The Opus functions resolved from a loaded library.
+struct Functions {
+    get_version_string: unsafe extern "C" fn() -> *const c_char,
+    encoder_get_size: unsafe extern "C" fn(c_int) -> c_int,
+    encoder_create: unsafe extern "C" fn(i32, c_int, c_int, *mut c_int) -> *mut ffi::OpusEncoder,
+    encoder_init: unsafe extern "C" fn(*mut ffi::OpusEncoder, i32, c_int, c_int) -> c_int,
+    encode:
+        unsafe extern "C" fn(*mut ffi::OpusEncoder, *const i16, c_int, *mut c_uchar, i32) -> i32,
+    encode_float:
+        unsafe extern "C" fn(*mut ffi::OpusEncoder, *const f32, c_int, *mut c_uchar, i32) -> i32,
+    encoder_ctl: unsafe extern "C" fn(*mut ffi::OpusEncoder, c_int, ...) -> c_int,
+    encoder_destroy: unsafe extern "C" fn(*mut ffi::OpusEncoder),
+    decoder_get_size: unsafe extern "C" fn(c_int) -> c_int,
+    decoder_create: unsafe extern "C" fn(i32, c_int, *mut c_int) -> *mut ffi::OpusDecoder,
+    decoder_init: unsafe extern "C" fn(*mut ffi::OpusDecoder, i32, c_int) -> c_int,
+    decode: unsafe extern "C" fn(
+        *mut ffi::OpusDecoder,
+        *const c_uchar,
+        i32,
+        *mut i16,
+        c_int,
+        c_int,
+    ) -> c_int,
+    decode_float: unsafe extern "C" fn(
+        *mut ffi::OpusDecoder,
+        *const c_uchar,
+        i32,
+        *mut f32,
+        c_int,
+        c_int,
+    ) -> c_int,
+    decoder_get_nb_samples:
+        unsafe extern "C" fn(*const ffi::OpusDecoder, *const c_uchar, i32) -> c_int,
+    decoder_ctl: unsafe extern "C" fn(*mut ffi::OpusDecoder, c_int, ...) -> c_int,
+    decoder_destroy: unsafe extern "C" fn(*mut ffi::OpusDecoder),
+}
+
+/// An Opus library loaded at runtime, e.g. to run encoders and decoders on a
+/// newer system Opus than the one linked at build time.
+///
+/// The linked Opus remains required: multistream, projection and Opus
+/// Custom coders, the packet and repacketizer functions, as well as states
+/// wrapped via `from_raw`, always call into it.
+pub struct OpusLibrary {
+    functions: Functions,
+    // Must outlive the resolved functions, hence is dropped last.
+    _library: Library,
+}
+
+impl OpusLibrary {
+    /// Loads Opus by the names it is commonly installed as on the current
+    /// platform, e.g. `libopus.so.0` on Linux.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::LibraryUnavailable`] if no library could be loaded
+    /// and [`Error::MissingSymbol`] if the library lacks an Opus function.
+    ///
+    /// [`Error::LibraryUnavailable`]: crate::error::Error::LibraryUnavailable
+    /// [`Error::MissingSymbol`]: crate::error::Error::MissingSymbol
+    pub fn load() -> Result<Self> {
+        for name in LIBRARY_NAMES {
+            let result = Self::load_from(name);
+
+            if !matches!(result, Err(Error::LibraryUnavailable)) {
+                return result;
+            }
+        }
+
+        Err(Error::LibraryUnavailable)
+    }
+
+    /// Loads Opus from `path`, a file name or a path to the library.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::LibraryUnavailable`] if the library could not be
+    /// loaded and [`Error::MissingSymbol`] if it lacks an Opus function.
+    ///
+    /// [`Error::LibraryUnavailable`]: crate::error::Error::LibraryUnavailable
+    /// [`Error::MissingSymbol`]: crate::error::Error::MissingSymbol
+    pub fn load_from<P: AsRef<OsStr>>(path: P) -> Result<Self> {
+        // Loading a library runs its initialisers, Opus has none.
+        let library = unsafe { Library::new(path) }.map_err(|_| Error::LibraryUnavailable)?;
+
+        let functions = unsafe {
+            Functions {
+                get_version_string: symbol(&library, "opus_get_version_string")?,
+                encoder_get_size: symbol(&library, "opus_encoder_get_size")?,
+                encoder_create: symbol(&library, "opus_encoder_create")?,
+                encoder_init: symbol(&library, "opus_encoder_init")?,
+                encode: symbol(&library, "opus_encode")?,
+                encode_float: symbol(&library, "opus_encode_float")?,
+                encoder_ctl: symbol(&library, "opus_encoder_ctl")?,
+                encoder_destroy: symbol(&library, "opus_encoder_destroy")?,
+                decoder_get_size: symbol(&library, "opus_decoder_get_size")?,
+                decoder_create: symbol(&library, "opus_decoder_create")?,
+                decoder_init: symbol(&library, "opus_decoder_init")?,
+                decode: symbol(&library, "opus_decode")?,
+                decode_float: symbol(&library, "opus_decode_float")?,
+                decoder_get_nb_samples: symbol(&library, "opus_decoder_get_nb_samples")?,
+                decoder_ctl: symbol(&library, "opus_decoder_ctl")?,
+                decoder_destroy: symbol(&library, "opus_decoder_destroy")?,
+            }
+        };
+
+        Ok(Self {
+            functions,
+            _library: library,
+        })
+    }
+
+    /// Gets the loaded library's version string, see [`Version::string`].
+    ///
+    /// [`Version::string`]: crate::version::Version::string
+    pub fn version(&self) -> &str {
+        // The version string is static within the library, which lives as
+        // long as `self`.
+        unsafe { CStr::from_ptr((self.functions.get_version_string)()) }
+            .to_str()
+            .unwrap_or_default()
+    }
+
+    /// Makes encoders and decoders created from now on run on this library
+    /// instead of the Opus linked at build time.
+    ///
+    /// Coders created earlier keep running on the Opus they were created
+    /// with. Multistream and projection coders, as well as states wrapped via
+    /// `from_raw`, always run on the linked Opus.
+    ///
+    /// The library stays loaded until the process exits.
+    pub fn install(self) {
+        backend::install(self);
+    }
+}
+
+impl OpusBackend for OpusLibrary {
+    unsafe fn encoder_get_size(&self, channels: i32) -> i32 {
+        (self.functions.encoder_get_size)(channels)
+    }
+
+    unsafe fn encoder_create(
+        &self,
+        sample_rate: i32,
+        channels: i32,
+        application: i32,
+        error: *mut i32,
+    ) -> *mut ffi::OpusEncoder {
+        (self.functions.encoder_create)(sample_rate, channels, application, error)
+    }
+
+    unsafe fn encoder_init(
+        &self,
+        encoder: *mut ffi::OpusEncoder,
+        sample_rate: i32,
+        channels: i32,
+        application: i32,
+    ) -> i32 {
+        (self.functions.encoder_init)(encoder, sample_rate, channels, application)
+    }
+
+    unsafe fn encode(
+        &self,
+        encoder: *mut ffi::OpusEncoder,
+        pcm: *const i16,
+        frame_size: i32,
+        data: *mut u8,
+        max_data_bytes: i32,
+    ) -> i32 {
+        (self.functions.encode)(encoder, pcm, frame_size, data, max_data_bytes)
+    }
+
+    unsafe fn encode_float(
+        &self,
+        encoder: *mut ffi::OpusEncoder,
+        pcm: *const f32,
+        frame_size: i32,
+        data: *mut u8,
+        max_data_bytes: i32,
+    ) -> i32 {
+        (self.functions.encode_float)(encoder, pcm, frame_size, data, max_data_bytes)
+    }
+
+    unsafe fn encoder_ctl(&self, encoder: *mut ffi::OpusEncoder, request: i32, value: i32) -> i32 {
+        (self.functions.encoder_ctl)(encoder, request, value)
+    }
+
+    unsafe fn encoder_ctl_get(
+        &self,
+        encoder: *mut ffi::OpusEncoder,
+        request: i32,
+        value: *mut i32,
+    ) -> i32 {
+        (self.functions.encoder_ctl)(encoder, request, value)
+    }
+
+    unsafe fn encoder_destroy(&self, encoder: *mut ffi::OpusEncoder) {
+        (self.functions.encoder_destroy)(encoder)
+    }
+
+    unsafe fn decoder_get_size(&self, channels: i32) -> i32 {
+        (self.functions.decoder_get_size)(channels)
+    }
+
+    unsafe fn decoder_create(
+        &self,
+        sample_rate: i32,
+        channels: i32,
+        error: *mut i32,
+    ) -> *mut ffi::OpusDecoder {
+        (self.functions.decoder_create)(sample_rate, channels, error)
+    }
+
+    unsafe fn decoder_init(
+        &self,
+        decoder: *mut ffi::OpusDecoder,
+        sample_rate: i32,
+        channels: i32,
+    ) -> i32 {
+        (self.functions.decoder_init)(decoder, sample_rate, channels)
+    }
+
+    unsafe fn decode(
+        &self,
+        decoder: *mut ffi::OpusDecoder,
+        data: *const u8,
+        len: i32,
+        pcm: *mut i16,
+        frame_size: i32,
+        decode_fec: i32,
+    ) -> i32 {
+        (self.functions.decode)(decoder, data, len, pcm, frame_size, decode_fec)
+    }
+
+    unsafe fn decode_float(
+        &self,
+        decoder: *mut ffi::OpusDecoder,
+        data: *const u8,
+        len: i32,
+        pcm: *mut f32,
+        frame_size: i32,
+        decode_fec: i32,
+    ) -> i32 {
+        (self.functions.decode_float)(decoder, data, len, pcm, frame_size, decode_fec)
+    }
+
+    unsafe fn decoder_get_nb_samples(
+        &self,
+        decoder: *const ffi::OpusDecoder,
+        data: *const u8,
+        len: i32,
+    ) -> i32 {
+        (self.functions.decoder_get_nb_samples)(decoder, data, len)
+    }
+
+    unsafe fn decoder_ctl(&self, decoder: *mut ffi::OpusDecoder, request: i32, value: i32) -> i32 {
+        (self.functions.decoder_ctl)(decoder, request, value)
+    }
+
+    unsafe fn decoder_ctl_get(
+        &self,
+        decoder: *mut ffi::OpusDecoder,
+        request: i32,
+        value: *mut i32,
+    ) -> i32 {
+        (self.functions.decoder_ctl)(decoder, request, value)
+    }
+
+    unsafe fn decoder_destroy(&self, decoder: *mut ffi::OpusDecoder) {
+        (self.functions.decoder_destroy)(decoder)
+    }
+}
+
+impl Debug for OpusLibrary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("OpusLibrary")
+            .field("version", &self.version())
+            .finish()
+    }
+}
+
+/// Resolves the function `name` of type `T` from `library`.
+///
+/// **Safety**:
+/// `T` must match the signature of the function.
+unsafe fn symbol<T: Copy>(library: &Library, name: &'static str) -> Result<T> {
+    library
+        .get::<T>(name.as_bytes())
+        .map(|symbol| *symbol)
+        .map_err(|_| Error::MissingSymbol(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OpusLibrary;
+    use crate::Error;
+    use matches::assert_matches;
+
+    #[test]
+    fn unavailable_library() {
+        assert_matches!(
+            OpusLibrary::load_from("libopus-does-not-exist.so"),
+            Err(Error::LibraryUnavailable)
+        );
+    }
+
+    #[test]
+    fn load_system_library() {
+        // Opus need not be installed as a shared library.
+        if let Ok(library) = OpusLibrary::load() {
+            assert!(library.version().starts_with("libopus"));
+        }
+    }
+}
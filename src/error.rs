@@ -2,6 +2,7 @@ use crate::ffi;
 use std::{
     error::Error as StdError,
     fmt::{Display, Formatter, Result as FmtResult},
+    io::{Error as IoError, ErrorKind},
 };
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -48,6 +49,62 @@ pub enum Error {
     PacketTooLarge,
     /// A `Vec` representing a mapping exceeded the expected value.
     MappingExpectedLen(usize),
+    /// A mapping entry referred to a stream channel that does not exist.
+    InvalidMappingIndex(u8),
+    /// The amount of streams is zero, the coupled streams exceed the
+    /// streams, or both together exceed `255`.
+    InvalidStreams { streams: u8, coupled_streams: u8 },
+    /// A demixing matrix did not have the expected length in bytes.
+    DemixingMatrixExpectedLen(usize),
+    /// A value failed to match a documented [`MappingFamily`].
+    ///
+    /// [`MappingFamily`]: crate::coder::multistream::MappingFamily
+    InvalidMappingFamily(u8),
+    /// A stream was requested that the multistream coder does not contain.
+    InvalidStreamId(u8),
+    /// A packet cannot be padded to the given length, as it is shorter than
+    /// the packet or exceeds the buffer.
+    InvalidPadLen(usize),
+    /// A value failed to match a documented [`FrameDuration`].
+    ///
+    /// [`FrameDuration`]: crate::FrameDuration
+    InvalidFrameDuration(i32),
+    /// A gain exceeded the accepted range, the value is in Q8 dB units.
+    InvalidGain(i32),
+    /// A signal depth exceeded the accepted range of 8 to 24 bits.
+    InvalidLsbDepth(u8),
+    /// A signal did not have the expected length in samples.
+    SignalsExpectedLen(usize),
+    /// The Opus library could not be loaded at runtime.
+    LibraryUnavailable,
+    /// The Opus library loaded at runtime lacks the named function.
+    MissingSymbol(&'static str),
+    /// An RTP packet was truncated or not of version 2.
+    InvalidRtpPacket,
+    /// An RTP packet carried a payload type other than the negotiated one.
+    UnexpectedPayloadType(u8),
+    /// A packet lasted longer than the maximum duration allowed per RTP
+    /// packet.
+    PacketDurationExceeded,
+    /// A RED payload's block headers were truncated or exceeded the payload.
+    InvalidRedPayload,
+    /// An SDP fmtp parameter of Opus had a malformed value.
+    InvalidFmtpParameter,
+    /// An Ogg page lacked the capture pattern, was truncated, or failed its
+    /// checksum.
+    InvalidOggPage,
+    /// An `OpusHead` or `OpusTags` header was truncated, of an unsupported
+    /// version, or lacked its magic signature.
+    InvalidOpusHeader,
+    /// A `METADATA_BLOCK_PICTURE` was truncated.
+    InvalidPicture,
+    /// A granule position exceeded the largest valid position.
+    GranulePosOverflow,
+    /// The resampler rejected its sample rates or channels, or failed to
+    /// process.
+    ResamplingFailed,
+    /// A mixing matrix did not have the expected length in coefficients.
+    MixingMatrixExpectedLen(usize),
 }
 
 impl StdError for Error {
@@ -74,6 +131,44 @@ impl Display for Error {
             Error::PacketTooLarge => f.write_str("Packet's length exceeded `i32::MAX`"),
             Error::InvalidBitrate(rate) => write!(f, "Invalid Bitrate: {}", rate),
             Error::MappingExpectedLen(len) => write!(f, "Wrong channel length, expected: {}", len),
+            Error::InvalidMappingIndex(index) => write!(f, "Invalid Mapping Index: {}", index),
+            Error::InvalidStreams {
+                streams,
+                coupled_streams,
+            } => write!(
+                f,
+                "Invalid Streams: {} streams with {} coupled",
+                streams, coupled_streams
+            ),
+            Error::DemixingMatrixExpectedLen(len) => {
+                write!(f, "Wrong demixing matrix length, expected: {}", len)
+            }
+            Error::InvalidMappingFamily(family) => write!(f, "Invalid Mapping Family: {}", family),
+            Error::InvalidStreamId(stream_id) => write!(f, "Invalid Stream ID: {}", stream_id),
+            Error::InvalidPadLen(len) => write!(f, "Invalid Pad Length: {}", len),
+            Error::InvalidFrameDuration(duration) => {
+                write!(f, "Invalid Frame Duration: {}", duration)
+            }
+            Error::InvalidGain(gain) => write!(f, "Invalid Gain: {}", gain),
+            Error::InvalidLsbDepth(depth) => write!(f, "Invalid LSB Depth: {}", depth),
+            Error::SignalsExpectedLen(len) => write!(f, "Wrong signal length, expected: {}", len),
+            Error::LibraryUnavailable => f.write_str("Opus library could not be loaded"),
+            Error::MissingSymbol(name) => write!(f, "Missing Opus function: {}", name),
+            Error::InvalidRtpPacket => f.write_str("Invalid RTP packet"),
+            Error::UnexpectedPayloadType(payload_type) => {
+                write!(f, "Unexpected RTP Payload Type: {}", payload_type)
+            }
+            Error::PacketDurationExceeded => f.write_str("Packet exceeded the maximum duration"),
+            Error::InvalidRedPayload => f.write_str("Invalid RED payload"),
+            Error::InvalidFmtpParameter => f.write_str("Invalid SDP fmtp parameter"),
+            Error::InvalidOggPage => f.write_str("Invalid Ogg page"),
+            Error::InvalidOpusHeader => f.write_str("Invalid Ogg Opus header"),
+            Error::InvalidPicture => f.write_str("Invalid METADATA_BLOCK_PICTURE"),
+            Error::GranulePosOverflow => f.write_str("Granule position overflowed"),
+            Error::ResamplingFailed => f.write_str("Resampling failed"),
+            Error::MixingMatrixExpectedLen(len) => {
+                write!(f, "Wrong mixing matrix length, expected: {}", len)
+            }
         }
     }
 }
@@ -84,6 +179,16 @@ impl From<ErrorCode> for Error {
     }
 }
 
+impl From<Error> for IoError {
+    /// Wraps the error as [`ErrorKind::InvalidData`], e.g. to report a
+    /// malformed packet read from a stream.
+    ///
+    /// [`ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+    fn from(error: Error) -> IoError {
+        IoError::new(ErrorKind::InvalidData, error)
+    }
+}
+
 #[repr(i32)]
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum ErrorCode {
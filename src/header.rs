@@ -0,0 +1,530 @@
+//! The identification and comment headers of Opus streams, [`OpusHead`]
+//! and [`OpusTags`], as specified by RFC 7845.
+//!
+//! The headers are independent of the Ogg layer, e.g. Matroska stores the
+//! `OpusHead` as codec private data.
+//!
+//! [`OpusHead`]: crate::header::OpusHead
+//! [`OpusTags`]: crate::header::OpusTags
+
+use crate::{
+    coder::multistream::{ChannelMapping, MappingFamily},
+    Channels, Error, Result, TryFrom,
+};
+
+/// The magic signature starting the identification header.
+pub(crate) const OPUS_HEAD: &[u8; 8] = b"OpusHead";
+
+/// The magic signature starting the comment header.
+pub(crate) const OPUS_TAGS: &[u8; 8] = b"OpusTags";
+
+/// The comment carrying a base64 encoded [`Picture`].
+///
+/// [`Picture`]: crate::header::Picture
+const METADATA_BLOCK_PICTURE: &str = "METADATA_BLOCK_PICTURE";
+
+/// The comment carrying the track's gain relative to the output gain.
+const R128_TRACK_GAIN: &str = "R128_TRACK_GAIN";
+
+/// The comment carrying the album's gain relative to the output gain.
+const R128_ALBUM_GAIN: &str = "R128_ALBUM_GAIN";
+
+/// The base64 alphabet of RFC 4648.
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The identification header of an Ogg Opus stream as specified by
+/// RFC 7845, describing how to set up the decoder.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct OpusHead {
+    /// The amount of output channels.
+    pub channels: u8,
+    /// The amount of samples at 48 kHz to discard from the decoder's
+    /// output when starting playback.
+    pub pre_skip: u16,
+    /// The sample rate of the original input, informational only.
+    pub input_sample_rate: u32,
+    /// The gain to apply to the decoder's output in Q7.8 dB.
+    pub output_gain: i16,
+    pub mapping_family: MappingFamily,
+    /// The stream layout, `None` for [`MappingFamily::Rtp`] which implies
+    /// one stream of `channels`.
+    ///
+    /// [`MappingFamily::Rtp`]: crate::coder::multistream::MappingFamily::Rtp
+    pub mapping: Option<ChannelMapping>,
+}
+
+impl OpusHead {
+    /// Creates the header of a mono or stereo stream.
+    pub fn new(channels: Channels, pre_skip: u16, input_sample_rate: u32) -> Self {
+        Self {
+            channels: channels as u8,
+            pre_skip,
+            input_sample_rate,
+            output_gain: 0,
+            mapping_family: MappingFamily::Rtp,
+            mapping: None,
+        }
+    }
+
+    /// Creates the header of a multistream stream laid out by `mapping`.
+    pub fn multistream(
+        mapping_family: MappingFamily,
+        mapping: ChannelMapping,
+        pre_skip: u16,
+        input_sample_rate: u32,
+    ) -> Self {
+        Self {
+            channels: mapping.channels(),
+            pre_skip,
+            input_sample_rate,
+            output_gain: 0,
+            mapping_family,
+            mapping: Some(mapping),
+        }
+    }
+
+    /// Parses the header from the packet starting an Ogg Opus stream.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidOpusHeader`] if `bytes` is truncated, lacks
+    /// the signature, or is of an incompatible version, and the errors of
+    /// [`ChannelMapping::new`] if the stream layout is invalid.
+    ///
+    /// [`Error::InvalidOpusHeader`]: crate::error::Error::InvalidOpusHeader
+    /// [`ChannelMapping::new`]: crate::coder::multistream::ChannelMapping::new
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        // Versions share the major version in the upper four bits.
+        if bytes.len() < 19 || &bytes[..8] != OPUS_HEAD || bytes[8] >> 4 != 0 {
+            return Err(Error::InvalidOpusHeader);
+        }
+
+        let channels = bytes[9];
+        let mapping_family = MappingFamily::try_from(bytes[18])?;
+
+        let mapping = match mapping_family {
+            MappingFamily::Rtp if channels == 0 || channels > 2 => {
+                return Err(Error::InvalidChannels(i32::from(channels)))
+            }
+            MappingFamily::Rtp => None,
+            _ => {
+                let table = bytes
+                    .get(21..21 + usize::from(channels))
+                    .ok_or(Error::InvalidOpusHeader)?;
+
+                Some(ChannelMapping::new(channels, bytes[19], bytes[20], table)?)
+            }
+        };
+
+        Ok(Self {
+            channels,
+            pre_skip: u16::from_le_bytes([bytes[10], bytes[11]]),
+            input_sample_rate: u32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]),
+            output_gain: i16::from_le_bytes([bytes[16], bytes[17]]),
+            mapping_family,
+            mapping,
+        })
+    }
+
+    /// Serializes the header as the packet starting an Ogg Opus stream.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(21 + usize::from(self.channels));
+        bytes.extend_from_slice(OPUS_HEAD);
+        bytes.push(1);
+        bytes.push(self.channels);
+        bytes.extend_from_slice(&self.pre_skip.to_le_bytes());
+        bytes.extend_from_slice(&self.input_sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&self.output_gain.to_le_bytes());
+        bytes.push(self.mapping_family.into());
+
+        if let Some(mapping) = &self.mapping {
+            bytes.push(mapping.streams());
+            bytes.push(mapping.coupled_streams());
+            bytes.extend_from_slice(mapping.mapping());
+        }
+
+        bytes
+    }
+}
+
+/// The comment header of an Ogg Opus stream, a vendor string and
+/// `KEY=value` comments as in Vorbis comments.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct OpusTags {
+    pub vendor: String,
+    /// The comments as key and value, keys are case-insensitive.
+    pub comments: Vec<(String, String)>,
+}
+
+impl OpusTags {
+    /// Creates tags without comments naming `vendor`.
+    pub fn new(vendor: impl Into<String>) -> Self {
+        Self {
+            vendor: vendor.into(),
+            comments: Vec::new(),
+        }
+    }
+
+    /// Appends the comment `key`=`value`, a key may appear multiple times.
+    pub fn add(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.comments.push((key.into(), value.into()));
+    }
+
+    /// Gets the value of the first comment named `key`, ignoring case.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.comments
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Replaces all comments named `key` by `key`=`value`.
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        let key = key.into();
+        self.comments
+            .retain(|(name, _)| !name.eq_ignore_ascii_case(&key));
+        self.add(key, value);
+    }
+
+    /// Gets the `R128_TRACK_GAIN` in Q7.8 dB, the gain normalizing the
+    /// track to -23 LUFS on top of the header's output gain, `None` if
+    /// absent or malformed.
+    pub fn track_gain(&self) -> Option<i16> {
+        self.get(R128_TRACK_GAIN)?.trim().parse().ok()
+    }
+
+    /// Sets the `R128_TRACK_GAIN` to `gain` in Q7.8 dB.
+    pub fn set_track_gain(&mut self, gain: i16) {
+        self.set(R128_TRACK_GAIN, gain.to_string());
+    }
+
+    /// Gets the `R128_ALBUM_GAIN` in Q7.8 dB, the gain normalizing the
+    /// album the track belongs to on top of the header's output gain, `None`
+    /// if absent or malformed.
+    pub fn album_gain(&self) -> Option<i16> {
+        self.get(R128_ALBUM_GAIN)?.trim().parse().ok()
+    }
+
+    /// Sets the `R128_ALBUM_GAIN` to `gain` in Q7.8 dB.
+    pub fn set_album_gain(&mut self, gain: i16) {
+        self.set(R128_ALBUM_GAIN, gain.to_string());
+    }
+
+    /// Gets the pictures attached via `METADATA_BLOCK_PICTURE` comments,
+    /// e.g. cover art, skipping malformed ones.
+    pub fn pictures(&self) -> Vec<Picture> {
+        self.comments
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case(METADATA_BLOCK_PICTURE))
+            .filter_map(|(_, value)| base64_decode(value))
+            .filter_map(|bytes| Picture::parse(&bytes).ok())
+            .collect()
+    }
+
+    /// Attaches `picture` as `METADATA_BLOCK_PICTURE` comment.
+    pub fn add_picture(&mut self, picture: &Picture) {
+        self.add(METADATA_BLOCK_PICTURE, base64_encode(&picture.to_bytes()));
+    }
+
+    /// Parses the tags from the second packet of an Ogg Opus stream.
+    ///
+    /// Comments lacking `=` are skipped, invalid UTF-8 is replaced.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidOpusHeader`] if `bytes` is truncated or
+    /// lacks the signature.
+    ///
+    /// [`Error::InvalidOpusHeader`]: crate::error::Error::InvalidOpusHeader
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut rest = bytes
+            .strip_prefix(&OPUS_TAGS[..])
+            .ok_or(Error::InvalidOpusHeader)?;
+
+        let vendor = read_string(&mut rest)?;
+        let count = read_u32_le(&mut rest, Error::InvalidOpusHeader)?;
+        // Every comment takes at least its length.
+        let mut comments = Vec::with_capacity((count as usize).min(rest.len() / 4));
+
+        for _ in 0..count {
+            let comment = read_string(&mut rest)?;
+
+            if let Some(split) = comment.find('=') {
+                comments.push((
+                    comment[..split].to_string(),
+                    comment[split + 1..].to_string(),
+                ));
+            }
+        }
+
+        Ok(Self { vendor, comments })
+    }
+
+    /// Serializes the tags as the second packet of an Ogg Opus stream.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(OPUS_TAGS);
+        push_string(&mut bytes, &self.vendor);
+        bytes.extend_from_slice(&(self.comments.len() as u32).to_le_bytes());
+
+        for (key, value) in &self.comments {
+            push_string(&mut bytes, &format!("{}={}", key, value));
+        }
+
+        bytes
+    }
+}
+
+impl Default for OpusTags {
+    /// Creates tags without comments naming this crate as vendor.
+    fn default() -> Self {
+        Self::new(concat!("audiopus ", env!("CARGO_PKG_VERSION")))
+    }
+}
+
+/// A picture attached to a stream as specified by FLAC's
+/// `METADATA_BLOCK_PICTURE`, e.g. the front cover.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Picture {
+    /// The ID3v2 APIC picture type, e.g. `3` for the front cover.
+    pub picture_type: u32,
+    /// The MIME type, e.g. `image/png`, or `-->` if `data` is a URL.
+    pub mime_type: String,
+    pub description: String,
+    pub width: u32,
+    pub height: u32,
+    /// The colour depth in bits per pixel.
+    pub depth: u32,
+    /// The amount of colours of indexed pictures, `0` otherwise.
+    pub colors: u32,
+    pub data: Vec<u8>,
+}
+
+impl Picture {
+    /// Parses the picture from the binary block, not base64 encoded.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidPicture`] if `bytes` is truncated.
+    ///
+    /// [`Error::InvalidPicture`]: crate::error::Error::InvalidPicture
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut rest = bytes;
+        let picture_type = read_u32_be(&mut rest)?;
+        let mime_type = read_picture_string(&mut rest)?;
+        let description = read_picture_string(&mut rest)?;
+        let width = read_u32_be(&mut rest)?;
+        let height = read_u32_be(&mut rest)?;
+        let depth = read_u32_be(&mut rest)?;
+        let colors = read_u32_be(&mut rest)?;
+        let len = read_u32_be(&mut rest)? as usize;
+        let data = take(&mut rest, len, Error::InvalidPicture)?.to_vec();
+
+        Ok(Self {
+            picture_type,
+            mime_type,
+            description,
+            width,
+            height,
+            depth,
+            colors,
+            data,
+        })
+    }
+
+    /// Serializes the picture as binary block, not base64 encoded.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(32 + self.mime_type.len() + self.data.len());
+        bytes.extend_from_slice(&self.picture_type.to_be_bytes());
+
+        for string in &[&self.mime_type, &self.description] {
+            bytes.extend_from_slice(&(string.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(string.as_bytes());
+        }
+
+        for value in &[self.width, self.height, self.depth, self.colors] {
+            bytes.extend_from_slice(&value.to_be_bytes());
+        }
+
+        bytes.extend_from_slice(&(self.data.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.data);
+
+        bytes
+    }
+}
+
+/// Appends `string` prefixed by its length as little-endian `u32`.
+fn push_string(bytes: &mut Vec<u8>, string: &str) {
+    bytes.extend_from_slice(&(string.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(string.as_bytes());
+}
+
+/// Splits `len` bytes off `bytes`, failing with `error` if it is shorter.
+fn take<'a>(bytes: &mut &'a [u8], len: usize, error: Error) -> Result<&'a [u8]> {
+    if bytes.len() < len {
+        return Err(error);
+    }
+
+    let (taken, rest) = bytes.split_at(len);
+    *bytes = rest;
+
+    Ok(taken)
+}
+
+/// Reads a little-endian `u32` off `bytes`.
+fn read_u32_le(bytes: &mut &[u8], error: Error) -> Result<u32> {
+    let value = take(bytes, 4, error)?;
+
+    Ok(u32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+}
+
+/// Reads a big-endian `u32` of a picture off `bytes`.
+fn read_u32_be(bytes: &mut &[u8]) -> Result<u32> {
+    let value = take(bytes, 4, Error::InvalidPicture)?;
+
+    Ok(u32::from_be_bytes([value[0], value[1], value[2], value[3]]))
+}
+
+/// Reads a string prefixed by its length as little-endian `u32` off
+/// `bytes`.
+fn read_string(bytes: &mut &[u8]) -> Result<String> {
+    let len = read_u32_le(bytes, Error::InvalidOpusHeader)? as usize;
+    let string = take(bytes, len, Error::InvalidOpusHeader)?;
+
+    Ok(String::from_utf8_lossy(string).into_owned())
+}
+
+/// Reads a string of a picture prefixed by its length as big-endian `u32`
+/// off `bytes`.
+fn read_picture_string(bytes: &mut &[u8]) -> Result<String> {
+    let len = read_u32_be(bytes)? as usize;
+    let string = take(bytes, len, Error::InvalidPicture)?;
+
+    Ok(String::from_utf8_lossy(string).into_owned())
+}
+
+/// Encodes `bytes` as padded base64.
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0_u32, |group, (index, &byte)| {
+                group | u32::from(byte) << (16 - 8 * index)
+            });
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let digit = (group >> (18 - 6 * index)) & 0x3f;
+                encoded.push(char::from(BASE64[digit as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Decodes padded base64, `None` if `encoded` is malformed.
+fn base64_decode(encoded: &str) -> Option<Vec<u8>> {
+    let encoded = encoded.as_bytes();
+
+    if encoded.len() % 4 != 0 {
+        return None;
+    }
+
+    let mut decoded = Vec::with_capacity(encoded.len() / 4 * 3);
+
+    for chunk in encoded.chunks(4) {
+        let padding = chunk
+            .iter()
+            .rev()
+            .take_while(|&&digit| digit == b'=')
+            .count();
+        let mut group = 0_u32;
+
+        for (index, &digit) in chunk[..4 - padding].iter().enumerate() {
+            let value = BASE64.iter().position(|&symbol| symbol == digit)? as u32;
+            group |= value << (18 - 6 * index);
+        }
+
+        if padding > 2 {
+            return None;
+        }
+
+        decoded.extend_from_slice(&group.to_be_bytes()[1..4 - padding]);
+    }
+
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{base64_decode, base64_encode, OpusHead, OpusTags, Picture};
+    use crate::{
+        coder::multistream::{ChannelMapping, MappingFamily},
+        Channels, Error,
+    };
+
+    #[test]
+    fn headers_roundtrip() {
+        let head = OpusHead::new(Channels::Stereo, 312, 44100);
+        assert_eq!(OpusHead::parse(&head.to_bytes()), Ok(head));
+
+        let head = OpusHead::multistream(
+            MappingFamily::Vorbis,
+            ChannelMapping::surround_5_1(),
+            312,
+            48000,
+        );
+        assert_eq!(OpusHead::parse(&head.to_bytes()), Ok(head.clone()));
+
+        let bytes = head.to_bytes();
+        assert_eq!(
+            OpusHead::parse(&bytes[..bytes.len() - 1]),
+            Err(Error::InvalidOpusHeader)
+        );
+
+        let mut tags = OpusTags::new("vendor");
+        tags.add("ARTIST", "Someone");
+        tags.add("Title", "a=b");
+        let parsed = OpusTags::parse(&tags.to_bytes()).unwrap();
+        assert_eq!(parsed, tags);
+        assert_eq!(parsed.get("title"), Some("a=b"));
+        assert_eq!(parsed.get("album"), None);
+
+        tags.set_track_gain(-512);
+        tags.set_track_gain(-384);
+        tags.add("R128_ALBUM_GAIN", "loud");
+        assert_eq!(tags.track_gain(), Some(-384));
+        assert_eq!(tags.album_gain(), None);
+        assert_eq!(tags.comments.len(), 4);
+    }
+
+    #[test]
+    fn attach_pictures() {
+        assert_eq!(base64_encode(b"Opus!"), "T3B1cyE=");
+        assert_eq!(base64_decode("T3B1cyE=").unwrap(), b"Opus!");
+        assert_eq!(base64_decode("T3B1"), Some(b"Opu".to_vec()));
+        assert!(base64_decode("T3B").is_none());
+
+        let picture = Picture {
+            picture_type: 3,
+            mime_type: "image/png".to_string(),
+            description: "Cover".to_string(),
+            width: 1,
+            height: 1,
+            depth: 24,
+            colors: 0,
+            data: vec![0x89, b'P', b'N', b'G'],
+        };
+
+        let mut tags = OpusTags::default();
+        tags.add_picture(&picture);
+        tags.add("METADATA_BLOCK_PICTURE", "not base64");
+
+        let parsed = OpusTags::parse(&tags.to_bytes()).unwrap();
+        assert_eq!(parsed.pictures(), vec![picture.clone()]);
+        assert_eq!(Picture::parse(&picture.to_bytes()), Ok(picture));
+    }
+}
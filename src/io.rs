@@ -0,0 +1,264 @@
+//! Blocking adapters between raw PCM and length-prefixed Opus packets on
+//! `std::io` streams, e.g. to pipe audio through command line tools, see
+//! [`OpusPacketWriter`] and [`OpusPacketReader`].
+//!
+//! Each packet is prefixed by its length as big-endian `u16`, PCM is
+//! 16-bit little-endian, interleaved if it has multiple channels.
+//!
+//! [`OpusPacketWriter`]: crate::io::OpusPacketWriter
+//! [`OpusPacketReader`]: crate::io::OpusPacketReader
+
+use crate::{
+    coder::{DecodeRequest, Decoder, Encoder, StreamEncoder},
+    packet::{Packet, PacketBuf},
+    Error, FrameDuration, TryFrom,
+};
+use std::io::{ErrorKind, Read, Result, Write};
+
+/// The length of the big-endian length prefix, in bytes.
+const PREFIX_LEN: usize = 2;
+
+/// `OpusPacketWriter` accepts raw PCM written to it, encodes it in frames of
+/// a fixed duration, and writes the length-prefixed packets to the wrapped
+/// writer.
+///
+/// Call [`finish`] after the last PCM to encode a trailing partial frame,
+/// padded with silence, dropping the writer discards it.
+///
+/// [`finish`]: crate::io::OpusPacketWriter::finish
+#[derive(Debug)]
+pub struct OpusPacketWriter<W> {
+    encoder: StreamEncoder,
+    writer: W,
+    /// The samples of the bytes being written.
+    samples: Vec<i16>,
+    /// A trailing byte of a sample split across writes.
+    odd_byte: Option<u8>,
+    packets: Vec<PacketBuf>,
+}
+
+impl<W: Write> OpusPacketWriter<W> {
+    /// Creates a new `OpusPacketWriter` encoding frames of `frame_duration`
+    /// via `encoder` into `writer`.
+    ///
+    /// **Errors**:
+    /// Returns [`ErrorKind::InvalidData`] wrapping [Error::Opus] when Opus
+    /// encountered a problem.
+    ///
+    /// [`ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn new(encoder: Encoder, frame_duration: FrameDuration, writer: W) -> Result<Self> {
+        Ok(Self {
+            encoder: StreamEncoder::new(encoder, frame_duration)?,
+            writer,
+            samples: Vec::new(),
+            odd_byte: None,
+            packets: Vec::new(),
+        })
+    }
+
+    /// Gets the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Encodes a trailing partial frame padded with silence, flushes, and
+    /// returns the wrapped writer.
+    ///
+    /// **Errors**:
+    /// Returns the writer's error or [`ErrorKind::InvalidData`] wrapping
+    /// [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn finish(mut self) -> Result<W> {
+        if let Some(packet) = self.encoder.flush()? {
+            self.packets.push(packet);
+        }
+
+        self.write_packets()?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    /// Writes the packets encoded, each prefixed by its length.
+    fn write_packets(&mut self) -> Result<()> {
+        for packet in self.packets.drain(..) {
+            let packet = packet.as_slice();
+            let prefix = u16::try_from(packet.len()).map_err(|_| Error::PacketTooLarge)?;
+
+            self.writer.write_all(&prefix.to_be_bytes())?;
+            self.writer.write_all(packet)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for OpusPacketWriter<W> {
+    /// Encodes the frames `buf` completes and writes their packets.
+    ///
+    /// If a frame fails to encode, it is dropped and the error returned
+    /// after writing the packets encoded before it, the PCM following it
+    /// stays pending.
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let mut bytes = buf;
+
+        if let (Some(low), Some((&high, rest))) = (self.odd_byte, bytes.split_first()) {
+            self.samples.push(i16::from_le_bytes([low, high]));
+            self.odd_byte = None;
+            bytes = rest;
+        }
+
+        for pair in bytes.chunks(2) {
+            match *pair {
+                [low, high] => self.samples.push(i16::from_le_bytes([low, high])),
+                [low] => self.odd_byte = Some(low),
+                _ => unreachable!("chunks hold one or two bytes"),
+            }
+        }
+
+        let encoded = self.encoder.push(&self.samples, &mut self.packets);
+        self.samples.clear();
+        self.write_packets()?;
+        encoded?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// `OpusPacketReader` reads length-prefixed packets from the wrapped
+/// reader and decodes them, readable as raw PCM or frame by frame.
+#[derive(Debug)]
+pub struct OpusPacketReader<R> {
+    decoder: Decoder,
+    reader: R,
+    packet: Vec<u8>,
+    /// Decoded PCM not read yet, as little-endian bytes.
+    pcm: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> OpusPacketReader<R> {
+    /// Creates a new `OpusPacketReader` decoding the packets of `reader`
+    /// via `decoder`.
+    pub fn new(decoder: Decoder, reader: R) -> Self {
+        Self {
+            decoder,
+            reader,
+            packet: Vec::new(),
+            pcm: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Unwraps the decoder and the reader.
+    pub fn into_inner(self) -> (Decoder, R) {
+        (self.decoder, self.reader)
+    }
+
+    /// Reads and decodes the next packet, `None` at the end of the stream.
+    ///
+    /// **Errors**:
+    /// Returns the reader's error, [`ErrorKind::UnexpectedEof`] if the
+    /// stream ends within a packet, or [`ErrorKind::InvalidData`] wrapping
+    /// the crate's [`Error`] if a packet fails to decode.
+    ///
+    /// [`ErrorKind::UnexpectedEof`]: std::io::ErrorKind::UnexpectedEof
+    /// [`ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+    /// [`Error`]: crate::error::Error
+    pub fn read_frame(&mut self) -> Result<Option<Vec<i16>>> {
+        let mut prefix = [0; PREFIX_LEN];
+
+        // Only a stream ending before the prefix's first byte ends cleanly.
+        match self.reader.read_exact(&mut prefix[..1]) {
+            Ok(()) => {}
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(error) => return Err(error),
+        }
+
+        self.reader.read_exact(&mut prefix[1..])?;
+
+        self.packet
+            .resize(usize::from(u16::from_be_bytes(prefix)), 0);
+        self.reader.read_exact(&mut self.packet)?;
+
+        let packet = Packet::new(&self.packet)?;
+        let pcm = self.decoder.decode_to_vec(DecodeRequest::Normal(packet))?;
+
+        Ok(Some(pcm))
+    }
+}
+
+impl<R: Read> Read for OpusPacketReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.position == self.pcm.len() {
+            let frame = match self.read_frame()? {
+                Some(frame) => frame,
+                None => return Ok(0),
+            };
+
+            self.pcm.clear();
+            self.position = 0;
+
+            for sample in frame {
+                self.pcm.extend_from_slice(&sample.to_le_bytes());
+            }
+        }
+
+        let len = buf.len().min(self.pcm.len() - self.position);
+        buf[..len].copy_from_slice(&self.pcm[self.position..self.position + len]);
+        self.position += len;
+
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OpusPacketReader, OpusPacketWriter};
+    use crate::{
+        coder::{Decoder, Encoder},
+        Application, Channels, FrameDuration, SampleRate,
+    };
+    use std::io::{Cursor, ErrorKind, Read, Write};
+
+    #[test]
+    fn pipe_pcm_through_packets() {
+        let encoder =
+            Encoder::new(SampleRate::Hz16000, Channels::Mono, Application::Audio).unwrap();
+        let mut writer = OpusPacketWriter::new(encoder, FrameDuration::Ms20, Vec::new()).unwrap();
+
+        // 1.5 frames, written with samples split across writes.
+        let pcm = vec![0_u8; 960];
+        writer.write_all(&pcm[..301]).unwrap();
+        writer.write_all(&pcm[301..]).unwrap();
+        let packets = writer.finish().unwrap();
+
+        let decoder = Decoder::new(SampleRate::Hz16000, Channels::Mono).unwrap();
+        let mut reader = OpusPacketReader::new(decoder, Cursor::new(&packets));
+        let mut decoded = Vec::new();
+        reader.read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded.len(), 2 * 320 * 2);
+
+        let decoder = Decoder::new(SampleRate::Hz16000, Channels::Mono).unwrap();
+        let mut reader = OpusPacketReader::new(decoder, Cursor::new(&packets[..packets.len() - 1]));
+        assert_eq!(reader.read_frame().unwrap().unwrap().len(), 320);
+        assert!(reader.read_frame().is_err());
+
+        // A stream truncated within a length prefix.
+        let first = usize::from(u16::from_be_bytes([packets[0], packets[1]])) + 2;
+        let decoder = Decoder::new(SampleRate::Hz16000, Channels::Mono).unwrap();
+        let mut reader = OpusPacketReader::new(decoder, Cursor::new(&packets[..first + 1]));
+        assert_eq!(reader.read_frame().unwrap().unwrap().len(), 320);
+        assert_eq!(
+            reader.read_frame().unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+}
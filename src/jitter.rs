@@ -0,0 +1,456 @@
+//! An adaptive jitter buffer, reordering packets received over an unreliable
+//! transport such as RTP and scheduling their playout, see [`JitterBuffer`].
+//!
+//! [`JitterBuffer`]: crate::jitter::JitterBuffer
+
+use crate::{
+    coder::{DecodeRequest, Decoder, GenericCtl},
+    packet::Packet,
+    Result, Sample, TryFrom,
+};
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+/// The clock rate of RTP timestamps for Opus, regardless of the sample rate.
+const TIMESTAMP_RATE: f64 = 48000.0;
+
+/// How many mean deviations of the transit time the target delay covers.
+const JITTER_MULTIPLIER: u32 = 4;
+
+/// How many pulls the buffer level is averaged over to detect clock drift.
+const DRIFT_SMOOTHING: f64 = 64.0;
+
+/// Bounds of the delay a [`JitterBuffer`] adapts within.
+///
+/// [`JitterBuffer`]: crate::jitter::JitterBuffer
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JitterConfig {
+    /// The delay kept buffered even without any measured jitter.
+    pub min_delay: Duration,
+    /// The delay never exceeded, however high the measured jitter.
+    pub max_delay: Duration,
+    /// Whether to compensate drift between the sender's and the playout's
+    /// clock by occasionally inserting or removing a sample.
+    pub drift_compensation: bool,
+}
+
+impl Default for JitterConfig {
+    fn default() -> Self {
+        Self {
+            min_delay: Duration::from_millis(40),
+            max_delay: Duration::from_millis(400),
+            drift_compensation: true,
+        }
+    }
+}
+
+/// The audio a [`JitterBuffer`] plays out next.
+///
+/// [`JitterBuffer`]: crate::jitter::JitterBuffer
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Playout<S> {
+    /// Too little audio is buffered to start playout, nothing is played.
+    Buffering,
+    /// The expected packet arrived in time.
+    Decoded(Vec<S>),
+    /// The expected packet is missing and was recovered from the in-band FEC
+    /// of the packet following it.
+    Recovered(Vec<S>),
+    /// The expected packet is missing and was concealed.
+    Concealed(Vec<S>),
+}
+
+/// Counters describing how a [`JitterBuffer`] coped with the network.
+///
+/// [`JitterBuffer`]: crate::jitter::JitterBuffer
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct JitterStats {
+    /// Packets accepted into the buffer.
+    pub received: u64,
+    /// Packets discarded for arriving after their playout or twice.
+    pub late: u64,
+    /// Packets discarded to shrink the buffer towards the target delay.
+    pub dropped: u64,
+    /// Packets recovered from in-band FEC.
+    pub recovered: u64,
+    /// Packets concealed.
+    pub concealed: u64,
+    /// Samples per channel inserted to compensate clock drift.
+    pub inserted: u64,
+    /// Samples per channel removed to compensate clock drift.
+    pub removed: u64,
+}
+
+/// `JitterBuffer` wraps a [`Decoder`], reordering received packets by their
+/// sequence number and playing them out at a delay adapting to the measured
+/// jitter.
+///
+/// Packets are passed to [`push`] as they arrive, audio is taken by calling
+/// [`pull`] whenever the playout consumed the previous output. Missing
+/// packets are recovered from in-band FEC if the following packet arrived,
+/// concealed otherwise. When the buffer runs dry, playout pauses until the
+/// target delay is buffered again.
+///
+/// Clocks of sender and playout never run at exactly the same rate, over a
+/// long call the buffer would slowly fill up or run dry. If the buffer level
+/// averaged over many pulls strays more than half a frame from the target,
+/// a sample is removed from or inserted into each output until it recovers.
+///
+/// [`Decoder`]: crate::coder::Decoder
+/// [`push`]: crate::jitter::JitterBuffer::push
+/// [`pull`]: crate::jitter::JitterBuffer::pull
+#[derive(Debug)]
+pub struct JitterBuffer {
+    decoder: Decoder,
+    config: JitterConfig,
+    packets: BTreeMap<i64, Vec<u8>>,
+    /// The extended sequence number played out next, `None` while buffering.
+    next_sequence: Option<i64>,
+    /// The sequence number played out last, packets at or before it are
+    /// late even while buffering.
+    last_played: Option<u16>,
+    highest_sequence: Option<i64>,
+    /// Arrival and RTP timestamp of the previous packet.
+    last_arrival: Option<(Instant, u32)>,
+    /// The mean deviation of the transit time, in seconds.
+    jitter: f64,
+    frame_duration: Duration,
+    /// The averaged deviation of the buffer level from the target delay,
+    /// in seconds.
+    level_error: f64,
+    stats: JitterStats,
+}
+
+impl JitterBuffer {
+    /// Creates a new `JitterBuffer` playing out via `decoder`.
+    pub fn new(decoder: Decoder, config: JitterConfig) -> Self {
+        Self {
+            decoder,
+            config,
+            packets: BTreeMap::new(),
+            next_sequence: None,
+            last_played: None,
+            highest_sequence: None,
+            last_arrival: None,
+            jitter: 0.0,
+            frame_duration: Duration::from_millis(20),
+            level_error: 0.0,
+            stats: JitterStats::default(),
+        }
+    }
+
+    /// Gets the wrapped decoder.
+    pub fn decoder(&self) -> &Decoder {
+        &self.decoder
+    }
+
+    /// Unwraps the decoder, discarding buffered packets.
+    pub fn into_inner(self) -> Decoder {
+        self.decoder
+    }
+
+    /// Gets the counters collected so far.
+    pub fn stats(&self) -> JitterStats {
+        self.stats
+    }
+
+    /// Gets the measured jitter, the mean deviation of packets' transit time.
+    pub fn jitter(&self) -> Duration {
+        Duration::from_secs_f64(self.jitter)
+    }
+
+    /// Gets the delay the buffer currently aims for, a multiple of the
+    /// measured jitter clamped to the configured bounds.
+    pub fn target_delay(&self) -> Duration {
+        (self.jitter() * JITTER_MULTIPLIER + self.frame_duration)
+            .max(self.config.min_delay)
+            .min(self.config.max_delay)
+    }
+
+    /// Gets the duration of audio buffered, including gaps of packets yet
+    /// to arrive.
+    pub fn buffered(&self) -> Duration {
+        self.next_sequence
+            .or_else(|| self.packets.keys().next().copied())
+            .map_or_else(Duration::default, |first| self.buffered_from(first))
+    }
+
+    /// Inserts the received `payload` numbered `sequence` with the RTP
+    /// `timestamp`, having arrived at `arrival`.
+    ///
+    /// Returns whether the packet was accepted, packets arriving after their
+    /// playout or twice are discarded.
+    ///
+    /// **Errors**:
+    /// Returns [Error::EmptyPacket] if `payload` is empty and
+    /// [Error::Opus] if it is not a valid Opus packet.
+    ///
+    /// [Error::EmptyPacket]: crate::error::Error::EmptyPacket
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn push(
+        &mut self,
+        sequence: u16,
+        timestamp: u32,
+        payload: &[u8],
+        arrival: Instant,
+    ) -> Result<bool> {
+        let frame_duration = Packet::try_from(payload)?.duration(self.decoder.sample_rate()?)?;
+
+        // Compared with wrap-around, the sequence number following 65535 is 0.
+        let late = self
+            .last_played
+            .map_or(false, |last| sequence.wrapping_sub(last) as i16 <= 0);
+        let sequence = self.extend(sequence);
+
+        if late || self.packets.contains_key(&sequence) {
+            self.stats.late += 1;
+
+            return Ok(false);
+        }
+
+        if let Some((last_arrival, last_timestamp)) = self.last_arrival {
+            let arrived = arrival
+                .saturating_duration_since(last_arrival)
+                .as_secs_f64()
+                - last_arrival
+                    .saturating_duration_since(arrival)
+                    .as_secs_f64();
+            let sent = f64::from(timestamp.wrapping_sub(last_timestamp) as i32) / TIMESTAMP_RATE;
+
+            // The running estimate of RFC 3550, section 6.4.1.
+            self.jitter += ((arrived - sent).abs() - self.jitter) / 16.0;
+        }
+
+        self.last_arrival = Some((arrival, timestamp));
+        self.frame_duration = frame_duration;
+        self.highest_sequence = Some(self.highest_sequence.map_or(sequence, |h| h.max(sequence)));
+        self.packets.insert(sequence, payload.to_vec());
+        self.stats.received += 1;
+
+        Ok(true)
+    }
+
+    /// Plays out the next frame.
+    ///
+    /// Call whenever the audio output consumed the previous frame, which may
+    /// be a sample shorter or longer than the packet's while compensating
+    /// clock drift.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn pull<S: Sample>(&mut self) -> Result<Playout<S>> {
+        let mut next = match self.next_sequence {
+            Some(next) => next,
+            None => match self.packets.keys().next() {
+                Some(&first) if self.buffered() >= self.target_delay() => first,
+                _ => return Ok(Playout::Buffering),
+            },
+        };
+
+        self.track_level(self.buffered_from(next));
+
+        // Adapt downwards by skipping a frame once the buffer exceeds its
+        // target by more than a frame.
+        if self.buffered_from(next) > self.target_delay() + self.frame_duration
+            && self.packets.remove(&next).is_some()
+        {
+            self.stats.dropped += 1;
+            next += 1;
+        }
+
+        self.next_sequence = Some(next + 1);
+        self.last_played = Some(next as u16);
+
+        if let Some(payload) = self.packets.remove(&next) {
+            let packet = Packet::try_from(&payload[..])?;
+
+            let output = self.decoder.decode_to_vec(DecodeRequest::Normal(packet))?;
+
+            return Ok(Playout::Decoded(self.compensate(output)));
+        }
+
+        if let Some(payload) = self.packets.get(&(next + 1)) {
+            let packet = Packet::try_from(&payload[..])?;
+            let output = self.decoder.decode_to_vec(DecodeRequest::Fec(packet))?;
+            self.stats.recovered += 1;
+
+            return Ok(Playout::Recovered(self.compensate(output)));
+        }
+
+        if self.packets.is_empty() {
+            // Ran dry, buffer up to the target delay again.
+            self.next_sequence = None;
+        }
+
+        self.stats.concealed += 1;
+
+        let output = self.decoder.conceal(self.frame_duration)?;
+
+        Ok(Playout::Concealed(self.compensate(output)))
+    }
+
+    /// Averages the deviation of the `buffered` duration from the target.
+    fn track_level(&mut self, buffered: Duration) {
+        let error = buffered.as_secs_f64() - self.target_delay().as_secs_f64();
+
+        self.level_error += (error - self.level_error) / DRIFT_SMOOTHING;
+    }
+
+    /// Removes or inserts a sample per channel into `output` if the averaged
+    /// buffer level strays from the target.
+    fn compensate<S: Sample>(&mut self, mut output: Vec<S>) -> Vec<S> {
+        let channels = (self.decoder.channels() as i32).max(1) as usize;
+        let threshold = self.frame_duration.as_secs_f64() / 2.0;
+
+        if !self.config.drift_compensation || output.len() <= channels {
+            return output;
+        }
+
+        if self.level_error > threshold {
+            output.truncate(output.len() - channels);
+            self.stats.removed += 1;
+        } else if self.level_error < -threshold {
+            let last = output[output.len() - channels..].to_vec();
+            output.extend(last);
+            self.stats.inserted += 1;
+        }
+
+        output
+    }
+
+    /// Extends the 16-bit `sequence` to the wrap-around closest to the
+    /// highest sequence number seen.
+    fn extend(&self, sequence: u16) -> i64 {
+        match self.highest_sequence {
+            Some(highest) => highest + i64::from(sequence.wrapping_sub(highest as u16) as i16),
+            None => i64::from(sequence),
+        }
+    }
+
+    /// Gets the duration of audio buffered from the extended sequence
+    /// number `first` on.
+    fn buffered_from(&self, first: i64) -> Duration {
+        match self.highest_sequence {
+            Some(highest) if highest >= first => self.frame_duration * (highest - first + 1) as u32,
+            _ => Duration::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JitterBuffer, JitterConfig, Playout};
+    use crate::{
+        coder::{Decoder, Encoder},
+        Application, Channels, SampleRate,
+    };
+    use std::time::{Duration, Instant};
+
+    fn packets(count: u16) -> Vec<Vec<u8>> {
+        let encoder = Encoder::new(SampleRate::Hz16000, Channels::Mono, Application::Voip).unwrap();
+
+        (0..count)
+            .map(|sequence| {
+                let input: Vec<i16> = (0..320)
+                    .map(|i| (((sequence as usize * 320 + i) as f32 * 0.1).sin() * 8000.0) as i16)
+                    .collect();
+
+                encoder.encode_to_vec(&input).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reorder_and_conceal() {
+        let decoder = Decoder::new(SampleRate::Hz16000, Channels::Mono).unwrap();
+        let config = JitterConfig {
+            min_delay: Duration::from_millis(120),
+            drift_compensation: false,
+            ..JitterConfig::default()
+        };
+        let mut buffer = JitterBuffer::new(decoder, config);
+        let packets = packets(6);
+        let start = Instant::now();
+
+        for &sequence in &[0_u16, 2, 1, 4, 5] {
+            let arrival = start + Duration::from_millis(u64::from(sequence) * 20);
+            let timestamp = u32::from(sequence) * 960;
+
+            assert!(buffer
+                .push(sequence, timestamp, &packets[sequence as usize], arrival)
+                .unwrap());
+        }
+
+        assert!(!buffer.push(1, 960, &packets[1], start).unwrap());
+
+        let mut decoded = 0;
+        let mut concealed = 0;
+
+        for _ in 0..6 {
+            match buffer.pull::<i16>().unwrap() {
+                Playout::Decoded(output) => {
+                    assert_eq!(output.len(), 320);
+                    decoded += 1;
+                }
+                Playout::Recovered(output) | Playout::Concealed(output) => {
+                    assert_eq!(output.len(), 320);
+                    concealed += 1;
+                }
+                Playout::Buffering => panic!("expected playout"),
+            }
+        }
+
+        assert_eq!((decoded, concealed), (5, 1));
+        assert_eq!(buffer.stats().late, 1);
+
+        // Running dry conceals once, then buffers again.
+        assert!(matches!(
+            buffer.pull::<i16>().unwrap(),
+            Playout::Concealed(_)
+        ));
+        assert_eq!(buffer.pull::<i16>().unwrap(), Playout::Buffering);
+
+        // Packets played out already stay late while buffering.
+        assert!(!buffer.push(5, 5 * 960, &packets[5], start).unwrap());
+        assert!(!buffer.push(3, 3 * 960, &packets[3], start).unwrap());
+        assert_eq!(buffer.stats().late, 3);
+    }
+
+    #[test]
+    fn compensate_drift() {
+        for &drift_compensation in &[false, true] {
+            let decoder = Decoder::new(SampleRate::Hz16000, Channels::Mono).unwrap();
+            let config = JitterConfig {
+                min_delay: Duration::from_millis(60),
+                drift_compensation,
+                ..JitterConfig::default()
+            };
+            let mut buffer = JitterBuffer::new(decoder, config);
+            let packets = packets(104);
+            let start = Instant::now();
+
+            // The sender stays a frame ahead of the target delay, as if its
+            // clock ran faster.
+            for (sequence, packet) in packets.iter().enumerate() {
+                let arrival = start + Duration::from_millis(sequence as u64 * 20);
+                buffer
+                    .push(sequence as u16, sequence as u32 * 960, packet, arrival)
+                    .unwrap();
+
+                if sequence >= 3 {
+                    assert!(!matches!(buffer.pull::<i16>().unwrap(), Playout::Buffering));
+                }
+            }
+
+            let stats = buffer.stats();
+            assert_eq!(stats.dropped, 0);
+            assert_eq!(stats.inserted, 0);
+            assert_eq!(stats.removed > 0, drift_compensation);
+        }
+    }
+}
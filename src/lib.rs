@@ -38,22 +38,61 @@
 // TODO: Document all public items.
 // #![deny(missing_docs)]
 
+mod backend;
+pub mod buffers;
+#[cfg(feature = "codec")]
+pub mod codec;
 pub mod coder;
+pub mod convert;
+#[cfg(feature = "cpal")]
+pub mod cpal;
+#[cfg(feature = "custom")]
+pub mod custom;
+#[cfg(feature = "dlopen")]
+pub mod dynamic;
 pub mod error;
+pub mod header;
+pub mod io;
+#[cfg(feature = "jitter")]
+pub mod jitter;
+pub mod loudness;
+pub mod matroska;
+pub mod mix;
+#[cfg(feature = "ogg")]
+pub mod ogg;
 pub mod packet;
 pub mod repacketizer;
+#[cfg(feature = "resample")]
+pub mod resample;
+#[cfg(feature = "rodio")]
+pub mod rodio;
+#[cfg(feature = "rtp")]
+pub mod rtp;
+pub mod sample;
 pub mod softclip;
+#[cfg(feature = "futures")]
+pub mod stream;
+#[cfg(feature = "symphonia")]
+pub mod symphonia;
+pub mod version;
+#[cfg(feature = "wav")]
+pub mod wav;
 
 use std::{
     convert::{TryFrom, TryInto},
-    ffi::CStr,
+    time::Duration,
 };
 
-pub use crate::error::{Error, ErrorCode, Result};
+pub use crate::{
+    error::{Error, ErrorCode, Result},
+    sample::Sample,
+    version::{has_dred, has_multistream, has_projection, Version},
+};
 pub use audiopus_sys as ffi;
 
 #[repr(i32)]
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Signal {
     Auto = ffi::OPUS_AUTO,
     Voice = ffi::OPUS_SIGNAL_VOICE,
@@ -73,7 +112,48 @@ impl TryFrom<i32> for Signal {
     }
 }
 
+/// Neural post-processing an Opus 1.5 decoder applies, selected via the
+/// decoder's complexity.
+#[cfg(feature = "opus_1_5")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Enhancement {
+    /// No neural processing, losses are concealed classically.
+    None,
+    /// Deep packet loss concealment.
+    DeepPlc,
+    /// Deep packet loss concealment and LACE speech enhancement.
+    Lace,
+    /// Deep packet loss concealment and NoLACE speech enhancement, the most
+    /// demanding.
+    NoLace,
+}
+
+#[cfg(feature = "opus_1_5")]
+impl Enhancement {
+    /// Gets the lowest decoder complexity enabling the enhancement.
+    pub fn complexity(self) -> u8 {
+        match self {
+            Enhancement::None => 0,
+            Enhancement::DeepPlc => 5,
+            Enhancement::Lace => 6,
+            Enhancement::NoLace => 7,
+        }
+    }
+
+    /// Gets the enhancement a decoder applies at `complexity`.
+    pub fn from_complexity(complexity: u8) -> Self {
+        match complexity {
+            0..=4 => Enhancement::None,
+            5 => Enhancement::DeepPlc,
+            6 => Enhancement::Lace,
+            _ => Enhancement::NoLace,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Bitrate {
     /// Explicit bitrate choice (in bits/second).
     BitsPerSecond(i32),
@@ -110,6 +190,7 @@ impl TryFrom<i32> for Bitrate {
 /// Values represent Hertz.
 #[repr(i32)]
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SampleRate {
     Hz8000 = 8000,
     Hz12000 = 12000,
@@ -118,6 +199,20 @@ pub enum SampleRate {
     Hz48000 = 48000,
 }
 
+impl SampleRate {
+    /// Gets the amount of samples per channel covering `duration`,
+    /// rounded down.
+    pub fn samples_in(self, duration: Duration) -> usize {
+        (duration.as_nanos() * self as u128 / 1_000_000_000) as usize
+    }
+
+    /// Gets the duration covered by `samples` per channel, rounded down to
+    /// whole nanoseconds.
+    pub fn duration_of(self, samples: usize) -> Duration {
+        Duration::from_nanos((samples as u128 * 1_000_000_000 / self as u128) as u64)
+    }
+}
+
 impl TryFrom<i32> for SampleRate {
     type Error = Error;
 
@@ -137,6 +232,7 @@ impl TryFrom<i32> for SampleRate {
 /// Represents possible application-types for Opus.
 #[repr(i32)]
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Application {
     /// Best for most VoIP/videoconference applications where listening quality
     /// and intelligibility matter most.
@@ -165,6 +261,7 @@ impl TryFrom<i32> for Application {
 /// Represents possible audio channels Opus can use.
 #[repr(i32)]
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Channels {
     /// Not supported when constructing encoders or decoders.
     Auto = ffi::OPUS_AUTO,
@@ -213,6 +310,7 @@ impl From<Channels> for i32 {
 /// Represents possible bandwidths of an Opus-stream.
 #[repr(i32)]
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Bandwidth {
     /// Pick the bandwidth automatically.
     Auto = ffi::OPUS_AUTO,
@@ -245,6 +343,174 @@ impl TryFrom<i32> for Bandwidth {
     }
 }
 
+/// Represents possible durations of a frame Opus can encode.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FrameDuration {
+    /// A 2.5 ms frame.
+    Ms2_5 = ffi::OPUS_FRAMESIZE_2_5_MS,
+    /// A 5 ms frame.
+    Ms5 = ffi::OPUS_FRAMESIZE_5_MS,
+    /// A 10 ms frame.
+    Ms10 = ffi::OPUS_FRAMESIZE_10_MS,
+    /// A 20 ms frame.
+    Ms20 = ffi::OPUS_FRAMESIZE_20_MS,
+    /// A 40 ms frame.
+    Ms40 = ffi::OPUS_FRAMESIZE_40_MS,
+    /// A 60 ms frame.
+    Ms60 = ffi::OPUS_FRAMESIZE_60_MS,
+    /// A 80 ms frame.
+    Ms80 = ffi::OPUS_FRAMESIZE_80_MS,
+    /// A 100 ms frame.
+    Ms100 = ffi::OPUS_FRAMESIZE_100_MS,
+    /// A 120 ms frame.
+    Ms120 = ffi::OPUS_FRAMESIZE_120_MS,
+}
+
+impl FrameDuration {
+    /// Gets the duration in microseconds.
+    pub(crate) const fn micros(self) -> u32 {
+        match self {
+            FrameDuration::Ms2_5 => 2_500,
+            FrameDuration::Ms5 => 5_000,
+            FrameDuration::Ms10 => 10_000,
+            FrameDuration::Ms20 => 20_000,
+            FrameDuration::Ms40 => 40_000,
+            FrameDuration::Ms60 => 60_000,
+            FrameDuration::Ms80 => 80_000,
+            FrameDuration::Ms100 => 100_000,
+            FrameDuration::Ms120 => 120_000,
+        }
+    }
+
+    /// Gets the frame's duration.
+    pub fn duration(self) -> Duration {
+        Duration::from_micros(u64::from(self.micros()))
+    }
+
+    /// Gets the amount of samples a single channel of the frame contains
+    /// at `sample_rate`.
+    pub const fn samples_per_channel(self, sample_rate: SampleRate) -> usize {
+        sample_rate as usize * self.micros() as usize / 1_000_000
+    }
+
+    /// Gets the amount of samples an interleaved frame of `channels`
+    /// contains at `sample_rate`, e.g. to size the frame's buffer.
+    pub const fn samples(self, sample_rate: SampleRate, channels: usize) -> usize {
+        self.samples_per_channel(sample_rate) * channels
+    }
+}
+
+impl TryFrom<i32> for FrameDuration {
+    type Error = Error;
+
+    /// Fails if a value does not match Opus' specified frame size-value.
+    fn try_from(value: i32) -> Result<Self> {
+        Ok(match value {
+            ffi::OPUS_FRAMESIZE_2_5_MS => FrameDuration::Ms2_5,
+            ffi::OPUS_FRAMESIZE_5_MS => FrameDuration::Ms5,
+            ffi::OPUS_FRAMESIZE_10_MS => FrameDuration::Ms10,
+            ffi::OPUS_FRAMESIZE_20_MS => FrameDuration::Ms20,
+            ffi::OPUS_FRAMESIZE_40_MS => FrameDuration::Ms40,
+            ffi::OPUS_FRAMESIZE_60_MS => FrameDuration::Ms60,
+            ffi::OPUS_FRAMESIZE_80_MS => FrameDuration::Ms80,
+            ffi::OPUS_FRAMESIZE_100_MS => FrameDuration::Ms100,
+            ffi::OPUS_FRAMESIZE_120_MS => FrameDuration::Ms120,
+            _ => return Err(Error::InvalidFrameDuration(value)),
+        })
+    }
+}
+
+impl From<FrameDuration> for Duration {
+    fn from(frame_duration: FrameDuration) -> Duration {
+        frame_duration.duration()
+    }
+}
+
+/// A granule position as used by Ogg Opus and other containers, the amount
+/// of samples per channel at 48 kHz decoded from the start of a stream,
+/// including the pre-skip.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GranulePos(pub u64);
+
+impl GranulePos {
+    /// Marks Ogg pages on which no packet ends, `-1` as signed integer.
+    pub const NONE: Self = Self(u64::MAX);
+
+    /// Gets the granule position `samples` per channel at 48 kHz from the
+    /// start.
+    pub const fn from_samples(samples: u64) -> Self {
+        Self(samples)
+    }
+
+    /// Gets the amount of samples per channel at 48 kHz from the start.
+    pub const fn samples(self) -> u64 {
+        self.0
+    }
+
+    /// Gets the granule position `duration` from the start, rounded down
+    /// to whole samples, `None` if it overflows.
+    pub fn from_duration(duration: Duration) -> Option<Self> {
+        let samples = duration.as_nanos() * SampleRate::Hz48000 as u128 / 1_000_000_000;
+
+        u64::try_from(samples).ok().map(Self)
+    }
+
+    /// Gets the duration from the start, rounded down to whole nanoseconds.
+    pub fn duration(self) -> Duration {
+        let nanos = u128::from(self.0) * 1_000_000_000 / SampleRate::Hz48000 as u128;
+
+        Duration::new(
+            (nanos / 1_000_000_000) as u64,
+            (nanos % 1_000_000_000) as u32,
+        )
+    }
+
+    /// Whether this is [`NONE`], marking pages on which no packet ends.
+    ///
+    /// [`NONE`]: crate::GranulePos::NONE
+    pub const fn is_none(self) -> bool {
+        self.0 == u64::MAX
+    }
+
+    /// Advances by `samples` per channel at 48 kHz, `None` if it overflows
+    /// or reaches [`NONE`].
+    ///
+    /// [`NONE`]: crate::GranulePos::NONE
+    pub fn checked_add(self, samples: u64) -> Option<Self> {
+        self.0
+            .checked_add(samples)
+            .map(Self)
+            .filter(|granule| !granule.is_none())
+    }
+
+    /// Goes back by `samples` per channel at 48 kHz, `None` if it
+    /// underflows.
+    pub fn checked_sub(self, samples: u64) -> Option<Self> {
+        self.0.checked_sub(samples).map(Self)
+    }
+
+    /// Gets the samples per channel at 48 kHz from `earlier` to this
+    /// position, `None` if `earlier` lies after it.
+    pub fn checked_since(self, earlier: Self) -> Option<u64> {
+        self.0.checked_sub(earlier.0)
+    }
+}
+
+impl From<u64> for GranulePos {
+    fn from(samples: u64) -> Self {
+        Self(samples)
+    }
+}
+
+impl From<GranulePos> for u64 {
+    fn from(granule: GranulePos) -> u64 {
+        granule.0
+    }
+}
+
 /// A newtype wrapping around a mutable buffer. They represent mutably borrowed
 /// arguments that will be filled by Opus.
 /// E.g. you pass this to an encode-method and Opus encodes data into the
@@ -288,28 +554,35 @@ impl<'a, T> MutSignals<'a, T> {
     }
 }
 
-/// Gets the libopus version string.
+/// Gets the version of the linked libopus, parsed from its version string,
+/// and which optional features it supports.
+///
+/// Applications may check [`Version::fixed_point`] to determine whether they
+/// have a fixed-point or floating-point build at runtime, and
+/// [`Version::dred`] before enabling deep redundancy.
 ///
-/// Applications may look for the substring "-fixed" in the version string to
-/// determine whether they have a fixed-point or floating-point build at runtime.
-pub fn version() -> &'static str {
-    // The pointer given from the `opus_get_version_string` function will be valid
-    // therefore we can create a `CStr` from this pointer.
-    unsafe { CStr::from_ptr(ffi::opus_get_version_string()) }
-        .to_str()
-        .unwrap()
+/// **Info**:
+/// The capabilities are probed by creating a short-lived encoder and decoder
+/// on the first call, later calls return the cached result.
+///
+/// [`Version::fixed_point`]: crate::version::Version::fixed_point
+/// [`Version::dred`]: crate::version::Version::dred
+pub fn version() -> Version {
+    Version::linked()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{ffi, version, Application, Error, Signal, TryFrom};
+    use super::{
+        ffi, version, Application, Error, FrameDuration, GranulePos, SampleRate, Signal, TryFrom,
+    };
     use matches::assert_matches;
+    use std::time::Duration;
 
     #[test]
     fn try_get_version() {
-        // We can't actually check the contents of the string, as it will change when the version
-        // changes. By just calling the function we can ensure that the CStr conversion succeeds.
-        version();
+        // The version changes with the linked Opus, only its prefix is fixed.
+        assert!(version().string.starts_with("libopus"));
     }
 
     #[test]
@@ -336,4 +609,77 @@ mod tests {
         );
         assert_matches!(Application::try_from(11), Err(Error::InvalidApplication));
     }
+
+    #[test]
+    fn sample_rate_duration_math() {
+        assert_eq!(
+            SampleRate::Hz48000.samples_in(Duration::from_millis(20)),
+            960
+        );
+        assert_eq!(
+            SampleRate::Hz8000.samples_in(Duration::from_micros(2500)),
+            20
+        );
+        assert_eq!(
+            SampleRate::Hz16000.samples_in(Duration::from_secs(3)),
+            48000
+        );
+
+        assert_eq!(
+            SampleRate::Hz48000.duration_of(960),
+            Duration::from_millis(20)
+        );
+        assert_eq!(
+            SampleRate::Hz12000.duration_of(12000),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            SampleRate::Hz48000.duration_of(1),
+            Duration::from_nanos(20833)
+        );
+    }
+
+    #[test]
+    fn granule_pos_math() {
+        let granule = GranulePos::from_duration(Duration::from_millis(1500)).unwrap();
+        assert_eq!(granule.samples(), 72000);
+        assert_eq!(granule.duration(), Duration::from_millis(1500));
+        assert_eq!(GranulePos(1).duration(), Duration::from_nanos(20833));
+
+        assert_eq!(granule.checked_add(312), Some(GranulePos(72312)));
+        assert_eq!(granule.checked_sub(72001), None);
+        assert_eq!(GranulePos(80000).checked_since(granule), Some(8000));
+        assert_eq!(granule.checked_since(GranulePos(80000)), None);
+
+        assert!(GranulePos::NONE.is_none());
+        assert_eq!(GranulePos(u64::MAX - 1).checked_add(1), None);
+        assert_eq!(
+            GranulePos::from_duration(Duration::from_secs(u64::MAX)),
+            None
+        );
+    }
+
+    #[test]
+    fn frame_duration_sizing() {
+        assert_eq!(
+            FrameDuration::Ms2_5.samples_per_channel(SampleRate::Hz48000),
+            120
+        );
+        assert_eq!(FrameDuration::Ms20.samples(SampleRate::Hz48000, 2), 1920);
+        assert_eq!(FrameDuration::Ms60.samples(SampleRate::Hz8000, 1), 480);
+        assert_eq!(FrameDuration::Ms120.duration(), Duration::from_millis(120));
+        assert_eq!(
+            Duration::from(FrameDuration::Ms2_5),
+            Duration::from_micros(2500)
+        );
+
+        assert_matches!(
+            FrameDuration::try_from(ffi::OPUS_FRAMESIZE_40_MS),
+            Ok(FrameDuration::Ms40)
+        );
+        assert_matches!(
+            FrameDuration::try_from(ffi::OPUS_FRAMESIZE_ARG),
+            Err(Error::InvalidFrameDuration(_))
+        );
+    }
 }
@@ -0,0 +1,254 @@
+//! Loudness measurement and normalization per EBU R128, see
+//! [`LoudnessMeter`].
+//!
+//! Feed the meter the PCM passed to the encoder, then either store the
+//! gain normalizing the track as `R128_TRACK_GAIN` tag or apply it to the
+//! PCM before encoding via [`apply_gain`], e.g. to reach -16 LUFS for
+//! podcasts or -23 LUFS for broadcast.
+//!
+//! [`LoudnessMeter`]: crate::loudness::LoudnessMeter
+//! [`apply_gain`]: crate::loudness::apply_gain
+
+use crate::{header::OpusTags, Sample};
+use std::{collections::VecDeque, f64::consts::PI};
+
+/// The loudness in LUFS the R128 gain tags of Opus normalize to.
+pub const R128_REFERENCE: f64 = -23.0;
+
+/// The loudness in LUFS below which blocks are ignored.
+const ABSOLUTE_GATE: f64 = -70.0;
+
+/// The loudness in LU below the ungated loudness at which blocks are
+/// ignored.
+const RELATIVE_GATE: f64 = -10.0;
+
+/// The 100 ms steps per gating block of 400 ms.
+const STEPS_PER_BLOCK: usize = 4;
+
+/// A biquad filter in transposed direct form II.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    state: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.state[0];
+        self.state[0] = self.b[1] * input - self.a[0] * output + self.state[1];
+        self.state[1] = self.b[2] * input - self.a[1] * output;
+
+        output
+    }
+}
+
+/// Creates the two stages of the K-weighting filter of ITU-R BS.1770 at
+/// `sample_rate`: a high shelf modelling the head followed by a high pass.
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = f64::from(sample_rate);
+
+    let k = (PI * 1_681.974_450_955_533 / rate).tan();
+    let q = 0.707_175_236_955_419_6;
+    let vh = 10_f64.powf(3.999_843_853_973_347 / 20.0);
+    let vb = vh.powf(0.499_666_774_154_541_6);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        state: [0.0; 2],
+    };
+
+    let k = (PI * 38.135_470_876_024_44 / rate).tan();
+    let q = 0.500_327_037_323_877_3;
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        state: [0.0; 2],
+    };
+
+    [shelf, high_pass]
+}
+
+/// Converts the mean square `power` to LUFS.
+fn loudness(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// `LoudnessMeter` measures the integrated loudness of PCM per EBU R128
+/// and ITU-R BS.1770, in gated blocks of 400 ms overlapping by 75 %.
+///
+/// All channels are weighted equally, as fits mono and stereo.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoudnessMeter {
+    channels: usize,
+    filters: Vec<[Biquad; 2]>,
+    /// The samples per channel of a 100 ms step.
+    step_len: usize,
+    /// The samples per channel and the summed power of the current step.
+    step_samples: usize,
+    step_power: f64,
+    /// The summed power of the latest steps.
+    steps: VecDeque<f64>,
+    /// The mean square of every block measured.
+    blocks: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    /// Creates a new `LoudnessMeter` for PCM of `channels` at `sample_rate`
+    /// in Hz.
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        Self {
+            channels: channels.max(1),
+            filters: vec![k_weighting(sample_rate); channels.max(1)],
+            step_len: (sample_rate as usize / 10).max(1),
+            step_samples: 0,
+            step_power: 0.0,
+            steps: VecDeque::with_capacity(STEPS_PER_BLOCK),
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Measures `pcm`, interleaved if multiple channels.
+    pub fn push<S: Sample>(&mut self, pcm: &[S]) {
+        for frame in pcm.chunks_exact(self.channels) {
+            for (filters, &sample) in self.filters.iter_mut().zip(frame) {
+                let weighted = filters
+                    .iter_mut()
+                    .fold(f64::from(sample.to_f32()), |sample, filter| {
+                        filter.process(sample)
+                    });
+
+                self.step_power += weighted * weighted;
+            }
+
+            self.step_samples += 1;
+
+            if self.step_samples == self.step_len {
+                self.end_step();
+            }
+        }
+    }
+
+    /// Gets the integrated loudness in LUFS of the PCM measured, `None` if
+    /// it is shorter than a block or silent.
+    pub fn integrated(&self) -> Option<f64> {
+        let mean = |threshold: f64| {
+            let (sum, count) = self
+                .blocks
+                .iter()
+                .filter(|&&power| loudness(power) > threshold)
+                .fold((0.0, 0_u32), |(sum, count), power| (sum + power, count + 1));
+
+            if count == 0 {
+                None
+            } else {
+                Some(sum / f64::from(count))
+            }
+        };
+
+        let relative = loudness(mean(ABSOLUTE_GATE)?) + RELATIVE_GATE;
+
+        mean(relative.max(ABSOLUTE_GATE)).map(loudness)
+    }
+
+    /// Gets the gain in dB normalizing the PCM measured to `target` LUFS,
+    /// `None` if its loudness is unknown.
+    pub fn gain_to(&self, target: f64) -> Option<f64> {
+        self.integrated().map(|integrated| target - integrated)
+    }
+
+    /// Gets the gain in Q7.8 dB normalizing the PCM measured to
+    /// [`R128_REFERENCE`] as `R128_TRACK_GAIN` expects, clamped to its
+    /// range, `None` if its loudness is unknown.
+    ///
+    /// [`R128_REFERENCE`]: crate::loudness::R128_REFERENCE
+    pub fn track_gain(&self) -> Option<i16> {
+        self.gain_to(R128_REFERENCE).map(|gain| {
+            (gain * 256.0)
+                .round()
+                .clamp(f64::from(i16::MIN), f64::from(i16::MAX)) as i16
+        })
+    }
+
+    /// Sets the `R128_TRACK_GAIN` of `tags` to the [`track_gain`], leaving
+    /// them unchanged if the loudness is unknown.
+    ///
+    /// The gain applies on top of the header's output gain, which should
+    /// be zero.
+    ///
+    /// [`track_gain`]: crate::loudness::LoudnessMeter::track_gain
+    pub fn write_track_gain(&self, tags: &mut OpusTags) {
+        if let Some(gain) = self.track_gain() {
+            tags.set_track_gain(gain);
+        }
+    }
+
+    /// Closes the current step, measuring the block ending with it.
+    fn end_step(&mut self) {
+        if self.steps.len() == STEPS_PER_BLOCK {
+            self.steps.pop_front();
+        }
+
+        self.steps.push_back(self.step_power);
+        self.step_power = 0.0;
+        self.step_samples = 0;
+
+        if self.steps.len() == STEPS_PER_BLOCK {
+            let samples = (STEPS_PER_BLOCK * self.step_len) as f64;
+            self.blocks.push(self.steps.iter().sum::<f64>() / samples);
+        }
+    }
+}
+
+/// Applies `gain` in dB to `pcm`, e.g. the [`LoudnessMeter::gain_to`] a
+/// target loudness.
+///
+/// Positive gains may clip, see [`SoftClip`].
+///
+/// [`LoudnessMeter::gain_to`]: crate::loudness::LoudnessMeter::gain_to
+/// [`SoftClip`]: crate::softclip::SoftClip
+pub fn apply_gain(pcm: &mut [f32], gain: f64) {
+    let factor = 10_f64.powf(gain / 20.0) as f32;
+
+    for sample in pcm {
+        *sample *= factor;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_gain, LoudnessMeter};
+    use std::f32::consts::PI;
+
+    #[test]
+    fn measure_sine() {
+        // EBU Tech 3341 case 1: a 1 kHz sine at -23 dBFS reads -23 LUFS.
+        let amplitude = 10_f32.powf(-23.0 / 20.0);
+        let mut pcm: Vec<f32> = (0..48000 * 20)
+            .flat_map(|index| {
+                let sample = amplitude * (2.0 * PI * (index % 48) as f32 / 48.0).sin();
+                vec![sample, sample]
+            })
+            .collect();
+
+        let mut meter = LoudnessMeter::new(48000, 2);
+        meter.push(&pcm);
+        assert!((meter.integrated().unwrap() + 23.0).abs() < 0.1);
+        assert!(meter.track_gain().unwrap().abs() < 26);
+
+        let gain = meter.gain_to(-16.0).unwrap();
+        apply_gain(&mut pcm, gain);
+
+        let mut meter = LoudnessMeter::new(48000, 2);
+        meter.push(&pcm);
+        assert!((meter.integrated().unwrap() + 16.0).abs() < 0.1);
+
+        assert!(LoudnessMeter::new(48000, 2).integrated().is_none());
+    }
+}
@@ -0,0 +1,79 @@
+//! The fields of Opus tracks in Matroska and WebM as specified by the
+//! Matroska codec mappings, for muxing and demuxing Opus with other
+//! crates.
+//!
+//! Unlike Ogg, Matroska stores the [`OpusHead`] without framing as the
+//! track's `CodecPrivate` and conveys the pre-skip and the pre-roll needed
+//! after seeking in nanoseconds as `CodecDelay` and `SeekPreRoll`.
+//!
+//! [`OpusHead`]: crate::header::OpusHead
+
+use crate::{header::OpusHead, Result, TryFrom};
+
+/// The `CodecID` of Opus tracks.
+pub const CODEC_ID: &str = "A_OPUS";
+
+/// The `SeekPreRoll` of Opus tracks in nanoseconds, the 80 ms to decode
+/// before the seek target to converge.
+pub const SEEK_PRE_ROLL: u64 = 80_000_000;
+
+/// The nanoseconds per second.
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// The sample rate Matroska's Opus timestamps and the pre-skip refer to.
+const SAMPLE_RATE: u64 = 48000;
+
+/// Creates the `CodecPrivate` of a track described by `head`.
+pub fn codec_private(head: &OpusHead) -> Vec<u8> {
+    head.to_bytes()
+}
+
+/// Parses the `CodecPrivate` of a track.
+///
+/// **Errors**:
+/// Returns [`Error::InvalidOpusHeader`] if `bytes` is no valid
+/// [`OpusHead`].
+///
+/// [`Error::InvalidOpusHeader`]: crate::error::Error::InvalidOpusHeader
+/// [`OpusHead`]: crate::header::OpusHead
+pub fn parse_codec_private(bytes: &[u8]) -> Result<OpusHead> {
+    OpusHead::parse(bytes)
+}
+
+/// Converts the pre-skip of `head`, in samples at 48 kHz, to the
+/// track's `CodecDelay` in nanoseconds.
+pub fn codec_delay(head: &OpusHead) -> u64 {
+    u64::from(head.pre_skip) * NANOS_PER_SECOND / SAMPLE_RATE
+}
+
+/// Converts a track's `CodecDelay` in nanoseconds to the pre-skip in
+/// samples at 48 kHz, rounding to the nearest sample.
+///
+/// Returns `None` if the delay exceeds the largest pre-skip.
+pub fn pre_skip(codec_delay: u64) -> Option<u16> {
+    let samples = (u128::from(codec_delay) * u128::from(SAMPLE_RATE)
+        + u128::from(NANOS_PER_SECOND / 2))
+        / u128::from(NANOS_PER_SECOND);
+
+    u16::try_from(samples).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{codec_delay, codec_private, parse_codec_private, pre_skip};
+    use crate::{header::OpusHead, Channels};
+
+    #[test]
+    fn track_fields() {
+        let head = OpusHead::new(Channels::Stereo, 312, 44100);
+
+        let private = codec_private(&head);
+        assert_eq!(&private[..8], b"OpusHead");
+        assert_eq!(parse_codec_private(&private).unwrap(), head);
+
+        assert_eq!(codec_delay(&head), 6_500_000);
+        assert_eq!(pre_skip(6_500_000), Some(312));
+        assert_eq!(pre_skip(6_499_990), Some(312));
+        assert_eq!(pre_skip(u64::MAX), None);
+    }
+}
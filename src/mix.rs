@@ -0,0 +1,167 @@
+//! Channel mixing of interleaved signals, e.g. to play a stereo stream on a
+//! mono device, see [`downmix_to_mono`], [`upmix_to_stereo`] and the
+//! general [`Mixer`].
+//!
+//! [`downmix_to_mono`]: crate::mix::downmix_to_mono
+//! [`upmix_to_stereo`]: crate::mix::upmix_to_stereo
+//! [`Mixer`]: crate::mix::Mixer
+
+use crate::{Error, Result, Sample};
+
+/// Averages the `channels` of the interleaved `input` to mono.
+///
+/// **Errors**:
+/// Returns [`Error::InvalidChannels`] if `channels` is zero and
+/// [`Error::SignalsExpectedLen`] if `input` does not contain the same
+/// amount of samples for every channel.
+///
+/// [`Error::InvalidChannels`]: crate::error::Error::InvalidChannels
+/// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+pub fn downmix_to_mono<S: Sample>(input: &[S], channels: usize) -> Result<Vec<S>> {
+    Mixer::downmix_to_mono(channels)?.mix_to_vec(input)
+}
+
+/// Duplicates the mono `input` to interleaved stereo.
+pub fn upmix_to_stereo<S: Sample>(input: &[S]) -> Vec<S> {
+    input
+        .iter()
+        .flat_map(|&sample| vec![sample, sample])
+        .collect()
+}
+
+/// `Mixer` mixes interleaved signals of one amount of channels to another
+/// by a matrix of gains, each output channel summing the input channels
+/// weighted by its row.
+///
+/// Integer samples saturate if the mix exceeds their range.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mixer {
+    inputs: usize,
+    outputs: usize,
+    /// The gains in row-major order, one row per output channel.
+    matrix: Vec<f32>,
+}
+
+impl Mixer {
+    /// Creates a new `Mixer` of `inputs` to `outputs` channels, `matrix`
+    /// holding the gain of each input channel per output channel in
+    /// row-major order.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidChannels`] if `inputs` or `outputs` is zero
+    /// and [`Error::MixingMatrixExpectedLen`] if `matrix` does not hold
+    /// `inputs * outputs` gains.
+    ///
+    /// [`Error::InvalidChannels`]: crate::error::Error::InvalidChannels
+    /// [`Error::MixingMatrixExpectedLen`]: crate::error::Error::MixingMatrixExpectedLen
+    pub fn new(inputs: usize, outputs: usize, matrix: Vec<f32>) -> Result<Self> {
+        if inputs == 0 || outputs == 0 {
+            return Err(Error::InvalidChannels(0));
+        }
+
+        if matrix.len() != inputs * outputs {
+            return Err(Error::MixingMatrixExpectedLen(inputs * outputs));
+        }
+
+        Ok(Self {
+            inputs,
+            outputs,
+            matrix,
+        })
+    }
+
+    /// Creates a `Mixer` averaging `channels` to mono.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidChannels`] if `channels` is zero.
+    ///
+    /// [`Error::InvalidChannels`]: crate::error::Error::InvalidChannels
+    pub fn downmix_to_mono(channels: usize) -> Result<Self> {
+        Self::new(channels, 1, vec![1.0 / channels.max(1) as f32; channels])
+    }
+
+    /// Gets the amount of input channels.
+    pub fn inputs(&self) -> usize {
+        self.inputs
+    }
+
+    /// Gets the amount of output channels.
+    pub fn outputs(&self) -> usize {
+        self.outputs
+    }
+
+    /// Mixes `input` into `output`.
+    ///
+    /// On success, returns the amount of samples per channel.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::SignalsExpectedLen`] if `input` does not contain the
+    /// same amount of samples for every channel or `output` cannot hold all
+    /// samples.
+    ///
+    /// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+    pub fn mix<S: Sample>(&self, input: &[S], output: &mut [S]) -> Result<usize> {
+        let samples = input.len() / self.inputs;
+
+        if input.len() % self.inputs != 0 {
+            return Err(Error::SignalsExpectedLen(samples * self.inputs));
+        }
+
+        if output.len() < samples * self.outputs {
+            return Err(Error::SignalsExpectedLen(samples * self.outputs));
+        }
+
+        let frames = input.chunks_exact(self.inputs);
+
+        for (input, output) in frames.zip(output.chunks_exact_mut(self.outputs)) {
+            for (sample, gains) in output.iter_mut().zip(self.matrix.chunks_exact(self.inputs)) {
+                let mixed = input
+                    .iter()
+                    .zip(gains)
+                    .map(|(sample, gain)| sample.to_f32() * gain)
+                    .sum::<f32>();
+
+                *sample = S::from_f32(mixed);
+            }
+        }
+
+        Ok(samples)
+    }
+
+    /// Mixes `input` into a new `Vec`.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::SignalsExpectedLen`] if `input` does not contain the
+    /// same amount of samples for every channel.
+    ///
+    /// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
+    pub fn mix_to_vec<S: Sample>(&self, input: &[S]) -> Result<Vec<S>> {
+        let mut output = vec![S::default(); input.len() / self.inputs * self.outputs];
+        self.mix(input, &mut output)?;
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{downmix_to_mono, upmix_to_stereo, Mixer};
+
+    #[test]
+    fn mix_channels() {
+        assert_eq!(
+            downmix_to_mono(&[1000_i16, 3000, -32768, -32768], 2).unwrap(),
+            [2000, -32768]
+        );
+        assert!(downmix_to_mono(&[0.0_f32; 3], 2).is_err());
+        assert_eq!(upmix_to_stereo(&[0.5_f32, -0.25]), [0.5, 0.5, -0.25, -0.25]);
+
+        // Swapping the channels and summing them saturates.
+        let mixer = Mixer::new(2, 3, vec![0.0, 1.0, 1.0, 0.0, 1.0, 1.0]).unwrap();
+        assert_eq!(
+            mixer.mix_to_vec(&[1_i16, 2, 30000, 30000]).unwrap(),
+            [2, 1, 3, 30000, 30000, 32767]
+        );
+        assert!(Mixer::new(2, 3, vec![1.0; 5]).is_err());
+    }
+}
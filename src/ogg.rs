@@ -0,0 +1,15 @@
+//! Ogg Opus streams as specified by RFC 7845, e.g. `.opus` files, see
+//! [`OggOpusWriter`] and [`OggOpusReader`].
+//!
+//! [`OggOpusWriter`]: crate::ogg::OggOpusWriter
+//! [`OggOpusReader`]: crate::ogg::OggOpusReader
+
+pub use self::{
+    reader::{GainMode, OggOpusReader},
+    writer::OggOpusWriter,
+};
+pub use crate::header::{OpusHead, OpusTags, Picture};
+
+mod page;
+mod reader;
+mod writer;
@@ -0,0 +1,244 @@
+use crate::{Error, GranulePos};
+use std::io::{ErrorKind, Read, Result, Write};
+
+/// The capture pattern starting every page.
+pub(crate) const CAPTURE_PATTERN: &[u8; 4] = b"OggS";
+
+/// The length of a page header without its segment table.
+pub(crate) const HEADER_LEN: usize = 27;
+
+/// The most lacing values a page holds.
+pub(crate) const MAX_SEGMENTS: usize = 255;
+
+/// The length of the longest possible page.
+pub(crate) const MAX_PAGE_LEN: usize = HEADER_LEN + MAX_SEGMENTS * 256;
+
+/// Flags a page continuing a packet of the previous page.
+pub(crate) const CONTINUED: u8 = 0x01;
+/// Flags the first page of a logical stream.
+pub(crate) const BEGIN_OF_STREAM: u8 = 0x02;
+/// Flags the last page of a logical stream.
+pub(crate) const END_OF_STREAM: u8 = 0x04;
+
+/// The lookup table of Ogg's CRC-32, polynomial `0x04c11db7` without
+/// reflection.
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut index = 0;
+
+    while index < 256 {
+        let mut crc = (index as u32) << 24;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 0x8000_0000 == 0 {
+                crc << 1
+            } else {
+                (crc << 1) ^ 0x04c1_1db7
+            };
+            bit += 1;
+        }
+
+        table[index] = crc;
+        index += 1;
+    }
+
+    table
+}
+
+/// Computes the checksum of `data`, continuing `crc`.
+pub(crate) fn crc32(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, &byte| {
+        (crc << 8) ^ CRC_TABLE[((crc >> 24) as u8 ^ byte) as usize]
+    })
+}
+
+/// Appends the lacing values of a packet of `len` bytes to `segments`.
+pub(crate) fn lace(len: usize, segments: &mut Vec<u8>) {
+    segments.extend(std::iter::repeat(255).take(len / 255));
+    segments.push((len % 255) as u8);
+}
+
+/// The fields of a page header, besides its segment table and checksum.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct PageHeader {
+    pub(crate) flags: u8,
+    /// The granule position, [`GranulePos::NONE`] if no packet ends on the
+    /// page.
+    ///
+    /// [`GranulePos::NONE`]: crate::GranulePos::NONE
+    pub(crate) granule: GranulePos,
+    pub(crate) serial: u32,
+    pub(crate) sequence: u32,
+}
+
+impl PageHeader {
+    /// Writes a page with the lacing values `segments` and the packet data
+    /// `body` to `writer`.
+    pub(crate) fn write<W: Write>(
+        self,
+        segments: &[u8],
+        body: &[u8],
+        writer: &mut W,
+    ) -> Result<()> {
+        let mut header = Vec::with_capacity(HEADER_LEN + segments.len());
+        header.extend_from_slice(CAPTURE_PATTERN);
+        header.push(0);
+        header.push(self.flags);
+        header.extend_from_slice(&self.granule.samples().to_le_bytes());
+        header.extend_from_slice(&self.serial.to_le_bytes());
+        header.extend_from_slice(&self.sequence.to_le_bytes());
+        header.extend_from_slice(&[0; 4]);
+        header.push(segments.len() as u8);
+        header.extend_from_slice(segments);
+
+        let crc = crc32(crc32(0, &header), body);
+        header[22..26].copy_from_slice(&crc.to_le_bytes());
+
+        writer.write_all(&header)?;
+        writer.write_all(body)
+    }
+}
+
+/// A page of a logical Ogg stream.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct Page {
+    pub(crate) header: PageHeader,
+    /// The lacing values, the length of each segment of `body`.
+    pub(crate) segments: Vec<u8>,
+    pub(crate) body: Vec<u8>,
+}
+
+impl Page {
+    /// Reads the next page off `reader`, `None` at the end of the stream.
+    ///
+    /// Fails with [`ErrorKind::InvalidData`] wrapping
+    /// [`Error::InvalidOggPage`] if the page is malformed or corrupt, and
+    /// with [`ErrorKind::UnexpectedEof`] if it is truncated.
+    pub(crate) fn read<R: Read>(reader: &mut R) -> Result<Option<Self>> {
+        let mut header = [0; HEADER_LEN];
+
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(error) => return Err(error),
+        }
+
+        if &header[..4] != CAPTURE_PATTERN || header[4] != 0 {
+            return Err(Error::InvalidOggPage.into());
+        }
+
+        let mut segments = vec![0; usize::from(header[26])];
+        reader.read_exact(&mut segments)?;
+
+        let len = segments.iter().map(|&segment| usize::from(segment)).sum();
+        let mut body = vec![0; len];
+        reader.read_exact(&mut body)?;
+
+        let crc = u32::from_le_bytes([header[22], header[23], header[24], header[25]]);
+        header[22..26].copy_from_slice(&[0; 4]);
+
+        if crc32(crc32(crc32(0, &header), &segments), &body) != crc {
+            return Err(Error::InvalidOggPage.into());
+        }
+
+        let mut granule = [0; 8];
+        granule.copy_from_slice(&header[6..14]);
+
+        Ok(Some(Self {
+            header: PageHeader {
+                flags: header[5],
+                granule: GranulePos(u64::from_le_bytes(granule)),
+                serial: u32::from_le_bytes([header[14], header[15], header[16], header[17]]),
+                sequence: u32::from_le_bytes([header[18], header[19], header[20], header[21]]),
+            },
+            segments,
+            body,
+        }))
+    }
+
+    /// Gets the length of the page including its header.
+    pub(crate) fn len(&self) -> usize {
+        HEADER_LEN + self.segments.len() + self.body.len()
+    }
+
+    /// Splits the body into its segments' packets, the last one partial if
+    /// its lacing value is `255`, continuing on the next page.
+    ///
+    /// Returns the packets and whether the last one is partial.
+    pub(crate) fn packets(&self) -> (Vec<&[u8]>, bool) {
+        let mut packets = Vec::new();
+        let mut start = 0;
+        let mut end = 0;
+
+        for &segment in &self.segments {
+            end += usize::from(segment);
+
+            if segment < 255 {
+                packets.push(&self.body[start..end]);
+                start = end;
+            }
+        }
+
+        let partial = start < end;
+
+        if partial {
+            packets.push(&self.body[start..end]);
+        }
+
+        (packets, partial)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc32, lace, Page, PageHeader, BEGIN_OF_STREAM};
+    use crate::GranulePos;
+    use std::io::Cursor;
+
+    #[test]
+    fn checksum_and_lacing() {
+        assert_eq!(crc32(0, b""), 0);
+        // The CRC-32/MPEG-2 check value without initial value and final
+        // inversion.
+        assert_eq!(crc32(0, b"123456789"), 0x89a1_897f);
+
+        let mut segments = Vec::new();
+        lace(255, &mut segments);
+        lace(3, &mut segments);
+        assert_eq!(segments, [255, 0, 3]);
+    }
+
+    #[test]
+    fn write_and_read_page() {
+        let mut segments = Vec::new();
+        lace(300, &mut segments);
+        segments.push(255);
+
+        let header = PageHeader {
+            flags: BEGIN_OF_STREAM,
+            granule: GranulePos(960),
+            serial: 0xdead_beef,
+            sequence: 3,
+        };
+        let mut buffer = Vec::new();
+        header.write(&segments, &[1; 555], &mut buffer).unwrap();
+
+        let mut reader = Cursor::new(&buffer);
+        let page = Page::read(&mut reader).unwrap().unwrap();
+        assert_eq!(page.header, header);
+
+        let (packets, partial) = page.packets();
+        assert_eq!(
+            packets.iter().map(|p| p.len()).collect::<Vec<_>>(),
+            [300, 255]
+        );
+        assert!(partial);
+        assert!(Page::read(&mut reader).unwrap().is_none());
+
+        buffer[40] ^= 1;
+        assert!(Page::read(&mut Cursor::new(&buffer)).is_err());
+    }
+}
@@ -0,0 +1,801 @@
+use super::page::{self, Page};
+use crate::{
+    coder::{
+        multistream::{MappingFamily, MultistreamDecoder},
+        DecodeRequest, Decoder, GenericCtl,
+    },
+    header::{OpusHead, OpusTags, OPUS_HEAD},
+    packet::{Packet, PacketBuf},
+    Channels, Error, GranulePos, MutSignals, Sample, SampleRate, TryFrom,
+};
+use std::{
+    collections::VecDeque,
+    io::{Error as IoError, ErrorKind, Read, Result, Seek, SeekFrom},
+    time::Duration,
+};
+
+/// The samples at 48 kHz to decode before a seek target to let the decoder
+/// converge, 80 ms as recommended by RFC 7845.
+const PRE_ROLL: u64 = 3840;
+
+/// The byte range below which seeking scans pages instead of bisecting.
+const BISECTION_LEN: u64 = page::MAX_PAGE_LEN as u64;
+
+/// Which gain an [`OggOpusReader`] applies to the decoded PCM.
+///
+/// [`OggOpusReader`]: crate::ogg::OggOpusReader
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum GainMode {
+    /// The header's output gain only.
+    Header,
+    /// The header's output gain and the `R128_TRACK_GAIN`.
+    Track,
+    /// The header's output gain and the `R128_ALBUM_GAIN`, falling back to
+    /// the `R128_TRACK_GAIN` if absent.
+    Album,
+}
+
+impl Default for GainMode {
+    fn default() -> Self {
+        GainMode::Header
+    }
+}
+
+/// `OggOpusReader` reads an Ogg Opus stream, e.g. an `.opus` file, parsing
+/// its [`OpusHead`] and [`OpusTags`] headers and yielding its packets or
+/// the decoded PCM.
+///
+/// Pages of other logical streams multiplexed into the file are skipped.
+/// The links of chained files are read one after another, see
+/// [`next_link`].
+/// Seekable readers can be seeked by time via [`seek`]. The PCM is scaled by
+/// the header's output gain and optionally by the R128 gain tags, see
+/// [`GainMode`].
+///
+/// [`GainMode`]: crate::ogg::GainMode
+/// [`next_link`]: crate::ogg::OggOpusReader::next_link
+/// [`seek`]: crate::ogg::OggOpusReader::seek
+/// [`OpusHead`]: crate::ogg::OpusHead
+/// [`OpusTags`]: crate::ogg::OpusTags
+#[derive(Debug)]
+pub struct OggOpusReader<R> {
+    reader: R,
+    serial: u32,
+    head: OpusHead,
+    tags: OpusTags,
+    /// The index of the current link of a chained stream.
+    link: usize,
+    /// Complete packets of the pages read.
+    packets: VecDeque<Vec<u8>>,
+    /// A packet continuing on the next page.
+    partial: Vec<u8>,
+    /// The granule position of the last page read.
+    granule: GranulePos,
+    end_of_stream: bool,
+    decoder: Option<LinkDecoder>,
+    gain_mode: GainMode,
+    /// Samples per channel at 48 kHz still to discard from the decoded
+    /// output.
+    skip: usize,
+    /// The granule position after the PCM decoded.
+    position: u64,
+    /// Whether the PCM beyond the final granule position is discarded.
+    end_trim: bool,
+    /// The bytes read, used to locate the stream when seeking.
+    consumed: u64,
+    /// The offset of the first audio page of the current link relative to
+    /// the start of the stream.
+    data_start: u64,
+    /// Whether the next page follows a seek, a packet it continues is
+    /// incomplete.
+    resync: bool,
+}
+
+impl<R: Read> OggOpusReader<R> {
+    /// Creates a new `OggOpusReader` reading the stream starting at
+    /// `reader`'s current position, parsing its headers.
+    ///
+    /// **Errors**:
+    /// Returns the reader's error, [`ErrorKind::UnexpectedEof`] if the
+    /// stream ends within its headers, or [`ErrorKind::InvalidData`]
+    /// wrapping [`Error::InvalidOggPage`] or [`Error::InvalidOpusHeader`]
+    /// if the stream is malformed.
+    ///
+    /// [`ErrorKind::UnexpectedEof`]: std::io::ErrorKind::UnexpectedEof
+    /// [`ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+    /// [`Error::InvalidOggPage`]: crate::error::Error::InvalidOggPage
+    /// [`Error::InvalidOpusHeader`]: crate::error::Error::InvalidOpusHeader
+    pub fn new(mut reader: R) -> Result<Self> {
+        let first =
+            Page::read(&mut reader)?.ok_or_else(|| IoError::from(ErrorKind::UnexpectedEof))?;
+        let head = parse_head(&first)?;
+
+        let mut ogg = Self {
+            reader,
+            serial: first.header.serial,
+            skip: usize::from(head.pre_skip),
+            position: 0,
+            end_trim: true,
+            head,
+            tags: OpusTags::default(),
+            link: 0,
+            packets: VecDeque::new(),
+            partial: Vec::new(),
+            granule: GranulePos(0),
+            end_of_stream: false,
+            decoder: None,
+            gain_mode: GainMode::default(),
+            consumed: first.len() as u64,
+            data_start: 0,
+            resync: false,
+        };
+        ogg.read_tags()?;
+
+        Ok(ogg)
+    }
+
+    /// Gets the identification header.
+    pub fn head(&self) -> &OpusHead {
+        &self.head
+    }
+
+    /// Gets the comment header.
+    pub fn tags(&self) -> &OpusTags {
+        &self.tags
+    }
+
+    /// Gets which gain is applied to the decoded PCM.
+    pub fn gain_mode(&self) -> GainMode {
+        self.gain_mode
+    }
+
+    /// Configures which gain is applied to the decoded PCM, the tag's gain
+    /// is folded into the decoder's gain.
+    ///
+    /// **Errors**:
+    /// Returns [`ErrorKind::InvalidData`] wrapping [Error::Opus] when Opus
+    /// encountered a problem.
+    ///
+    /// [`ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn set_gain_mode(&mut self, gain_mode: GainMode) -> Result<()> {
+        self.gain_mode = gain_mode;
+
+        if let Some(decoder) = &self.decoder {
+            decoder.set_gain(self.gain())?;
+        }
+
+        Ok(())
+    }
+
+    /// Configures whether the PCM beyond the final granule position, the
+    /// padding of the last packet, is discarded, enabled by default.
+    pub fn set_end_trim(&mut self, end_trim: bool) {
+        self.end_trim = end_trim;
+    }
+
+    /// Gets the index of the current link of a chained stream, `0` for the
+    /// first.
+    pub fn link(&self) -> usize {
+        self.link
+    }
+
+    /// Gets the serial number of the logical stream read.
+    pub fn serial(&self) -> u32 {
+        self.serial
+    }
+
+    /// Gets the granule position of the last page read, its samples at
+    /// 48 kHz including the pre-skip.
+    pub fn granule_position(&self) -> GranulePos {
+        self.granule
+    }
+
+    /// Unwraps the reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next packet, `None` at the end of the stream.
+    ///
+    /// Empty packets, marking lost packets, are skipped.
+    ///
+    /// **Errors**:
+    /// Returns the reader's error or [`ErrorKind::InvalidData`] wrapping
+    /// [`Error::InvalidOggPage`] if a page is malformed.
+    ///
+    /// [`ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+    /// [`Error::InvalidOggPage`]: crate::error::Error::InvalidOggPage
+    pub fn read_packet(&mut self) -> Result<Option<PacketBuf>> {
+        loop {
+            match self.packets.pop_front() {
+                Some(packet) if packet.is_empty() => continue,
+                Some(packet) => return Ok(Some(PacketBuf::try_from(packet)?)),
+                None if self.read_page()? => continue,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Reads and decodes the next packets until PCM remains after the
+    /// pre-skip, `None` at the end of the stream.
+    ///
+    /// Unless disabled via [`set_end_trim`], the PCM of the last page beyond
+    /// the final granule position is discarded, returning exactly the
+    /// samples the stream was encoded from.
+    ///
+    /// The PCM is at 48 kHz, interleaved in the order of the header's channel
+    /// mapping if multichannel, and scaled according to the gain mode.
+    ///
+    /// **Errors**:
+    /// Returns the errors of [`read_packet`], or [`ErrorKind::InvalidData`]
+    /// wrapping [`Error::InvalidMappingFamily`] if the stream's channels are
+    /// coded via a projection and [Error::Opus] when Opus encountered a
+    /// problem.
+    ///
+    /// [`set_end_trim`]: crate::ogg::OggOpusReader::set_end_trim
+    /// [`read_packet`]: crate::ogg::OggOpusReader::read_packet
+    /// [`ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+    /// [`Error::InvalidMappingFamily`]: crate::error::Error::InvalidMappingFamily
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn read_pcm<S: Sample>(&mut self) -> Result<Option<Vec<S>>> {
+        let channels = usize::from(self.head.channels);
+
+        while let Some(packet) = self.read_packet()? {
+            let mut decoder = match self.decoder.take() {
+                Some(decoder) => decoder,
+                None => create_decoder(&self.head, self.gain())?,
+            };
+
+            let decoded = decoder.decode_to_vec::<S>(packet.as_packet());
+            self.decoder = Some(decoder);
+            let mut pcm = decoded?;
+
+            let samples = pcm.len() / channels;
+            self.position += samples as u64;
+
+            // The final granule position trims the padding of the last page.
+            let end = self.granule.samples();
+
+            if self.end_trim && self.end_of_stream && self.position > end {
+                let excess = (self.position - end).min(samples as u64) as usize;
+                pcm.truncate((samples - excess) * channels);
+            }
+
+            let skip = self.skip.min(pcm.len() / channels);
+            pcm.drain(..skip * channels);
+            self.skip -= skip;
+
+            if !pcm.is_empty() {
+                return Ok(Some(pcm));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Advances to the next link of a chained stream, e.g. the next track of
+    /// an internet radio recording, skipping what is left of the current
+    /// one.
+    ///
+    /// The [`head`] and [`tags`] are replaced by the next link's, and the
+    /// decoder is recreated. Returns `false` if no link follows.
+    ///
+    /// **Errors**:
+    /// Returns the errors of [`new`].
+    ///
+    /// [`head`]: crate::ogg::OggOpusReader::head
+    /// [`tags`]: crate::ogg::OggOpusReader::tags
+    /// [`new`]: crate::ogg::OggOpusReader::new
+    pub fn next_link(&mut self) -> Result<bool> {
+        while self.read_page()? {}
+
+        loop {
+            let page = match Page::read(&mut self.reader)? {
+                Some(page) => page,
+                None => return Ok(false),
+            };
+
+            self.consumed += page.len() as u64;
+
+            // Links may group Opus with other logical streams.
+            let opus =
+                matches!(page.packets().0.first(), Some(packet) if packet.starts_with(OPUS_HEAD));
+
+            if page.header.flags & page::BEGIN_OF_STREAM == 0 || !opus {
+                continue;
+            }
+
+            self.head = parse_head(&page)?;
+            self.serial = page.header.serial;
+            self.skip = usize::from(self.head.pre_skip);
+            self.position = 0;
+            self.link += 1;
+            self.packets.clear();
+            self.partial.clear();
+            self.granule = GranulePos(0);
+            self.end_of_stream = false;
+            self.decoder = None;
+            self.resync = false;
+            self.read_tags()?;
+
+            return Ok(true);
+        }
+    }
+
+    /// Gets the gain to apply in Q7.8 dB according to the gain mode.
+    fn gain(&self) -> i32 {
+        let tag_gain = match self.gain_mode {
+            GainMode::Header => None,
+            GainMode::Track => self.tags.track_gain(),
+            GainMode::Album => self.tags.album_gain().or_else(|| self.tags.track_gain()),
+        };
+
+        let gain = i32::from(self.head.output_gain) + i32::from(tag_gain.unwrap_or(0));
+
+        gain.clamp(i32::from(i16::MIN), i32::from(i16::MAX))
+    }
+
+    /// Reads the comment header following the identification header.
+    fn read_tags(&mut self) -> Result<()> {
+        while self.packets.is_empty() {
+            if !self.read_page()? {
+                return Err(ErrorKind::UnexpectedEof.into());
+            }
+        }
+
+        let tags = self.packets.pop_front().ok_or(Error::InvalidOpusHeader)?;
+        self.tags = OpusTags::parse(&tags)?;
+        self.data_start = self.consumed;
+
+        Ok(())
+    }
+
+    /// Reads the next page of the stream, appending its packets.
+    ///
+    /// Returns `false` at the end of the stream.
+    fn read_page(&mut self) -> Result<bool> {
+        loop {
+            if self.end_of_stream {
+                return Ok(false);
+            }
+
+            let page = match Page::read(&mut self.reader)? {
+                Some(page) => page,
+                None => return Ok(false),
+            };
+
+            self.consumed += page.len() as u64;
+
+            if page.header.serial != self.serial {
+                continue;
+            }
+
+            self.end_of_stream = page.header.flags & page::END_OF_STREAM != 0;
+
+            // A partial packet this page does not continue is incomplete.
+            if page.header.flags & page::CONTINUED == 0 {
+                self.partial.clear();
+            }
+
+            let (packets, partial) = page.packets();
+            let count = packets.len();
+            let skip = usize::from(self.resync && page.header.flags & page::CONTINUED != 0);
+            self.resync = false;
+
+            for (index, packet) in packets.into_iter().enumerate().skip(skip) {
+                self.partial.extend_from_slice(packet);
+
+                if !partial || index + 1 < count {
+                    self.packets.push_back(std::mem::take(&mut self.partial));
+                }
+            }
+
+            // Pages without a complete packet carry no granule position.
+            if !page.header.granule.is_none() {
+                self.granule = page.header.granule;
+            }
+
+            return Ok(true);
+        }
+    }
+}
+
+impl<R: Read + Seek> OggOpusReader<R> {
+    /// Seeks to `position` after the pre-skip, bisecting the pages by their
+    /// granule positions.
+    ///
+    /// Decoding resumes 80 ms ahead of `position` for the decoder to
+    /// converge, [`read_pcm`] discards the PCM preceding `position`.
+    /// Returns the position sought as samples per channel at 48 kHz.
+    ///
+    /// **Errors**:
+    /// Returns the reader's error.
+    ///
+    /// [`read_pcm`]: crate::ogg::OggOpusReader::read_pcm
+    pub fn seek(&mut self, position: Duration) -> Result<u64> {
+        let samples = SampleRate::Hz48000.samples_in(position) as u64;
+        let target = GranulePos(u64::from(self.head.pre_skip).saturating_add(samples));
+        let pre_roll = target.checked_sub(PRE_ROLL).unwrap_or_default();
+
+        let start = self.reader.stream_position()? - self.consumed;
+        let data_start = start + self.data_start;
+        let mut low = data_start;
+        let mut high = self.reader.seek(SeekFrom::End(0))?;
+
+        // The end of the last page known to end at or before the pre-roll,
+        // and its granule position.
+        let mut resume = (data_start, GranulePos(0));
+
+        while high - low > BISECTION_LEN {
+            let middle = low + (high - low) / 2;
+
+            match self.page_after(middle)? {
+                Some((offset, page)) if offset < high && page.header.granule <= pre_roll => {
+                    low = offset + page.len() as u64;
+
+                    if !page.packets().1 {
+                        resume = (low, page.header.granule);
+                    }
+                }
+                _ => high = middle,
+            }
+        }
+
+        // Scan the pages left for the last one ending a packet before the
+        // pre-roll.
+        let mut offset = resume.0;
+
+        while let Some((page_start, page)) = self.page_after(offset)? {
+            let granule = page.header.granule;
+
+            if !granule.is_none() && granule > pre_roll {
+                break;
+            }
+
+            offset = page_start + page.len() as u64;
+
+            if !granule.is_none() && !page.packets().1 {
+                resume = (offset, granule);
+            }
+        }
+
+        self.reader.seek(SeekFrom::Start(resume.0))?;
+        self.consumed = resume.0 - start;
+        self.packets.clear();
+        self.partial.clear();
+        self.granule = resume.1;
+        self.end_of_stream = false;
+        self.resync = true;
+        self.skip = target.checked_since(resume.1).unwrap_or_default() as usize;
+        self.position = resume.1.samples();
+
+        if let Some(decoder) = &mut self.decoder {
+            decoder.reset_state()?;
+        }
+
+        Ok(samples)
+    }
+
+    /// Finds the first intact page of the stream starting at or after
+    /// `offset`, returning the page and its offset.
+    fn page_after(&mut self, offset: u64) -> Result<Option<(u64, Page)>> {
+        self.reader.seek(SeekFrom::Start(offset))?;
+
+        // A window of two pages contains the start of a complete page.
+        let mut window = Vec::with_capacity(2 * page::MAX_PAGE_LEN);
+        (&mut self.reader)
+            .take(2 * page::MAX_PAGE_LEN as u64)
+            .read_to_end(&mut window)?;
+
+        let mut index = 0;
+
+        while index + page::HEADER_LEN <= window.len() {
+            if &window[index..index + 4] != page::CAPTURE_PATTERN {
+                index += 1;
+
+                continue;
+            }
+
+            match Page::read(&mut &window[index..]) {
+                Ok(Some(page)) if page.header.serial == self.serial => {
+                    return Ok(Some((offset + index as u64, page)))
+                }
+                Ok(Some(page)) => index += page.len(),
+                Ok(None) => break,
+                Err(error) if error.kind() == ErrorKind::UnexpectedEof => break,
+                Err(_) => index += 1,
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Parses the identification header off the first page of a stream.
+fn parse_head(page: &Page) -> Result<OpusHead> {
+    if page.header.flags & page::BEGIN_OF_STREAM == 0 {
+        return Err(Error::InvalidOggPage.into());
+    }
+
+    match page.packets() {
+        (packets, false) if packets.len() == 1 => Ok(OpusHead::parse(packets[0])?),
+        _ => Err(Error::InvalidOpusHeader.into()),
+    }
+}
+
+/// The decoder of a link, a multistream decoder unless the stream is mono
+/// or stereo.
+#[derive(Debug)]
+enum LinkDecoder {
+    Single(Decoder),
+    Multistream(MultistreamDecoder),
+}
+
+impl LinkDecoder {
+    /// Decodes `packet` into interleaved PCM.
+    fn decode_to_vec<S: Sample>(&mut self, packet: Packet<'_>) -> crate::Result<Vec<S>> {
+        match self {
+            LinkDecoder::Single(decoder) => decoder.decode_to_vec(DecodeRequest::Normal(packet)),
+            LinkDecoder::Multistream(decoder) => {
+                let channels = usize::from(decoder.channels());
+                let samples = packet.nb_samples(SampleRate::Hz48000)?;
+                let mut output = vec![S::default(); samples * channels];
+                let len = decoder.decode(
+                    DecodeRequest::Normal(packet),
+                    MutSignals::try_from(&mut output)?,
+                )?;
+                output.truncate(len * channels);
+
+                Ok(output)
+            }
+        }
+    }
+
+    /// Configures the gain in Q7.8 dB.
+    fn set_gain(&self, gain: i32) -> crate::Result<()> {
+        match self {
+            LinkDecoder::Single(decoder) => decoder.set_gain(gain),
+            LinkDecoder::Multistream(decoder) => decoder.set_gain(gain),
+        }
+    }
+
+    /// Resets the state, e.g. after seeking.
+    fn reset_state(&mut self) -> crate::Result<()> {
+        match self {
+            LinkDecoder::Single(decoder) => decoder.reset_state(),
+            LinkDecoder::Multistream(decoder) => decoder.reset_state(),
+        }
+    }
+}
+
+/// Creates the decoder of a stream at 48 kHz applying `gain` in Q7.8 dB,
+/// restoring the channels of multistream streams via the header's mapping.
+fn create_decoder(head: &OpusHead, gain: i32) -> Result<LinkDecoder> {
+    let decoder = match (head.mapping_family, &head.mapping) {
+        (MappingFamily::Rtp, _) => {
+            let channels = Channels::try_from(i32::from(head.channels))?;
+
+            LinkDecoder::Single(Decoder::new(SampleRate::Hz48000, channels)?)
+        }
+        (MappingFamily::Vorbis, Some(mapping))
+        | (MappingFamily::Ambisonics, Some(mapping))
+        | (MappingFamily::Undefined, Some(mapping)) => {
+            LinkDecoder::Multistream(MultistreamDecoder::new(SampleRate::Hz48000, mapping)?)
+        }
+        _ => return Err(Error::InvalidMappingFamily(head.mapping_family.into()).into()),
+    };
+    decoder.set_gain(gain)?;
+
+    Ok(decoder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GainMode, OggOpusReader};
+    use crate::{
+        coder::{
+            multistream::{MappingFamily, MultistreamEncoder},
+            Encoder,
+        },
+        ogg::{OggOpusWriter, OpusHead, OpusTags},
+        packet::Packet,
+        Application, Bitrate, Channels, GranulePos, SampleRate,
+    };
+    use std::{io::Cursor, time::Duration};
+
+    #[test]
+    fn read_written_stream() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        let head = OpusHead::new(Channels::Stereo, encoder.pre_skip(false).unwrap(), 48000);
+        let mut tags = OpusTags::default();
+        tags.add("TITLE", "Silence");
+        tags.set_track_gain(-1280);
+
+        let mut writer = OggOpusWriter::new(Vec::new(), &head, &tags, 7).unwrap();
+
+        for _ in 0..60 {
+            let packet = encoder.encode_to_vec(&[0_i16; 1920]).unwrap();
+            writer.write_packet(Packet::new(&packet).unwrap()).unwrap();
+        }
+
+        let file = writer.finish(None).unwrap();
+
+        let mut reader = OggOpusReader::new(Cursor::new(&file)).unwrap();
+        assert_eq!(reader.head(), &head);
+        assert_eq!(reader.tags().get("title"), Some("Silence"));
+        assert_eq!(reader.serial(), 7);
+        assert_eq!(reader.tags().track_gain(), Some(-1280));
+        reader.set_gain_mode(GainMode::Album).unwrap();
+
+        let mut samples = 0;
+
+        while let Some(pcm) = reader.read_pcm::<f32>().unwrap() {
+            samples += pcm.len() / 2;
+        }
+
+        assert_eq!(samples, 57600 - usize::from(head.pre_skip));
+        assert_eq!(reader.granule_position(), GranulePos(57600));
+        assert!(reader.read_packet().unwrap().is_none());
+    }
+
+    #[test]
+    fn read_surround_stream() {
+        let (mut encoder, mapping) = MultistreamEncoder::new_surround(
+            SampleRate::Hz48000,
+            6,
+            MappingFamily::Vorbis,
+            Application::Audio,
+        )
+        .unwrap();
+        let head = OpusHead::multistream(MappingFamily::Vorbis, mapping, 312, 48000);
+        let mut writer = OggOpusWriter::new(Vec::new(), &head, &OpusTags::default(), 1).unwrap();
+
+        for _ in 0..10 {
+            let mut packet = [0; 1500];
+            let len = encoder.encode(&[0_i16; 960 * 6], &mut packet).unwrap();
+            writer
+                .write_packet(Packet::new(&packet[..len]).unwrap())
+                .unwrap();
+        }
+
+        let file = writer.finish(None).unwrap();
+        let mut reader = OggOpusReader::new(Cursor::new(&file)).unwrap();
+        let mut samples = 0;
+
+        while let Some(pcm) = reader.read_pcm::<i16>().unwrap() {
+            samples += pcm.len() / 6;
+        }
+
+        assert_eq!(samples, 9600 - 312);
+    }
+
+    #[test]
+    fn seek_by_time() {
+        let mut encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Audio).unwrap();
+        encoder
+            .set_bitrate(Bitrate::BitsPerSecond(128_000))
+            .unwrap();
+        let head = OpusHead::new(Channels::Mono, encoder.pre_skip(false).unwrap(), 48000);
+        let mut writer = OggOpusWriter::new(Vec::new(), &head, &OpusTags::default(), 1).unwrap();
+
+        // 10 seconds of noise span several pages.
+        let mut seed = 1_u32;
+
+        for _ in 0..500 {
+            let frame: Vec<i16> = (0..960)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    (seed >> 16) as i16 / 4
+                })
+                .collect();
+            let packet = encoder.encode_to_vec(&frame).unwrap();
+            writer.write_packet(Packet::new(&packet).unwrap()).unwrap();
+        }
+
+        let file = writer.finish(None).unwrap();
+        let mut reader = OggOpusReader::new(Cursor::new(&file)).unwrap();
+
+        let remaining = |reader: &mut OggOpusReader<_>| {
+            let mut samples = 0;
+
+            while let Some(pcm) = reader.read_pcm::<i16>().unwrap() {
+                samples += pcm.len();
+            }
+
+            samples
+        };
+
+        let total = 480_000 - usize::from(head.pre_skip);
+
+        for &millis in &[7300, 500, 0] {
+            let position = reader.seek(Duration::from_millis(millis)).unwrap();
+            assert_eq!(position, millis * 48);
+            assert_eq!(remaining(&mut reader), total - millis as usize * 48);
+        }
+    }
+
+    #[test]
+    fn read_chained_stream() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        let head = OpusHead::new(Channels::Stereo, encoder.pre_skip(false).unwrap(), 48000);
+        let mut writer =
+            Some(OggOpusWriter::new(Vec::new(), &head, &OpusTags::default(), 1).unwrap());
+
+        for link in 0..3_u32 {
+            let mut tags = OpusTags::default();
+            tags.add("TITLE", format!("Track {}", link));
+
+            if link > 0 {
+                let previous = writer.take().unwrap();
+                writer = Some(previous.chain(None, &head, &tags, link + 1).unwrap());
+            }
+
+            for _ in 0..10 * (link + 1) {
+                let packet = encoder.encode_to_vec(&[0_i16; 1920]).unwrap();
+                let writer = writer.as_mut().unwrap();
+                writer.write_packet(Packet::new(&packet).unwrap()).unwrap();
+            }
+        }
+
+        let file = writer.unwrap().finish(None).unwrap();
+        let mut reader = OggOpusReader::new(Cursor::new(&file)).unwrap();
+
+        // The first link is skipped before being read entirely.
+        assert!(reader.read_pcm::<i16>().unwrap().is_some());
+        assert!(reader.next_link().unwrap());
+        assert_eq!(reader.link(), 1);
+        assert_eq!(reader.serial(), 2);
+        assert_eq!(reader.tags().get("TITLE"), Some("Track 1"));
+
+        let mut samples = 0;
+
+        while let Some(pcm) = reader.read_pcm::<i16>().unwrap() {
+            samples += pcm.len() / 2;
+        }
+
+        assert_eq!(samples, 20 * 960 - usize::from(head.pre_skip));
+
+        assert!(reader.next_link().unwrap());
+        assert_eq!(reader.tags().get("TITLE"), Some("Track 2"));
+        assert!(!reader.next_link().unwrap());
+    }
+
+    #[test]
+    fn trim_gaplessly() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Audio).unwrap();
+        let pre_skip = encoder.pre_skip(false).unwrap();
+        let head = OpusHead::new(Channels::Mono, pre_skip, 48000);
+        let mut writer = OggOpusWriter::new(Vec::new(), &head, &OpusTags::default(), 1).unwrap();
+
+        // The input padded to frames covering the pre-skip.
+        let samples = 48_123;
+        let frames = (samples + usize::from(pre_skip) + 959) / 960;
+
+        for _ in 0..frames {
+            let packet = encoder.encode_to_vec(&[0_i16; 960]).unwrap();
+            writer.write_packet(Packet::new(&packet).unwrap()).unwrap();
+        }
+
+        let file = writer.finish(Some(samples as u64)).unwrap();
+
+        let decoded = |end_trim| {
+            let mut reader = OggOpusReader::new(Cursor::new(&file)).unwrap();
+            reader.set_end_trim(end_trim);
+            let mut decoded = 0;
+
+            while let Some(pcm) = reader.read_pcm::<f32>().unwrap() {
+                decoded += pcm.len();
+            }
+
+            decoded
+        };
+
+        assert_eq!(decoded(true), samples);
+        assert_eq!(decoded(false), frames * 960 - usize::from(pre_skip));
+    }
+}
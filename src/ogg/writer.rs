@@ -0,0 +1,248 @@
+use super::page::{self, PageHeader};
+use crate::{
+    header::{OpusHead, OpusTags},
+    packet::Packet,
+    Error, GranulePos, SampleRate,
+};
+use std::io::{Result, Write};
+
+/// The most samples at 48 kHz a page spans before it is written, one
+/// second as recommended by RFC 7845.
+const MAX_PAGE_SAMPLES: u64 = 48000;
+
+/// `OggOpusWriter` writes an Ogg Opus stream, e.g. an `.opus` file: the
+/// [`OpusHead`] and [`OpusTags`] headers followed by the encoded packets
+/// paginated with their granule positions.
+///
+/// Call [`finish`] after the last packet to mark the end of the stream,
+/// dropping the writer discards the packets of the last page.
+///
+/// [`OpusHead`]: crate::ogg::OpusHead
+/// [`OpusTags`]: crate::ogg::OpusTags
+/// [`finish`]: crate::ogg::OggOpusWriter::finish
+#[derive(Debug)]
+pub struct OggOpusWriter<W> {
+    writer: W,
+    serial: u32,
+    sequence: u32,
+    pre_skip: u16,
+    /// The samples at 48 kHz of all packets written.
+    granule: GranulePos,
+    /// The granule position the pending page starts at.
+    page_start: GranulePos,
+    segments: Vec<u8>,
+    body: Vec<u8>,
+}
+
+impl<W: Write> OggOpusWriter<W> {
+    /// Creates a new `OggOpusWriter` writing the stream `serial` to
+    /// `writer`, starting with the pages of `head` and `tags`.
+    ///
+    /// **Errors**:
+    /// Returns the writer's error.
+    pub fn new(writer: W, head: &OpusHead, tags: &OpusTags, serial: u32) -> Result<Self> {
+        let mut ogg = Self {
+            writer,
+            serial,
+            sequence: 0,
+            pre_skip: head.pre_skip,
+            granule: GranulePos(0),
+            page_start: GranulePos(0),
+            segments: Vec::new(),
+            body: Vec::new(),
+        };
+
+        ogg.write_header(&head.to_bytes(), page::BEGIN_OF_STREAM)?;
+        ogg.write_header(&tags.to_bytes(), 0)?;
+
+        Ok(ogg)
+    }
+
+    /// Gets the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Gets the granule position after the packets written, their samples
+    /// at 48 kHz including the pre-skip.
+    pub fn granule_position(&self) -> GranulePos {
+        self.granule
+    }
+
+    /// Appends `packet` to the stream.
+    ///
+    /// **Errors**:
+    /// Returns the writer's error or [`ErrorKind::InvalidData`] wrapping
+    /// [Error::Opus] if `packet` is malformed and
+    /// [`Error::GranulePosOverflow`] if the stream exceeds the largest
+    /// granule position.
+    ///
+    /// [`ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+    /// [Error::Opus]: crate::error::Error::Opus
+    /// [`Error::GranulePosOverflow`]: crate::error::Error::GranulePosOverflow
+    pub fn write_packet(&mut self, packet: Packet<'_>) -> Result<()> {
+        let samples = packet.nb_samples(SampleRate::Hz48000)? as u64;
+        let data = packet.as_slice();
+        let lacing = data.len() / 255 + 1;
+        let granule = self
+            .granule
+            .checked_add(samples)
+            .ok_or(Error::GranulePosOverflow)?;
+
+        if !self.segments.is_empty()
+            && (self.segments.len() + lacing > page::MAX_SEGMENTS
+                || self.granule.checked_since(self.page_start) >= Some(MAX_PAGE_SAMPLES))
+        {
+            self.write_page(0)?;
+        }
+
+        page::lace(data.len(), &mut self.segments);
+        self.body.extend_from_slice(data);
+        self.granule = granule;
+
+        Ok(())
+    }
+
+    /// Writes the last page marking the end of the stream, flushes, and
+    /// returns the wrapped writer.
+    ///
+    /// If `samples` is given, the original amount of samples per channel at
+    /// 48 kHz, the padding of the last packet beyond it is trimmed by the
+    /// final granule position. For readers to get every sample back, the
+    /// packets must cover the pre-skip beyond the original samples, e.g. by
+    /// encoding silence after the input.
+    ///
+    /// **Errors**:
+    /// Returns the writer's error.
+    pub fn finish(mut self, samples: Option<u64>) -> Result<W> {
+        if let Some(samples) = samples {
+            let end = u64::from(self.pre_skip).saturating_add(samples);
+            self.granule = GranulePos(end).min(self.granule).max(self.page_start);
+        }
+
+        self.write_page(page::END_OF_STREAM)?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    /// Ends the stream as [`finish`] does and chains a new stream `serial`
+    /// to it, starting with the pages of `head` and `tags`, e.g. to start
+    /// the next track of a recording.
+    ///
+    /// The serial must differ from the ended stream's.
+    ///
+    /// **Errors**:
+    /// Returns the writer's error.
+    ///
+    /// [`finish`]: crate::ogg::OggOpusWriter::finish
+    pub fn chain(
+        self,
+        samples: Option<u64>,
+        head: &OpusHead,
+        tags: &OpusTags,
+        serial: u32,
+    ) -> Result<Self> {
+        let writer = self.finish(samples)?;
+
+        Self::new(writer, head, tags, serial)
+    }
+
+    /// Writes the packets pending as a page ending at the current granule
+    /// position.
+    fn write_page(&mut self, flags: u8) -> Result<()> {
+        let header = PageHeader {
+            flags,
+            granule: self.granule,
+            serial: self.serial,
+            sequence: self.sequence,
+        };
+        header.write(&self.segments, &self.body, &mut self.writer)?;
+
+        self.sequence += 1;
+        self.page_start = self.granule;
+        self.segments.clear();
+        self.body.clear();
+
+        Ok(())
+    }
+
+    /// Writes the header packet `data` on pages of its own, the first page
+    /// flagged with `flags`.
+    fn write_header(&mut self, data: &[u8], flags: u8) -> Result<()> {
+        let mut segments = Vec::new();
+        page::lace(data.len(), &mut segments);
+
+        let mut offset = 0;
+        let mut flags = flags;
+
+        for chunk in segments.chunks(page::MAX_SEGMENTS) {
+            let len = chunk
+                .iter()
+                .map(|&segment| usize::from(segment))
+                .sum::<usize>();
+
+            let header = PageHeader {
+                flags,
+                granule: GranulePos(0),
+                serial: self.serial,
+                sequence: self.sequence,
+            };
+            header.write(chunk, &data[offset..offset + len], &mut self.writer)?;
+
+            self.sequence += 1;
+            offset += len;
+            flags = page::CONTINUED;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OggOpusWriter;
+    use crate::{
+        coder::Encoder,
+        ogg::{OpusHead, OpusTags},
+        packet::Packet,
+        Application, Channels, GranulePos, SampleRate,
+    };
+
+    #[test]
+    fn write_pages() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        let head = OpusHead::new(Channels::Stereo, encoder.pre_skip(false).unwrap(), 48000);
+        let mut tags = OpusTags::default();
+        tags.add("TITLE", "Silence");
+
+        let mut writer = OggOpusWriter::new(Vec::new(), &head, &tags, 7).unwrap();
+
+        // 1.2 seconds span two pages.
+        for _ in 0..60 {
+            let packet = encoder.encode_to_vec(&[0_i16; 1920]).unwrap();
+            writer.write_packet(Packet::new(&packet).unwrap()).unwrap();
+        }
+        assert_eq!(writer.granule_position(), GranulePos(57600));
+
+        let samples = 57600 - 960 - u64::from(head.pre_skip);
+        let file = writer.finish(Some(samples)).unwrap();
+
+        // The identification header stands alone on the first page.
+        assert_eq!(&file[..4], b"OggS");
+        assert_eq!(file[5], 0x02);
+        assert_eq!(&file[28..36], b"OpusHead");
+
+        let pages: Vec<usize> = (0..file.len() - 3)
+            .filter(|&index| &file[index..index + 4] == b"OggS")
+            .collect();
+        assert_eq!(pages.len(), 4);
+
+        let last = pages[3];
+        assert_eq!(file[last + 5], 0x04);
+        let mut granule = [0; 8];
+        granule.copy_from_slice(&file[last + 6..last + 14]);
+        assert_eq!(u64::from_le_bytes(granule), 57600 - 960);
+    }
+}
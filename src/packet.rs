@@ -1,7 +1,41 @@
 use crate::{
-    error::try_map_opus_error, ffi, Bandwidth, Channels, Error, Result, SampleRate, TryFrom,
-    TryInto,
+    error::try_map_opus_error, ffi, repacketizer::Repacketizer, Bandwidth, Bitrate, Channels,
+    Error, ErrorCode, FrameDuration, Result, SampleRate, TryFrom, TryInto,
 };
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
+use std::{borrow::Cow, time::Duration};
+
+/// The buffer size Opus recommends for encoding a packet, large enough to
+/// hold any packet the encoder produces.
+pub const MAX_PACKET_SIZE: usize = 4000;
+
+/// Gets a buffer size sufficient for a packet of `frame_duration` encoded
+/// at `bitrate`, e.g. to allocate fixed buffers at compile time.
+///
+/// The size allows variable bitrate frames to use twice the average bitrate
+/// and never exceeds [`MAX_PACKET_SIZE`], which is returned for
+/// [`Bitrate::Max`] and [`Bitrate::Auto`].
+///
+/// [`MAX_PACKET_SIZE`]: crate::packet::MAX_PACKET_SIZE
+/// [`Bitrate::Max`]: crate::Bitrate::Max
+/// [`Bitrate::Auto`]: crate::Bitrate::Auto
+pub const fn recommended_packet_capacity(bitrate: Bitrate, frame_duration: FrameDuration) -> usize {
+    let bits_per_second = match bitrate {
+        Bitrate::BitsPerSecond(bits) if bits > 0 => bits as u64,
+        _ => return MAX_PACKET_SIZE,
+    };
+
+    // Twice the average size in bytes, plus the table-of-contents, frame
+    // count, and padding bytes.
+    let capacity = 2 * bits_per_second * frame_duration.micros() as u64 / 8_000_000 + 3;
+
+    if capacity > MAX_PACKET_SIZE as u64 {
+        MAX_PACKET_SIZE
+    } else {
+        capacity as usize
+    }
+}
 
 fn packet_len_check(packet_buffer: &[u8]) -> Result<i32> {
     match packet_buffer {
@@ -16,20 +50,431 @@ fn packet_len_check(packet_buffer: &[u8]) -> Result<i32> {
 /// A newtype around `&[u8]` to guarantee:
 /// - Minimum one element: A packet cannot be empty.
 /// - Limited size: A packet's length may not exceed `std::i32::MAX`.
-#[derive(Debug)]
+///
+/// The guarantees are verified once upon construction, borrowing the
+/// buffer without copying it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub struct Packet<'a>(&'a [u8]);
 
 impl<'a> Packet<'a> {
+    /// Creates a packet borrowing `buffer`, e.g. a `[u8]`, `Vec<u8>`,
+    /// array, or `Cow<[u8]>`.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::EmptyPacket`] if `buffer` is empty and
+    /// [`Error::PacketTooLarge`] if its length exceeds `std::i32::MAX`.
+    ///
+    /// [`Error::EmptyPacket`]: crate::error::Error::EmptyPacket
+    /// [`Error::PacketTooLarge`]: crate::error::Error::PacketTooLarge
+    pub fn new<B: AsRef<[u8]> + ?Sized>(buffer: &'a B) -> Result<Self> {
+        let buffer = buffer.as_ref();
+
+        packet_len_check(buffer).map(|_| Self(buffer))
+    }
+
     pub fn as_ptr(&self) -> *const u8 {
         self.0.as_ptr()
     }
 
+    /// Gets the underlying buffer.
+    pub fn as_slice(&self) -> &'a [u8] {
+        self.0
+    }
+
     /// The underlying type is immutably borrowed and has been verified upon
     /// construction of `Packet`, thus we know casting `usize` will fit
     /// inside `i32`.
     pub fn i32_len(&self) -> i32 {
         self.0.len() as i32
     }
+
+    /// Gets the table-of-contents of the packet, stored in its first byte.
+    ///
+    /// A `Packet` cannot be empty, hence the first byte always exists.
+    pub fn toc(&self) -> Toc {
+        Toc::from(self.0[0])
+    }
+
+    /// Gets the bandwidth of the packet.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidBandwidth`] if Opus reports a value not
+    /// matching a documented [`Bandwidth`].
+    ///
+    /// [`Error::InvalidBandwidth`]: crate::error::Error::InvalidBandwidth
+    /// [`Bandwidth`]: crate::Bandwidth
+    pub fn bandwidth(&self) -> Result<Bandwidth> {
+        unsafe { ffi::opus_packet_get_bandwidth(self.as_ptr()) }.try_into()
+    }
+
+    /// Gets number of samples per frame of the packet.
+    pub fn samples_per_frame(&self, sample_rate: SampleRate) -> usize {
+        unsafe {
+            ffi::opus_packet_get_samples_per_frame(self.as_ptr(), sample_rate as i32) as usize
+        }
+    }
+
+    /// Gets number of samples in the packet.
+    ///
+    /// **Errors**:
+    /// Returns [`InvalidPacket`] if the packet is malformed.
+    ///
+    /// [`InvalidPacket`]: crate::error::ErrorCode::InvalidPacket
+    pub fn nb_samples(&self, sample_rate: SampleRate) -> Result<usize> {
+        try_map_opus_error(unsafe {
+            ffi::opus_packet_get_nb_samples(self.as_ptr(), self.i32_len(), sample_rate as i32)
+        })
+        .map(|n| n as usize)
+    }
+
+    /// Gets number of channels of the packet.
+    pub fn nb_channels(&self) -> Result<Channels> {
+        try_map_opus_error(unsafe { ffi::opus_packet_get_nb_channels(self.as_ptr()) })
+            .and_then(Channels::try_from)
+    }
+
+    /// Gets number of frames in the packet.
+    ///
+    /// **Errors**:
+    /// Returns [`InvalidPacket`] if the packet is malformed.
+    ///
+    /// [`InvalidPacket`]: crate::error::ErrorCode::InvalidPacket
+    pub fn nb_frames(&self) -> Result<usize> {
+        try_map_opus_error(unsafe { ffi::opus_packet_get_nb_frames(self.as_ptr(), self.i32_len()) })
+            .map(|n| n as usize)
+    }
+
+    /// Gets the duration of the audio contained in the packet.
+    ///
+    /// The duration is computed from the packet's frame count and samples per
+    /// frame, the `sample_rate` merely determines the sample resolution.
+    ///
+    /// **Errors**:
+    /// Returns [`InvalidPacket`] if the packet is malformed.
+    ///
+    /// [`InvalidPacket`]: crate::error::ErrorCode::InvalidPacket
+    pub fn duration(&self, sample_rate: SampleRate) -> Result<Duration> {
+        let samples = self.nb_frames()? * self.samples_per_frame(sample_rate);
+
+        Ok(sample_rate.duration_of(samples))
+    }
+
+    /// Parses the packet into its table-of-contents and frames without
+    /// decoding it.
+    ///
+    /// The returned frames borrow from the packet's underlying buffer.
+    ///
+    /// **Errors**:
+    /// Returns [`InvalidPacket`] if the packet is malformed.
+    ///
+    /// [`InvalidPacket`]: crate::error::ErrorCode::InvalidPacket
+    pub fn parse(&self) -> Result<ParsedPacket<'a>> {
+        let mut toc = 0;
+        let mut frame_pointers = [std::ptr::null(); MAX_FRAMES];
+        let mut frame_sizes = [0_i16; MAX_FRAMES];
+        let mut payload_offset = 0;
+
+        // Opus writes at most 48 frames, which both arrays can hold.
+        let nb_frames = try_map_opus_error(unsafe {
+            ffi::opus_packet_parse(
+                self.as_ptr(),
+                self.i32_len(),
+                &mut toc,
+                frame_pointers.as_mut_ptr(),
+                frame_sizes.as_mut_ptr(),
+                &mut payload_offset,
+            )
+        })? as usize;
+
+        let packet: &'a [u8] = self.0;
+        let packet_start = packet.as_ptr() as usize;
+
+        // Each frame pointer points into the packet, hence the offset is the
+        // frame's position inside the packet's buffer.
+        let frames = frame_pointers
+            .iter()
+            .zip(frame_sizes.iter())
+            .take(nb_frames)
+            .map(|(&pointer, &size)| {
+                let offset = pointer as usize - packet_start;
+
+                &packet[offset..offset + size as usize]
+            })
+            .collect();
+
+        Ok(ParsedPacket {
+            toc: Toc::from(toc),
+            payload_offset: payload_offset as usize,
+            frames,
+        })
+    }
+
+    /// Parses a self-delimited packet at the start of the underlying buffer,
+    /// as used for all but the last stream of a multistream packet,
+    /// see RFC 6716, appendix B.
+    ///
+    /// On success, returns the parsed packet and the amount of bytes it
+    /// occupies, any bytes following it belong to the next packet.
+    ///
+    /// **Errors**:
+    /// Returns [`InvalidPacket`] if the packet is malformed.
+    ///
+    /// [`InvalidPacket`]: crate::error::ErrorCode::InvalidPacket
+    pub fn parse_self_delimited(&self) -> Result<(ParsedPacket<'a>, usize)> {
+        let invalid = || Error::Opus(ErrorCode::InvalidPacket);
+        let packet: &'a [u8] = self.0;
+        let toc = self.toc();
+        let mut offset = 1;
+        let mut padding = 0;
+        let mut frame_sizes = Vec::new();
+
+        let nb_frames = match toc.frame_count_code() {
+            FrameCountCode::One => 1,
+            FrameCountCode::TwoEqual => 2,
+            FrameCountCode::TwoDifferent => {
+                let (size, len) = read_frame_size(&packet[offset..]).ok_or_else(invalid)?;
+                offset += len;
+                frame_sizes.push(size);
+
+                2
+            }
+            FrameCountCode::Arbitrary => {
+                let count = *packet.get(offset).ok_or_else(invalid)?;
+                offset += 1;
+
+                let nb_frames = usize::from(count & 0x3F);
+
+                if nb_frames == 0
+                    || toc.frame_duration() * u32::from(count & 0x3F) > MAX_PACKET_DURATION
+                {
+                    return Err(invalid());
+                }
+
+                if count & 0x40 != 0 {
+                    loop {
+                        let byte = *packet.get(offset).ok_or_else(invalid)?;
+                        offset += 1;
+
+                        if byte == 255 {
+                            padding += 254;
+                        } else {
+                            padding += usize::from(byte);
+                            break;
+                        }
+                    }
+                }
+
+                if count & 0x80 != 0 {
+                    for _ in 1..nb_frames {
+                        let (size, len) = read_frame_size(&packet[offset..]).ok_or_else(invalid)?;
+                        offset += len;
+                        frame_sizes.push(size);
+                    }
+                }
+
+                nb_frames
+            }
+        };
+
+        let (size, len) = read_frame_size(&packet[offset..]).ok_or_else(invalid)?;
+        let payload_offset = offset + len;
+
+        // Equally sized frames only state their size once.
+        if frame_sizes.len() + 1 < nb_frames {
+            frame_sizes = vec![size; nb_frames];
+        } else {
+            frame_sizes.push(size);
+        }
+
+        let mut end = payload_offset;
+        let mut frames = Vec::with_capacity(nb_frames);
+
+        for size in frame_sizes {
+            frames.push(packet.get(end..end + size).ok_or_else(invalid)?);
+            end += size;
+        }
+
+        let len = end + padding;
+
+        if len > packet.len() {
+            return Err(invalid());
+        }
+
+        Ok((
+            ParsedPacket {
+                toc,
+                payload_offset,
+                frames,
+            },
+            len,
+        ))
+    }
+}
+
+/// The maximum amount of frames an Opus packet may contain.
+const MAX_FRAMES: usize = 48;
+
+/// The maximum duration of audio an Opus packet may contain.
+pub(crate) const MAX_PACKET_DURATION: Duration = Duration::from_millis(120);
+
+/// Reads a frame size encoded in one or two bytes, as specified in RFC 6716,
+/// section 3.2.1.
+///
+/// On success, returns the size and the amount of bytes it occupied.
+fn read_frame_size(data: &[u8]) -> Option<(usize, usize)> {
+    match data {
+        [first, ..] if *first < 252 => Some((usize::from(*first), 1)),
+        [first, second, ..] => Some((usize::from(*second) * 4 + usize::from(*first), 2)),
+        _ => None,
+    }
+}
+
+/// Writes `size` encoded in one or two bytes into `output`, as specified in
+/// RFC 6716, section 3.2.1.
+///
+/// Returns the amount of bytes written.
+fn write_frame_size(size: usize, output: &mut [u8; 2]) -> usize {
+    if size < 252 {
+        output[0] = size as u8;
+
+        1
+    } else {
+        output[0] = 252 + (size & 0b11) as u8;
+        output[1] = ((size - usize::from(output[0])) >> 2) as u8;
+
+        2
+    }
+}
+
+/// A packet split into its frames by [`Packet::parse`].
+///
+/// [`Packet::parse`]: crate::packet::Packet::parse
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ParsedPacket<'a> {
+    toc: Toc,
+    payload_offset: usize,
+    frames: Vec<&'a [u8]>,
+}
+
+impl<'a> ParsedPacket<'a> {
+    /// Gets the table-of-contents of the packet.
+    pub fn toc(&self) -> Toc {
+        self.toc
+    }
+
+    /// Gets the offset in bytes at which the frame data begins.
+    pub fn payload_offset(&self) -> usize {
+        self.payload_offset
+    }
+
+    /// Gets the frames contained in the packet, their sizes are the slices'
+    /// lengths.
+    pub fn frames(&self) -> &[&'a [u8]] {
+        &self.frames
+    }
+}
+
+/// Represents the coding mode Opus used for a packet.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Mode {
+    /// Linear prediction, used for speech at lower bandwidths.
+    Silk,
+    /// Linear prediction for the lower and MDCT for the upper bandwidth.
+    Hybrid,
+    /// MDCT, used for music and lowest latency.
+    Celt,
+}
+
+/// Represents how many frames a packet contains, as signalled by the last two
+/// bits of the table-of-contents byte.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum FrameCountCode {
+    /// The packet contains one frame.
+    One,
+    /// The packet contains two frames of equal size.
+    TwoEqual,
+    /// The packet contains two frames of different sizes.
+    TwoDifferent,
+    /// The packet contains an arbitrary amount of frames, stated in the byte
+    /// following the table-of-contents byte.
+    Arbitrary,
+}
+
+/// The table-of-contents byte each Opus packet starts with, as specified in
+/// RFC 6716, section 3.1.
+///
+/// Every byte is a valid table-of-contents, inspecting it does not require
+/// decoding the packet.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Toc(u8);
+
+impl From<u8> for Toc {
+    fn from(byte: u8) -> Self {
+        Self(byte)
+    }
+}
+
+impl From<Toc> for u8 {
+    fn from(toc: Toc) -> u8 {
+        toc.0
+    }
+}
+
+impl Toc {
+    /// Gets the configuration number from `0` to `31`, combining mode,
+    /// bandwidth, and frame duration.
+    pub fn config(self) -> u8 {
+        self.0 >> 3
+    }
+
+    /// Gets the coding mode.
+    pub fn mode(self) -> Mode {
+        match self.config() {
+            0..=11 => Mode::Silk,
+            12..=15 => Mode::Hybrid,
+            _ => Mode::Celt,
+        }
+    }
+
+    /// Gets the audio bandwidth.
+    pub fn bandwidth(self) -> Bandwidth {
+        match self.config() {
+            0..=3 | 16..=19 => Bandwidth::Narrowband,
+            4..=7 => Bandwidth::Mediumband,
+            8..=11 | 20..=23 => Bandwidth::Wideband,
+            12..=13 | 24..=27 => Bandwidth::Superwideband,
+            _ => Bandwidth::Fullband,
+        }
+    }
+
+    /// Gets the duration of each frame in the packet.
+    pub fn frame_duration(self) -> Duration {
+        let config = self.config();
+
+        let micros = match self.mode() {
+            Mode::Silk => [10_000, 20_000, 40_000, 60_000][usize::from(config % 4)],
+            Mode::Hybrid => [10_000, 20_000][usize::from(config % 2)],
+            Mode::Celt => [2_500, 5_000, 10_000, 20_000][usize::from(config % 4)],
+        };
+
+        Duration::from_micros(micros)
+    }
+
+    /// Returns `true` if the packet is coded as stereo.
+    pub fn is_stereo(self) -> bool {
+        self.0 & 0b100 != 0
+    }
+
+    /// Gets the frame count code, describing how many frames the packet
+    /// contains.
+    pub fn frame_count_code(self) -> FrameCountCode {
+        match self.0 & 0b11 {
+            0 => FrameCountCode::One,
+            1 => FrameCountCode::TwoEqual,
+            2 => FrameCountCode::TwoDifferent,
+            _ => FrameCountCode::Arbitrary,
+        }
+    }
 }
 
 impl<'a> TryFrom<&'a Vec<u8>> for Packet<'a> {
@@ -44,7 +489,108 @@ impl<'a> TryFrom<&'a [u8]> for Packet<'a> {
     type Error = Error;
 
     fn try_from(value: &'a [u8]) -> Result<Packet<'a>> {
-        packet_len_check(value).map(|_| Self(value))
+        Self::new(value)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<'a> TryFrom<&'a Bytes> for Packet<'a> {
+    type Error = Error;
+
+    fn try_from(value: &'a Bytes) -> Result<Packet<'a>> {
+        Self::new(value)
+    }
+}
+
+impl<'a, const N: usize> TryFrom<&'a [u8; N]> for Packet<'a> {
+    type Error = Error;
+
+    fn try_from(value: &'a [u8; N]) -> Result<Packet<'a>> {
+        Self::new(value)
+    }
+}
+
+impl<'a> TryFrom<&'a Cow<'_, [u8]>> for Packet<'a> {
+    type Error = Error;
+
+    fn try_from(value: &'a Cow<'_, [u8]>) -> Result<Packet<'a>> {
+        Self::new(value)
+    }
+}
+
+/// An owned packet upholding the same guarantees as [`Packet`], e.g. to
+/// store packets in a queue.
+///
+/// [`Packet`]: crate::packet::Packet
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PacketBuf(Vec<u8>);
+
+impl PacketBuf {
+    /// Borrows the packet as [`Packet`].
+    ///
+    /// [`Packet`]: crate::packet::Packet
+    pub fn as_packet(&self) -> Packet<'_> {
+        Packet(&self.0)
+    }
+
+    /// Gets the underlying buffer.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Unwraps the underlying buffer.
+    pub fn into_vec(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for PacketBuf {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl TryFrom<Vec<u8>> for PacketBuf {
+    type Error = Error;
+
+    fn try_from(value: Vec<u8>) -> Result<Self> {
+        packet_len_check(&value).map(|_| Self(value))
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl TryFrom<Bytes> for PacketBuf {
+    type Error = Error;
+
+    /// Reuses the buffer if `value` is its only reference, copies it
+    /// otherwise.
+    fn try_from(value: Bytes) -> Result<Self> {
+        Vec::from(value).try_into()
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl From<PacketBuf> for Bytes {
+    fn from(packet: PacketBuf) -> Self {
+        Bytes::from(packet.0)
+    }
+}
+
+impl<'a> From<Packet<'a>> for PacketBuf {
+    fn from(packet: Packet<'a>) -> Self {
+        Self(packet.0.to_vec())
+    }
+}
+
+impl<'a> From<&'a PacketBuf> for Packet<'a> {
+    fn from(packet: &'a PacketBuf) -> Self {
+        packet.as_packet()
+    }
+}
+
+impl From<PacketBuf> for Vec<u8> {
+    fn from(packet: PacketBuf) -> Self {
+        packet.0
     }
 }
 
@@ -54,6 +600,21 @@ impl<'a> TryFrom<&'a [u8]> for Packet<'a> {
 pub struct MutPacket<'a>(&'a mut [u8]);
 
 impl<'a> MutPacket<'a> {
+    /// Creates a packet mutably borrowing `buffer`, e.g. a `[u8]`,
+    /// `Vec<u8>`, or array.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::EmptyPacket`] if `buffer` is empty and
+    /// [`Error::PacketTooLarge`] if its length exceeds `std::i32::MAX`.
+    ///
+    /// [`Error::EmptyPacket`]: crate::error::Error::EmptyPacket
+    /// [`Error::PacketTooLarge`]: crate::error::Error::PacketTooLarge
+    pub fn new<B: AsMut<[u8]> + ?Sized>(buffer: &'a mut B) -> Result<Self> {
+        let buffer = buffer.as_mut();
+
+        packet_len_check(buffer).map(move |_| Self(buffer))
+    }
+
     pub fn as_mut_ptr(&mut self) -> *mut u8 {
         self.0.as_mut_ptr()
     }
@@ -80,12 +641,173 @@ impl<'a> TryFrom<&'a mut [u8]> for MutPacket<'a> {
     }
 }
 
+/// Verifies that a packet of `len` bytes can be padded to `new_len` bytes
+/// inside a buffer of `buffer_len` bytes.
+fn pad_len_check(buffer_len: usize, len: usize, new_len: usize) -> Result<(i32, i32)> {
+    if len == 0 {
+        return Err(Error::EmptyPacket);
+    }
+
+    if new_len > std::i32::MAX as usize {
+        return Err(Error::PacketTooLarge);
+    }
+
+    if len > new_len || new_len > buffer_len {
+        return Err(Error::InvalidPadLen(new_len));
+    }
+
+    Ok((len as i32, new_len as i32))
+}
+
+/// Pads a packet of `len` bytes at the start of `buffer` to `new_len` bytes,
+/// e.g. to achieve constant-size RTP payloads.
+///
+/// On success, returns the new length.
+///
+/// **Errors**:
+/// Returns [`Error::InvalidPadLen`] if `new_len` is smaller than `len` or
+/// exceeds `buffer`'s length.
+///
+/// [`Error::InvalidPadLen`]: crate::error::Error::InvalidPadLen
+pub fn pad(mut buffer: MutPacket<'_>, len: usize, new_len: usize) -> Result<usize> {
+    let (len, new_len) = pad_len_check(buffer.0.len(), len, new_len)?;
+
+    // The buffer has been verified to hold at least `new_len` bytes.
+    try_map_opus_error(unsafe { ffi::opus_packet_pad(buffer.as_mut_ptr(), len, new_len) })?;
+
+    Ok(new_len as usize)
+}
+
+/// Removes all padding from a `packet` in place.
+///
+/// On success, returns the new length, the packet occupies the start of
+/// the buffer.
+pub fn unpad(mut packet: MutPacket<'_>) -> Result<usize> {
+    try_map_opus_error(unsafe { ffi::opus_packet_unpad(packet.as_mut_ptr(), packet.i32_len()?) })
+        .map(|n| n as usize)
+}
+
+/// Pads a multistream packet of `len` bytes at the start of `buffer` to
+/// `new_len` bytes, e.g. to achieve a constant size for CBR transports.
+///
+/// `streams` must be the amount of streams the packet contains.
+/// On success, returns the new length.
+///
+/// **Errors**:
+/// Returns [`Error::InvalidPadLen`] if `new_len` is smaller than `len` or
+/// exceeds `buffer`'s length.
+///
+/// [`Error::InvalidPadLen`]: crate::error::Error::InvalidPadLen
+pub fn multistream_pad(
+    mut buffer: MutPacket<'_>,
+    len: usize,
+    new_len: usize,
+    streams: u8,
+) -> Result<usize> {
+    let (len, new_len) = pad_len_check(buffer.0.len(), len, new_len)?;
+
+    // The buffer has been verified to hold at least `new_len` bytes.
+    try_map_opus_error(unsafe {
+        ffi::opus_multistream_packet_pad(buffer.as_mut_ptr(), len, new_len, i32::from(streams))
+    })?;
+
+    Ok(new_len as usize)
+}
+
+/// Removes all padding from a multistream `packet` in place.
+///
+/// `streams` must be the amount of streams the packet contains.
+/// On success, returns the new length, the packet occupies the start of
+/// the buffer.
+pub fn multistream_unpad(mut packet: MutPacket<'_>, streams: u8) -> Result<usize> {
+    try_map_opus_error(unsafe {
+        ffi::opus_multistream_packet_unpad(
+            packet.as_mut_ptr(),
+            packet.i32_len()?,
+            i32::from(streams),
+        )
+    })
+    .map(|n| n as usize)
+}
+
+/// Writes `packet` in self-delimited framing into `output`, allowing several
+/// packets to be concatenated, see RFC 6716, appendix B.
+///
+/// On success, returns the amount of bytes written.
+///
+/// **Errors**:
+/// Returns [`InvalidPacket`] if the packet is malformed and
+/// [`BufferTooSmall`] if `output` cannot hold the self-delimited packet.
+///
+/// [`InvalidPacket`]: crate::error::ErrorCode::InvalidPacket
+/// [`BufferTooSmall`]: crate::error::ErrorCode::BufferTooSmall
+pub fn write_self_delimited(packet: Packet<'_>, mut output: MutPacket<'_>) -> Result<usize> {
+    let parsed = packet.parse()?;
+
+    // A successfully parsed packet contains at least one frame.
+    let last_frame_len = parsed.frames().last().map_or(0, |frame| frame.len());
+
+    let mut size = [0; 2];
+    let size_len = write_frame_size(last_frame_len, &mut size);
+
+    let offset = parsed.payload_offset();
+    let len = packet.0.len() + size_len;
+
+    if output.0.len() < len {
+        return Err(Error::Opus(ErrorCode::BufferTooSmall));
+    }
+
+    output.0[..offset].copy_from_slice(&packet.0[..offset]);
+    output.0[offset..offset + size_len].copy_from_slice(&size[..size_len]);
+    output.0[offset + size_len..len].copy_from_slice(&packet.0[offset..]);
+
+    Ok(len)
+}
+
+/// Splits `packet` into packets of up to `target_duration` each, e.g. to
+/// break a 60 ms packet into three 20 ms packets.
+///
+/// Frames cannot be split, hence each packet contains at least one frame,
+/// even if a frame exceeds `target_duration`.
+///
+/// **Errors**:
+/// Returns [`InvalidPacket`] if the packet is malformed.
+///
+/// [`InvalidPacket`]: crate::error::ErrorCode::InvalidPacket
+pub fn split(packet: Packet<'_>, target_duration: Duration) -> Result<Vec<Vec<u8>>> {
+    let frame_duration = packet.toc().frame_duration();
+    let frames_per_packet =
+        (target_duration.as_nanos() / frame_duration.as_nanos()).max(1) as usize;
+    let len = packet.0.len();
+
+    // The repacketizer references the frames of `packet`, which outlives it.
+    let repacketizer = Repacketizer::new();
+    repacketizer.repacketizer_cat(packet)?;
+
+    let nb_frames = repacketizer.nb_frames();
+    let mut packets = Vec::with_capacity((nb_frames + frames_per_packet - 1) / frames_per_packet);
+
+    for begin in (0..nb_frames).step_by(frames_per_packet) {
+        let end = (begin + frames_per_packet).min(nb_frames);
+
+        // A subset of the frames, a table-of-contents and frame count byte,
+        // and up to two bytes per frame to state its size.
+        let mut output = vec![0; len + 2 + 2 * (end - begin)];
+        let output_len = repacketizer.out_range(begin, end, MutPacket::try_from(&mut output)?)?;
+
+        output.truncate(output_len);
+        packets.push(output);
+    }
+
+    Ok(packets)
+}
+
 /// Gets bandwidth of an Opus `packet`.
 ///
 /// **Errors**:
 /// Empty `packet` will return `Error::EmptyPacket`.
 pub fn bandwidth(packet: Packet<'_>) -> Result<Bandwidth> {
-    unsafe { ffi::opus_packet_get_bandwidth(packet.as_ptr()) }.try_into()
+    packet.bandwidth()
 }
 
 /// Gets number of samples per frame of an Opus `packet`.
@@ -93,9 +815,7 @@ pub fn bandwidth(packet: Packet<'_>) -> Result<Bandwidth> {
 /// **Errors**:
 /// Empty `packet` will return `Error::EmptyPacket`.
 pub fn samples_per_frame(packet: Packet<'_>, sample_rate: SampleRate) -> Result<usize> {
-    unsafe {
-        Ok(ffi::opus_packet_get_samples_per_frame(packet.as_ptr(), sample_rate as i32) as usize)
-    }
+    Ok(packet.samples_per_frame(sample_rate))
 }
 
 /// Gets number of samples in an Opus `packet`.
@@ -103,14 +823,7 @@ pub fn samples_per_frame(packet: Packet<'_>, sample_rate: SampleRate) -> Result<
 /// **Errors**:
 /// Empty `packet` will return `Error::EmptyPacket`.
 pub fn nb_samples(packet: Packet<'_>, sample_rate: SampleRate) -> Result<usize> {
-    unsafe {
-        try_map_opus_error(ffi::opus_packet_get_nb_samples(
-            packet.as_ptr(),
-            packet.i32_len(),
-            sample_rate as i32,
-        ))
-        .map(|n| n as usize)
-    }
+    packet.nb_samples(sample_rate)
 }
 
 /// Gets number of channels in an Opus `packet`.
@@ -118,11 +831,7 @@ pub fn nb_samples(packet: Packet<'_>, sample_rate: SampleRate) -> Result<usize>
 /// **Errors**:
 /// Empty `packet` will return `Error::EmptyPacket`.
 pub fn nb_channels(packet: Packet<'_>) -> Result<Channels> {
-    unsafe {
-        Ok(Channels::try_from(ffi::opus_packet_get_nb_channels(
-            packet.as_ptr(),
-        ))?)
-    }
+    packet.nb_channels()
 }
 
 /// Gets number of frames in an Opus `packet`.
@@ -130,27 +839,338 @@ pub fn nb_channels(packet: Packet<'_>) -> Result<Channels> {
 /// **Errors**:
 /// Empty `packet` will return [`Error::EmptyPacket`].
 pub fn nb_frames(packet: Packet<'_>) -> Result<usize> {
-    unsafe {
-        try_map_opus_error(ffi::opus_packet_get_nb_frames(
-            packet.as_ptr(),
-            packet.i32_len(),
-        ))
-        .map(|n| n as usize)
-    }
+    packet.nb_frames()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::bandwidth;
-    use crate::{packet::Packet, Bandwidth, Error};
+    use super::{
+        bandwidth, multistream_pad, multistream_unpad, pad, recommended_packet_capacity, split,
+        unpad, write_self_delimited, FrameCountCode, Mode, Toc, MAX_PACKET_SIZE,
+    };
+    use crate::{
+        coder::{
+            multistream::{ChannelMapping, MultistreamEncoder},
+            Encoder,
+        },
+        packet::{MutPacket, Packet, PacketBuf},
+        repacketizer::RepacketizerBuilder,
+        Application, Bandwidth, Bitrate, Channels, Error, ErrorCode, FrameDuration, SampleRate,
+    };
     use matches::assert_matches;
+    use std::{borrow::Cow, convert::TryFrom, time::Duration};
+
+    #[test]
+    fn toc_inspection() {
+        let celt_stereo = Toc::from(252);
+        assert_eq!(celt_stereo.config(), 31);
+        assert_eq!(celt_stereo.mode(), Mode::Celt);
+        assert_eq!(celt_stereo.bandwidth(), Bandwidth::Fullband);
+        assert_eq!(celt_stereo.frame_duration(), Duration::from_millis(20));
+        assert!(celt_stereo.is_stereo());
+        assert_eq!(celt_stereo.frame_count_code(), FrameCountCode::One);
+
+        let silk_mono = Toc::from(0b0001_1001);
+        assert_eq!(silk_mono.config(), 3);
+        assert_eq!(silk_mono.mode(), Mode::Silk);
+        assert_eq!(silk_mono.bandwidth(), Bandwidth::Narrowband);
+        assert_eq!(silk_mono.frame_duration(), Duration::from_millis(60));
+        assert!(!silk_mono.is_stereo());
+        assert_eq!(silk_mono.frame_count_code(), FrameCountCode::TwoEqual);
+
+        let hybrid = Toc::from(0b0110_1011);
+        assert_eq!(hybrid.mode(), Mode::Hybrid);
+        assert_eq!(hybrid.bandwidth(), Bandwidth::Superwideband);
+        assert_eq!(hybrid.frame_duration(), Duration::from_millis(20));
+        assert_eq!(hybrid.frame_count_code(), FrameCountCode::Arbitrary);
+
+        let packet = [248_u8, 255, 254];
+        assert_eq!(Packet::try_from(&packet[..]).unwrap().toc(), Toc::from(248));
+    }
+
+    #[test]
+    fn packet_construction() {
+        let array = [248_u8, 255, 254];
+        let vec = array.to_vec();
+        let cow = Cow::Borrowed(&array[..]);
+
+        assert_eq!(Packet::new(&array).unwrap(), Packet::new(&vec).unwrap());
+        assert_eq!(Packet::new(&cow).unwrap().as_slice(), &array[..]);
+        assert_eq!(Packet::try_from(&array).unwrap().as_slice(), &array[..]);
+        assert_eq!(Packet::try_from(&cow).unwrap().as_slice(), &array[..]);
+        assert_matches!(Packet::new(&Vec::<u8>::new()), Err(Error::EmptyPacket));
+
+        let mut buffer = [0_u8; 4];
+        assert_matches!(MutPacket::new(&mut buffer).unwrap().i32_len(), Ok(4));
+        assert_matches!(
+            MutPacket::new(&mut Vec::<u8>::new()),
+            Err(Error::EmptyPacket)
+        );
+    }
+
+    #[test]
+    fn owned_packet() {
+        let packet = PacketBuf::try_from(vec![248_u8, 255, 254]).unwrap();
+        assert_eq!(packet.as_packet().toc(), Toc::from(248));
+        assert_eq!(Packet::from(&packet).as_slice(), &[248, 255, 254]);
+
+        let borrowed = [252_u8, 255, 254];
+        let owned = PacketBuf::from(Packet::new(&borrowed).unwrap());
+        assert_eq!(owned.as_slice(), &borrowed[..]);
+        assert_eq!(owned.into_vec(), borrowed.to_vec());
+
+        assert_matches!(PacketBuf::try_from(Vec::new()), Err(Error::EmptyPacket));
+    }
+
+    #[test]
+    fn packet_capacity() {
+        // 64 kbit/s for 20 ms are 160 bytes on average.
+        assert_eq!(
+            recommended_packet_capacity(Bitrate::BitsPerSecond(64000), FrameDuration::Ms20),
+            323
+        );
+        assert_eq!(
+            recommended_packet_capacity(Bitrate::BitsPerSecond(512000), FrameDuration::Ms120),
+            MAX_PACKET_SIZE
+        );
+        assert_eq!(
+            recommended_packet_capacity(Bitrate::Max, FrameDuration::Ms20),
+            MAX_PACKET_SIZE
+        );
+
+        const CAPACITY: usize =
+            recommended_packet_capacity(Bitrate::BitsPerSecond(32000), FrameDuration::Ms10);
+        assert_eq!([0_u8; CAPACITY].len(), 83);
+    }
+
+    #[test]
+    fn parse_frames() {
+        let packet = [252_u8, 255, 254];
+        let parsed = Packet::try_from(&packet[..]).unwrap().parse().unwrap();
+
+        assert_eq!(parsed.toc(), Toc::from(252));
+        assert_eq!(parsed.payload_offset(), 1);
+        assert_eq!(parsed.frames(), &[&[255_u8, 254][..]]);
+
+        // Two equally sized frames of two bytes each.
+        let packet = [253_u8, 1, 2, 3, 4];
+        let parsed = Packet::try_from(&packet[..]).unwrap().parse().unwrap();
+        assert_eq!(parsed.frames(), &[&[1_u8, 2][..], &[3, 4][..]]);
+
+        let invalid_packet = [253_u8, 1, 2, 3];
+        assert_matches!(
+            Packet::try_from(&invalid_packet[..]).unwrap().parse(),
+            Err(Error::Opus(ErrorCode::InvalidPacket))
+        );
+    }
+
+    #[test]
+    fn self_delimited_framing() {
+        // Two equally sized frames of two bytes each.
+        let packet = [253_u8, 1, 2, 3, 4];
+        // Two frames of one and two bytes.
+        let other_packet = [254_u8, 1, 9, 8, 7];
+        let mut buffer = vec![0_u8; 16];
+
+        let len = write_self_delimited(
+            Packet::try_from(&packet[..]).unwrap(),
+            MutPacket::try_from(&mut buffer).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(&buffer[..len], &[253, 2, 1, 2, 3, 4]);
+
+        let other_len = write_self_delimited(
+            Packet::try_from(&other_packet[..]).unwrap(),
+            MutPacket::try_from(&mut buffer[len..]).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(&buffer[len..len + other_len], &[254, 1, 2, 9, 8, 7]);
+
+        let (parsed, parsed_len) = Packet::try_from(&buffer[..])
+            .unwrap()
+            .parse_self_delimited()
+            .unwrap();
+        assert_eq!(parsed_len, len);
+        assert_eq!(parsed.toc(), Toc::from(253));
+        assert_eq!(parsed.payload_offset(), 2);
+        assert_eq!(parsed.frames(), &[&[1_u8, 2][..], &[3, 4][..]]);
+
+        let (parsed, parsed_len) = Packet::try_from(&buffer[len..])
+            .unwrap()
+            .parse_self_delimited()
+            .unwrap();
+        assert_eq!(parsed_len, other_len);
+        assert_eq!(parsed.frames(), &[&[9_u8][..], &[8, 7][..]]);
+
+        let mut small_buffer = vec![0_u8; 5];
+        assert_matches!(
+            write_self_delimited(
+                Packet::try_from(&packet[..]).unwrap(),
+                MutPacket::try_from(&mut small_buffer).unwrap(),
+            ),
+            Err(Error::Opus(ErrorCode::BufferTooSmall))
+        );
+
+        let truncated_packet = [253_u8, 2, 1, 2, 3];
+        assert_matches!(
+            Packet::try_from(&truncated_packet[..])
+                .unwrap()
+                .parse_self_delimited(),
+            Err(Error::Opus(ErrorCode::InvalidPacket))
+        );
+    }
+
+    #[test]
+    fn packet_queries() {
+        let packet = [252_u8, 255, 254];
+        let packet = Packet::try_from(&packet[..]).unwrap();
+
+        assert_matches!(packet.bandwidth(), Ok(Bandwidth::Fullband));
+        assert_matches!(packet.nb_frames(), Ok(1));
+        assert_matches!(packet.nb_channels(), Ok(Channels::Stereo));
+        assert_eq!(packet.samples_per_frame(SampleRate::Hz48000), 960);
+        assert_matches!(packet.nb_samples(SampleRate::Hz48000), Ok(960));
+        assert_matches!(packet.nb_samples(SampleRate::Hz8000), Ok(160));
+        assert_eq!(
+            packet.duration(SampleRate::Hz48000).unwrap(),
+            Duration::from_millis(20)
+        );
+        assert_eq!(
+            packet.duration(SampleRate::Hz8000).unwrap(),
+            Duration::from_millis(20)
+        );
+
+        // Two frames of 2.5 ms each.
+        let packet = [225_u8, 1, 2];
+        let packet = Packet::try_from(&packet[..]).unwrap();
+        assert_eq!(
+            packet.duration(SampleRate::Hz48000).unwrap(),
+            Duration::from_micros(5_000)
+        );
+
+        let code_three_packet = [255_u8];
+        let code_three_packet = Packet::try_from(&code_three_packet[..]).unwrap();
+        assert_matches!(
+            code_three_packet.nb_frames(),
+            Err(Error::Opus(ErrorCode::InvalidPacket))
+        );
+    }
+
+    #[test]
+    fn split_packet() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+
+        // 48000Hz * 2 channels * 20 ms / 1000
+        const STEREO_20MS: usize = 48000 * 2 * 20 / 1000;
+        let input = [0_i16; STEREO_20MS];
+        let mut buffer = [0_u8; 256];
+
+        let len = encoder.encode(&input, &mut buffer).unwrap();
+        let mut sink = RepacketizerBuilder::new().build();
+
+        for _ in 0..3 {
+            sink.push(Packet::try_from(&buffer[..len]).unwrap())
+                .unwrap();
+        }
+
+        let long_packet = sink.flush().unwrap().unwrap();
+
+        let packets = split(
+            Packet::try_from(&long_packet).unwrap(),
+            Duration::from_millis(20),
+        )
+        .unwrap();
+        assert_eq!(packets.len(), 3);
+
+        for packet in &packets {
+            let packet = Packet::try_from(packet).unwrap();
+            assert_eq!(
+                packet.duration(SampleRate::Hz48000).unwrap(),
+                Duration::from_millis(20)
+            );
+        }
+
+        let packets = split(
+            Packet::try_from(&long_packet).unwrap(),
+            Duration::from_millis(40),
+        )
+        .unwrap();
+        assert_eq!(packets.len(), 2);
+        assert_matches!(Packet::try_from(&packets[0]).unwrap().nb_frames(), Ok(2));
+        assert_matches!(Packet::try_from(&packets[1]).unwrap().nb_frames(), Ok(1));
+
+        // Frames cannot be split any further.
+        let packets = split(
+            Packet::try_from(&long_packet).unwrap(),
+            Duration::from_millis(10),
+        )
+        .unwrap();
+        assert_eq!(packets.len(), 3);
+    }
+
+    #[test]
+    fn pad_and_unpad() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+
+        // 48000Hz * 2 channels * 20 ms / 1000
+        const STEREO_20MS: usize = 48000 * 2 * 20 / 1000;
+        let input = [0_i16; STEREO_20MS];
+        let mut buffer = vec![0_u8; 64];
+
+        let len = encoder.encode(&input, &mut buffer).unwrap();
+
+        assert_matches!(
+            pad(MutPacket::try_from(&mut buffer).unwrap(), len, len - 1),
+            Err(Error::InvalidPadLen(_))
+        );
+        assert_matches!(
+            pad(MutPacket::try_from(&mut buffer).unwrap(), len, 65),
+            Err(Error::InvalidPadLen(65))
+        );
+
+        let padded_len = pad(MutPacket::try_from(&mut buffer).unwrap(), len, 64).unwrap();
+        assert_eq!(padded_len, 64);
+
+        let unpadded_len = unpad(MutPacket::try_from(&mut buffer).unwrap()).unwrap();
+        assert_eq!(unpadded_len, len);
+    }
+
+    #[test]
+    fn multistream_pad_and_unpad() {
+        let mut encoder = MultistreamEncoder::new(
+            SampleRate::Hz48000,
+            &ChannelMapping::quad(),
+            Application::Audio,
+        )
+        .unwrap();
+
+        // 48000Hz * 4 channels * 20 ms / 1000
+        const FOUR_CHANNELS_20MS: usize = 48000 * 4 * 20 / 1000;
+        let input = [0_i16; FOUR_CHANNELS_20MS];
+        let mut buffer = vec![0_u8; 256];
+
+        let len = encoder.encode(&input, &mut buffer).unwrap();
+
+        assert_matches!(
+            multistream_pad(MutPacket::try_from(&mut buffer).unwrap(), len, 257, 2),
+            Err(Error::InvalidPadLen(257))
+        );
+
+        let padded_len =
+            multistream_pad(MutPacket::try_from(&mut buffer).unwrap(), len, 200, 2).unwrap();
+        assert_eq!(padded_len, 200);
+
+        let unpadded_len =
+            multistream_unpad(MutPacket::try_from(&mut buffer[..padded_len]).unwrap(), 2).unwrap();
+        assert!(unpadded_len < padded_len);
+    }
 
     #[test]
     /// We verify the `TryFrom`-impl for `Packet` by creating and then
     /// converting from `Vec`s that meet and violate the contract.
     fn packet_bandwidth() {
-        use std::convert::TryFrom;
-
         let empty_packet = vec![];
         let empty_packet_bandwidth = Packet::try_from(&empty_packet);
         assert_matches!(empty_packet_bandwidth, Err(Error::EmptyPacket));
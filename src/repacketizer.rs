@@ -1,15 +1,17 @@
 use crate::{
     error::try_map_opus_error,
     ffi,
-    packet::{MutPacket, Packet},
-    Result,
+    packet::{MutPacket, Packet, MAX_PACKET_DURATION},
+    Error, ErrorCode, Result, SampleRate,
 };
+use std::time::Duration;
 
 /// Returns Opus' internal `OpusRepacketizer`'s size in bytes.
 pub fn repacketizer_size() -> usize {
     unsafe { ffi::opus_repacketizer_get_size() as usize }
 }
 
+#[deprecated(note = "use `packet::multistream_pad` instead")]
 pub fn multistream_packet_pad(
     mut data: MutPacket<'_>,
     new_len: usize,
@@ -26,6 +28,7 @@ pub fn multistream_packet_pad(
     .map(|_| ())
 }
 
+#[deprecated(note = "use `packet::multistream_unpad` instead")]
 pub fn multistream_packet_unpad(mut data: MutPacket<'_>, nb_streams: usize) -> Result<()> {
     try_map_opus_error(unsafe {
         ffi::opus_multistream_packet_unpad(data.as_mut_ptr(), data.i32_len()?, nb_streams as i32)
@@ -33,11 +36,13 @@ pub fn multistream_packet_unpad(mut data: MutPacket<'_>, nb_streams: usize) -> R
     .map(|_| ())
 }
 
+#[deprecated(note = "use `packet::pad` instead")]
 pub fn packet_pad(mut data: MutPacket<'_>, new_len: i32) -> Result<()> {
     try_map_opus_error(unsafe { ffi::opus_packet_pad(data.as_mut_ptr(), data.i32_len()?, new_len) })
         .map(|_| ())
 }
 
+#[deprecated(note = "use `packet::unpad` instead")]
 pub fn packet_unpad(mut data: MutPacket<'_>) -> Result<()> {
     try_map_opus_error(unsafe { ffi::opus_packet_unpad(data.as_mut_ptr(), data.i32_len()?) })
         .map(|_| ())
@@ -69,6 +74,13 @@ impl Repacketizer {
         Self { pointer }
     }
 
+    /// Resets the repacketizer, discarding all added packets.
+    pub fn reset(&mut self) {
+        unsafe { ffi::opus_repacketizer_init(self.pointer) };
+    }
+
+    /// Gets the total number of frames contained in the packets added since
+    /// the last reset.
     pub fn nb_frames(&self) -> usize {
         unsafe { ffi::opus_repacketizer_get_nb_frames(self.pointer) as usize }
     }
@@ -99,6 +111,36 @@ impl Repacketizer {
         .map(|_| ())
     }
 
+    /// Writes the added frames from `begin` up to, but excluding, `end` as a
+    /// single packet into `output`.
+    ///
+    /// On success, returns the length of the written packet.
+    ///
+    /// **Errors**:
+    /// Returns [`BadArgument`] if the range is empty or exceeds
+    /// [`nb_frames`] and [`BufferTooSmall`] if `output` cannot hold the
+    /// packet.
+    ///
+    /// [`BadArgument`]: crate::error::ErrorCode::BadArgument
+    /// [`BufferTooSmall`]: crate::error::ErrorCode::BufferTooSmall
+    /// [`nb_frames`]: Repacketizer::nb_frames
+    pub fn out_range(&self, begin: usize, end: usize, mut output: MutPacket<'_>) -> Result<usize> {
+        if begin >= end || end > self.nb_frames() {
+            return Err(Error::Opus(ErrorCode::BadArgument));
+        }
+
+        try_map_opus_error(unsafe {
+            ffi::opus_repacketizer_out_range(
+                self.pointer,
+                begin as i32,
+                end as i32,
+                output.as_mut_ptr(),
+                output.i32_len()?,
+            )
+        })
+        .map(|n| n as usize)
+    }
+
     pub fn repacketizer_cat(&self, data: Packet<'_>) -> Result<()> {
         try_map_opus_error(unsafe {
             ffi::opus_repacketizer_cat(self.pointer, data.as_ptr(), data.i32_len())
@@ -106,3 +148,257 @@ impl Repacketizer {
         .map(|_| ())
     }
 }
+
+/// Builds a [`RepacketizerSink`].
+///
+/// [`RepacketizerSink`]: crate::repacketizer::RepacketizerSink
+#[derive(Clone, Copy, Debug)]
+pub struct RepacketizerBuilder {
+    max_duration: Duration,
+}
+
+impl Default for RepacketizerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RepacketizerBuilder {
+    /// Creates a builder emitting packets of up to 120 ms, the maximum
+    /// duration of an Opus packet.
+    pub fn new() -> Self {
+        Self {
+            max_duration: MAX_PACKET_DURATION,
+        }
+    }
+
+    /// Sets the maximum duration of an emitted packet.
+    ///
+    /// Values exceeding 120 ms are clamped to 120 ms.
+    pub fn max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = max_duration.min(MAX_PACKET_DURATION);
+
+        self
+    }
+
+    /// Builds the sink.
+    pub fn build(self) -> RepacketizerSink {
+        RepacketizerSink {
+            repacketizer: Repacketizer::new(),
+            max_duration: self.max_duration,
+            packets: Vec::new(),
+            duration: Duration::default(),
+            len: 0,
+        }
+    }
+}
+
+/// Merges consecutive packets into larger ones, emitting the merged packet
+/// whenever the next packet would exceed the maximum duration or uses a
+/// different configuration.
+///
+/// Created via [`RepacketizerBuilder`].
+///
+/// [`RepacketizerBuilder`]: crate::repacketizer::RepacketizerBuilder
+#[derive(Debug)]
+pub struct RepacketizerSink {
+    repacketizer: Repacketizer,
+    max_duration: Duration,
+    /// Opus references the frames of added packets until the output is
+    /// written, each packet's heap buffer stays in place until then.
+    packets: Vec<Vec<u8>>,
+    duration: Duration,
+    len: usize,
+}
+
+impl RepacketizerSink {
+    /// Adds `packet` to the accumulated packets.
+    ///
+    /// If `packet` would exceed the maximum duration or its configuration
+    /// differs from the accumulated packets, the accumulated packets are
+    /// emitted first and the sink is reset.
+    /// A single packet exceeding the maximum duration is emitted on its own.
+    ///
+    /// **Errors**:
+    /// Returns [`InvalidPacket`] if the packet is malformed.
+    ///
+    /// [`InvalidPacket`]: crate::error::ErrorCode::InvalidPacket
+    pub fn push(&mut self, packet: Packet<'_>) -> Result<Option<Vec<u8>>> {
+        let duration = packet.duration(SampleRate::Hz48000)?;
+
+        // The last two bits of the table-of-contents state the frame count,
+        // all others must match to merge packets.
+        let config_changed = self.packets.first().map_or(false, |first| {
+            first[0] & 0xFC != u8::from(packet.toc()) & 0xFC
+        });
+
+        let emitted = if config_changed || self.duration + duration > self.max_duration {
+            self.flush()?
+        } else {
+            None
+        };
+
+        let data = packet.as_slice().to_vec();
+
+        try_map_opus_error(unsafe {
+            ffi::opus_repacketizer_cat(self.repacketizer.pointer, data.as_ptr(), data.len() as i32)
+        })?;
+
+        self.duration += duration;
+        self.len += data.len();
+        self.packets.push(data);
+
+        Ok(emitted)
+    }
+
+    /// Gets the duration of the accumulated packets.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Emits the accumulated packets merged into one packet and resets the
+    /// sink.
+    ///
+    /// Returns `None` if no packets have been accumulated.
+    pub fn flush(&mut self) -> Result<Option<Vec<u8>>> {
+        if self.packets.is_empty() {
+            return Ok(None);
+        }
+
+        // The frame data, a table-of-contents and frame count byte, and up to
+        // two bytes per frame to state its size.
+        let nb_frames = self.repacketizer.nb_frames();
+        let mut output = vec![0; self.len + 2 + 2 * nb_frames];
+
+        let len = try_map_opus_error(unsafe {
+            ffi::opus_repacketizer_out(
+                self.repacketizer.pointer,
+                output.as_mut_ptr(),
+                output.len() as i32,
+            )
+        })?;
+
+        output.truncate(len as usize);
+        self.reset();
+
+        Ok(Some(output))
+    }
+
+    /// Discards the accumulated packets.
+    pub fn reset(&mut self) {
+        self.repacketizer.reset();
+        self.packets.clear();
+        self.duration = Duration::default();
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Repacketizer, RepacketizerBuilder};
+    use crate::{
+        coder::Encoder,
+        packet::{MutPacket, Packet},
+        Application, Channels, Error, ErrorCode, SampleRate,
+    };
+    use matches::assert_matches;
+    use std::{convert::TryFrom, time::Duration};
+
+    #[test]
+    fn out_range() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+
+        // 48000Hz * 2 channels * 20 ms / 1000
+        const STEREO_20MS: usize = 48000 * 2 * 20 / 1000;
+        let input = [0_i16; STEREO_20MS];
+        let mut buffer = [0_u8; 256];
+
+        let len = encoder.encode(&input, &mut buffer).unwrap();
+        let packet = &buffer[..len];
+
+        let repacketizer = Repacketizer::new();
+        repacketizer
+            .repacketizer_cat(Packet::try_from(packet).unwrap())
+            .unwrap();
+        repacketizer
+            .repacketizer_cat(Packet::try_from(packet).unwrap())
+            .unwrap();
+        assert_eq!(repacketizer.nb_frames(), 2);
+
+        let mut output = vec![0_u8; 512];
+        let output_len = repacketizer
+            .out_range(1, 2, MutPacket::try_from(&mut output).unwrap())
+            .unwrap();
+        let output_packet = Packet::try_from(&output[..output_len]).unwrap();
+        assert_eq!(output_packet.nb_frames().unwrap(), 1);
+
+        assert_matches!(
+            repacketizer.out_range(1, 3, MutPacket::try_from(&mut output).unwrap()),
+            Err(Error::Opus(ErrorCode::BadArgument))
+        );
+        assert_matches!(
+            repacketizer.out_range(1, 1, MutPacket::try_from(&mut output).unwrap()),
+            Err(Error::Opus(ErrorCode::BadArgument))
+        );
+    }
+
+    #[test]
+    fn sink_emits_at_max_duration() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+
+        // 48000Hz * 2 channels * 20 ms / 1000
+        const STEREO_20MS: usize = 48000 * 2 * 20 / 1000;
+        let input = [0_i16; STEREO_20MS];
+        let mut buffer = [0_u8; 256];
+
+        let len = encoder.encode(&input, &mut buffer).unwrap();
+        let packet = &buffer[..len];
+
+        let mut sink = RepacketizerBuilder::new().build();
+
+        for _ in 0..6 {
+            assert!(sink
+                .push(Packet::try_from(packet).unwrap())
+                .unwrap()
+                .is_none());
+        }
+
+        assert_eq!(sink.duration(), Duration::from_millis(120));
+
+        let merged = sink
+            .push(Packet::try_from(packet).unwrap())
+            .unwrap()
+            .unwrap();
+        let merged = Packet::try_from(&merged[..]).unwrap();
+        assert_eq!(merged.nb_frames().unwrap(), 6);
+        assert_eq!(
+            merged.duration(SampleRate::Hz48000).unwrap(),
+            Duration::from_millis(120)
+        );
+
+        assert_eq!(sink.duration(), Duration::from_millis(20));
+
+        let rest = sink.flush().unwrap().unwrap();
+        assert_eq!(Packet::try_from(&rest[..]).unwrap().nb_frames().unwrap(), 1);
+        assert!(sink.flush().unwrap().is_none());
+
+        let mut sink = RepacketizerBuilder::new()
+            .max_duration(Duration::from_millis(40))
+            .build();
+
+        assert!(sink
+            .push(Packet::try_from(packet).unwrap())
+            .unwrap()
+            .is_none());
+        assert!(sink
+            .push(Packet::try_from(packet).unwrap())
+            .unwrap()
+            .is_none());
+        assert!(sink
+            .push(Packet::try_from(packet).unwrap())
+            .unwrap()
+            .is_some());
+    }
+}
@@ -0,0 +1,351 @@
+//! Resampling between arbitrary sample rates and those Opus supports, e.g.
+//! to encode 44.1 kHz audio, via rubato.
+//!
+//! [`ResamplingEncoder`] resamples in front of an encoder and
+//! [`ResamplingDecoder`] after a decoder, both built on [`Resampler`].
+//!
+//! [`ResamplingEncoder`]: crate::resample::ResamplingEncoder
+//! [`ResamplingDecoder`]: crate::resample::ResamplingDecoder
+//! [`Resampler`]: crate::resample::Resampler
+
+use crate::{
+    coder::{DecodeRequest, Decoder, Encoder, GenericCtl, StreamEncoder},
+    packet::PacketBuf,
+    Error, FrameDuration, Result,
+};
+use rubato::{FftFixedIn, Resampler as _};
+use std::fmt;
+
+/// The frames per channel the resampler processes at once.
+const CHUNK_FRAMES: usize = 1024;
+
+/// `Resampler` converts interleaved `f32` PCM of any length from one
+/// sample rate to another, buffering the input until a chunk is complete.
+///
+/// The output is aligned with the input, the resampler's delay is
+/// discarded at the start and flushed at the end.
+pub struct Resampler {
+    inner: FftFixedIn<f32>,
+    channels: usize,
+    from: u64,
+    to: u64,
+    /// The input pending per channel.
+    input: Vec<Vec<f32>>,
+    /// The frames of delay left to discard.
+    skip: usize,
+    /// The frames per channel passed in and out.
+    consumed: u64,
+    produced: u64,
+}
+
+impl fmt::Debug for Resampler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Resampler")
+            .field("channels", &self.channels)
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .field("skip", &self.skip)
+            .field("consumed", &self.consumed)
+            .field("produced", &self.produced)
+            .finish()
+    }
+}
+
+impl Resampler {
+    /// Creates a new `Resampler` converting `channels` from the sample rate
+    /// `from` to `to`, in Hz.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::ResamplingFailed`] if a rate or the channels are
+    /// zero.
+    ///
+    /// [`Error::ResamplingFailed`]: crate::error::Error::ResamplingFailed
+    pub fn new(from: u32, to: u32, channels: usize) -> Result<Self> {
+        if from == 0 || to == 0 || channels == 0 {
+            return Err(Error::ResamplingFailed);
+        }
+
+        let inner = FftFixedIn::new(from as usize, to as usize, CHUNK_FRAMES, 2, channels)
+            .map_err(|_| Error::ResamplingFailed)?;
+
+        Ok(Self {
+            skip: inner.output_delay(),
+            inner,
+            channels,
+            from: u64::from(from),
+            to: u64::from(to),
+            input: vec![Vec::with_capacity(CHUNK_FRAMES); channels],
+            consumed: 0,
+            produced: 0,
+        })
+    }
+
+    /// Resamples `pcm`, interleaved if multiple channels, returning the
+    /// PCM of every chunk completed.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::ResamplingFailed`] if the resampler failed.
+    ///
+    /// [`Error::ResamplingFailed`]: crate::error::Error::ResamplingFailed
+    pub fn process(&mut self, pcm: &[f32]) -> Result<Vec<f32>> {
+        for frame in pcm.chunks_exact(self.channels) {
+            for (plane, &sample) in self.input.iter_mut().zip(frame) {
+                plane.push(sample);
+            }
+        }
+
+        let mut output = Vec::new();
+
+        while self.input[0].len() >= self.inner.input_frames_next() {
+            let len = self.inner.input_frames_next();
+            let chunk: Vec<&[f32]> = self.input.iter().map(|plane| &plane[..len]).collect();
+            let planes = self
+                .inner
+                .process(&chunk, None)
+                .map_err(|_| Error::ResamplingFailed)?;
+
+            for plane in &mut self.input {
+                plane.drain(..len);
+            }
+
+            self.consumed += len as u64;
+            self.interleave(&planes, &mut output);
+        }
+
+        Ok(output)
+    }
+
+    /// Resamples the input pending and the resampler's delay, e.g. at the
+    /// end of a stream, returning the rest of the output.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::ResamplingFailed`] if the resampler failed.
+    ///
+    /// [`Error::ResamplingFailed`]: crate::error::Error::ResamplingFailed
+    pub fn flush(&mut self) -> Result<Vec<f32>> {
+        let mut output = Vec::new();
+
+        if !self.input[0].is_empty() {
+            let planes = self
+                .inner
+                .process_partial(Some(self.input.as_slice()), None)
+                .map_err(|_| Error::ResamplingFailed)?;
+
+            self.consumed += self.input[0].len() as u64;
+            self.input.iter_mut().for_each(Vec::clear);
+            self.interleave(&planes, &mut output);
+        }
+
+        let expected = (self.consumed * self.to + self.from - 1) / self.from;
+
+        while self.produced < expected {
+            let planes = self
+                .inner
+                .process_partial(None::<&[Vec<f32>]>, None)
+                .map_err(|_| Error::ResamplingFailed)?;
+
+            self.interleave(&planes, &mut output);
+        }
+
+        let excess = (self.produced - expected) as usize * self.channels;
+        output.truncate(output.len().saturating_sub(excess));
+
+        self.inner.reset();
+        self.skip = self.inner.output_delay();
+        self.consumed = 0;
+        self.produced = 0;
+
+        Ok(output)
+    }
+
+    /// Appends `planes` interleaved to `output`, discarding the delay.
+    fn interleave(&mut self, planes: &[Vec<f32>], output: &mut Vec<f32>) {
+        let frames = planes.first().map_or(0, Vec::len);
+        let skip = self.skip.min(frames);
+        self.skip -= skip;
+
+        for index in skip..frames {
+            output.extend(planes.iter().map(|plane| plane[index]));
+        }
+
+        self.produced += (frames - skip) as u64;
+    }
+}
+
+/// `ResamplingEncoder` encodes PCM of any sample rate and length, resampling
+/// it to the encoder's rate and encoding complete frames.
+#[derive(Debug)]
+pub struct ResamplingEncoder {
+    resampler: Resampler,
+    encoder: StreamEncoder<f32>,
+}
+
+impl ResamplingEncoder {
+    /// Creates a new `ResamplingEncoder` encoding PCM at `input_rate` in Hz
+    /// via `encoder` in frames of `frame_duration`.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::ResamplingFailed`] if `input_rate` is zero and
+    /// [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`Error::ResamplingFailed`]: crate::error::Error::ResamplingFailed
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn new(encoder: Encoder, input_rate: u32, frame_duration: FrameDuration) -> Result<Self> {
+        let resampler = Resampler::new(
+            input_rate,
+            encoder.sample_rate()? as u32,
+            encoder.channels() as usize,
+        )?;
+
+        Ok(Self {
+            resampler,
+            encoder: StreamEncoder::new(encoder, frame_duration)?,
+        })
+    }
+
+    /// Gets the encoder.
+    pub fn encoder(&self) -> &Encoder {
+        self.encoder.encoder()
+    }
+
+    /// Gets the encoder mutably, e.g. to change the bitrate mid-stream.
+    pub fn encoder_mut(&mut self) -> &mut Encoder {
+        self.encoder.encoder_mut()
+    }
+
+    /// Resamples and appends `pcm`, interleaved if 2 channels, and encodes
+    /// every frame completed as [`StreamEncoder::push`] does.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::ResamplingFailed`] if the resampler failed and
+    /// [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`StreamEncoder::push`]: crate::coder::StreamEncoder::push
+    /// [`Error::ResamplingFailed`]: crate::error::Error::ResamplingFailed
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn push(&mut self, pcm: &[f32], packets: &mut Vec<PacketBuf>) -> Result<usize> {
+        let pcm = self.resampler.process(pcm)?;
+
+        self.encoder.push(&pcm, packets)
+    }
+
+    /// Encodes all PCM pending, the last frame padded with silence, e.g.
+    /// at the end of a stream, appending the packets to `packets`.
+    ///
+    /// On success, returns the amount of packets appended.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::ResamplingFailed`] if the resampler failed and
+    /// [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`Error::ResamplingFailed`]: crate::error::Error::ResamplingFailed
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn flush(&mut self, packets: &mut Vec<PacketBuf>) -> Result<usize> {
+        let pcm = self.resampler.flush()?;
+        let len = packets.len();
+        self.encoder.push(&pcm, packets)?;
+        packets.extend(self.encoder.flush()?);
+
+        Ok(packets.len() - len)
+    }
+}
+
+/// `ResamplingDecoder` decodes packets and resamples the PCM to any sample
+/// rate.
+#[derive(Debug)]
+pub struct ResamplingDecoder {
+    decoder: Decoder,
+    resampler: Resampler,
+}
+
+impl ResamplingDecoder {
+    /// Creates a new `ResamplingDecoder` resampling the output of `decoder`
+    /// to `output_rate` in Hz.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::ResamplingFailed`] if `output_rate` is zero and
+    /// [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`Error::ResamplingFailed`]: crate::error::Error::ResamplingFailed
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn new(decoder: Decoder, output_rate: u32) -> Result<Self> {
+        let resampler = Resampler::new(
+            decoder.sample_rate()? as u32,
+            output_rate,
+            decoder.channels() as usize,
+        )?;
+
+        Ok(Self { decoder, resampler })
+    }
+
+    /// Gets the decoder.
+    pub fn decoder(&self) -> &Decoder {
+        &self.decoder
+    }
+
+    /// Gets the decoder mutably.
+    pub fn decoder_mut(&mut self) -> &mut Decoder {
+        &mut self.decoder
+    }
+
+    /// Decodes `request` as [`Decoder::decode_to_vec`] does, returning the
+    /// resampled PCM of every chunk completed.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::ResamplingFailed`] if the resampler failed and
+    /// [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [`Decoder::decode_to_vec`]: crate::coder::Decoder::decode_to_vec
+    /// [`Error::ResamplingFailed`]: crate::error::Error::ResamplingFailed
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn decode(&mut self, request: DecodeRequest<'_>) -> Result<Vec<f32>> {
+        let pcm = self.decoder.decode_to_vec::<f32>(request)?;
+
+        self.resampler.process(&pcm)
+    }
+
+    /// Returns the rest of the resampled PCM, e.g. at the end of a stream.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::ResamplingFailed`] if the resampler failed.
+    ///
+    /// [`Error::ResamplingFailed`]: crate::error::Error::ResamplingFailed
+    pub fn flush(&mut self) -> Result<Vec<f32>> {
+        self.resampler.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ResamplingDecoder, ResamplingEncoder};
+    use crate::{
+        coder::{DecodeRequest, Decoder, Encoder},
+        Application, Channels, FrameDuration, SampleRate,
+    };
+
+    #[test]
+    fn resample_44100() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        let mut encoder = ResamplingEncoder::new(encoder, 44100, FrameDuration::Ms20).unwrap();
+
+        // One second in odd-sized chunks resamples to exactly 50 frames.
+        let mut packets = Vec::new();
+        for _ in 0..100 {
+            encoder.push(&[0.0; 882], &mut packets).unwrap();
+        }
+        encoder.flush(&mut packets).unwrap();
+        assert_eq!(packets.len(), 50);
+
+        let decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+        let mut decoder = ResamplingDecoder::new(decoder, 44100).unwrap();
+
+        let mut samples = 0;
+        for packet in &packets {
+            let request = DecodeRequest::Normal(packet.as_packet());
+            samples += decoder.decode(request).unwrap().len();
+        }
+        samples += decoder.flush().unwrap().len();
+        assert_eq!(samples, 44100 * 2);
+    }
+}
@@ -0,0 +1,183 @@
+//! Playback of Opus via rodio, see [`OpusSource`].
+//!
+//! [`OpusSource`]: crate::rodio::OpusSource
+
+#[cfg(feature = "ogg")]
+use crate::ogg::OggOpusReader;
+use crate::{
+    coder::{DecodeRequest, Decoder, GenericCtl},
+    packet::PacketBuf,
+    Result,
+};
+use rodio::Source;
+#[cfg(feature = "ogg")]
+use std::io::{Read, Result as IoResult};
+use std::{fmt, time::Duration};
+
+/// The audio `OpusSource` decodes.
+enum Input {
+    #[cfg(feature = "ogg")]
+    Ogg(OggOpusReader<Box<dyn Read + Send>>),
+    Packets {
+        decoder: Decoder,
+        packets: Box<dyn Iterator<Item = PacketBuf> + Send>,
+    },
+}
+
+/// `OpusSource` implements rodio's `Source`, decoding an Ogg Opus stream
+/// or a stream of packets to `f32` samples on the fly, e.g. to play it on
+/// a `Sink`:
+///
+/// ```ignore
+/// let source = OpusSource::from_ogg(File::open("music.opus")?)?;
+/// sink.append(source);
+/// ```
+///
+/// The source ends with the stream or on the first packet failing to
+/// decode.
+pub struct OpusSource {
+    input: Input,
+    channels: u16,
+    sample_rate: u32,
+    frame: Vec<f32>,
+    index: usize,
+}
+
+impl fmt::Debug for OpusSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpusSource")
+            .field("channels", &self.channels)
+            .field("sample_rate", &self.sample_rate)
+            .field("frame", &self.frame)
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+impl OpusSource {
+    /// Creates a new `OpusSource` playing the Ogg Opus stream `reader`
+    /// yields, such as an `.opus` file, at 48 kHz.
+    ///
+    /// Only the first stream of chained streams is played.
+    ///
+    /// **Errors**:
+    /// Returns the errors of [`OggOpusReader::new`].
+    ///
+    /// [`OggOpusReader::new`]: crate::ogg::OggOpusReader::new
+    #[cfg(feature = "ogg")]
+    pub fn from_ogg<R: Read + Send + 'static>(reader: R) -> IoResult<Self> {
+        let reader = OggOpusReader::new(Box::new(reader) as Box<dyn Read + Send>)?;
+
+        Ok(Self {
+            channels: u16::from(reader.head().channels),
+            sample_rate: 48000,
+            input: Input::Ogg(reader),
+            frame: Vec::new(),
+            index: 0,
+        })
+    }
+
+    /// Creates a new `OpusSource` playing `packets` decoded by `decoder`,
+    /// e.g. packets received live, at the decoder's sample rate.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn from_packets<I>(decoder: Decoder, packets: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = PacketBuf>,
+        I::IntoIter: Send + 'static,
+    {
+        Ok(Self {
+            channels: decoder.channels() as u16,
+            sample_rate: decoder.sample_rate()? as u32,
+            input: Input::Packets {
+                decoder,
+                packets: Box::new(packets.into_iter()),
+            },
+            frame: Vec::new(),
+            index: 0,
+        })
+    }
+
+    /// Decodes the next frame, `None` at the end of the stream or on
+    /// failure.
+    fn next_frame(&mut self) -> Option<Vec<f32>> {
+        match &mut self.input {
+            #[cfg(feature = "ogg")]
+            Input::Ogg(reader) => reader.read_pcm().ok().flatten(),
+            Input::Packets { decoder, packets } => {
+                let packet = packets.next()?;
+
+                decoder
+                    .decode_to_vec(DecodeRequest::Normal(packet.as_packet()))
+                    .ok()
+            }
+        }
+    }
+}
+
+impl Iterator for OpusSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        while self.index == self.frame.len() {
+            self.frame = self.next_frame()?;
+            self.index = 0;
+        }
+
+        let sample = self.frame[self.index];
+        self.index += 1;
+
+        Some(sample)
+    }
+}
+
+impl Source for OpusSource {
+    // The channels and the sample rate never change.
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OpusSource;
+    use crate::{
+        coder::{Decoder, Encoder},
+        packet::PacketBuf,
+        Application, Channels, SampleRate, TryFrom,
+    };
+    use rodio::Source;
+
+    #[test]
+    fn play_packets() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        let packets: Vec<PacketBuf> = (0..5)
+            .map(|_| {
+                let data = encoder.encode_to_vec(&[0_i16; 1920]).unwrap();
+                PacketBuf::try_from(data).unwrap()
+            })
+            .collect();
+
+        let decoder = Decoder::new(SampleRate::Hz48000, Channels::Stereo).unwrap();
+        let source = OpusSource::from_packets(decoder, packets).unwrap();
+        assert_eq!(source.channels(), 2);
+        assert_eq!(source.sample_rate(), 48000);
+        assert_eq!(source.count(), 5 * 1920);
+    }
+}
@@ -0,0 +1,399 @@
+//! RTP payloading of Opus packets as specified by RFC 7587, see
+//! [`RtpPacketizer`] and [`RtpDepacketizer`].
+//!
+//! Each RTP packet carries exactly one Opus packet. Timestamps count
+//! samples at 48 kHz, whatever sample rate the stream was coded at.
+//!
+//! For redundancy as WebRTC uses it, RED payloads of RFC 2198 carry prior
+//! packets along the current one, see [`RedEncoder`] and [`RedDecoder`].
+//!
+//! The Opus parameters negotiated via SDP map to coder settings via
+//! [`Fmtp`].
+//!
+//! Conferencing servers learn how loud each participant is from the audio
+//! level header extension of RFC 6464, see [`AudioLevel`].
+//!
+//! [`RtpPacketizer`]: crate::rtp::RtpPacketizer
+//! [`RtpDepacketizer`]: crate::rtp::RtpDepacketizer
+//! [`RedEncoder`]: crate::rtp::RedEncoder
+//! [`RedDecoder`]: crate::rtp::RedDecoder
+//! [`Fmtp`]: crate::rtp::Fmtp
+//! [`AudioLevel`]: crate::rtp::AudioLevel
+
+pub use self::{
+    fmtp::Fmtp,
+    level::AudioLevel,
+    red::{RedBlock, RedDecoder, RedEncoder},
+};
+
+use crate::{packet::Packet, Error, ErrorCode, Result, SampleRate, TryFrom};
+use std::time::Duration;
+
+mod fmtp;
+mod level;
+mod red;
+
+/// The RTP version RFC 3550 specifies.
+const VERSION: u8 = 2;
+
+/// The longest duration of an Opus packet, and thus an RTP packet.
+const MAX_DURATION: Duration = Duration::from_millis(120);
+
+/// The fixed part of an RTP header.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct RtpHeader {
+    /// Set on the first packet of a talkspurt, i.e. after discontinuous
+    /// transmission.
+    pub marker: bool,
+    pub payload_type: u8,
+    pub sequence: u16,
+    /// The timestamp of the packet's first sample at 48 kHz.
+    pub timestamp: u32,
+    pub ssrc: u32,
+}
+
+impl RtpHeader {
+    /// The length of the header in bytes, without CSRCs and extensions.
+    pub const LEN: usize = 12;
+
+    /// Writes the header into the first [`LEN`] bytes of `output`.
+    ///
+    /// **Errors**:
+    /// Returns [Error::InvalidRtpPacket] if `output` is shorter than
+    /// [`LEN`].
+    ///
+    /// [`LEN`]: crate::rtp::RtpHeader::LEN
+    /// [Error::InvalidRtpPacket]: crate::error::Error::InvalidRtpPacket
+    pub fn write(&self, output: &mut [u8]) -> Result<()> {
+        let output = output.get_mut(..Self::LEN).ok_or(Error::InvalidRtpPacket)?;
+
+        output[0] = VERSION << 6;
+        output[1] = (u8::from(self.marker) << 7) | (self.payload_type & 0x7f);
+        output[2..4].copy_from_slice(&self.sequence.to_be_bytes());
+        output[4..8].copy_from_slice(&self.timestamp.to_be_bytes());
+        output[8..12].copy_from_slice(&self.ssrc.to_be_bytes());
+
+        Ok(())
+    }
+
+    /// Parses the header of the RTP packet `input`, returning it along the
+    /// payload without CSRCs, header extension and padding.
+    ///
+    /// **Errors**:
+    /// Returns [Error::InvalidRtpPacket] if `input` is truncated or not of
+    /// RTP version 2.
+    ///
+    /// [Error::InvalidRtpPacket]: crate::error::Error::InvalidRtpPacket
+    pub fn parse(input: &[u8]) -> Result<(Self, &[u8])> {
+        if input.len() < Self::LEN || input[0] >> 6 != VERSION {
+            return Err(Error::InvalidRtpPacket);
+        }
+
+        let padding = input[0] & 0x20 != 0;
+        let extension = input[0] & 0x10 != 0;
+        let csrc_count = usize::from(input[0] & 0x0f);
+
+        let header = Self {
+            marker: input[1] & 0x80 != 0,
+            payload_type: input[1] & 0x7f,
+            sequence: u16::from_be_bytes([input[2], input[3]]),
+            timestamp: u32::from_be_bytes([input[4], input[5], input[6], input[7]]),
+            ssrc: u32::from_be_bytes([input[8], input[9], input[10], input[11]]),
+        };
+
+        let mut start = Self::LEN + csrc_count * 4;
+        let mut end = input.len();
+
+        if extension {
+            let words = input
+                .get(start + 2..start + 4)
+                .ok_or(Error::InvalidRtpPacket)?;
+            start += 4 + usize::from(u16::from_be_bytes([words[0], words[1]])) * 4;
+        }
+
+        if padding {
+            let padding_len = usize::from(input[end - 1]);
+            end = end
+                .checked_sub(padding_len)
+                .ok_or(Error::InvalidRtpPacket)?;
+        }
+
+        let payload = input.get(start..end).ok_or(Error::InvalidRtpPacket)?;
+
+        Ok((header, payload))
+    }
+}
+
+/// `RtpPacketizer` wraps Opus packets into RTP packets of one stream,
+/// advancing sequence number and timestamp.
+///
+/// After discontinuous transmission, signalled via [`skip`], the next packet
+/// carries the marker bit.
+///
+/// [`skip`]: crate::rtp::RtpPacketizer::skip
+#[derive(Clone, Debug)]
+pub struct RtpPacketizer {
+    payload_type: u8,
+    ssrc: u32,
+    sequence: u16,
+    timestamp: u32,
+    max_duration: Duration,
+    talkspurt: bool,
+}
+
+impl RtpPacketizer {
+    /// Creates a new `RtpPacketizer` for the dynamic `payload_type`
+    /// negotiated for Opus, starting at the random `sequence` and `timestamp`
+    /// RFC 3550 recommends.
+    pub fn new(payload_type: u8, ssrc: u32, sequence: u16, timestamp: u32) -> Self {
+        Self {
+            payload_type,
+            ssrc,
+            sequence,
+            timestamp,
+            max_duration: MAX_DURATION,
+            talkspurt: true,
+        }
+    }
+
+    /// Limits the duration of packets, e.g. to the `maxptime` negotiated via
+    /// SDP. Limits beyond 120 ms, the longest Opus packet, have no effect.
+    pub fn set_max_duration(&mut self, max_duration: Duration) {
+        self.max_duration = max_duration.min(MAX_DURATION);
+    }
+
+    /// Gets the sequence number of the next packet.
+    pub fn sequence(&self) -> u16 {
+        self.sequence
+    }
+
+    /// Gets the timestamp of the next packet.
+    pub fn timestamp(&self) -> u32 {
+        self.timestamp
+    }
+
+    /// Advances the timestamp by `duration` without sending a packet, e.g.
+    /// for a frame not transmitted due to discontinuous transmission.
+    ///
+    /// The next packet starts a new talkspurt and carries the marker bit.
+    pub fn skip(&mut self, duration: Duration) {
+        let samples = SampleRate::Hz48000.samples_in(duration);
+
+        self.timestamp = self.timestamp.wrapping_add(samples as u32);
+        self.talkspurt = true;
+    }
+
+    /// Wraps `packet` into an RTP packet written to `output`, returning the
+    /// RTP packet's length.
+    ///
+    /// **Errors**:
+    /// Returns [Error::PacketDurationExceeded] if `packet` lasts longer than
+    /// the maximum duration, [Error::Opus] with [ErrorCode::BufferTooSmall]
+    /// if `output` cannot hold header and payload, and [Error::Opus] if
+    /// `packet` is invalid.
+    ///
+    /// [Error::PacketDurationExceeded]: crate::error::Error::PacketDurationExceeded
+    /// [Error::Opus]: crate::error::Error::Opus
+    /// [ErrorCode::BufferTooSmall]: crate::error::ErrorCode::BufferTooSmall
+    pub fn packetize(&mut self, packet: Packet<'_>, output: &mut [u8]) -> Result<usize> {
+        let samples = self.checked_samples(packet)?;
+        let payload = packet.as_slice();
+        let len = RtpHeader::LEN + payload.len();
+        let output = output.get_mut(..len).ok_or(ErrorCode::BufferTooSmall)?;
+
+        self.next_header(samples).write(output)?;
+        output[RtpHeader::LEN..].copy_from_slice(payload);
+
+        Ok(len)
+    }
+
+    /// Wraps `packet` into a newly allocated RTP packet.
+    ///
+    /// **Errors**:
+    /// Same as [`packetize`].
+    ///
+    /// [`packetize`]: crate::rtp::RtpPacketizer::packetize
+    pub fn packetize_to_vec(&mut self, packet: Packet<'_>) -> Result<Vec<u8>> {
+        let mut output = vec![0; RtpHeader::LEN + packet.as_slice().len()];
+        self.packetize(packet, &mut output)?;
+
+        Ok(output)
+    }
+
+    /// Wraps `packet` into a newly allocated RTP packet with a RED payload
+    /// built by `red`, carrying prior packets redundantly.
+    ///
+    /// The packetizer's payload type must be the one negotiated for RED,
+    /// `red`'s the one negotiated for Opus.
+    ///
+    /// **Errors**:
+    /// Returns [Error::PacketDurationExceeded] if `packet` lasts longer than
+    /// the maximum duration and [Error::Opus] if `packet` is invalid.
+    ///
+    /// [Error::PacketDurationExceeded]: crate::error::Error::PacketDurationExceeded
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn packetize_red(&mut self, red: &mut RedEncoder, packet: Packet<'_>) -> Result<Vec<u8>> {
+        let samples = self.checked_samples(packet)?;
+        let payload = red.encode(packet, self.timestamp);
+        let mut output = vec![0; RtpHeader::LEN + payload.len()];
+
+        self.next_header(samples).write(&mut output)?;
+        output[RtpHeader::LEN..].copy_from_slice(&payload);
+
+        Ok(output)
+    }
+
+    /// Gets the samples at 48 kHz `packet` lasts, checking its duration.
+    fn checked_samples(&self, packet: Packet<'_>) -> Result<usize> {
+        let samples = packet.nb_samples(SampleRate::Hz48000)?;
+
+        if SampleRate::Hz48000.duration_of(samples) > self.max_duration {
+            return Err(Error::PacketDurationExceeded);
+        }
+
+        Ok(samples)
+    }
+
+    /// Gets the header of the next packet, advancing past its `samples`.
+    fn next_header(&mut self, samples: usize) -> RtpHeader {
+        let header = RtpHeader {
+            marker: self.talkspurt,
+            payload_type: self.payload_type,
+            sequence: self.sequence,
+            timestamp: self.timestamp,
+            ssrc: self.ssrc,
+        };
+
+        self.sequence = self.sequence.wrapping_add(1);
+        self.timestamp = self.timestamp.wrapping_add(samples as u32);
+        self.talkspurt = false;
+
+        header
+    }
+}
+
+/// `RtpDepacketizer` unwraps the Opus packets of received RTP packets.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct RtpDepacketizer {
+    payload_type: u8,
+    max_duration: Duration,
+}
+
+impl RtpDepacketizer {
+    /// Creates a new `RtpDepacketizer` accepting the dynamic
+    /// `payload_type` negotiated for Opus.
+    pub fn new(payload_type: u8) -> Self {
+        Self {
+            payload_type,
+            max_duration: MAX_DURATION,
+        }
+    }
+
+    /// Limits the duration of accepted packets, e.g. to the negotiated
+    /// `maxptime`.
+    pub fn set_max_duration(&mut self, max_duration: Duration) {
+        self.max_duration = max_duration.min(MAX_DURATION);
+    }
+
+    /// Unwraps the RTP packet `input`, returning its header and Opus packet.
+    ///
+    /// **Errors**:
+    /// Returns [Error::InvalidRtpPacket] if `input` is malformed,
+    /// [Error::UnexpectedPayloadType] if it carries another payload type,
+    /// [Error::EmptyPacket] if it carries no payload,
+    /// [Error::PacketDurationExceeded] if the Opus packet lasts longer than
+    /// the maximum duration, and [Error::Opus] if it is invalid.
+    ///
+    /// [Error::InvalidRtpPacket]: crate::error::Error::InvalidRtpPacket
+    /// [Error::UnexpectedPayloadType]: crate::error::Error::UnexpectedPayloadType
+    /// [Error::EmptyPacket]: crate::error::Error::EmptyPacket
+    /// [Error::PacketDurationExceeded]: crate::error::Error::PacketDurationExceeded
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn depacketize<'a>(&self, input: &'a [u8]) -> Result<(RtpHeader, Packet<'a>)> {
+        let (header, payload) = RtpHeader::parse(input)?;
+
+        if header.payload_type != self.payload_type {
+            return Err(Error::UnexpectedPayloadType(header.payload_type));
+        }
+
+        let packet = Packet::try_from(payload)?;
+
+        if packet.duration(SampleRate::Hz48000)? > self.max_duration {
+            return Err(Error::PacketDurationExceeded);
+        }
+
+        Ok((header, packet))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RtpDepacketizer, RtpHeader, RtpPacketizer};
+    use crate::{
+        coder::Encoder, packet::Packet, Application, Channels, Error, SampleRate, TryFrom,
+    };
+    use matches::assert_matches;
+    use std::time::Duration;
+
+    #[test]
+    fn packetize_and_depacketize() {
+        let encoder = Encoder::new(SampleRate::Hz16000, Channels::Mono, Application::Voip).unwrap();
+        let opus = encoder.encode_to_vec(&[0_i16; 320]).unwrap();
+        let opus = Packet::try_from(&opus).unwrap();
+
+        let mut packetizer = RtpPacketizer::new(111, 0x1234_5678, 0xffff, 1000);
+        let depacketizer = RtpDepacketizer::new(111);
+
+        let first = packetizer.packetize_to_vec(opus).unwrap();
+        let (header, packet) = depacketizer.depacketize(&first).unwrap();
+        assert!(header.marker);
+        assert_eq!((header.sequence, header.timestamp), (0xffff, 1000));
+        assert_eq!(header.ssrc, 0x1234_5678);
+        assert_eq!(packet.as_slice(), opus.as_slice());
+
+        // 20 ms advance the timestamp by 960 at 48 kHz, whatever the
+        // encoder's sample rate.
+        let second = packetizer.packetize_to_vec(opus).unwrap();
+        let (header, _) = depacketizer.depacketize(&second).unwrap();
+        assert!(!header.marker);
+        assert_eq!((header.sequence, header.timestamp), (0, 1960));
+
+        packetizer.skip(Duration::from_millis(40));
+        let third = packetizer.packetize_to_vec(opus).unwrap();
+        let (header, _) = depacketizer.depacketize(&third).unwrap();
+        assert!(header.marker);
+        assert_eq!((header.sequence, header.timestamp), (1, 4840));
+
+        packetizer.set_max_duration(Duration::from_millis(10));
+        assert_matches!(
+            packetizer.packetize_to_vec(opus),
+            Err(Error::PacketDurationExceeded)
+        );
+
+        assert_matches!(
+            RtpDepacketizer::new(96).depacketize(&first),
+            Err(Error::UnexpectedPayloadType(111))
+        );
+        assert_matches!(
+            depacketizer.depacketize(&first[..8]),
+            Err(Error::InvalidRtpPacket)
+        );
+    }
+
+    #[test]
+    fn parse_csrcs_extension_and_padding() {
+        let mut input = vec![0xb1, 111, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3];
+        // One CSRC.
+        input.extend(&[0, 0, 0, 4]);
+        // A one-word header extension.
+        input.extend(&[0xbe, 0xde, 0, 1, 1, 2, 3, 4]);
+        // The payload.
+        input.extend(&[0xf8, 0xff]);
+        // Two bytes of padding.
+        input.extend(&[0, 2]);
+
+        let (header, payload) = RtpHeader::parse(&input).unwrap();
+        assert_eq!((header.sequence, header.timestamp, header.ssrc), (1, 2, 3));
+        assert_eq!(payload, &[0xf8, 0xff]);
+    }
+}
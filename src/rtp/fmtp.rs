@@ -0,0 +1,252 @@
+use crate::{
+    coder::{DecoderConfig, EncoderConfig},
+    Bandwidth, Bitrate, Channels, Error, Result, SampleRate,
+};
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+
+/// The lowest bitrate `maxaveragebitrate` may request.
+const MIN_AVERAGE_BITRATE: u32 = 6000;
+
+/// The highest bitrate `maxaveragebitrate` may request.
+const MAX_AVERAGE_BITRATE: u32 = 510_000;
+
+/// The Opus parameters of an SDP `a=fmtp` line as specified by RFC 7587,
+/// e.g. `minptime=10;useinbandfec=1`.
+///
+/// Parameters without the `sprop-` prefix describe what the announcing side
+/// prefers to receive, hence they configure the encoder of the other side,
+/// see [`encoder_config`], while `sprop-` parameters describe what it sends,
+/// configuring the local decoder, see [`decoder_config`]. Parameters left
+/// as `None` were absent.
+///
+/// Unknown parameters are ignored when parsing.
+///
+/// [`encoder_config`]: crate::rtp::Fmtp::encoder_config
+/// [`decoder_config`]: crate::rtp::Fmtp::decoder_config
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Fmtp {
+    /// `maxplaybackrate`, the highest sample rate worth sending.
+    pub max_playback_rate: Option<u32>,
+    /// `sprop-maxcapturerate`, the highest sample rate the announcing side
+    /// captures at.
+    pub sprop_max_capture_rate: Option<u32>,
+    /// `stereo`, whether stereo is preferred.
+    pub stereo: Option<bool>,
+    /// `sprop-stereo`, whether the announcing side is likely to send stereo.
+    pub sprop_stereo: Option<bool>,
+    /// `useinbandfec`, whether inband FEC is preferred.
+    pub use_inband_fec: Option<bool>,
+    /// `usedtx`, whether discontinuous transmission is preferred.
+    pub use_dtx: Option<bool>,
+    /// `maxaveragebitrate`, the highest average bitrate worth sending.
+    pub max_average_bitrate: Option<u32>,
+    /// `cbr`, whether constant bitrate is preferred.
+    pub cbr: Option<bool>,
+}
+
+impl Fmtp {
+    /// Converts the parameters the other side announced into settings for
+    /// the local encoder.
+    ///
+    /// Settings the parameters do not mention are left as `None`.
+    pub fn encoder_config(&self) -> EncoderConfig {
+        EncoderConfig {
+            max_bandwidth: self.max_playback_rate.map(bandwidth_of),
+            force_channels: self.stereo.map(|stereo| {
+                if stereo {
+                    Channels::Auto
+                } else {
+                    Channels::Mono
+                }
+            }),
+            inband_fec: self.use_inband_fec,
+            dtx: self.use_dtx,
+            bitrate: self.max_average_bitrate.map(|bitrate| {
+                let bitrate = bitrate.max(MIN_AVERAGE_BITRATE).min(MAX_AVERAGE_BITRATE);
+
+                Bitrate::BitsPerSecond(bitrate as i32)
+            }),
+            vbr: self.cbr.map(|cbr| !cbr),
+            ..EncoderConfig::default()
+        }
+    }
+
+    /// Converts the parameters the other side announced into settings for
+    /// the local decoder, its sample rate and channels as
+    /// [`decoder_sample_rate`] and [`decoder_channels`] judge them.
+    ///
+    /// [`decoder_sample_rate`]: crate::rtp::Fmtp::decoder_sample_rate
+    /// [`decoder_channels`]: crate::rtp::Fmtp::decoder_channels
+    pub fn decoder_config(&self) -> DecoderConfig {
+        DecoderConfig {
+            sample_rate: Some(self.decoder_sample_rate()),
+            channels: Some(self.decoder_channels()),
+            ..DecoderConfig::default()
+        }
+    }
+
+    /// Gets the lowest sample rate decoding the other side's stream without
+    /// loss, judged by `sprop-maxcapturerate`, full band if absent.
+    pub fn decoder_sample_rate(&self) -> SampleRate {
+        match self.sprop_max_capture_rate {
+            Some(rate) if rate <= 8000 => SampleRate::Hz8000,
+            Some(rate) if rate <= 12000 => SampleRate::Hz12000,
+            Some(rate) if rate <= 16000 => SampleRate::Hz16000,
+            Some(rate) if rate <= 24000 => SampleRate::Hz24000,
+            _ => SampleRate::Hz48000,
+        }
+    }
+
+    /// Gets the channels to decode the other side's stream to, judged by
+    /// `sprop-stereo`, mono if absent as RFC 7587 specifies.
+    pub fn decoder_channels(&self) -> Channels {
+        if self.sprop_stereo.unwrap_or(false) {
+            Channels::Stereo
+        } else {
+            Channels::Mono
+        }
+    }
+}
+
+/// Gets the widest bandwidth worth coding for playback at `rate`.
+fn bandwidth_of(rate: u32) -> Bandwidth {
+    match rate {
+        0..=8000 => Bandwidth::Narrowband,
+        8001..=12000 => Bandwidth::Mediumband,
+        12001..=16000 => Bandwidth::Wideband,
+        16001..=24000 => Bandwidth::Superwideband,
+        _ => Bandwidth::Fullband,
+    }
+}
+
+fn parse_flag(value: &str) -> Result<bool> {
+    match value {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(Error::InvalidFmtpParameter),
+    }
+}
+
+fn parse_number(value: &str) -> Result<u32> {
+    value.parse().map_err(|_| Error::InvalidFmtpParameter)
+}
+
+impl FromStr for Fmtp {
+    type Err = Error;
+
+    /// Parses the parameters of an `a=fmtp` line without the attribute and
+    /// payload type, e.g. `maxplaybackrate=16000; stereo=1`.
+    ///
+    /// Fails with [Error::InvalidFmtpParameter] if a known parameter has a
+    /// malformed value.
+    ///
+    /// [Error::InvalidFmtpParameter]: crate::error::Error::InvalidFmtpParameter
+    fn from_str(input: &str) -> Result<Self> {
+        let mut fmtp = Self::default();
+
+        for parameter in input.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            let mut parts = parameter.splitn(2, '=');
+            let name = parts.next().unwrap_or_default().trim();
+            let value = parts.next().unwrap_or_default().trim();
+
+            match name.to_ascii_lowercase().as_str() {
+                "maxplaybackrate" => fmtp.max_playback_rate = Some(parse_number(value)?),
+                "sprop-maxcapturerate" => fmtp.sprop_max_capture_rate = Some(parse_number(value)?),
+                "stereo" => fmtp.stereo = Some(parse_flag(value)?),
+                "sprop-stereo" => fmtp.sprop_stereo = Some(parse_flag(value)?),
+                "useinbandfec" => fmtp.use_inband_fec = Some(parse_flag(value)?),
+                "usedtx" => fmtp.use_dtx = Some(parse_flag(value)?),
+                "maxaveragebitrate" => fmtp.max_average_bitrate = Some(parse_number(value)?),
+                "cbr" => fmtp.cbr = Some(parse_flag(value)?),
+                _ => {}
+            }
+        }
+
+        Ok(fmtp)
+    }
+}
+
+impl Display for Fmtp {
+    /// Formats the present parameters as an `a=fmtp` line's parameters,
+    /// separated by `;`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let numbers = [
+            ("maxplaybackrate", self.max_playback_rate),
+            ("sprop-maxcapturerate", self.sprop_max_capture_rate),
+            ("maxaveragebitrate", self.max_average_bitrate),
+        ];
+        let flags = [
+            ("stereo", self.stereo),
+            ("sprop-stereo", self.sprop_stereo),
+            ("useinbandfec", self.use_inband_fec),
+            ("usedtx", self.use_dtx),
+            ("cbr", self.cbr),
+        ];
+
+        let parameters = numbers
+            .iter()
+            .filter_map(|(name, value)| value.map(|value| (name, value)))
+            .chain(
+                flags
+                    .iter()
+                    .filter_map(|(name, value)| value.map(|value| (name, u32::from(value)))),
+            );
+
+        for (i, (name, value)) in parameters.enumerate() {
+            if i > 0 {
+                f.write_str(";")?;
+            }
+
+            write!(f, "{}={}", name, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Fmtp;
+    use crate::{Bandwidth, Bitrate, Channels, Error, SampleRate};
+    use matches::assert_matches;
+
+    #[test]
+    fn parse_and_format_fmtp() {
+        let fmtp: Fmtp = "minptime=10; useinbandfec=1;maxplaybackrate=16000;stereo=0;\
+                          sprop-stereo=1;maxaveragebitrate=1000000;cbr=1"
+            .parse()
+            .unwrap();
+
+        assert_eq!(fmtp.use_inband_fec, Some(true));
+        assert_eq!(fmtp.use_dtx, None);
+        assert_eq!(fmtp.decoder_channels(), Channels::Stereo);
+        assert_eq!(fmtp.decoder_sample_rate(), SampleRate::Hz48000);
+
+        let config = fmtp.decoder_config();
+        assert_eq!(config.sample_rate, Some(SampleRate::Hz48000));
+        assert_eq!(config.channels, Some(Channels::Stereo));
+
+        let config = fmtp.encoder_config();
+        assert_eq!(config.inband_fec, Some(true));
+        assert_eq!(config.max_bandwidth, Some(Bandwidth::Wideband));
+        assert_eq!(config.force_channels, Some(Channels::Mono));
+        assert_eq!(config.bitrate, Some(Bitrate::BitsPerSecond(510_000)));
+        assert_eq!(config.vbr, Some(false));
+        assert_eq!(config.dtx, None);
+
+        assert_eq!(
+            fmtp.to_string(),
+            "maxplaybackrate=16000;maxaveragebitrate=1000000;stereo=0;sprop-stereo=1;\
+             useinbandfec=1;cbr=1"
+        );
+        assert_eq!(fmtp.to_string().parse::<Fmtp>().unwrap(), fmtp);
+
+        assert_matches!(
+            "usedtx=yes".parse::<Fmtp>(),
+            Err(Error::InvalidFmtpParameter)
+        );
+    }
+}
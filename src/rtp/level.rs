@@ -0,0 +1,86 @@
+use crate::Sample;
+
+/// The level RFC 6464 assigns to digital silence, -127 dBov.
+const SILENCE: u8 = 127;
+
+/// The audio level of a frame as carried by the client-to-mixer RTP header
+/// extension of RFC 6464.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct AudioLevel {
+    /// The level in -dBov, from `0` for full scale to `127` for silence.
+    pub level: u8,
+    /// Whether the frame contains voice, `None` if unknown.
+    pub voice_activity: Option<bool>,
+}
+
+impl AudioLevel {
+    /// Computes the level of `frame`, interleaved if it has multiple
+    /// channels, from its root mean square relative to full scale.
+    pub fn of<S: Sample>(frame: &[S]) -> Self {
+        let energy: f64 = frame
+            .iter()
+            .map(|sample| f64::from(sample.to_f32()).powi(2))
+            .sum();
+        let rms = (energy / frame.len().max(1) as f64).sqrt();
+
+        let level = if rms > 0.0 {
+            (-20.0 * rms.log10())
+                .round()
+                .max(0.0)
+                .min(f64::from(SILENCE)) as u8
+        } else {
+            SILENCE
+        };
+
+        Self {
+            level,
+            voice_activity: None,
+        }
+    }
+
+    /// Attaches a voice activity decision, e.g. whether an encoder with
+    /// discontinuous transmission left it for the frame.
+    pub fn with_voice_activity(self, voice_activity: bool) -> Self {
+        Self {
+            voice_activity: Some(voice_activity),
+            ..self
+        }
+    }
+
+    /// Gets the level as the extension's byte, the voice activity flag in
+    /// the highest bit followed by the level. Unknown activity is sent as
+    /// no activity.
+    pub fn to_byte(self) -> u8 {
+        (u8::from(self.voice_activity.unwrap_or(false)) << 7) | self.level.min(SILENCE)
+    }
+
+    /// Reads the level from the extension's byte.
+    pub fn from_byte(byte: u8) -> Self {
+        Self {
+            level: byte & 0x7f,
+            voice_activity: Some(byte & 0x80 != 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AudioLevel;
+
+    #[test]
+    fn compute_audio_level() {
+        assert_eq!(AudioLevel::of(&[0_i16; 960]).level, 127);
+        assert_eq!(AudioLevel::of::<f32>(&[]).level, 127);
+        assert_eq!(AudioLevel::of(&[1.0_f32, -1.0]).level, 0);
+
+        // A square wave at a tenth of full scale lies 20 dB below it.
+        let quiet: Vec<i16> = (0..960)
+            .map(|i| if i % 2 == 0 { 3277 } else { -3277 })
+            .collect();
+        let level = AudioLevel::of(&quiet).with_voice_activity(true);
+        assert_eq!(level.level, 20);
+
+        assert_eq!(level.to_byte(), 0x80 | 20);
+        assert_eq!(AudioLevel::from_byte(level.to_byte()), level);
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{packet::Packet, Error, Result, TryFrom};
+use std::collections::VecDeque;
+
+/// The largest timestamp offset a RED block header can express.
+const MAX_TIMESTAMP_OFFSET: u32 = 0x3fff;
+
+/// The largest block a RED block header can express, in bytes.
+const MAX_BLOCK_LEN: usize = 0x3ff;
+
+/// A block of a RED payload as specified by RFC 2198.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct RedBlock<'a> {
+    pub payload_type: u8,
+    /// How many samples at 48 kHz the block precedes the RTP timestamp,
+    /// zero for the primary block.
+    pub timestamp_offset: u16,
+    pub data: &'a [u8],
+}
+
+impl<'a> RedBlock<'a> {
+    /// Parses the blocks of the RED payload `input`, the redundant blocks
+    /// oldest first followed by the primary block.
+    ///
+    /// **Errors**:
+    /// Returns [Error::InvalidRedPayload] if `input` is truncated.
+    ///
+    /// [Error::InvalidRedPayload]: crate::error::Error::InvalidRedPayload
+    pub fn parse(input: &'a [u8]) -> Result<Vec<Self>> {
+        let mut headers = Vec::new();
+        let mut offset = 0;
+
+        loop {
+            let first = *input.get(offset).ok_or(Error::InvalidRedPayload)?;
+
+            if first & 0x80 == 0 {
+                headers.push((first, 0, None));
+                offset += 1;
+                break;
+            }
+
+            let header = input
+                .get(offset..offset + 4)
+                .ok_or(Error::InvalidRedPayload)?;
+            let timestamp_offset = (u16::from(header[1]) << 6) | u16::from(header[2] >> 2);
+            let len = (usize::from(header[2] & 0x03) << 8) | usize::from(header[3]);
+
+            headers.push((first & 0x7f, timestamp_offset, Some(len)));
+            offset += 4;
+        }
+
+        headers
+            .into_iter()
+            .map(|(payload_type, timestamp_offset, len)| {
+                let end = len.map_or(input.len(), |len| offset + len);
+                let data = input.get(offset..end).ok_or(Error::InvalidRedPayload)?;
+                offset = end;
+
+                Ok(RedBlock {
+                    payload_type,
+                    timestamp_offset,
+                    data,
+                })
+            })
+            .collect()
+    }
+}
+
+/// `RedEncoder` builds RED payloads carrying the current Opus packet plus
+/// copies of the packets preceding it, as WebRTC's audio redundancy does.
+#[derive(Clone, Debug)]
+pub struct RedEncoder {
+    payload_type: u8,
+    redundancy: usize,
+    /// Prior packets along their RTP timestamp, oldest first.
+    history: VecDeque<(u32, Vec<u8>)>,
+}
+
+impl RedEncoder {
+    /// Creates a new `RedEncoder` carrying up to `redundancy` prior packets
+    /// of the Opus `payload_type` in every payload.
+    pub fn new(payload_type: u8, redundancy: usize) -> Self {
+        Self {
+            payload_type,
+            redundancy,
+            history: VecDeque::with_capacity(redundancy),
+        }
+    }
+
+    /// Builds the RED payload for `packet` at the RTP `timestamp`.
+    ///
+    /// Prior packets too old or too large for a RED block header are left
+    /// out.
+    pub fn encode(&mut self, packet: Packet<'_>, timestamp: u32) -> Vec<u8> {
+        let redundant: Vec<_> = self
+            .history
+            .iter()
+            .map(|(prior, data)| (timestamp.wrapping_sub(*prior), data))
+            .filter(|(offset, data)| *offset <= MAX_TIMESTAMP_OFFSET && data.len() <= MAX_BLOCK_LEN)
+            .collect();
+
+        let data_len: usize = redundant.iter().map(|(_, data)| data.len()).sum();
+        let mut output =
+            Vec::with_capacity(redundant.len() * 4 + 1 + data_len + packet.as_slice().len());
+
+        for (offset, data) in &redundant {
+            output.push(0x80 | self.payload_type);
+            output.push((offset >> 6) as u8);
+            output.push(((offset << 2) as u8) | (data.len() >> 8) as u8);
+            output.push(data.len() as u8);
+        }
+
+        output.push(self.payload_type & 0x7f);
+
+        for (_, data) in &redundant {
+            output.extend_from_slice(data);
+        }
+
+        output.extend_from_slice(packet.as_slice());
+
+        if self.redundancy > 0 {
+            if self.history.len() == self.redundancy {
+                self.history.pop_front();
+            }
+
+            self.history
+                .push_back((timestamp, packet.as_slice().to_vec()));
+        }
+
+        output
+    }
+}
+
+/// `RedDecoder` unpacks the Opus packets of received RED payloads,
+/// delivering redundant packets only if their original was not received.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct RedDecoder {
+    /// The timestamp of the newest packet delivered.
+    last_timestamp: Option<u32>,
+}
+
+impl RedDecoder {
+    /// Creates a new `RedDecoder`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Unpacks the RED payload `input` of the RTP packet at `timestamp`,
+    /// returning the packets not delivered before along their RTP timestamp,
+    /// oldest first.
+    ///
+    /// Blocks of payload types other than Opus' `payload_type`, e.g. comfort
+    /// noise, are skipped.
+    ///
+    /// **Errors**:
+    /// Returns [Error::InvalidRedPayload] if `input` is malformed and
+    /// [Error::EmptyPacket] if the primary block is empty.
+    ///
+    /// [Error::InvalidRedPayload]: crate::error::Error::InvalidRedPayload
+    /// [Error::EmptyPacket]: crate::error::Error::EmptyPacket
+    pub fn decode<'a>(
+        &mut self,
+        input: &'a [u8],
+        timestamp: u32,
+        payload_type: u8,
+    ) -> Result<Vec<(u32, Packet<'a>)>> {
+        let blocks = RedBlock::parse(input)?;
+        let mut packets = Vec::with_capacity(blocks.len());
+
+        for block in blocks {
+            if block.payload_type != payload_type {
+                continue;
+            }
+
+            let block_timestamp = timestamp.wrapping_sub(u32::from(block.timestamp_offset));
+
+            // Timestamps at or behind the last delivered one wrap to
+            // offsets of at least half the range.
+            let new = self.last_timestamp.map_or(true, |last| {
+                let ahead = block_timestamp.wrapping_sub(last);
+                ahead != 0 && ahead < 0x8000_0000
+            });
+
+            if new {
+                packets.push((block_timestamp, Packet::try_from(block.data)?));
+                self.last_timestamp = Some(block_timestamp);
+            }
+        }
+
+        Ok(packets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RedBlock, RedDecoder, RedEncoder};
+    use crate::{packet::Packet, Error};
+    use matches::assert_matches;
+
+    #[test]
+    fn encode_and_decode_redundancy() {
+        let packets: Vec<[u8; 3]> = (0..4).map(|i| [0xf8, i, i]).collect();
+        let mut encoder = RedEncoder::new(111, 2);
+        let mut decoder = RedDecoder::new();
+
+        let payloads: Vec<Vec<u8>> = packets
+            .iter()
+            .enumerate()
+            .map(|(i, packet)| encoder.encode(Packet::new(packet).unwrap(), i as u32 * 960))
+            .collect();
+
+        let blocks = RedBlock::parse(&payloads[3]).unwrap();
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].timestamp_offset, 1920);
+        assert_eq!(blocks[0].data, &packets[1][..]);
+        assert_eq!(blocks[2].timestamp_offset, 0);
+        assert_eq!(blocks[2].data, &packets[3][..]);
+
+        let received = decoder.decode(&payloads[0], 0, 111).unwrap();
+        assert_eq!(received.len(), 1);
+
+        // Payloads 1 and 2 are lost, payload 3 recovers them.
+        let received = decoder.decode(&payloads[3], 3 * 960, 111).unwrap();
+        let timestamps: Vec<u32> = received.iter().map(|(timestamp, _)| *timestamp).collect();
+        assert_eq!(timestamps, [960, 1920, 2880]);
+        assert_eq!(received[0].1.as_slice(), &packets[1][..]);
+
+        // A late payload delivers nothing new.
+        assert!(decoder
+            .decode(&payloads[2], 2 * 960, 111)
+            .unwrap()
+            .is_empty());
+
+        assert_matches!(
+            RedBlock::parse(&payloads[3][..2]),
+            Err(Error::InvalidRedPayload)
+        );
+    }
+}
@@ -0,0 +1,347 @@
+//! Sample types Opus can encode from and decode into.
+
+#[cfg(all(feature = "opus_1_5", opus_dred))]
+use crate::coder::{dred, OpusDRED};
+#[cfg(feature = "custom")]
+use crate::custom::{self, OpusCustomDecoder, OpusCustomEncoder};
+use crate::{backend::OpusBackend, ffi};
+
+mod private {
+    #[cfg(all(feature = "opus_1_5", opus_dred))]
+    use crate::coder::OpusDRED;
+    #[cfg(feature = "custom")]
+    use crate::custom::{OpusCustomDecoder, OpusCustomEncoder};
+    use crate::{backend::OpusBackend, ffi};
+
+    /// Dispatches to the Opus function matching the sample type, running
+    /// single stream coders on their backend.
+    ///
+    /// The trait is private, hence [`Sample`] cannot be implemented outside
+    /// this crate.
+    ///
+    /// [`Sample`]: crate::sample::Sample
+    pub trait Sealed: Sized {
+        unsafe fn encode(
+            backend: &dyn OpusBackend,
+            encoder: *mut ffi::OpusEncoder,
+            pcm: *const Self,
+            frame_size: i32,
+            data: *mut u8,
+            max_data_bytes: i32,
+        ) -> i32;
+
+        unsafe fn decode(
+            backend: &dyn OpusBackend,
+            decoder: *mut ffi::OpusDecoder,
+            data: *const u8,
+            len: i32,
+            pcm: *mut Self,
+            frame_size: i32,
+            decode_fec: i32,
+        ) -> i32;
+
+        unsafe fn multistream_encode(
+            encoder: *mut ffi::OpusMSEncoder,
+            pcm: *const Self,
+            frame_size: i32,
+            data: *mut u8,
+            max_data_bytes: i32,
+        ) -> i32;
+
+        unsafe fn multistream_decode(
+            decoder: *mut ffi::OpusMSDecoder,
+            data: *const u8,
+            len: i32,
+            pcm: *mut Self,
+            frame_size: i32,
+            decode_fec: i32,
+        ) -> i32;
+
+        #[cfg(opus_projection)]
+        unsafe fn projection_encode(
+            encoder: *mut ffi::OpusProjectionEncoder,
+            pcm: *const Self,
+            frame_size: i32,
+            data: *mut u8,
+            max_data_bytes: i32,
+        ) -> i32;
+
+        #[cfg(opus_projection)]
+        unsafe fn projection_decode(
+            decoder: *mut ffi::OpusProjectionDecoder,
+            data: *const u8,
+            len: i32,
+            pcm: *mut Self,
+            frame_size: i32,
+            decode_fec: i32,
+        ) -> i32;
+
+        #[cfg(all(feature = "opus_1_5", opus_dred))]
+        unsafe fn dred_decode(
+            decoder: *mut ffi::OpusDecoder,
+            dred: *const OpusDRED,
+            dred_offset: i32,
+            pcm: *mut Self,
+            frame_size: i32,
+        ) -> i32;
+
+        #[cfg(feature = "custom")]
+        unsafe fn custom_encode(
+            encoder: *mut OpusCustomEncoder,
+            pcm: *const Self,
+            frame_size: i32,
+            data: *mut u8,
+            max_data_bytes: i32,
+        ) -> i32;
+
+        #[cfg(feature = "custom")]
+        unsafe fn custom_decode(
+            decoder: *mut OpusCustomDecoder,
+            data: *const u8,
+            len: i32,
+            pcm: *mut Self,
+            frame_size: i32,
+        ) -> i32;
+
+        /// Converts the sample to floating point in the range of `-1.0` to
+        /// `1.0`.
+        fn to_f32(self) -> f32;
+
+        /// Converts floating point in the range of `-1.0` to `1.0` to the
+        /// sample, saturating beyond the range.
+        fn from_f32(value: f32) -> Self;
+    }
+}
+
+/// A sample type Opus can encode from and decode into, either
+/// 16-bit signed integers or 32-bit floating point numbers.
+///
+/// Floating point samples are expected in the range of `-1.0` to `1.0`.
+///
+/// This trait is sealed and cannot be implemented for other types.
+pub trait Sample: private::Sealed + Copy + Default + 'static {}
+
+impl Sample for i16 {}
+
+impl Sample for f32 {}
+
+impl private::Sealed for i16 {
+    unsafe fn encode(
+        backend: &dyn OpusBackend,
+        encoder: *mut ffi::OpusEncoder,
+        pcm: *const Self,
+        frame_size: i32,
+        data: *mut u8,
+        max_data_bytes: i32,
+    ) -> i32 {
+        backend.encode(encoder, pcm, frame_size, data, max_data_bytes)
+    }
+
+    unsafe fn decode(
+        backend: &dyn OpusBackend,
+        decoder: *mut ffi::OpusDecoder,
+        data: *const u8,
+        len: i32,
+        pcm: *mut Self,
+        frame_size: i32,
+        decode_fec: i32,
+    ) -> i32 {
+        backend.decode(decoder, data, len, pcm, frame_size, decode_fec)
+    }
+
+    unsafe fn multistream_encode(
+        encoder: *mut ffi::OpusMSEncoder,
+        pcm: *const Self,
+        frame_size: i32,
+        data: *mut u8,
+        max_data_bytes: i32,
+    ) -> i32 {
+        ffi::opus_multistream_encode(encoder, pcm, frame_size, data, max_data_bytes)
+    }
+
+    unsafe fn multistream_decode(
+        decoder: *mut ffi::OpusMSDecoder,
+        data: *const u8,
+        len: i32,
+        pcm: *mut Self,
+        frame_size: i32,
+        decode_fec: i32,
+    ) -> i32 {
+        ffi::opus_multistream_decode(decoder, data, len, pcm, frame_size, decode_fec)
+    }
+
+    #[cfg(opus_projection)]
+    unsafe fn projection_encode(
+        encoder: *mut ffi::OpusProjectionEncoder,
+        pcm: *const Self,
+        frame_size: i32,
+        data: *mut u8,
+        max_data_bytes: i32,
+    ) -> i32 {
+        ffi::opus_projection_encode(encoder, pcm, frame_size, data, max_data_bytes)
+    }
+
+    #[cfg(opus_projection)]
+    unsafe fn projection_decode(
+        decoder: *mut ffi::OpusProjectionDecoder,
+        data: *const u8,
+        len: i32,
+        pcm: *mut Self,
+        frame_size: i32,
+        decode_fec: i32,
+    ) -> i32 {
+        ffi::opus_projection_decode(decoder, data, len, pcm, frame_size, decode_fec)
+    }
+
+    #[cfg(all(feature = "opus_1_5", opus_dred))]
+    unsafe fn dred_decode(
+        decoder: *mut ffi::OpusDecoder,
+        dred: *const OpusDRED,
+        dred_offset: i32,
+        pcm: *mut Self,
+        frame_size: i32,
+    ) -> i32 {
+        dred::opus_decoder_dred_decode(decoder, dred, dred_offset, pcm, frame_size)
+    }
+
+    #[cfg(feature = "custom")]
+    unsafe fn custom_encode(
+        encoder: *mut OpusCustomEncoder,
+        pcm: *const Self,
+        frame_size: i32,
+        data: *mut u8,
+        max_data_bytes: i32,
+    ) -> i32 {
+        custom::opus_custom_encode(encoder, pcm, frame_size, data, max_data_bytes)
+    }
+
+    #[cfg(feature = "custom")]
+    unsafe fn custom_decode(
+        decoder: *mut OpusCustomDecoder,
+        data: *const u8,
+        len: i32,
+        pcm: *mut Self,
+        frame_size: i32,
+    ) -> i32 {
+        custom::opus_custom_decode(decoder, data, len, pcm, frame_size)
+    }
+
+    fn to_f32(self) -> f32 {
+        f32::from(self) / 32768.0
+    }
+
+    fn from_f32(value: f32) -> Self {
+        // Float to integer casts saturate.
+        (value * 32768.0).round() as i16
+    }
+}
+
+impl private::Sealed for f32 {
+    unsafe fn encode(
+        backend: &dyn OpusBackend,
+        encoder: *mut ffi::OpusEncoder,
+        pcm: *const Self,
+        frame_size: i32,
+        data: *mut u8,
+        max_data_bytes: i32,
+    ) -> i32 {
+        backend.encode_float(encoder, pcm, frame_size, data, max_data_bytes)
+    }
+
+    unsafe fn decode(
+        backend: &dyn OpusBackend,
+        decoder: *mut ffi::OpusDecoder,
+        data: *const u8,
+        len: i32,
+        pcm: *mut Self,
+        frame_size: i32,
+        decode_fec: i32,
+    ) -> i32 {
+        backend.decode_float(decoder, data, len, pcm, frame_size, decode_fec)
+    }
+
+    unsafe fn multistream_encode(
+        encoder: *mut ffi::OpusMSEncoder,
+        pcm: *const Self,
+        frame_size: i32,
+        data: *mut u8,
+        max_data_bytes: i32,
+    ) -> i32 {
+        ffi::opus_multistream_encode_float(encoder, pcm, frame_size, data, max_data_bytes)
+    }
+
+    unsafe fn multistream_decode(
+        decoder: *mut ffi::OpusMSDecoder,
+        data: *const u8,
+        len: i32,
+        pcm: *mut Self,
+        frame_size: i32,
+        decode_fec: i32,
+    ) -> i32 {
+        ffi::opus_multistream_decode_float(decoder, data, len, pcm, frame_size, decode_fec)
+    }
+
+    #[cfg(opus_projection)]
+    unsafe fn projection_encode(
+        encoder: *mut ffi::OpusProjectionEncoder,
+        pcm: *const Self,
+        frame_size: i32,
+        data: *mut u8,
+        max_data_bytes: i32,
+    ) -> i32 {
+        ffi::opus_projection_encode_float(encoder, pcm, frame_size, data, max_data_bytes)
+    }
+
+    #[cfg(opus_projection)]
+    unsafe fn projection_decode(
+        decoder: *mut ffi::OpusProjectionDecoder,
+        data: *const u8,
+        len: i32,
+        pcm: *mut Self,
+        frame_size: i32,
+        decode_fec: i32,
+    ) -> i32 {
+        ffi::opus_projection_decode_float(decoder, data, len, pcm, frame_size, decode_fec)
+    }
+
+    #[cfg(all(feature = "opus_1_5", opus_dred))]
+    unsafe fn dred_decode(
+        decoder: *mut ffi::OpusDecoder,
+        dred: *const OpusDRED,
+        dred_offset: i32,
+        pcm: *mut Self,
+        frame_size: i32,
+    ) -> i32 {
+        dred::opus_decoder_dred_decode_float(decoder, dred, dred_offset, pcm, frame_size)
+    }
+
+    #[cfg(feature = "custom")]
+    unsafe fn custom_encode(
+        encoder: *mut OpusCustomEncoder,
+        pcm: *const Self,
+        frame_size: i32,
+        data: *mut u8,
+        max_data_bytes: i32,
+    ) -> i32 {
+        custom::opus_custom_encode_float(encoder, pcm, frame_size, data, max_data_bytes)
+    }
+
+    #[cfg(feature = "custom")]
+    unsafe fn custom_decode(
+        decoder: *mut OpusCustomDecoder,
+        data: *const u8,
+        len: i32,
+        pcm: *mut Self,
+        frame_size: i32,
+    ) -> i32 {
+        custom::opus_custom_decode_float(decoder, data, len, pcm, frame_size)
+    }
+
+    fn to_f32(self) -> f32 {
+        self
+    }
+
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+}
@@ -1,27 +1,89 @@
-use crate::{ffi, Channels, MutSignals, Result};
+use crate::{ffi, Channels, Error, MutSignals, Result, TryFrom};
 
+/// `SoftClip` brings `f32` signals within the range of `-1.0` to `1.0`
+/// via Opus' soft clipping, which avoids the distortion of hard clipping.
+///
+/// The clipping carries per-channel state across calls, hence one
+/// `SoftClip` must process one signal continuously, see [`reset`] to
+/// start another.
+///
+/// [`reset`]: crate::softclip::SoftClip::reset
 #[derive(Clone, Debug)]
 pub struct SoftClip {
-    channels: Channels,
-    memory: [f32; 2],
+    /// The state of each channel.
+    memory: Vec<f32>,
 }
 
 impl SoftClip {
+    /// Creates a new `SoftClip` for a mono or stereo signal,
+    /// [`Channels::Auto`] meaning mono.
+    ///
+    /// [`Channels::Auto`]: crate::Channels::Auto
     pub fn new(channels: Channels) -> Self {
+        let channels = match channels {
+            Channels::Stereo => 2,
+            Channels::Mono | Channels::Auto => 1,
+        };
+
         Self {
-            channels,
-            memory: [0.0; 2],
+            memory: vec![0.0; channels],
+        }
+    }
+
+    /// Creates a new `SoftClip` for a signal of any amount of `channels`,
+    /// e.g. the output of a multistream decoder.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::InvalidChannels`] if `channels` is zero.
+    ///
+    /// [`Error::InvalidChannels`]: crate::error::Error::InvalidChannels
+    pub fn multichannel(channels: usize) -> Result<Self> {
+        let count = i32::try_from(channels).map_err(|_| Error::InvalidChannels(i32::MAX))?;
+
+        if count == 0 {
+            return Err(Error::InvalidChannels(count));
         }
+
+        Ok(Self {
+            memory: vec![0.0; channels],
+        })
+    }
+
+    /// Gets the amount of channels.
+    pub fn channels(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Resets the state of every channel, e.g. to process another signal.
+    pub fn reset(&mut self) {
+        self.memory.iter_mut().for_each(|memory| *memory = 0.0);
     }
 
     /// Opus applies soft-clipping to bring a f32 signal within the
     /// [-1,1] range.
+    ///
+    /// **Errors**:
+    /// Returns [`Error::SignalsExpectedLen`] if `signals`, interleaved if
+    /// multiple channels, does not contain the same amount of samples for
+    /// every channel.
+    ///
+    /// [`Error::SignalsExpectedLen`]: crate::error::Error::SignalsExpectedLen
     pub fn apply(&mut self, mut signals: MutSignals<'_, f32>) -> Result<()> {
+        // The amount of channels is below `i32::MAX` by construction.
+        let channels = self.memory.len() as i32;
+        let len = signals.i32_len();
+
+        if len % channels != 0 {
+            return Err(Error::SignalsExpectedLen(
+                (len / channels * channels) as usize,
+            ));
+        }
+
         unsafe {
             ffi::opus_pcm_soft_clip(
                 signals.as_mut_ptr(),
-                signals.i32_len() / self.channels as i32,
-                self.channels as i32,
+                len / channels,
+                channels,
                 self.memory.as_mut_ptr(),
             )
         };
@@ -50,4 +112,25 @@ mod tests {
         assert!(signals[0] <= 1.0 && signals[0] >= -1.0);
         assert!(signals[1] <= 1.0 && signals[1] >= -1.0);
     }
+
+    #[test]
+    fn soft_clip_multichannel() {
+        let mut soft_clip = SoftClip::multichannel(6).unwrap();
+        assert_eq!(soft_clip.channels(), 6);
+        assert!(SoftClip::multichannel(0).is_err());
+
+        let mut signals = vec![3.0_f32; 6 * 10];
+        soft_clip.apply((&mut signals).try_into().unwrap()).unwrap();
+        assert!(signals.iter().all(|&sample| sample <= 1.0));
+
+        let mut signals = vec![0.0_f32; 7];
+        assert!(soft_clip.apply((&mut signals).try_into().unwrap()).is_err());
+
+        soft_clip.reset();
+        let mut signals = vec![0.5_f32; 6];
+        soft_clip.apply((&mut signals).try_into().unwrap()).unwrap();
+        assert!(signals
+            .iter()
+            .all(|&sample| (sample - 0.5).abs() < f32::EPSILON));
+    }
 }
@@ -0,0 +1,270 @@
+//! Asynchronous adapters coding between PCM and packets of `futures`
+//! streams and sinks, see [`EncoderSink`] and [`DecoderStream`].
+//!
+//! [`EncoderSink`]: crate::stream::EncoderSink
+//! [`DecoderStream`]: crate::stream::DecoderStream
+
+use crate::{
+    coder::{fec, Decoder, Encoder, StreamEncoder},
+    packet::PacketBuf,
+    Error, FrameDuration, Sample,
+};
+use futures::{ready, Sink, Stream};
+use std::{
+    collections::VecDeque,
+    error::Error as StdError,
+    fmt::{Display, Formatter, Result as FmtResult},
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// An error of an [`EncoderSink`] or [`DecoderStream`], either from Opus or
+/// from the wrapped sink or stream.
+///
+/// [`EncoderSink`]: crate::stream::EncoderSink
+/// [`DecoderStream`]: crate::stream::DecoderStream
+#[derive(Debug)]
+pub enum StreamError<E> {
+    Opus(Error),
+    Inner(E),
+}
+
+impl<E: Display> Display for StreamError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            StreamError::Opus(error) => write!(f, "{}", error),
+            StreamError::Inner(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl<E: StdError + 'static> StdError for StreamError<E> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            StreamError::Opus(error) => Some(error),
+            StreamError::Inner(error) => Some(error),
+        }
+    }
+}
+
+impl<E> From<Error> for StreamError<E> {
+    fn from(error: Error) -> Self {
+        StreamError::Opus(error)
+    }
+}
+
+/// `EncoderSink` accepts PCM of any length, encodes it in frames of a fixed
+/// duration, and sends the packets to the wrapped sink.
+///
+/// Closing the sink pads a trailing partial frame with silence.
+#[derive(Debug)]
+pub struct EncoderSink<Si, S = i16> {
+    encoder: StreamEncoder<S>,
+    inner: Si,
+    packets: VecDeque<PacketBuf>,
+}
+
+impl<Si, S: Sample> EncoderSink<Si, S> {
+    /// Creates a new `EncoderSink` encoding frames of `frame_duration` via
+    /// `encoder` into `inner`.
+    ///
+    /// **Errors**:
+    /// Returns [Error::Opus] when Opus encountered a problem.
+    ///
+    /// [Error::Opus]: crate::error::Error::Opus
+    pub fn new(encoder: Encoder, frame_duration: FrameDuration, inner: Si) -> Result<Self, Error> {
+        Ok(Self {
+            encoder: StreamEncoder::new(encoder, frame_duration)?,
+            inner,
+            packets: VecDeque::new(),
+        })
+    }
+
+    /// Unwraps the encoder and the inner sink, discarding PCM and packets
+    /// not sent yet.
+    pub fn into_inner(self) -> (Encoder, Si) {
+        (self.encoder.into_inner(), self.inner)
+    }
+
+    /// Sends the queued packets to the inner sink.
+    fn poll_send<E>(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), StreamError<E>>>
+    where
+        Si: Sink<PacketBuf, Error = E> + Unpin,
+    {
+        while !self.packets.is_empty() {
+            ready!(Pin::new(&mut self.inner).poll_ready(cx)).map_err(StreamError::Inner)?;
+
+            if let Some(packet) = self.packets.pop_front() {
+                Pin::new(&mut self.inner)
+                    .start_send(packet)
+                    .map_err(StreamError::Inner)?;
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<'a, Si, S> Sink<&'a [S]> for EncoderSink<Si, S>
+where
+    Si: Sink<PacketBuf> + Unpin,
+    S: Sample + Unpin,
+{
+    type Error = StreamError<Si::Error>;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().poll_send(cx)
+    }
+
+    /// Encodes the frames `item` completes.
+    ///
+    /// If a frame fails to encode, it is dropped and the error returned,
+    /// the packets encoded before it are still sent.
+    fn start_send(self: Pin<&mut Self>, item: &'a [S]) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        let mut packets = Vec::new();
+        let encoded = this.encoder.push(item, &mut packets);
+        this.packets.extend(packets);
+        encoded?;
+
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        ready!(this.poll_send(cx))?;
+
+        Pin::new(&mut this.inner)
+            .poll_flush(cx)
+            .map_err(StreamError::Inner)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+
+        if let Some(packet) = this.encoder.flush()? {
+            this.packets.push_back(packet);
+        }
+
+        ready!(this.poll_send(cx))?;
+
+        Pin::new(&mut this.inner)
+            .poll_close(cx)
+            .map_err(StreamError::Inner)
+    }
+}
+
+/// `DecoderStream` decodes the packets of the wrapped stream, each paired
+/// with its sequence number, into PCM.
+///
+/// Lost packets are recovered from the in-band FEC of the packet following
+/// them or concealed, as [`FecChannel::receive`] does.
+///
+/// [`FecChannel::receive`]: crate::coder::FecChannel::receive
+#[derive(Debug)]
+pub struct DecoderStream<St, S = i16> {
+    decoder: Decoder,
+    inner: St,
+    next_sequence: Option<u16>,
+    _sample: PhantomData<S>,
+}
+
+impl<St, S: Sample> DecoderStream<St, S> {
+    /// Creates a new `DecoderStream` decoding the packets of `inner` via
+    /// `decoder`.
+    pub fn new(decoder: Decoder, inner: St) -> Self {
+        Self {
+            decoder,
+            inner,
+            next_sequence: None,
+            _sample: PhantomData,
+        }
+    }
+
+    /// Unwraps the decoder and the inner stream.
+    pub fn into_inner(self) -> (Decoder, St) {
+        (self.decoder, self.inner)
+    }
+}
+
+impl<St, S, E> Stream for DecoderStream<St, S>
+where
+    St: Stream<Item = Result<(u16, PacketBuf), E>> + Unpin,
+    S: Sample + Unpin,
+{
+    type Item = Result<Vec<S>, StreamError<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            let (sequence, packet) = match ready!(Pin::new(&mut this.inner).poll_next(cx)) {
+                Some(Ok(item)) => item,
+                Some(Err(error)) => return Poll::Ready(Some(Err(StreamError::Inner(error)))),
+                None => return Poll::Ready(None),
+            };
+
+            let output = fec::receive(
+                &mut this.decoder,
+                &mut this.next_sequence,
+                sequence,
+                packet.as_packet(),
+            );
+
+            match output {
+                // Late packets decode to nothing, wait for the next one.
+                Ok(output) if output.is_empty() => continue,
+                Ok(output) => return Poll::Ready(Some(Ok(output))),
+                Err(error) => return Poll::Ready(Some(Err(StreamError::Opus(error)))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecoderStream, EncoderSink};
+    use crate::{
+        coder::{Decoder, Encoder},
+        packet::PacketBuf,
+        Application, Channels, FrameDuration, SampleRate,
+    };
+    use futures::{channel::mpsc, executor::block_on, stream, SinkExt, StreamExt};
+    use std::convert::Infallible;
+
+    #[test]
+    fn encode_and_decode_asynchronously() {
+        let encoder = Encoder::new(SampleRate::Hz16000, Channels::Mono, Application::Voip).unwrap();
+        let (sender, receiver) = mpsc::unbounded::<PacketBuf>();
+        let mut sink = EncoderSink::new(encoder, FrameDuration::Ms20, sender).unwrap();
+
+        block_on(async {
+            // 2.5 frames, the last padded on close.
+            sink.send(&[0_i16; 500][..]).await.unwrap();
+            sink.send(&[0_i16; 300][..]).await.unwrap();
+            sink.close().await.unwrap();
+        });
+
+        let packets: Vec<PacketBuf> = block_on(receiver.collect());
+        assert_eq!(packets.len(), 3);
+
+        // The second packet is lost.
+        let received = packets
+            .into_iter()
+            .enumerate()
+            .filter(|(sequence, _)| *sequence != 1)
+            .map(|(sequence, packet)| Ok::<_, Infallible>((sequence as u16, packet)));
+
+        let decoder = Decoder::new(SampleRate::Hz16000, Channels::Mono).unwrap();
+        let decoded: Vec<Vec<i16>> = block_on(
+            DecoderStream::new(decoder, stream::iter(received))
+                .map(Result::unwrap)
+                .collect(),
+        );
+
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].len(), 320);
+        assert_eq!(decoded[1].len(), 640);
+    }
+}
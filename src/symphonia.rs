@@ -0,0 +1,199 @@
+//! Decoding Opus with libopus inside Symphonia, see [`OpusDecoder`].
+//!
+//! Register the decoder with a `CodecRegistry` to decode the Opus tracks
+//! of any format Symphonia demuxes, such as Ogg or Matroska.
+//!
+//! [`OpusDecoder`]: crate::symphonia::OpusDecoder
+
+use crate::{
+    coder::{multistream::MappingFamily, DecodeRequest, Decoder, GenericCtl},
+    header::OpusHead,
+    packet::Packet as OpusPacket,
+    Channels, SampleRate, TryFrom,
+};
+use std::{
+    fmt,
+    sync::{Mutex, PoisonError},
+};
+use symphonia_core::{
+    audio::{
+        AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels as Layout, Signal, SignalSpec,
+    },
+    codecs::{
+        CodecDescriptor, CodecParameters, Decoder as SymphoniaDecoder, DecoderOptions,
+        FinalizeResult, CODEC_TYPE_OPUS,
+    },
+    errors::{decode_error, unsupported_error, Result},
+    formats::Packet,
+    support_codec,
+};
+
+/// The most samples per channel a packet decodes to, 120 ms at 48 kHz.
+const MAX_FRAMES: u64 = 5760;
+
+/// `OpusDecoder` implements Symphonia's `Decoder` trait backed by libopus,
+/// decoding mono and stereo Opus tracks to `f32` at 48 kHz.
+///
+/// The track's `OpusHead`, passed by demuxers as the codec parameters'
+/// extra data, configures the channels and the output gain. Symphonia
+/// trims the pre-skip itself when gapless playback is enabled.
+pub struct OpusDecoder {
+    // Symphonia requires decoders to be `Sync`, which libopus' decoder
+    // state is only behind a lock.
+    decoder: Mutex<Decoder>,
+    params: CodecParameters,
+    buffer: AudioBuffer<f32>,
+}
+
+impl fmt::Debug for OpusDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpusDecoder")
+            .field("decoder", &self.decoder)
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+impl OpusDecoder {
+    fn decoder(&mut self) -> &mut Decoder {
+        self.decoder
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn decode_packet(&mut self, data: &[u8]) -> Result<()> {
+        let packet = OpusPacket::new(data).or_else(|_| decode_error("opus: invalid packet"))?;
+        let pcm = self
+            .decoder()
+            .decode_to_vec::<f32>(DecodeRequest::Normal(packet))
+            .or_else(|_| decode_error("opus: failed to decode packet"))?;
+
+        let channels = self.buffer.spec().channels.count();
+        self.buffer.clear();
+        self.buffer.render_reserved(Some(pcm.len() / channels));
+
+        for channel in 0..channels {
+            let plane = self.buffer.chan_mut(channel);
+
+            for (sample, frame) in plane.iter_mut().zip(pcm.chunks_exact(channels)) {
+                *sample = frame[channel];
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl SymphoniaDecoder for OpusDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        if params.codec != CODEC_TYPE_OPUS {
+            return unsupported_error("opus: invalid codec type");
+        }
+
+        let (channels, gain) = match &params.extra_data {
+            Some(extra_data) => {
+                let head = OpusHead::parse(extra_data)
+                    .or_else(|_| decode_error("opus: invalid identification header"))?;
+
+                if head.mapping_family != MappingFamily::Rtp {
+                    return unsupported_error("opus: multistream tracks are unsupported");
+                }
+
+                (i32::from(head.channels), i32::from(head.output_gain))
+            }
+            None => {
+                let channels = params.channels.map_or(0, |channels| channels.count());
+
+                (i32::try_from(channels).unwrap_or(0), 0)
+            }
+        };
+
+        let channels = Channels::try_from(channels)
+            .or_else(|_| unsupported_error("opus: unsupported channel count"))?;
+        let decoder = Decoder::new(SampleRate::Hz48000, channels)
+            .or_else(|_| unsupported_error("opus: failed to create the decoder"))?;
+        decoder
+            .set_gain(gain)
+            .or_else(|_| decode_error("opus: invalid output gain"))?;
+
+        let layout = match channels {
+            Channels::Mono => Layout::FRONT_LEFT,
+            _ => Layout::FRONT_LEFT | Layout::FRONT_RIGHT,
+        };
+        let spec = SignalSpec::new(48000, layout);
+
+        Ok(Self {
+            decoder: Mutex::new(decoder),
+            params: params.clone(),
+            buffer: AudioBuffer::new(MAX_FRAMES, spec),
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[support_codec!(CODEC_TYPE_OPUS, "opus", "Opus (libopus)")]
+    }
+
+    fn reset(&mut self) {
+        // Resetting only fails for invalid decoder states.
+        let _ = self.decoder().reset_state();
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        if let Err(error) = self.decode_packet(packet.buf()) {
+            self.buffer.clear();
+
+            return Err(error);
+        }
+
+        Ok(self.buffer.as_audio_buffer_ref())
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        FinalizeResult::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buffer.as_audio_buffer_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OpusDecoder;
+    use crate::{coder::Encoder, header::OpusHead, Application, Channels, SampleRate};
+    use symphonia_core::{
+        codecs::{CodecParameters, Decoder, DecoderOptions, CODEC_TYPE_OPUS},
+        formats::Packet,
+    };
+
+    #[test]
+    fn decode_packets() {
+        let encoder =
+            Encoder::new(SampleRate::Hz48000, Channels::Stereo, Application::Audio).unwrap();
+        let head = OpusHead::new(Channels::Stereo, encoder.pre_skip(false).unwrap(), 48000);
+
+        let mut params = CodecParameters::new();
+        params
+            .for_codec(CODEC_TYPE_OPUS)
+            .with_sample_rate(48000)
+            .with_extra_data(head.to_bytes().into_boxed_slice());
+
+        let mut decoder = OpusDecoder::try_new(&params, &DecoderOptions::default()).unwrap();
+
+        let data = encoder.encode_to_vec(&[0_i16; 1920]).unwrap();
+        let buffer = decoder
+            .decode(&Packet::new_from_slice(0, 0, 960, &data))
+            .unwrap();
+        assert_eq!(buffer.frames(), 960);
+        assert_eq!(buffer.spec().channels.count(), 2);
+
+        assert!(decoder
+            .decode(&Packet::new_from_slice(0, 960, 960, &[]))
+            .is_err());
+        assert_eq!(decoder.last_decoded().frames(), 0);
+    }
+}
@@ -0,0 +1,212 @@
+//! The version and capabilities of the linked Opus, see [`Version`].
+//!
+//! [`Version`]: crate::version::Version
+
+use crate::{
+    coder::{CtlRequest, Decoder, Encoder},
+    ffi, Channels,
+};
+use std::{
+    ffi::CStr,
+    fmt::{Display, Formatter, Result as FmtResult},
+    ptr,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+/// The request configuring DRED, unknown to Opus before 1.5 and to builds
+/// without DRED.
+const OPUS_SET_DRED_DURATION_REQUEST: i32 = 4050;
+
+/// The version of the linked Opus once probed, null before.
+///
+/// The probed version is leaked, it lives as long as the linked Opus.
+static LINKED: AtomicPtr<Version> = AtomicPtr::new(ptr::null_mut());
+
+/// The version of the linked Opus, parsed from its version string, and which
+/// optional features it supports.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Version {
+    pub major: u8,
+    pub minor: u8,
+    /// The patch version, zero if the version string omits it.
+    pub patch: u8,
+    /// Whether Opus uses fixed-point instead of floating point arithmetic.
+    pub fixed_point: bool,
+    /// Whether the encoder supports deep redundancy (DRED).
+    pub dred: bool,
+    /// Whether the decoder accepts the complexity gating neural enhancements
+    /// such as OSCE, as introduced by Opus 1.5.
+    pub osce: bool,
+    /// The unparsed version string, e.g. `libopus 1.3.1`.
+    pub string: &'static str,
+}
+
+impl Version {
+    /// Parses the version from `string`, e.g. `libopus 1.3.1-fixed`.
+    ///
+    /// Numbers missing or failing to parse are zero, the capabilities are
+    /// not probed.
+    pub(crate) fn parse(string: &'static str) -> Self {
+        let version = string
+            .trim_start_matches("libopus")
+            .trim_start()
+            .split(|c: char| c.is_whitespace() || c == '-')
+            .next()
+            .unwrap_or_default();
+        let mut numbers = version.split('.').map(|n| n.parse().unwrap_or(0));
+
+        Self {
+            major: numbers.next().unwrap_or(0),
+            minor: numbers.next().unwrap_or(0),
+            patch: numbers.next().unwrap_or(0),
+            fixed_point: string.contains("-fixed"),
+            dred: false,
+            osce: false,
+            string,
+        }
+    }
+
+    /// Gets the version of the linked Opus, probing its capabilities by
+    /// creating a short-lived encoder and decoder on the first call.
+    pub(crate) fn linked() -> Self {
+        let version = LINKED.load(Ordering::Acquire);
+
+        if !version.is_null() {
+            // Probed versions are never freed.
+            return unsafe { *version };
+        }
+
+        let version = Self::probe();
+        let cached = Box::into_raw(Box::new(version));
+
+        if LINKED
+            .compare_exchange(ptr::null_mut(), cached, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            // Another thread cached the same version first.
+            drop(unsafe { Box::from_raw(cached) });
+        }
+
+        version
+    }
+
+    /// Probes the version of the linked Opus.
+    fn probe() -> Self {
+        // The version string is static within Opus.
+        let string = unsafe { CStr::from_ptr(ffi::opus_get_version_string()) }
+            .to_str()
+            .unwrap_or_default();
+
+        Self {
+            dred: probe_dred(),
+            osce: probe_osce(),
+            ..Self::parse(string)
+        }
+    }
+
+    /// Whether the version is at least `major`.`minor`.
+    pub fn at_least(&self, major: u8, minor: u8) -> bool {
+        (self.major, self.minor) >= (major, minor)
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(self.string)
+    }
+}
+
+/// Whether multistream coders are available, which every Opus since 1.0
+/// provides.
+pub fn has_multistream() -> bool {
+    true
+}
+
+/// Whether projection coders are available, i.e. the crate was built
+/// against Opus 1.3 or newer.
+///
+/// **Info**:
+/// The build script probes the symbols of the linked Opus, the
+/// `OPUS_VERSION` environment variable takes precedence.
+pub fn has_projection() -> bool {
+    cfg!(opus_projection)
+}
+
+/// Whether deep redundancy is available, i.e. the crate was built with the
+/// `opus_1_5`-feature against Opus 1.5 or newer and the linked Opus
+/// supports DRED.
+pub fn has_dred() -> bool {
+    cfg!(all(feature = "opus_1_5", opus_dred)) && Version::linked().dred
+}
+
+/// Checks whether the linked encoder accepts configuring DRED.
+fn probe_dred() -> bool {
+    let mut opus_code = 0;
+
+    let pointer =
+        unsafe { ffi::opus_encoder_create(16000, 1, ffi::OPUS_APPLICATION_VOIP, &mut opus_code) };
+
+    if opus_code != ffi::OPUS_OK || pointer.is_null() {
+        return false;
+    }
+
+    let mut encoder = unsafe { Encoder::from_raw(pointer, Channels::Mono) };
+
+    encoder
+        .set_encoder_ctl_request(OPUS_SET_DRED_DURATION_REQUEST, 0)
+        .is_ok()
+}
+
+/// Checks whether the linked decoder accepts configuring its complexity.
+fn probe_osce() -> bool {
+    let mut opus_code = 0;
+
+    let pointer = unsafe { ffi::opus_decoder_create(16000, 1, &mut opus_code) };
+
+    if opus_code != ffi::OPUS_OK || pointer.is_null() {
+        return false;
+    }
+
+    let mut decoder = unsafe { Decoder::from_raw(pointer, Channels::Mono) };
+
+    unsafe { decoder.raw_ctl(CtlRequest::SetComplexity(0)) }.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{has_dred, has_multistream, Version};
+
+    #[test]
+    fn parse_version_strings() {
+        let version = Version::parse("libopus 1.3.1-fixed");
+        assert_eq!((version.major, version.minor, version.patch), (1, 3, 1));
+        assert!(version.fixed_point);
+
+        let version = Version::parse("libopus 1.5");
+        assert_eq!((version.major, version.minor, version.patch), (1, 5, 0));
+        assert!(!version.fixed_point);
+        assert!(version.at_least(1, 5));
+        assert!(!version.at_least(1, 6));
+
+        let version = Version::parse("libopus 1.4-rc1");
+        assert_eq!((version.major, version.minor), (1, 4));
+
+        let version = Version::parse("unknown");
+        assert_eq!((version.major, version.minor, version.patch), (0, 0, 0));
+    }
+
+    #[test]
+    fn linked_version() {
+        let version = Version::linked();
+
+        assert!(version.major >= 1);
+        assert_eq!(version.to_string(), version.string);
+        assert!(has_multistream());
+
+        if has_dred() {
+            assert!(version.dred && version.at_least(1, 5));
+        }
+
+        assert_eq!(Version::linked(), version);
+    }
+}
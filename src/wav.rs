@@ -0,0 +1,325 @@
+//! Transcoding between WAV and Ogg Opus files, see [`encode_wav_file`] and
+//! [`decode_to_wav`].
+//!
+//! [`encode_wav_file`]: crate::wav::encode_wav_file
+//! [`decode_to_wav`]: crate::wav::decode_to_wav
+
+#[cfg(feature = "resample")]
+use crate::resample::ResamplingEncoder;
+use crate::{
+    coder::{Encoder, StreamEncoder},
+    ogg::{OggOpusReader, OggOpusWriter, OpusHead, OpusTags},
+    packet::PacketBuf,
+    Application, Bitrate, Channels, FrameDuration, Result as OpusResult, SampleRate, TryFrom,
+};
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use std::{
+    fs::File,
+    io::{BufReader, Error as IoError, ErrorKind, Read, Result, Seek, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The settings [`encode_wav`] encodes with.
+///
+/// [`encode_wav`]: crate::wav::encode_wav
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct EncodeConfig {
+    pub application: Application,
+    pub bitrate: Bitrate,
+    pub frame_duration: FrameDuration,
+}
+
+impl Default for EncodeConfig {
+    fn default() -> Self {
+        Self {
+            application: Application::Audio,
+            bitrate: Bitrate::Auto,
+            frame_duration: FrameDuration::Ms20,
+        }
+    }
+}
+
+/// Encodes the WAV file at `path` according to `config`, returning the
+/// Ogg Opus stream.
+///
+/// **Errors**:
+/// Returns the errors of [`encode_wav`] and those opening the file.
+///
+/// [`encode_wav`]: crate::wav::encode_wav
+pub fn encode_wav_file<P: AsRef<Path>>(path: P, config: &EncodeConfig) -> Result<Vec<u8>> {
+    let file = BufReader::new(File::open(path)?);
+
+    encode_wav(file, config, Vec::new())
+}
+
+/// Encodes the WAV `reader` yields according to `config` and writes the
+/// Ogg Opus stream to `writer`, returning it.
+///
+/// The WAV must be mono or stereo. It is read and encoded in chunks, the
+/// stream's end is trimmed to the WAV's length. With the `resample`
+/// feature, WAV at a sample rate Opus does not support, e.g. 44.1 kHz, is
+/// resampled to 48 kHz; without it, such WAV is rejected.
+///
+/// **Errors**:
+/// Returns the reader's or writer's error, or [`ErrorKind::InvalidData`]
+/// if the WAV is malformed, wrapping [`Error::InvalidSampleRate`] if Opus
+/// does not support its sample rate and the `resample` feature is
+/// disabled, [`Error::InvalidChannels`] if Opus does not support its
+/// channels, [`Error::ResamplingFailed`] if resampling failed and
+/// [Error::Opus] when Opus encountered a problem.
+///
+/// [`ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+/// [`Error::InvalidSampleRate`]: crate::error::Error::InvalidSampleRate
+/// [`Error::InvalidChannels`]: crate::error::Error::InvalidChannels
+/// [`Error::ResamplingFailed`]: crate::error::Error::ResamplingFailed
+/// [Error::Opus]: crate::error::Error::Opus
+pub fn encode_wav<R: Read, W: Write>(reader: R, config: &EncodeConfig, writer: W) -> Result<W> {
+    let mut wav = WavReader::new(reader).map_err(wav_error)?;
+    let spec = wav.spec();
+    let channels = Channels::try_from(i32::from(spec.channels))?;
+
+    let new_encoder = |sample_rate: SampleRate| -> OpusResult<Encoder> {
+        let mut encoder = Encoder::new(sample_rate, channels, config.application)?;
+        encoder.set_bitrate(config.bitrate)?;
+
+        Ok(encoder)
+    };
+
+    let mut encoder =
+        match SampleRate::try_from(i32::try_from(spec.sample_rate).unwrap_or(i32::MAX)) {
+            Ok(sample_rate) => WavEncoder::Direct(StreamEncoder::new(
+                new_encoder(sample_rate)?,
+                config.frame_duration,
+            )?),
+            #[cfg(feature = "resample")]
+            Err(_) => WavEncoder::Resampling(ResamplingEncoder::new(
+                new_encoder(SampleRate::Hz48000)?,
+                spec.sample_rate,
+                config.frame_duration,
+            )?),
+            #[cfg(not(feature = "resample"))]
+            Err(error) => return Err(error.into()),
+        };
+
+    let pre_skip = encoder.encoder().pre_skip(false)?;
+    let head = OpusHead::new(channels, pre_skip, spec.sample_rate);
+    let mut ogg = OggOpusWriter::new(writer, &head, &OpusTags::default(), serial())?;
+
+    let chunk_len = CHUNK_FRAMES * usize::from(spec.channels);
+    let mut samples = read_samples(&mut wav);
+    let mut pcm = Vec::with_capacity(chunk_len);
+    let mut packets = Vec::new();
+    let mut len = 0;
+
+    loop {
+        for sample in samples.by_ref().take(chunk_len) {
+            pcm.push(sample?);
+        }
+
+        if pcm.is_empty() {
+            break;
+        }
+
+        len += pcm.len();
+        encoder.push(&pcm, &mut packets)?;
+        pcm.clear();
+
+        for packet in packets.drain(..) {
+            ogg.write_packet(packet.as_packet())?;
+        }
+    }
+
+    // Silence covering the pre-skip keeps the end of the input audible.
+    let silence = (u64::from(pre_skip) * u64::from(spec.sample_rate) + 47999) / 48000;
+    encoder.push(
+        &vec![0.0; silence as usize * usize::from(spec.channels)],
+        &mut packets,
+    )?;
+    encoder.flush(&mut packets)?;
+
+    for packet in packets {
+        ogg.write_packet(packet.as_packet())?;
+    }
+
+    let frames = (len / usize::from(spec.channels)) as u64;
+
+    ogg.finish(Some(frames * 48000 / u64::from(spec.sample_rate)))
+}
+
+/// Decodes the Ogg Opus stream `reader` yields and writes it to `writer` as
+/// 16-bit WAV at 48 kHz.
+///
+/// **Errors**:
+/// Returns the errors of [`OggOpusReader::read_pcm`] and the writer's
+/// error.
+///
+/// [`OggOpusReader::read_pcm`]: crate::ogg::OggOpusReader::read_pcm
+pub fn decode_to_wav<R: Read, W: Write + Seek>(reader: R, writer: W) -> Result<()> {
+    let mut ogg = OggOpusReader::new(reader)?;
+
+    let spec = WavSpec {
+        channels: u16::from(ogg.head().channels),
+        sample_rate: 48000,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+    let mut wav = WavWriter::new(writer, spec).map_err(wav_error)?;
+
+    while let Some(pcm) = ogg.read_pcm::<i16>()? {
+        for sample in pcm {
+            wav.write_sample(sample).map_err(wav_error)?;
+        }
+    }
+
+    wav.finalize().map_err(wav_error)
+}
+
+/// The frames per channel [`encode_wav`] reads and encodes at once.
+///
+/// [`encode_wav`]: crate::wav::encode_wav
+const CHUNK_FRAMES: usize = 4096;
+
+/// The encoder of [`encode_wav`], resampling if the WAV's sample rate is
+/// not supported by Opus.
+///
+/// [`encode_wav`]: crate::wav::encode_wav
+enum WavEncoder {
+    Direct(StreamEncoder<f32>),
+    #[cfg(feature = "resample")]
+    Resampling(ResamplingEncoder),
+}
+
+impl WavEncoder {
+    fn encoder(&self) -> &Encoder {
+        match self {
+            WavEncoder::Direct(encoder) => encoder.encoder(),
+            #[cfg(feature = "resample")]
+            WavEncoder::Resampling(encoder) => encoder.encoder(),
+        }
+    }
+
+    fn push(&mut self, pcm: &[f32], packets: &mut Vec<PacketBuf>) -> OpusResult<usize> {
+        match self {
+            WavEncoder::Direct(encoder) => encoder.push(pcm, packets),
+            #[cfg(feature = "resample")]
+            WavEncoder::Resampling(encoder) => encoder.push(pcm, packets),
+        }
+    }
+
+    fn flush(&mut self, packets: &mut Vec<PacketBuf>) -> OpusResult<usize> {
+        match self {
+            WavEncoder::Direct(encoder) => {
+                let len = packets.len();
+                packets.extend(encoder.flush()?);
+
+                Ok(packets.len() - len)
+            }
+            #[cfg(feature = "resample")]
+            WavEncoder::Resampling(encoder) => encoder.flush(packets),
+        }
+    }
+}
+
+/// Reads the samples of `wav` one by one as `f32`.
+fn read_samples<'a, R: Read>(
+    wav: &'a mut WavReader<R>,
+) -> Box<dyn Iterator<Item = Result<f32>> + 'a> {
+    let spec = wav.spec();
+
+    match spec.sample_format {
+        SampleFormat::Float => Box::new(wav.samples::<f32>().map(|s| s.map_err(wav_error))),
+        SampleFormat::Int => {
+            let scale = 0.5_f32.powi(i32::from(spec.bits_per_sample) - 1);
+
+            Box::new(
+                wav.samples::<i32>()
+                    .map(move |sample| sample.map(|s| s as f32 * scale).map_err(wav_error)),
+            )
+        }
+    }
+}
+
+/// Converts hound's errors, malformed WAV being invalid data.
+fn wav_error(error: hound::Error) -> IoError {
+    match error {
+        hound::Error::IoError(error) => error,
+        error => IoError::new(ErrorKind::InvalidData, error),
+    }
+}
+
+/// Picks a serial for a new stream.
+fn serial() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.subsec_nanos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_to_wav, encode_wav, EncodeConfig};
+    use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+    use std::io::Cursor;
+
+    #[test]
+    fn transcode_wav() {
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: 16000,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut input = Cursor::new(Vec::new());
+        let mut wav = WavWriter::new(&mut input, spec).unwrap();
+        for index in 0..16000_i32 {
+            let sample = ((index % 64 - 32) * 256) as i16;
+            wav.write_sample(sample).unwrap();
+            wav.write_sample(sample).unwrap();
+        }
+        wav.finalize().unwrap();
+
+        input.set_position(0);
+        let ogg = encode_wav(input, &EncodeConfig::default(), Vec::new()).unwrap();
+
+        let mut output = Cursor::new(Vec::new());
+        decode_to_wav(Cursor::new(ogg), &mut output).unwrap();
+
+        output.set_position(0);
+        let wav = WavReader::new(output).unwrap();
+        assert_eq!(wav.spec().channels, 2);
+        assert_eq!(wav.spec().sample_rate, 48000);
+        // One second, trimmed to the input's length.
+        assert_eq!(wav.duration(), 48000);
+    }
+
+    #[test]
+    fn encode_wav_44100() {
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut input = Cursor::new(Vec::new());
+        let mut wav = WavWriter::new(&mut input, spec).unwrap();
+        for index in 0..44100_i32 {
+            wav.write_sample(((index % 64 - 32) * 256) as i16).unwrap();
+        }
+        wav.finalize().unwrap();
+
+        input.set_position(0);
+        let encoded = encode_wav(input, &EncodeConfig::default(), Vec::new());
+
+        #[cfg(feature = "resample")]
+        {
+            let mut output = Cursor::new(Vec::new());
+            decode_to_wav(Cursor::new(encoded.unwrap()), &mut output).unwrap();
+
+            output.set_position(0);
+            assert_eq!(WavReader::new(output).unwrap().duration(), 48000);
+        }
+
+        #[cfg(not(feature = "resample"))]
+        assert_eq!(encoded.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,48 @@
+//! Installing a library swaps the backend of the whole process, hence this
+//! runs in its own test binary.
+#![cfg(feature = "dlopen")]
+
+use audiopus::{
+    coder::{DecodeRequest, Decoder, Encoder},
+    dynamic::OpusLibrary,
+    packet::Packet,
+    Application, Channels, Error, ErrorCode, MutSignals, SampleRate,
+};
+use matches::assert_matches;
+use std::convert::TryFrom;
+
+#[test]
+fn coders_run_on_installed_library() {
+    // Opus need not be installed as a shared library.
+    let library = match OpusLibrary::load() {
+        Ok(library) => library,
+        Err(_) => return,
+    };
+    library.install();
+
+    let encoder = Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Voip).unwrap();
+    let mut decoder = Decoder::new(SampleRate::Hz48000, Channels::Mono).unwrap();
+
+    let input = [0_i16; 960];
+    let mut packet = [0; 256];
+    let len = encoder.encode(&input, &mut packet).unwrap();
+
+    let mut output = [0_i16; 960];
+    let packet = Packet::try_from(&packet[..len]).unwrap();
+    let signals = MutSignals::try_from(&mut output[..]).unwrap();
+
+    assert_matches!(
+        decoder.decode(DecodeRequest::Normal(packet), signals),
+        Ok(960)
+    );
+
+    // `from_raw` assumes the linked Opus, the state must not escape.
+    assert_matches!(
+        encoder.into_raw(),
+        Err(Error::Opus(ErrorCode::Unimplemented))
+    );
+    assert_matches!(
+        decoder.into_raw(),
+        Err(Error::Opus(ErrorCode::Unimplemented))
+    );
+}